@@ -0,0 +1,134 @@
+//! `#[derive(TreeNode)]`, which implements `entmut::node::TreeNode` for types
+//! whose children are marked with `#[child]` and `#[children]`. Use it through
+//! entmut's `derive` feature, which re-exports it as `entmut::node::TreeNode`.
+
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+extern crate syn;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use syn::{Data, DeriveInput, Error, Fields, Ident, Member};
+
+/// Implements `entmut::node::TreeNode`. The children of a value are those of
+/// its fields marked `#[child]`, each of which holds one child, and
+/// `#[children]`, each of which holds any number, in declaration order.
+///
+/// A `#[child]` field may be of any type that borrows as `Self`, such as
+/// `Box<Self>`. A `#[children]` field may be of any type that iterates by
+/// reference over items that borrow as `Self`, such as `Vec<Self>` or
+/// `Option<Box<Self>>`.
+#[proc_macro_derive(TreeNode, attributes(child, children))]
+pub fn derive_tree_node(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    expand(&input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+enum Kind {
+    One,
+    Many,
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let mut count_arms = Vec::new();
+    let mut child_arms = Vec::new();
+    match input.data {
+        Data::Struct(ref data) => {
+            let (count, child) = arms(quote! { #name }, &data.fields)?;
+            count_arms.push(count);
+            child_arms.push(child);
+        },
+        Data::Enum(ref data) => for variant in &data.variants {
+            let variant_name = &variant.ident;
+            let (count, child) = arms(quote! { #name::#variant_name }, &variant.fields)?;
+            count_arms.push(count);
+            child_arms.push(child);
+        },
+        Data::Union(ref data) =>
+            return Err(Error::new_spanned(data.union_token, "TreeNode cannot be derived for unions")),
+    }
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::entmut::node::TreeNode for #name #ty_generics #where_clause {
+            fn child_count(&self) -> usize {
+                match *self {
+                    #(#count_arms,)*
+                }
+            }
+
+            #[allow(unused_assignments, unused_mut, unreachable_code)]
+            fn child(&self, index: usize) -> ::std::option::Option<&Self> {
+                let mut index = index;
+                match *self {
+                    #(#child_arms)*
+                }
+                ::std::option::Option::None
+            }
+        }
+    })
+}
+
+// Returns the match arms of `child_count` and `child` for the struct or variant
+// at `path` with `fields`.
+fn arms(path: TokenStream2, fields: &Fields) -> syn::Result<(TokenStream2, TokenStream2)> {
+    let children = child_fields(fields)?;
+    let members = children.iter().map(|(member, _)| member);
+    let bindings: Vec<Ident> =
+        (0..children.len()).map(|i| format_ident!("__entmut_child_{}", i)).collect();
+    let pattern = quote! { #path { #(#members: ref #bindings,)* .. } };
+    let counts = children.iter().zip(&bindings).map(|((_, kind), binding)| match *kind {
+        Kind::One => quote! { 1 },
+        Kind::Many => quote! { ::std::iter::IntoIterator::into_iter(#binding).count() },
+    });
+    let seeks = children.iter().zip(&bindings).map(|((_, kind), binding)| match *kind {
+        Kind::One => quote! {
+            if index == 0 {
+                return ::std::option::Option::Some(::std::borrow::Borrow::<Self>::borrow(#binding));
+            }
+            index -= 1;
+        },
+        Kind::Many => quote! {
+            let count = ::std::iter::IntoIterator::into_iter(#binding).count();
+            if index < count {
+                return ::std::iter::IntoIterator::into_iter(#binding).nth(index)
+                    .map(::std::borrow::Borrow::<Self>::borrow);
+            }
+            index -= count;
+        },
+    });
+    Ok((quote! { #pattern => 0 #(+ #counts)* }, quote! { #pattern => { #(#seeks)* } }))
+}
+
+// Returns the marked fields of a struct or variant, in declaration order.
+fn child_fields(fields: &Fields) -> syn::Result<Vec<(Member, Kind)>> {
+    let mut children = Vec::new();
+    for (index, field) in fields.iter().enumerate() {
+        let mut kind = None;
+        for attr in &field.attrs {
+            let this_kind = if attr.path().is_ident("child") {
+                Kind::One
+            } else if attr.path().is_ident("children") {
+                Kind::Many
+            } else {
+                continue
+            };
+            attr.meta.require_path_only()?;
+            if kind.is_some() {
+                return Err(Error::new_spanned(attr, "a field may be marked only once as #[child] \
+                                                     or #[children]"))
+            }
+            kind = Some(this_kind);
+        }
+        if let Some(kind) = kind {
+            let member = match field.ident {
+                Some(ref ident) => Member::Named(ident.clone()),
+                None => Member::Unnamed(index.into()),
+            };
+            children.push((member, kind));
+        }
+    }
+    Ok(children)
+}
@@ -0,0 +1,64 @@
+extern crate entmut;
+
+use entmut::Nav;
+use entmut::node::{NodeView, TreeNode};
+
+#[derive(Debug, PartialEq, TreeNode)]
+enum Expr {
+    Num(i32),
+    Neg(#[child] Box<Expr>),
+    Call { name: &'static str, #[children] args: Vec<Expr> },
+    If {
+        #[child] cond: Box<Expr>,
+        #[child] then: Box<Expr>,
+        #[children] otherwise: Option<Box<Expr>>,
+    },
+}
+
+use Expr::*;
+
+#[derive(TreeNode)]
+struct Dir<'a, T> {
+    name: T,
+    #[children] entries: Vec<&'a Dir<'a, T>>,
+}
+
+#[test]
+fn counts_and_orders_children() {
+    let e = If {
+        cond: Box::new(Call { name: "f", args: vec![Num(1), Num(2)], }),
+        then: Box::new(Neg(Box::new(Num(3)))),
+        otherwise: Some(Box::new(Num(4))),
+    };
+    assert_eq![3, e.child_count()];
+    assert_eq![Some(&Num(4)), e.child(2)];
+    assert_eq![None, e.child(3)];
+    let no_else = Call { name: "g", args: Vec::new(), };
+    assert_eq![0, no_else.child_count()];
+    assert_eq![None, no_else.child(0)];
+
+    let mut v = NodeView::new(&e);
+    assert![v.seek_child(0)];
+    assert![v.seek_child(1)];
+    assert_eq![Num(2), *v];
+    v.to_root();
+    assert![v.seek_child(1)];
+    assert_eq![1, v.child_count()];
+    assert![v.seek_child(0)];
+    assert_eq![Num(3), *v];
+    assert![v.to_parent()];
+    assert![v.seek_sibling(1)];
+    assert_eq![Num(4), *v];
+}
+
+#[test]
+fn derives_for_generic_structs() {
+    let a = Dir { name: "a", entries: Vec::new(), };
+    let b = Dir { name: "b", entries: vec![&a], };
+    let root = Dir { name: "/", entries: vec![&a, &b], };
+    let mut v = NodeView::new(&root);
+    assert_eq![2, v.child_count()];
+    assert![v.seek_child(1)];
+    assert![v.seek_child(0)];
+    assert_eq!["a", v.name];
+}
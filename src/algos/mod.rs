@@ -0,0 +1,4 @@
+/// Constant-time range-minimum queries over fixed sequences.
+pub mod range_min;
+/// Ropes of text stored in augmented trees.
+pub mod rope;
@@ -0,0 +1,83 @@
+use std::ops::Range;
+
+/// Sparse table answering range-minimum queries over a fixed sequence in
+/// constant time, after O(n log n) preprocessing.
+///
+/// This is the usual companion of an Euler tour, over which the lowest common
+/// ancestor of two nodes is the shallowest node between their first visits.
+#[derive(Clone, Debug)]
+pub struct RangeMin<K> {
+    values: Vec<K>,
+    // `levels[k][i]` is the index of the least value in `i..i + 2^k`. Ties go
+    // to the leftmost.
+    levels: Vec<Vec<usize>>,
+}
+
+impl<K: Ord> RangeMin<K> {
+    pub fn new(values: Vec<K>) -> Self {
+        let mut levels = vec![(0..values.len()).collect::<Vec<_>>()];
+        let mut width = 1;
+        while 2 * width <= values.len() {
+            let next = {
+                let prev = levels.last().unwrap();
+                (0..values.len() + 1 - 2 * width)
+                    .map(|i| Self::lesser(&values, prev[i], prev[i + width]))
+                    .collect()
+            };
+            levels.push(next);
+            width *= 2;
+        }
+        RangeMin { values, levels, }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn values(&self) -> &[K] {
+        &self.values
+    }
+
+    /// Returns the index of the least value in `range`, preferring the
+    /// leftmost of equal values. Panics if `range` is empty or out of bounds.
+    pub fn min_index(&self, range: Range<usize>) -> usize {
+        assert![range.start < range.end && range.end <= self.values.len(),
+                "range {:?} is empty or out of bounds", range];
+        let level = (usize::BITS - 1 - (range.end - range.start).leading_zeros()) as usize;
+        let width = 1 << level;
+        Self::lesser(&self.values, self.levels[level][range.start],
+                     self.levels[level][range.end - width])
+    }
+
+    fn lesser(values: &[K], a: usize, b: usize) -> usize {
+        if values[b] < values[a] { b } else { a }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::algos::range_min::RangeMin;
+
+    #[test]
+    fn finds_minimum_of_every_range() {
+        let values = vec![5, 2, 7, 2, 9, 1, 4, 8, 3];
+        let rmq = RangeMin::new(values.clone());
+        for start in 0..values.len() {
+            for end in start + 1..values.len() + 1 {
+                let expected = (start..end)
+                    .fold(start, |m, i| if values[i] < values[m] { i } else { m });
+                assert_eq![expected, rmq.min_index(start..end)];
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn empty_range_panics() {
+        RangeMin::new(vec![1, 2]).min_index(1..1);
+    }
+}
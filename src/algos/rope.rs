@@ -0,0 +1,322 @@
+use ::{Editor, Nav};
+use ::augmented::{self, Augmented};
+use ::owned;
+
+use std::cmp;
+use std::fmt;
+use std::ops::Range;
+
+/// Maximum number of chars stored in a single chunk.
+const MAX_CHUNK: usize = 64;
+/// Maximum number of children of an interior node.
+const MAX_CHILDREN: usize = 8;
+
+type CharCount = fn(&String, &[&usize]) -> usize;
+
+fn char_count<S: AsRef<str>>(data: &S, children: &[&usize]) -> usize {
+    data.as_ref().chars().count() + children.iter().map(|c| **c).sum::<usize>()
+}
+
+/// Returns the byte offset of the char at `char_index` in `s`, or the length
+/// of `s` if `char_index` is past its end.
+fn byte_offset(s: &str, char_index: usize) -> usize {
+    s.char_indices().nth(char_index).map_or(s.len(), |(i, _)| i)
+}
+
+/// Splits `s` into as few chunks of near-equal length as will fit.
+fn split_chunks(s: &str) -> Vec<String> {
+    let len = s.chars().count();
+    let count = len.div_ceil(MAX_CHUNK);
+    let mut chars = s.chars();
+    (0..count).map(|i| {
+        let size = (i + 1) * len / count - i * len / count;
+        chars.by_ref().take(size).collect()
+    }).collect()
+}
+
+/// Text stored as a tree of string chunks.
+///
+/// Chunks of up to 64 chars are stored in the leaves of an augmented tree in
+/// which each node caches the number of chars beneath it, so that offsets can
+/// be found in time proportional to the depth of the tree. Interior nodes are
+/// split as they fill up, in the manner of a B-tree, so all leaves stay at the
+/// same depth and insertion keeps the tree balanced. Removal discards emptied
+/// nodes but does not merge sparse ones.
+///
+/// Offsets are measured in chars, not bytes.
+pub struct Rope {
+    tree: Augmented<String, usize, CharCount>,
+}
+
+impl Rope {
+    /// Creates an empty rope.
+    pub fn new() -> Self {
+        Rope { tree: Augmented::new(owned::Tree::leaf(String::new()),
+                                    char_count::<String> as CharCount), }
+    }
+
+    /// Returns the number of chars in the rope.
+    pub fn len_chars(&self) -> usize {
+        *self.tree.aggregate()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len_chars() == 0
+    }
+
+    /// Inserts `text` so that it begins at `char_index`. Panics if
+    /// `char_index` is greater than the length of the rope.
+    pub fn insert(&mut self, char_index: usize, text: &str) {
+        assert![char_index <= self.len_chars(),
+                "char index {} out of bounds for rope of length {}",
+                char_index, self.len_chars()];
+        if text.is_empty() {
+            return
+        }
+        let mut e = self.tree.editor();
+        // Descend to the leaf containing `char_index`, preferring the leftmost
+        // leaf when it falls on a chunk boundary.
+        let mut at = char_index;
+        while e.child_count() > 0 {
+            let last = e.child_count() - 1;
+            let mut index = 0;
+            while index < last {
+                let count = *e.child_aggregate(index).unwrap();
+                if at <= count {
+                    break
+                }
+                at -= count;
+                index += 1;
+            }
+            e.seek_child(index);
+        }
+        if e.at_root() {
+            for chunk in split_chunks(text) {
+                e.push_leaf(chunk);
+                e.to_parent();
+            }
+        } else {
+            let mut chunks = {
+                let chunk: &String = &e;
+                let split = byte_offset(chunk, at);
+                let mut joined = String::with_capacity(chunk.len() + text.len());
+                joined.push_str(&chunk[..split]);
+                joined.push_str(text);
+                joined.push_str(&chunk[split..]);
+                split_chunks(&joined)
+            };
+            let last = chunks.pop().unwrap();
+            e.set_data(last);
+            for chunk in chunks.into_iter().rev() {
+                e.insert_sibling_leaf(0, chunk);
+            }
+            e.to_parent();
+        }
+        Rope::split_overfull(&mut e);
+    }
+
+    // Splits each overfull interior node from the focus up to the root.
+    fn split_overfull(e: &mut augmented::TreeEditor<'_, String, usize, CharCount>) {
+        loop {
+            if e.child_count() > MAX_CHILDREN {
+                if e.at_root() {
+                    // Grow the tree by one level.
+                    let children = (0..e.child_count())
+                        .map(|_| e.remove_child(0).unwrap())
+                        .collect();
+                    e.push_child(owned::Tree::new(String::new(), children));
+                    continue
+                }
+                while e.child_count() > MAX_CHILDREN {
+                    let left = (0..MAX_CHILDREN / 2)
+                        .map(|_| e.remove_child(0).unwrap())
+                        .collect();
+                    e.insert_sibling(0, owned::Tree::new(String::new(), left));
+                    e.seek_sibling(1);
+                }
+            }
+            if !e.to_parent() {
+                return
+            }
+        }
+    }
+
+    /// Removes the chars in `range`. Panics if the range is decreasing or
+    /// extends past the end of the rope.
+    pub fn remove(&mut self, range: Range<usize>) {
+        assert![range.start <= range.end && range.end <= self.len_chars(),
+                "char range {:?} out of bounds for rope of length {}",
+                range, self.len_chars()];
+        let mut remaining = range.end - range.start;
+        while remaining > 0 {
+            let mut e = self.tree.editor();
+            let mut at = range.start;
+            while e.child_count() > 0 {
+                let mut index = 0;
+                loop {
+                    let count = *e.child_aggregate(index).unwrap();
+                    if at < count {
+                        break
+                    }
+                    at -= count;
+                    index += 1;
+                }
+                e.seek_child(index);
+            }
+            let rest = {
+                let chunk: &String = &e;
+                let take = cmp::min(remaining, chunk.chars().count() - at);
+                remaining -= take;
+                let start = byte_offset(chunk, at);
+                let end = start + byte_offset(&chunk[start..], take);
+                format!["{}{}", &chunk[..start], &chunk[end..]]
+            };
+            if rest.is_empty() {
+                e.remove();
+                // Interior nodes left without children are removed as well.
+                while !e.at_root() && e.child_count() == 0 && e.is_empty() {
+                    e.remove();
+                }
+            } else {
+                e.set_data(rest);
+            }
+        }
+    }
+
+    /// Iterates over the chunks of text in the rope, in order.
+    pub fn chunks(&self) -> Chunks<'_> {
+        let mut view = self.tree.view();
+        while view.seek_child(0) {}
+        Chunks { view: if view.at_root() { None } else { Some(view) }, }
+    }
+}
+
+impl Default for Rope {
+    fn default() -> Self {
+        Rope::new()
+    }
+}
+
+impl<'s> From<&'s str> for Rope {
+    fn from(text: &'s str) -> Self {
+        let mut rope = Rope::new();
+        rope.insert(0, text);
+        rope
+    }
+}
+
+impl fmt::Display for Rope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for chunk in self.chunks() {
+            f.write_str(chunk)?;
+        }
+        Ok(())
+    }
+}
+
+/// Iterator over the chunks of a rope.
+pub struct Chunks<'a> {
+    // Focused on the next chunk to return.
+    view: Option<augmented::TreeView<'a, String, usize>>,
+}
+
+impl<'a> Iterator for Chunks<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let (chunk, done) = {
+            let view = self.view.as_mut()?;
+            let chunk = view.data();
+            let done = loop {
+                if view.seek_sibling(1) {
+                    while view.seek_child(0) {}
+                    break false
+                }
+                if !view.to_parent() {
+                    break true
+                }
+            };
+            (chunk, done)
+        };
+        if done {
+            self.view = None;
+        }
+        Some(chunk)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::algos::rope::{MAX_CHUNK, Rope};
+
+    use std::cmp;
+
+    fn check(rope: &Rope, expected: &str) {
+        assert_eq![rope.to_string(), expected];
+        assert_eq![rope.len_chars(), expected.chars().count()];
+        for chunk in rope.chunks() {
+            assert![!chunk.is_empty()];
+            assert![chunk.chars().count() <= MAX_CHUNK];
+        }
+    }
+
+    #[test]
+    fn empty() {
+        let rope = Rope::new();
+        assert![rope.is_empty()];
+        assert_eq![rope.chunks().count(), 0];
+        check(&rope, "");
+    }
+
+    #[test]
+    fn insert_small() {
+        let mut rope = Rope::from("hello world");
+        rope.insert(5, ",");
+        rope.insert(12, "!");
+        rope.insert(0, "¡");
+        check(&rope, "¡hello, world!");
+        assert_eq![rope.chunks().count(), 1];
+    }
+
+    #[test]
+    fn insert_and_remove_match_string() {
+        let mut rope = Rope::new();
+        let mut expected: Vec<char> = Vec::new();
+        for i in 0..500 {
+            let text = format!["<{}é>", i];
+            let at = (i * 7919) % (expected.len() + 1);
+            rope.insert(at, &text);
+            for (j, c) in text.chars().enumerate() {
+                expected.insert(at + j, c);
+            }
+        }
+        check(&rope, &expected.iter().collect::<String>());
+        assert![rope.chunks().count() > 8];
+        for i in 0..100 {
+            let start = (i * 104729) % expected.len();
+            let end = cmp::min(expected.len(), start + i % 40);
+            rope.remove(start..end);
+            expected.drain(start..end);
+        }
+        check(&rope, &expected.iter().collect::<String>());
+        let len = rope.len_chars();
+        rope.remove(0..len);
+        check(&rope, "");
+        rope.insert(0, "again");
+        check(&rope, "again");
+    }
+
+    #[test]
+    fn bulk_insert_is_chunked() {
+        let text: String = (0..10000).map(|i| (b'a' + (i % 26) as u8) as char).collect();
+        let rope = Rope::from(&text[..]);
+        check(&rope, &text);
+        assert_eq![rope.chunks().count(), 10000usize.div_ceil(MAX_CHUNK)];
+    }
+
+    #[test]
+    #[should_panic]
+    fn insert_out_of_bounds() {
+        Rope::from("abc").insert(4, "d");
+    }
+}
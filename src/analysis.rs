@@ -0,0 +1,296 @@
+use ::Nav;
+use ::owned::Tree;
+use ::path::TreePath;
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+
+/// Summary of a subtree computed bottom-up.
+pub(crate) struct Subtree<'a, T: 'a> {
+    pub(crate) tree: &'a Tree<T>,
+    pub(crate) path: TreePath,
+    pub(crate) hash: u64,
+    pub(crate) size: usize,
+}
+
+/// Computes the structural hash and size of every subtree of `tree`, in
+/// post-order.
+pub(crate) fn summarize<T: Hash>(tree: &Tree<T>) -> Vec<Subtree<'_, T>> {
+    struct Frame<'a, T: 'a> {
+        tree: &'a Tree<T>,
+        next_child: usize,
+        hasher: DefaultHasher,
+        size: usize,
+    }
+
+    fn frame<T: Hash>(tree: &Tree<T>) -> Frame<'_, T> {
+        let mut hasher = DefaultHasher::new();
+        tree.data().hash(&mut hasher);
+        tree.children().len().hash(&mut hasher);
+        Frame { tree, next_child: 0, hasher, size: 1, }
+    }
+
+    let mut summaries = Vec::new();
+    let mut path = TreePath::root();
+    let mut stack = vec![frame(tree)];
+    loop {
+        let child = {
+            let top = stack.last_mut().unwrap();
+            let child = top.tree.children().get(top.next_child);
+            if child.is_some() {
+                path.push(top.next_child);
+                top.next_child += 1;
+            }
+            child
+        };
+        match child {
+            Some(child) => stack.push(frame(child)),
+            None => {
+                let done = stack.pop().unwrap();
+                let summary = Subtree { tree: done.tree,
+                                        path: path.clone(),
+                                        hash: done.hasher.finish(),
+                                        size: done.size, };
+                path.pop();
+                match stack.last_mut() {
+                    Some(parent) => {
+                        summary.hash.hash(&mut parent.hasher);
+                        parent.size += summary.size;
+                    },
+                    None => {
+                        summaries.push(summary);
+                        return summaries
+                    },
+                }
+                summaries.push(summary);
+            },
+        }
+    }
+}
+
+/// Finds groups of structurally identical subtrees.
+///
+/// Two subtrees are identical if their roots have equal data and their children
+/// are pairwise identical. Only subtrees of at least `min_size` nodes are
+/// considered. Each group in the result lists the paths of two or more
+/// identical subtrees in document order, and groups are ordered by their first
+/// path. When identical subtrees themselves contain repeated subtrees, those
+/// are reported as groups of their own.
+///
+/// Candidates are found by hashing subtrees bottom-up, so this runs in time
+/// roughly linear in the size of `tree`; candidates with equal hashes are
+/// compared exactly before being grouped.
+pub fn find_duplicates<T>(tree: &Tree<T>, min_size: usize) -> Vec<Vec<TreePath>>
+    where T: Eq + Hash {
+        let mut buckets: HashMap<(u64, usize), Vec<Subtree<'_, T>>> = HashMap::new();
+        for summary in summarize(tree) {
+            if summary.size >= min_size {
+                buckets.entry((summary.hash, summary.size)).or_default().push(summary);
+            }
+        }
+        let mut groups = Vec::new();
+        for (_, bucket) in buckets {
+            if bucket.len() < 2 {
+                continue
+            }
+            // Partition the bucket into classes of exactly equal subtrees, in
+            // case of hash collisions.
+            let mut classes: Vec<Vec<Subtree<'_, T>>> = Vec::new();
+            for summary in bucket {
+                match classes.iter().position(|class| class[0].tree == summary.tree) {
+                    Some(i) => classes[i].push(summary),
+                    None => classes.push(vec![summary]),
+                }
+            }
+            for class in classes {
+                if class.len() >= 2 {
+                    let mut paths: Vec<TreePath> = class.into_iter().map(|s| s.path).collect();
+                    paths.sort();
+                    groups.push(paths);
+                }
+            }
+        }
+        groups.sort();
+        groups
+    }
+
+/// Returns true iff the subtrees under `a` and `b` have the same shape and
+/// `eq` holds for the data at each pair of corresponding nodes.
+///
+/// Pairs of nodes are compared in pre-order, and comparison stops at the first
+/// mismatch, so `eq` may keep state across calls, such as a mapping between
+/// names bound in `a` and in `b`. The two subtrees may come from different
+/// backends.
+pub fn equiv_by<M, N, F>(a: M, b: N, mut eq: F) -> bool
+    where M: Nav + Clone + Deref, N: Nav + Clone + Deref,
+          F: FnMut(&M::Target, &N::Target) -> bool {
+        let mut stack = vec![(a, b)];
+        while let Some((a, b)) = stack.pop() {
+            if a.child_count() != b.child_count() || !eq(&a, &b) {
+                return false
+            }
+            for i in (0..a.child_count()).rev() {
+                let (mut a_child, mut b_child) = (a.clone(), b.clone());
+                a_child.seek_child(i);
+                b_child.seek_child(i);
+                stack.push((a_child, b_child));
+            }
+        }
+        true
+    }
+
+/// Returns true iff the subtrees under `a` and `b` are equal down to `depth`
+/// levels below their roots: corresponding nodes at those levels have equal
+/// data, and those above the last level have the same number of children.
+/// Nodes further down are not visited, so with a small `depth` this is cheap
+/// even for large trees. A `depth` of 0 compares just the roots' data.
+pub fn eq_to_depth<M, N>(a: M, b: N, depth: usize) -> bool
+    where M: Nav + Clone + Deref, N: Nav + Clone + Deref,
+          M::Target: PartialEq<N::Target> {
+        let mut stack = vec![(a, b, 0)];
+        while let Some((a, b, level)) = stack.pop() {
+            if *a != *b {
+                return false
+            }
+            if level == depth {
+                continue
+            }
+            if a.child_count() != b.child_count() {
+                return false
+            }
+            for i in (0..a.child_count()).rev() {
+                let (mut a_child, mut b_child) = (a.clone(), b.clone());
+                a_child.seek_child(i);
+                b_child.seek_child(i);
+                stack.push((a_child, b_child, level + 1));
+            }
+        }
+        true
+    }
+
+/// Returns true iff the subtree under `a` is a prefix of the one under `b`:
+/// each node in `a` has a counterpart in `b` with equal data at the same path,
+/// though nodes in `b` may have more children after those that correspond to
+/// nodes in `a`. Every subtree is a prefix of itself.
+pub fn is_prefix_of<M, N>(a: M, b: N) -> bool
+    where M: Nav + Clone + Deref, N: Nav + Clone + Deref,
+          M::Target: PartialEq<N::Target> {
+        let mut stack = vec![(a, b)];
+        while let Some((a, b)) = stack.pop() {
+            if a.child_count() > b.child_count() || *a != *b {
+                return false
+            }
+            for i in (0..a.child_count()).rev() {
+                let (mut a_child, mut b_child) = (a.clone(), b.clone());
+                a_child.seek_child(i);
+                b_child.seek_child(i);
+                stack.push((a_child, b_child));
+            }
+        }
+        true
+    }
+
+#[cfg(test)]
+mod test {
+    use ::analysis::{eq_to_depth, equiv_by, find_duplicates, is_prefix_of};
+    use ::owned_tree;
+    use ::shared_tree;
+    use ::path::TreePath;
+
+    fn paths(v: Vec<Vec<usize>>) -> Vec<TreePath> {
+        v.into_iter().map(TreePath::from).collect()
+    }
+
+    #[test]
+    fn no_duplicates() {
+        let t = owned_tree!["a", ["b"], ["c", ["d"]]];
+        assert![find_duplicates(&t, 1).is_empty()];
+    }
+
+    #[test]
+    fn duplicate_leaves() {
+        let t = owned_tree!["+", ["x"], ["*", ["x"], ["y"]]];
+        assert_eq![find_duplicates(&t, 1), vec![paths(vec![vec![0], vec![1, 0]])]];
+        assert![find_duplicates(&t, 2).is_empty()];
+    }
+
+    #[test]
+    fn duplicate_subtrees_and_nested_duplicates() {
+        let t = owned_tree!["+",
+                            ["*", ["x"], ["y"]],
+                            ["-", ["*", ["x"], ["y"]], ["z"]]];
+        assert_eq![find_duplicates(&t, 3), vec![paths(vec![vec![0], vec![1, 0]])]];
+        assert_eq![find_duplicates(&t, 1), vec![
+            paths(vec![vec![0], vec![1, 0]]),
+            paths(vec![vec![0, 0], vec![1, 0, 0]]),
+            paths(vec![vec![0, 1], vec![1, 0, 1]])]];
+        for path in &find_duplicates(&t, 3)[0] {
+            assert_eq![*t.subtree(path).unwrap(), owned_tree!["*", ["x"], ["y"]]];
+        }
+    }
+
+    #[test]
+    fn child_order_matters() {
+        let t = owned_tree!["r", ["a", ["x"], ["y"]], ["a", ["y"], ["x"]]];
+        assert_eq![find_duplicates(&t, 3), Vec::<Vec<TreePath>>::new()];
+    }
+
+    #[test]
+    fn equiv_by_compares_with_custom_equality() {
+        // Nodes are (name, span) pairs; spans are ignored.
+        let a = owned_tree![("add", 0), [("x", 4)], [("1", 8)]];
+        let b = shared_tree![("add", 10), [("x", 14)], [("1", 18)]];
+        assert![equiv_by(a.view(), b.view(), |x, y| x.0 == y.0)];
+        assert![! equiv_by(a.view(), b.view(), |x, y| x == y)];
+        let c = owned_tree![("add", 0), [("x", 4)]];
+        assert![! equiv_by(a.view(), c.view(), |x, y| x.0 == y.0)];
+    }
+
+    #[test]
+    fn equiv_by_visits_pairs_in_preorder() {
+        use std::collections::HashMap;
+
+        // Equal up to a consistent renaming of variables.
+        let a = owned_tree!["let", ["x"], ["add", ["x"], ["y"]]];
+        let b = owned_tree!["let", ["u"], ["add", ["u"], ["v"]]];
+        let c = owned_tree!["let", ["u"], ["add", ["v"], ["v"]]];
+        let renaming = |x: &&str, y: &&str, names: &mut HashMap<String, String>| {
+            if x.len() > 1 || y.len() > 1 {
+                return x == y
+            }
+            names.entry(x.to_string()).or_insert_with(|| y.to_string()) == y
+        };
+        let mut names = HashMap::new();
+        assert![equiv_by(a.view(), b.view(), |x, y| renaming(x, y, &mut names))];
+        let mut names = HashMap::new();
+        assert![! equiv_by(a.view(), c.view(), |x, y| renaming(x, y, &mut names))];
+        let mut visited = Vec::new();
+        equiv_by(a.view(), b.view(), |x, _| { visited.push(*x); true });
+        assert_eq![vec!["let", "x", "add", "x", "y"], visited];
+    }
+
+    #[test]
+    fn eq_to_depth_ignores_deeper_levels() {
+        let a = owned_tree!["a", ["b", ["c"]], ["d"]];
+        let b = shared_tree!["a", ["b", ["x"], ["y"]], ["d"]];
+        assert![eq_to_depth(a.view(), b.view(), 0)];
+        assert![eq_to_depth(a.view(), b.view(), 1)];
+        assert![! eq_to_depth(a.view(), b.view(), 2)];
+        let c = owned_tree!["a", ["b"]];
+        assert![! eq_to_depth(a.view(), c.view(), 1)];
+        assert![! eq_to_depth(a.view(), owned_tree!["z"].view(), 0)];
+    }
+
+    #[test]
+    fn prefixes_of_trees() {
+        let a = owned_tree!["a", ["b", ["c"]]];
+        let b = shared_tree!["a", ["b", ["c"], ["x"]], ["d"]];
+        assert![is_prefix_of(a.view(), b.view())];
+        assert![is_prefix_of(a.view(), a.view())];
+        assert![! is_prefix_of(b.view(), a.view())];
+        assert![! is_prefix_of(owned_tree!["a", ["d"]].view(), b.view())];
+        assert![! is_prefix_of(owned_tree!["a", ["b", ["x"]]].view(), b.view())];
+    }
+}
@@ -0,0 +1,616 @@
+use ::error::{EditError, ParseError};
+use ::indented::{self, Indent};
+use ::owned;
+use ::path::TreePath;
+
+#[cfg(feature = "serde")]
+use serde::de::{self, Deserialize, DeserializeSeed, Deserializer, SeqAccess, Visitor};
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Write};
+
+/// Identifier of a node in an arena `Tree`.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct NodeId(usize);
+
+/// Error returned by arena operations on nodes in the wrong state.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum ArenaError {
+    /// The node holds data, so it cannot be filled.
+    NotPlaceholder(NodeId),
+    /// The tree still has placeholders, listed in pre-order.
+    Incomplete(Vec<NodeId>),
+}
+
+impl fmt::Display for ArenaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ArenaError::NotPlaceholder(id) => write!(f, "node {:?} is not a placeholder", id),
+            ArenaError::Incomplete(ref holes) =>
+                write!(f, "tree has {} unfilled placeholders", holes.len()),
+        }
+    }
+}
+
+impl Error for ArenaError {}
+
+#[derive(Clone, Debug)]
+struct Node<T> {
+    // `None` for a placeholder.
+    data: Option<T>,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+}
+
+/// Tree whose nodes are stored together in one vector and addressed by
+/// `NodeId`.
+///
+/// A node may be a placeholder: a reserved position that counts as a child of
+/// its parent but holds no data until it is filled. This lets a tree be built
+/// with its final shape before all of its data is known, as when instantiating
+/// a template or parsing incrementally. `validate` finds any placeholders that
+/// remain.
+///
+/// The slots of nodes removed by `drain_subtree` are reused by later
+/// insertions, so the `NodeId` of a removed node may come to name a new one.
+///
+/// Methods that take a `NodeId` panic if it does not name a node of this tree.
+#[derive(Clone, Debug)]
+pub struct Tree<T> {
+    // `None` for a slot freed by removing its node.
+    nodes: Vec<Option<Node<T>>>,
+    free: Vec<NodeId>,
+}
+
+impl<T> Tree<T> {
+    /// Creates a tree with a single node holding `data`.
+    pub fn new(data: T) -> Self {
+        Tree { nodes: vec![Some(Node { data: Some(data), parent: None, children: Vec::new(), })],
+               free: Vec::new(), }
+    }
+
+    /// Creates a tree whose root is a placeholder.
+    pub fn placeholder() -> Self {
+        Tree { nodes: vec![Some(Node { data: None, parent: None, children: Vec::new(), })],
+               free: Vec::new(), }
+    }
+
+    pub fn root(&self) -> NodeId {
+        NodeId(0)
+    }
+
+    /// Returns the number of nodes in the tree, including placeholders.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len() - self.free.len()
+    }
+
+    /// Returns the data at `id`, or `None` if it is a placeholder.
+    pub fn data(&self, id: NodeId) -> Option<&T> {
+        self.node(id).data.as_ref()
+    }
+
+    /// Returns the data at `id`, or `None` if it is a placeholder.
+    pub fn data_mut(&mut self, id: NodeId) -> Option<&mut T> {
+        self.node_mut(id).data.as_mut()
+    }
+
+    pub fn is_placeholder(&self, id: NodeId) -> bool {
+        self.node(id).data.is_none()
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.node(id).parent
+    }
+
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        &self.node(id).children
+    }
+
+    /// Adds a node holding `data` to the end of the children of `parent`.
+    pub fn push_child(&mut self, parent: NodeId, data: T) -> NodeId {
+        let index = self.node(parent).children.len();
+        self.attach(parent, index, Some(data))
+    }
+
+    /// Adds a placeholder to the end of the children of `parent`.
+    pub fn push_placeholder(&mut self, parent: NodeId) -> NodeId {
+        let index = self.node(parent).children.len();
+        self.attach(parent, index, None)
+    }
+
+    /// Inserts a node holding `data` at the given position in the children of
+    /// `parent`. Fails if `index` is greater than the number of children.
+    pub fn insert_child(&mut self, parent: NodeId, index: usize, data: T)
+                        -> Result<NodeId, EditError> {
+        self.check_insert(parent, index)?;
+        Ok(self.attach(parent, index, Some(data)))
+    }
+
+    /// Inserts a placeholder at the given position in the children of
+    /// `parent`. Fails if `index` is greater than the number of children.
+    pub fn insert_placeholder(&mut self, parent: NodeId, index: usize)
+                              -> Result<NodeId, EditError> {
+        self.check_insert(parent, index)?;
+        Ok(self.attach(parent, index, None))
+    }
+
+    /// Stores `data` in the placeholder at `id`. Fails, leaving the tree
+    /// unchanged, if `id` already holds data.
+    pub fn fill_placeholder(&mut self, id: NodeId, data: T) -> Result<(), ArenaError> {
+        let node = self.node_mut(id);
+        if node.data.is_some() {
+            return Err(ArenaError::NotPlaceholder(id))
+        }
+        node.data = Some(data);
+        Ok(())
+    }
+
+    /// Returns the placeholders remaining in the tree, in pre-order.
+    pub fn holes(&self) -> Vec<NodeId> {
+        let mut holes = Vec::new();
+        let mut stack = vec![self.root()];
+        while let Some(id) = stack.pop() {
+            let node = self.node(id);
+            if node.data.is_none() {
+                holes.push(id);
+            }
+            stack.extend(node.children.iter().rev());
+        }
+        holes
+    }
+
+    /// Checks that every placeholder has been filled.
+    pub fn validate(&self) -> Result<(), ArenaError> {
+        let holes = self.holes();
+        if holes.is_empty() {
+            Ok(())
+        } else {
+            Err(ArenaError::Incomplete(holes))
+        }
+    }
+
+    /// Converts this into an owned tree. Fails if any placeholders remain.
+    pub fn into_owned(self) -> Result<owned::Tree<T>, ArenaError> {
+        self.convert_to_owned(None)
+    }
+
+    /// Converts this into an owned tree as `into_owned` does, and also returns
+    /// the path in the result of each node in this tree, so that anything
+    /// keyed by `NodeId` can be rekeyed by path.
+    pub fn into_owned_with_provenance(self)
+                                      -> Result<(owned::Tree<T>, HashMap<NodeId, TreePath>),
+                                                ArenaError> {
+        let mut provenance = HashMap::with_capacity(self.node_count());
+        let tree = self.convert_to_owned(Some(&mut provenance))?;
+        Ok((tree, provenance))
+    }
+
+    // Converts this into an owned tree, recording the path of each node in
+    // `provenance` if it is given.
+    fn convert_to_owned(self, mut provenance: Option<&mut HashMap<NodeId, TreePath>>)
+                        -> Result<owned::Tree<T>, ArenaError> {
+        self.validate()?;
+        let root = self.root();
+        let mut nodes = self.nodes;
+        let mut path = TreePath::root();
+        if let Some(ref mut provenance) = provenance {
+            provenance.insert(root, path.clone());
+        }
+        // Nodes whose children are being converted, with the next child to
+        // convert and the children converted so far.
+        let mut stack = vec![(nodes[root.0].take().unwrap(), 0, Vec::new())];
+        loop {
+            let next = {
+                let top = stack.last_mut().unwrap();
+                let next = top.0.children.get(top.1).cloned();
+                if next.is_some() {
+                    path.push(top.1);
+                }
+                top.1 += 1;
+                next
+            };
+            match next {
+                Some(child) => {
+                    if let Some(ref mut provenance) = provenance {
+                        provenance.insert(child, path.clone());
+                    }
+                    let node = nodes[child.0].take().unwrap();
+                    let count = node.children.len();
+                    stack.push((node, 0, Vec::with_capacity(count)));
+                },
+                None => {
+                    let (node, _, children) = stack.pop().unwrap();
+                    let tree = owned::Tree::new(node.data.unwrap(), children);
+                    path.pop();
+                    match stack.last_mut() {
+                        Some(parent) => parent.2.push(tree),
+                        None => return Ok(tree),
+                    }
+                },
+            }
+        }
+    }
+
+    /// Removes the subtree at `id`, returning an iterator over the data of its
+    /// nodes in pre-order. Placeholders in the subtree are removed but yield
+    /// nothing.
+    ///
+    /// The subtree is detached from its parent at once, and each node's slot
+    /// is freed for reuse as the iterator reaches it, so no owned copy of the
+    /// subtree is built. Dropping the iterator early removes the rest of the
+    /// subtree without yielding it. Panics if `id` is the root.
+    pub fn drain_subtree(&mut self, id: NodeId) -> DrainSubtree<'_, T> {
+        let parent = self.node(id).parent.expect("cannot drain the root of an arena tree");
+        self.node_mut(parent).children.retain(|&child| child != id);
+        DrainSubtree { tree: self, stack: vec![id], }
+    }
+
+    fn node(&self, id: NodeId) -> &Node<T> {
+        self.nodes[id.0].as_ref().unwrap_or_else(|| panic!["node {:?} has been removed", id])
+    }
+
+    fn node_mut(&mut self, id: NodeId) -> &mut Node<T> {
+        self.nodes[id.0].as_mut().unwrap_or_else(|| panic!["node {:?} has been removed", id])
+    }
+
+    fn check_insert(&self, parent: NodeId, index: usize) -> Result<(), EditError> {
+        let child_count = self.node(parent).children.len();
+        if index > child_count {
+            return Err(EditError::ChildOutOfRange { index, child_count, })
+        }
+        Ok(())
+    }
+
+    fn attach(&mut self, parent: NodeId, index: usize, data: Option<T>) -> NodeId {
+        let node = Node { data, parent: Some(parent), children: Vec::new(), };
+        let id = match self.free.pop() {
+            Some(id) => {
+                self.nodes[id.0] = Some(node);
+                id
+            },
+            None => {
+                self.nodes.push(Some(node));
+                NodeId(self.nodes.len() - 1)
+            },
+        };
+        self.node_mut(parent).children.insert(index, id);
+        id
+    }
+}
+
+impl Tree<String> {
+    /// Parses indented text into a tree whose data is the text of each line,
+    /// as `indented::parse` does.
+    pub fn from_indented(text: &str, indent: Indent) -> Result<Tree<String>, ParseError> {
+        Ok(indented::parse(text, indent)?)
+    }
+}
+
+impl<T: fmt::Display> Tree<T> {
+    /// Writes this tree as indented text that `from_indented` reads back, as
+    /// `indented::write` does.
+    pub fn write_indented<W: Write>(&self, w: &mut W, indent: Indent) -> io::Result<()> {
+        indented::write(self, w, indent)
+    }
+}
+
+/// Iterator returned by `Tree::drain_subtree`.
+pub struct DrainSubtree<'a, T: 'a> {
+    tree: &'a mut Tree<T>,
+    // Nodes still to be removed, with the next on top.
+    stack: Vec<NodeId>,
+}
+
+impl<'a, T> Iterator for DrainSubtree<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        while let Some(id) = self.stack.pop() {
+            let node = self.tree.nodes[id.0].take().unwrap();
+            self.tree.free.push(id);
+            self.stack.extend(node.children.into_iter().rev());
+            if node.data.is_some() {
+                return node.data
+            }
+        }
+        None
+    }
+}
+
+impl<'a, T> Drop for DrainSubtree<'a, T> {
+    fn drop(&mut self) {
+        self.for_each(drop);
+    }
+}
+
+impl<T> Tree<T> {
+    /// Converts `tree` as `From<owned::Tree<T>>` does, and also returns the
+    /// node in the result that each path in `tree` leads to, so that anything
+    /// keyed by paths in `tree` can be rekeyed by node.
+    pub fn from_owned_with_provenance(tree: owned::Tree<T>) -> (Self, HashMap<TreePath, NodeId>) {
+        let mut provenance = HashMap::new();
+        let arena = Tree::convert_from_owned(tree, Some(&mut provenance));
+        (arena, provenance)
+    }
+
+    // Converts `tree` into an arena tree, recording the node that each of its
+    // paths leads to in `provenance` if it is given.
+    fn convert_from_owned(tree: owned::Tree<T>,
+                          mut provenance: Option<&mut HashMap<TreePath, NodeId>>) -> Self {
+        let (data, children) = tree.into_parts();
+        let mut arena = Tree::new(data);
+        let mut path = TreePath::root();
+        if let Some(ref mut provenance) = provenance {
+            provenance.insert(path.clone(), arena.root());
+        }
+        let mut stack = vec![(arena.root(), children.into_iter().enumerate())];
+        while let Some(&mut (parent, ref mut children)) = stack.last_mut() {
+            match children.next() {
+                Some((index, child)) => {
+                    let (data, grandchildren) = child.into_parts();
+                    let id = arena.push_child(parent, data);
+                    path.push(index);
+                    if let Some(ref mut provenance) = provenance {
+                        provenance.insert(path.clone(), id);
+                    }
+                    stack.push((id, grandchildren.into_iter().enumerate()));
+                },
+                None => {
+                    stack.pop();
+                    path.pop();
+                },
+            }
+        }
+        arena
+    }
+}
+
+impl<T> From<owned::Tree<T>> for Tree<T> {
+    fn from(tree: owned::Tree<T>) -> Self {
+        Tree::convert_from_owned(tree, None)
+    }
+}
+
+/// Reads a tree written as nested pairs of a node's data and its children, the
+/// form in which `owned::Nested` is serialized. Each node is allocated in the
+/// arena as it is read, without building an owned tree first. Reading recurses
+/// once per level, so very deep trees may overflow the stack.
+#[cfg(feature = "serde")]
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Tree<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut tree = Tree::placeholder();
+        NodeSeed { tree: &mut tree, parent: None, }.deserialize(deserializer)?;
+        Ok(tree)
+    }
+}
+
+// Reads a node into the last child of `parent`, or into the root placeholder if
+// there is no parent.
+#[cfg(feature = "serde")]
+struct NodeSeed<'a, T: 'a> {
+    tree: &'a mut Tree<T>,
+    parent: Option<NodeId>,
+}
+
+#[cfg(feature = "serde")]
+impl<'de, 'a, T: Deserialize<'de>> DeserializeSeed<'de> for NodeSeed<'a, T> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_tuple(2, self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, 'a, T: Deserialize<'de>> Visitor<'de> for NodeSeed<'a, T> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a node's data and children")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let data = seq.next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &"a node's data and children"))?;
+        let id = match self.parent {
+            Some(parent) => self.tree.push_child(parent, data),
+            None => {
+                let root = self.tree.root();
+                self.tree.fill_placeholder(root, data).unwrap();
+                root
+            },
+        };
+        seq.next_element_seed(ChildrenSeed { tree: self.tree, parent: id, })?
+            .ok_or_else(|| de::Error::invalid_length(1, &"a node's data and children"))
+    }
+}
+
+// Reads a sequence of nodes into the children of `parent`.
+#[cfg(feature = "serde")]
+struct ChildrenSeed<'a, T: 'a> {
+    tree: &'a mut Tree<T>,
+    parent: NodeId,
+}
+
+#[cfg(feature = "serde")]
+impl<'de, 'a, T: Deserialize<'de>> DeserializeSeed<'de> for ChildrenSeed<'a, T> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, 'a, T: Deserialize<'de>> Visitor<'de> for ChildrenSeed<'a, T> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a sequence of child nodes")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        let parent = Some(self.parent);
+        while let Some(()) = seq.next_element_seed(NodeSeed { tree: &mut *self.tree, parent, })? {}
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::arena::{ArenaError, Tree};
+    use ::error::{EditError, ParseError};
+    use ::indented::{Indent, ParseIndentedError};
+    use ::owned_tree;
+    use ::path::TreePath;
+
+    #[test]
+    fn parses_indented_text() {
+        let t = Tree::from_indented("a\n  b\n    c\n  d", Indent::Spaces(2)).unwrap();
+        assert_eq![t.children(t.root()).len(), 2];
+        assert_eq![t.into_owned().unwrap(),
+                   owned_tree!["a".to_string(), ["b".to_string(), ["c".to_string()]],
+                               ["d".to_string()]]];
+        match Tree::from_indented("a\nb", Indent::Tabs) {
+            Err(e) => assert_eq![e, ParseError::Indented(ParseIndentedError::MultipleRoots {
+                line: 2,
+            })],
+            Ok(_) => panic!["parsed two roots"],
+        }
+    }
+
+    #[test]
+    fn builds_trees() {
+        let mut t = Tree::new("r");
+        let root = t.root();
+        let a = t.push_child(root, "a");
+        let c = t.push_child(root, "c");
+        let b = t.insert_child(root, 1, "b").unwrap();
+        let d = t.push_child(a, "d");
+        assert_eq![&[a, b, c], t.children(root)];
+        assert_eq![Some(a), t.parent(d)];
+        assert_eq![None, t.parent(root)];
+        assert_eq![Some(&"b"), t.data(b)];
+        *t.data_mut(b).unwrap() = "B";
+        assert_eq![5, t.node_count()];
+        assert_eq![Err(EditError::ChildOutOfRange { index: 4, child_count: 3, }),
+                   t.insert_child(root, 4, "x")];
+        assert_eq![owned_tree!["r", ["a", ["d"]], ["B"], ["c"]], t.into_owned().unwrap()];
+    }
+
+    #[test]
+    fn fills_placeholders() {
+        let mut t = Tree::placeholder();
+        let root = t.root();
+        let a = t.push_placeholder(root);
+        let b = t.push_child(root, "b");
+        let c = t.insert_placeholder(a, 0).unwrap();
+        assert![t.is_placeholder(root)];
+        assert![! t.is_placeholder(b)];
+        assert_eq![None, t.data(a)];
+        assert_eq![vec![root, a, c], t.holes()];
+        assert_eq![Err(ArenaError::Incomplete(vec![root, a, c])), t.validate()];
+        assert_eq![Err(ArenaError::NotPlaceholder(b)), t.fill_placeholder(b, "x")];
+
+        t.fill_placeholder(a, "a").unwrap();
+        t.fill_placeholder(root, "r").unwrap();
+        assert_eq![Err(ArenaError::Incomplete(vec![c])), t.clone().into_owned()];
+        t.fill_placeholder(c, "c").unwrap();
+        assert_eq![Ok(()), t.validate()];
+        assert_eq![owned_tree!["r", ["a", ["c"]], ["b"]], t.into_owned().unwrap()];
+    }
+
+    #[test]
+    fn converts_owned_trees() {
+        let owned = owned_tree![1, [2, [3], [4]], [5, [6, [7]]]];
+        let t = Tree::from(owned.clone());
+        assert_eq![7, t.node_count()];
+        assert_eq![2, t.children(t.root()).len()];
+        assert_eq![owned, t.into_owned().unwrap()];
+    }
+
+    #[test]
+    fn drains_subtrees() {
+        let mut t = Tree::from(owned_tree!["r", ["a", ["b"], ["c", ["d"]]], ["e"]]);
+        let root = t.root();
+        let a = t.children(root)[0];
+        let e = t.children(root)[1];
+        t.push_placeholder(a);
+        assert_eq![vec!["a", "b", "c", "d"], t.drain_subtree(a).collect::<Vec<_>>()];
+        assert_eq![&[e], t.children(root)];
+        assert_eq![2, t.node_count()];
+
+        // Freed slots are reused.
+        let f = t.push_child(e, "f");
+        let g = t.push_child(e, "g");
+        assert_ne![f, g];
+        assert_eq![7, t.nodes.len()];
+        assert_eq![4, t.node_count()];
+        assert_eq![owned_tree!["r", ["e", ["f"], ["g"]]], t.clone().into_owned().unwrap()];
+
+        // Dropping the iterator early still removes the whole subtree.
+        let mut drain = t.drain_subtree(e);
+        assert_eq![Some("e"), drain.next()];
+        drop(drain);
+        assert_eq![1, t.node_count()];
+        assert![t.children(root).is_empty()];
+        t.push_placeholder(root);
+        assert_eq![vec![t.children(root)[0]], t.holes()];
+    }
+
+    #[test]
+    #[should_panic(expected = "has been removed")]
+    fn removed_nodes_are_gone() {
+        let mut t = Tree::new("r");
+        let root = t.root();
+        let a = t.push_child(root, "a");
+        t.drain_subtree(a).count();
+        t.data(a);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot drain the root")]
+    fn root_cannot_be_drained() {
+        let mut t = Tree::new("r");
+        let root = t.root();
+        t.drain_subtree(root).count();
+    }
+
+    #[test]
+    fn conversions_map_nodes_to_paths() {
+        let original = owned_tree!["a", ["b", ["c"]], ["d"]];
+        let (mut t, ids) = Tree::from_owned_with_provenance(original.clone());
+        assert_eq![4, ids.len()];
+        assert_eq![Some(&"c"), t.data(ids[&TreePath::new(vec![0, 0])])];
+        let root = t.root();
+        let e = t.insert_child(root, 0, "e").unwrap();
+        let (owned, paths) = t.into_owned_with_provenance().unwrap();
+        assert_eq![owned_tree!["a", ["e"], ["b", ["c"]], ["d"]], owned];
+        assert_eq![TreePath::new(vec![0]), paths[&e]];
+        for (path, id) in &ids {
+            assert_eq![original.subtree(path).unwrap().data(),
+                       owned.subtree(&paths[id]).unwrap().data()];
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn deserializes_into_the_arena() {
+        use serde_json;
+
+        let t: Tree<String> =
+            serde_json::from_str(r#"["a", [["b", [["c", []]]], ["d", []]]]"#).unwrap();
+        assert_eq![4, t.node_count()];
+        assert_eq![owned_tree!["a".to_string(), ["b".to_string(), ["c".to_string()]],
+                               ["d".to_string()]],
+                   t.into_owned().unwrap()];
+        let original = owned_tree![1, [2], [3, [4], [5]]];
+        let json = serde_json::to_string(&original.clone().into_nested()).unwrap();
+        let t: Tree<i32> = serde_json::from_str(&json).unwrap();
+        assert_eq![original, t.into_owned().unwrap()];
+        assert![serde_json::from_str::<Tree<i32>>("[1]").is_err()];
+        assert![serde_json::from_str::<Tree<i32>>("[1, [[2]]]").is_err()];
+    }
+}
@@ -0,0 +1,969 @@
+use ::{Editor, Nav, RemoveFocusPolicy};
+use ::error::{EditError, NavError};
+use ::owned;
+use ::path::TreePath;
+use ::traversal::EnumerateSiblings;
+use ::index::{ChildIndex, SiblingIndex};
+
+use std::clone::Clone;
+use std::cmp::Ordering;
+use std::mem;
+use std::ops::Deref;
+use std::ptr;
+use std::slice;
+
+struct Node<T, A> {
+    data: T, aggregate: A, children: Vec<Node<T, A>>,
+    // Whether `aggregate` is out of date. Only set during a batch, and then
+    // also set on every ancestor.
+    stale: bool,
+}
+
+impl<T, A> Node<T, A> {
+    fn new<F>(data: T, children: Vec<Node<T, A>>, f: &F) -> Self
+        where F: Fn(&T, &[&A]) -> A {
+            let aggregate = {
+                let child_aggregates: Vec<&A> = children.iter().map(|c| &c.aggregate).collect();
+                f(&data, &child_aggregates)
+            };
+            Node { data, aggregate, children, stale: false, }
+        }
+
+    fn build<F>(tree: owned::Tree<T>, f: &F) -> Self where F: Fn(&T, &[&A]) -> A {
+        let (data, children) = tree.into_nodes();
+        let mut stack = vec![(data, children, Vec::new())];
+        loop {
+            let next = stack.last_mut().unwrap().1.next();
+            match next {
+                Some((data, children)) => stack.push((data, children, Vec::new())),
+                None => {
+                    let (data, _, children) = stack.pop().unwrap();
+                    let node = Node::new(data, children, f);
+                    match stack.last_mut() {
+                        Some(parent) => parent.2.push(node),
+                        None => return node,
+                    }
+                },
+            }
+        }
+    }
+
+    fn into_tree(self) -> owned::Tree<T> {
+        let mut stack = vec![(self.data, self.children.into_iter(), Vec::new())];
+        loop {
+            let next = stack.last_mut().unwrap().1.next();
+            match next {
+                Some(node) => stack.push((node.data, node.children.into_iter(), Vec::new())),
+                None => {
+                    let (data, _, children) = stack.pop().unwrap();
+                    let tree = owned::Tree::new(data, children);
+                    match stack.last_mut() {
+                        Some(parent) => parent.2.push(tree),
+                        None => return tree,
+                    }
+                },
+            }
+        }
+    }
+
+    fn recompute<F>(&mut self, f: &F) where F: Fn(&T, &[&A]) -> A {
+        let child_aggregates: Vec<&A> = self.children.iter().map(|c| &c.aggregate).collect();
+        self.aggregate = f(&self.data, &child_aggregates);
+    }
+
+    // Recomputes the aggregates of the node at `path` and each of its
+    // ancestors.
+    fn refresh<F>(&mut self, path: &[usize], f: &F) where F: Fn(&T, &[&A]) -> A {
+        // The children of each node on the path, taken out of their node, so
+        // that the node below can be recomputed before the one above it gets
+        // its children back.
+        let mut spine = Vec::with_capacity(path.len());
+        let mut children = mem::take(&mut self.children);
+        for &index in path {
+            let grandchildren = mem::take(&mut children[index].children);
+            spine.push(children);
+            children = grandchildren;
+        }
+        for &index in path.iter().rev() {
+            let mut siblings = spine.pop().unwrap();
+            siblings[index].children = children;
+            siblings[index].recompute(f);
+            children = siblings;
+        }
+        self.children = children;
+        self.recompute(f);
+    }
+
+    // Marks the node at `path` and each of its ancestors as stale.
+    fn mark_stale(&mut self, path: &[usize]) {
+        let mut node = self;
+        node.stale = true;
+        for &index in path {
+            node = &mut node.children[index];
+            node.stale = true;
+        }
+    }
+
+    // Recomputes the aggregates of all stale nodes, children first.
+    fn normalize<F>(&mut self, f: &F) where F: Fn(&T, &[&A]) -> A {
+        if !self.stale {
+            return
+        }
+        // The children of the stale nodes being normalized, each taken out of
+        // its node, which is the last stale child visited in the entry below
+        // (or `self`), with the index of the next child to visit. A node gets
+        // its children back and is recomputed once they are all done.
+        let mut stack = vec![(mem::take(&mut self.children), 0)];
+        loop {
+            let next_stale = {
+                let &mut (ref mut children, ref mut next) = stack.last_mut().unwrap();
+                match (*next..children.len()).find(|&i| children[i].stale) {
+                    Some(index) => {
+                        *next = index + 1;
+                        Some(mem::take(&mut children[index].children))
+                    },
+                    None => None,
+                }
+            };
+            if let Some(grandchildren) = next_stale {
+                stack.push((grandchildren, 0));
+                continue
+            }
+            let (children, _) = stack.pop().unwrap();
+            let node = match stack.last_mut() {
+                Some(&mut (ref mut siblings, next)) => &mut siblings[next - 1],
+                None => &mut *self,
+            };
+            node.children = children;
+            node.recompute(f);
+            node.stale = false;
+            if stack.is_empty() {
+                return
+            }
+        }
+    }
+}
+
+/// Tree in which each node caches an aggregate of its subtree.
+///
+/// The aggregate at a node is computed by `F` from the node's data and the
+/// aggregates of its children, so it can describe any property of a subtree
+/// that can be computed bottom-up (such as its size, the sum of its data, or
+/// its maximum). Edits made through a `TreeEditor` recompute aggregates only
+/// along the path from the edited node to the root, or, within
+/// `Editor::batch`, once for every changed node when the batch ends.
+pub struct Augmented<T, A, F> where F: Fn(&T, &[&A]) -> A {
+    root: Node<T, A>, f: F,
+}
+
+impl<T, A, F> Augmented<T, A, F> where F: Fn(&T, &[&A]) -> A {
+    /// Computes the aggregates of every node in `tree` with `f`.
+    pub fn new(tree: owned::Tree<T>, f: F) -> Self {
+        let root = Node::build(tree, &f);
+        Augmented { root, f, }
+    }
+
+    /// Returns the data at the tree root.
+    pub fn data(&self) -> &T {
+        &self.root.data
+    }
+
+    /// Returns the aggregate of the whole tree.
+    pub fn aggregate(&self) -> &A {
+        &self.root.aggregate
+    }
+
+    pub fn view(&self) -> TreeView<'_, T, A> {
+        TreeView { here: &self.root, path: Vec::new(), }
+    }
+
+    pub fn editor(&mut self) -> TreeEditor<'_, T, A, F> {
+        TreeEditor { tree: self, path: Vec::new(),
+                     remove_focus_policy: RemoveFocusPolicy::default(), batching: false, }
+    }
+
+    /// Discards the cached aggregates and returns the underlying tree.
+    pub fn into_tree(self) -> owned::Tree<T> {
+        self.root.into_tree()
+    }
+}
+
+/// Aggregate that records how many leaves a subtree has, as computed by
+/// `leaf_count`.
+///
+/// Besides the subtree's count, this keeps the number of leaves under the
+/// children before each child, so that `select_leaf` can binary search them.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct LeafCount {
+    count: usize,
+    // For each child, the number of leaves under the children before it.
+    before: Vec<usize>,
+}
+
+impl LeafCount {
+    /// Returns the number of leaves in the subtree.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+/// Aggregate function that counts the leaves in a subtree, for use with
+/// `Augmented::select_leaf` and `Augmented::rank`.
+pub fn leaf_count<T>(_: &T, children: &[&LeafCount]) -> LeafCount {
+    if children.is_empty() {
+        return LeafCount { count: 1, before: Vec::new(), }
+    }
+    let mut count = 0;
+    let before = children.iter().map(|c| {
+        let before = count;
+        count += c.count;
+        before
+    }).collect();
+    LeafCount { count, before, }
+}
+
+impl<T, F> Augmented<T, LeafCount, F> where F: Fn(&T, &[&LeafCount]) -> LeafCount {
+    /// Returns the path to the leaf at index `k` in left-to-right order, or
+    /// `None` if the tree has `k` or fewer leaves.
+    ///
+    /// This uses the cached leaf counts to descend directly to the leaf,
+    /// binary searching the children of each node on the way, so it takes
+    /// O(d log c) time for a leaf at depth d whose ancestors have at most c
+    /// children.
+    pub fn select_leaf(&self, mut k: usize) -> Option<TreePath> {
+        if k >= self.root.aggregate.count {
+            return None
+        }
+        let mut path = TreePath::root();
+        let mut node = &self.root;
+        while !node.children.is_empty() {
+            let before = &node.aggregate.before;
+            // The last child with at most `k` leaves before it. Counts that are
+            // not those of `leaf_count` may leave no such child.
+            let index = before.partition_point(|&b| b <= k).checked_sub(1)?;
+            let child = node.children.get(index)?;
+            k -= before[index];
+            path.push(index);
+            node = child;
+        }
+        Some(path)
+    }
+
+    /// Returns the number of leaves that come before the node at `path` in
+    /// left-to-right order, or `None` if there is no node at `path`. This takes
+    /// time proportional to the length of `path`.
+    ///
+    /// For a leaf, this is the inverse of `select_leaf`.
+    pub fn rank(&self, path: &TreePath) -> Option<usize> {
+        let mut rank = 0;
+        let mut node = &self.root;
+        for &index in path.indices() {
+            let child = node.children.get(index)?;
+            rank += node.aggregate.before.get(index)?;
+            node = child;
+        }
+        Some(rank)
+    }
+}
+
+/// Read-only view of an augmented tree.
+pub struct TreeView<'a, T: 'a, A: 'a> {
+    here: &'a Node<T, A>,
+    path: Vec<(&'a Node<T, A>, usize)>,
+}
+
+impl<'a, T: 'a, A: 'a> TreeView<'a, T, A> {
+    fn root(&self) -> &'a Node<T, A> {
+        self.path.first().map_or(self.here, |&(root, _)| root)
+    }
+
+    /// Returns the aggregate of the subtree rooted at the focus.
+    pub fn aggregate(&self) -> &'a A {
+        &self.here.aggregate
+    }
+
+    /// Returns the data at the focus, borrowed for as long as the tree is.
+    pub fn data(&self) -> &'a T {
+        &self.here.data
+    }
+
+    /// Returns the aggregate of the subtree rooted at the child at `index`, if
+    /// there is one.
+    pub fn child_aggregate(&self, index: usize) -> Option<&'a A> {
+        self.here.children.get(index).map(|c| &c.aggregate)
+    }
+
+    /// Iterates over the data of the focus and its siblings, in order, with
+    /// each one's offset from the focus.
+    pub fn enumerate_siblings(&self)
+                              -> EnumerateSiblings<impl ExactSizeIterator<Item=&'a T> + 'a> {
+        let (siblings, here_index) = match self.path.last() {
+            None => (slice::from_ref(self.here), 0),
+            Some(&(parent, here_index)) => (&parent.children[..], here_index),
+        };
+        EnumerateSiblings::new(siblings.iter().map(|c| &c.data), here_index)
+    }
+}
+
+impl<'a, T: 'a, A: 'a> Clone for TreeView<'a, T, A> {
+    fn clone(&self) -> Self {
+        TreeView { here: self.here, path: self.path.clone(), }
+    }
+}
+
+impl<'a, T: 'a, A: 'a> PartialEq for TreeView<'a, T, A> {
+    fn eq(&self, other: &Self) -> bool {
+        ptr::eq(self.root(), other.root())
+            && self.path.iter().map(|&(_, i)| i).eq(other.path.iter().map(|&(_, i)| i))
+    }
+}
+
+impl<'a, T: 'a, A: 'a> PartialOrd for TreeView<'a, T, A> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if ptr::eq(self.root(), other.root()) {
+            Some(self.path.iter().map(|&(_, i)| i).cmp(other.path.iter().map(|&(_, i)| i)))
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, T: 'a, A: 'a> Deref for TreeView<'a, T, A> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.here.data
+    }
+}
+
+impl<'a, T: 'a, A: 'a> Nav for TreeView<'a, T, A> {
+    fn seek_sibling(&mut self, offset: isize) -> bool {
+        if offset == 0 {
+            return true
+        }
+        let (parent, here_index) = match self.path.last() {
+            None => return false,
+            Some(&(parent, here_index)) => (parent, here_index),
+        };
+        match SiblingIndex::compute(parent.children.len(), here_index, offset) {
+            Some(new_index) => {
+                self.path.pop();
+                self.path.push((parent, new_index));
+                self.here = &parent.children[new_index];
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn seek_child(&mut self, index: usize) -> bool {
+        match ChildIndex::compute(self.child_count(), index) {
+            Some(new_index) => {
+                self.path.push((self.here, new_index));
+                self.here = &self.here.children[new_index];
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn child_count(&self) -> usize {
+        self.here.children.len()
+    }
+
+    fn at_root(&self) -> bool {
+        self.path.is_empty()
+    }
+
+    fn index_in_parent(&self) -> Option<usize> {
+        self.path.last().map(|&(_, index)| index)
+    }
+
+    fn to_parent(&mut self) -> bool {
+        match self.path.pop() {
+            Some((parent, _)) => {
+                self.here = parent;
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn to_root(&mut self) {
+        if let Some(&(root, _)) = self.path.first() {
+            self.here = root;
+            self.path.clear();
+        }
+    }
+}
+
+/// Editor for an augmented tree.
+///
+/// Node data can be read through `Deref` but is only modified with `set_data`
+/// or `update_data`, so that aggregates are kept up to date. Subtrees are
+/// inserted and removed as `owned::Tree` values.
+pub struct TreeEditor<'a, T: 'a, A: 'a, F: 'a> where F: Fn(&T, &[&A]) -> A {
+    tree: &'a mut Augmented<T, A, F>,
+    // Child indices leading from the root to the focus.
+    path: Vec<usize>,
+    remove_focus_policy: RemoveFocusPolicy,
+    // Whether aggregate updates are deferred to the end of a batch.
+    batching: bool,
+}
+
+impl<'a, T: 'a, A: 'a, F: 'a> TreeEditor<'a, T, A, F> where F: Fn(&T, &[&A]) -> A {
+    fn node(&self, depth: usize) -> &Node<T, A> {
+        let mut node = &self.tree.root;
+        for &index in &self.path[..depth] {
+            node = &node.children[index];
+        }
+        node
+    }
+
+    fn node_mut(&mut self, depth: usize) -> &mut Node<T, A> {
+        let mut node = &mut self.tree.root;
+        for &index in &self.path[..depth] {
+            node = &mut node.children[index];
+        }
+        node
+    }
+
+    fn here(&self) -> &Node<T, A> {
+        self.node(self.path.len())
+    }
+
+    fn here_mut(&mut self) -> &mut Node<T, A> {
+        let depth = self.path.len();
+        self.node_mut(depth)
+    }
+
+    fn parent_mut(&mut self) -> Option<&mut Node<T, A>> {
+        match self.path.len() {
+            0 => None,
+            depth => Some(self.node_mut(depth - 1)),
+        }
+    }
+
+    fn refresh(&mut self) {
+        let tree = &mut *self.tree;
+        if self.batching {
+            tree.root.mark_stale(&self.path);
+        } else {
+            tree.root.refresh(&self.path, &tree.f);
+        }
+    }
+
+    fn build(&self, tree: owned::Tree<T>) -> Node<T, A> {
+        Node::build(tree, &self.tree.f)
+    }
+
+    /// Returns the aggregate of the subtree rooted at the focus.
+    pub fn aggregate(&self) -> &A {
+        &self.here().aggregate
+    }
+
+    /// Returns the aggregate of the subtree rooted at the child at `index`, if
+    /// there is one.
+    pub fn child_aggregate(&self, index: usize) -> Option<&A> {
+        self.here().children.get(index).map(|c| &c.aggregate)
+    }
+
+    /// Iterates over the data of the focus and its siblings, in order, with
+    /// each one's offset from the focus.
+    pub fn enumerate_siblings(&self)
+                              -> EnumerateSiblings<impl ExactSizeIterator<Item=&T> + '_> {
+        let (siblings, here_index) = match self.path.last() {
+            None => (slice::from_ref(self.here()), 0),
+            Some(&here_index) =>
+                (&self.node(self.path.len() - 1).children[..], here_index),
+        };
+        EnumerateSiblings::new(siblings.iter().map(|c| &c.data), here_index)
+    }
+
+    /// Replaces the data at the focus, returning the old data.
+    pub fn set_data(&mut self, data: T) -> T {
+        let old = mem::replace(&mut self.here_mut().data, data);
+        self.refresh();
+        old
+    }
+
+    /// Modifies the data at the focus in place.
+    pub fn update_data<G>(&mut self, g: G) where G: FnOnce(&mut T) {
+        g(&mut self.here_mut().data);
+        self.refresh();
+    }
+}
+
+impl<'a, T: 'a, A: 'a, F: 'a> Deref for TreeEditor<'a, T, A, F> where F: Fn(&T, &[&A]) -> A {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.here().data
+    }
+}
+
+impl<'a, T: 'a, A: 'a, F: 'a> Nav for TreeEditor<'a, T, A, F> where F: Fn(&T, &[&A]) -> A {
+    fn seek_sibling(&mut self, offset: isize) -> bool {
+        if offset == 0 {
+            return true
+        }
+        let here_index = match self.path.last() {
+            None => return false,
+            Some(&here_index) => here_index,
+        };
+        let sibling_count = self.node(self.path.len() - 1).children.len();
+        match SiblingIndex::compute(sibling_count, here_index, offset) {
+            Some(new_index) => {
+                *self.path.last_mut().unwrap() = new_index;
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn seek_child(&mut self, index: usize) -> bool {
+        match ChildIndex::compute(self.child_count(), index) {
+            Some(new_index) => {
+                self.path.push(new_index);
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn child_count(&self) -> usize {
+        self.here().children.len()
+    }
+
+    fn at_root(&self) -> bool {
+        self.path.is_empty()
+    }
+
+    fn index_in_parent(&self) -> Option<usize> {
+        self.path.last().cloned()
+    }
+
+    fn to_parent(&mut self) -> bool {
+        self.path.pop().is_some()
+    }
+
+    fn to_root(&mut self) {
+        self.path.clear();
+    }
+}
+
+impl<'a, T: 'a, A: 'a, F: 'a> Editor for TreeEditor<'a, T, A, F> where F: Fn(&T, &[&A]) -> A {
+    type Data = T;
+    type Tree = owned::Tree<T>;
+
+    fn push_leaf(&mut self, data: T) {
+        self.push_child(owned::Tree::leaf(data));
+    }
+
+    fn push_child(&mut self, child: owned::Tree<T>) {
+        let node = self.build(child);
+        let index = {
+            let here = self.here_mut();
+            here.children.push(node);
+            here.children.len() - 1
+        };
+        self.path.push(index);
+        self.refresh();
+    }
+
+    fn insert_leaf(&mut self, index: usize, data: T) -> bool {
+        self.insert_child(index, owned::Tree::leaf(data))
+    }
+
+    fn insert_child(&mut self, index: usize, child: owned::Tree<T>) -> bool {
+        match ChildIndex::compute(self.child_count(), index) {
+            Some(new_index) => {
+                let node = self.build(child);
+                self.here_mut().children.insert(new_index, node);
+                self.path.push(new_index);
+                self.refresh();
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn insert_sibling_leaf(&mut self, offset: isize, data: T) -> bool {
+        self.insert_sibling(offset, owned::Tree::leaf(data))
+    }
+
+    fn insert_sibling(&mut self, offset: isize, sibling: owned::Tree<T>) -> bool {
+        let here_index = match self.path.last() {
+            None => return false,
+            Some(&here_index) => here_index,
+        };
+        let sibling_count = self.node(self.path.len() - 1).children.len();
+        match SiblingIndex::compute(sibling_count, here_index, offset) {
+            Some(new_index) => {
+                let node = self.build(sibling);
+                self.parent_mut().unwrap().children.insert(new_index, node);
+                *self.path.last_mut().unwrap() = new_index;
+                self.refresh();
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn remove_focus_policy(&self) -> RemoveFocusPolicy {
+        self.remove_focus_policy
+    }
+
+    fn set_remove_focus_policy(&mut self, policy: RemoveFocusPolicy) -> bool {
+        self.remove_focus_policy = policy;
+        true
+    }
+
+    fn remove(&mut self) -> owned::Tree<T> {
+        let here_index = *self.path.last().unwrap_or_else(|| panic!["{}", NavError::AtRoot]);
+        let (removed, remaining) = {
+            let parent = self.parent_mut().unwrap();
+            let removed = parent.children.remove(here_index);
+            (removed, parent.children.len())
+        };
+        match self.remove_focus_policy.focus_after_remove(here_index, remaining) {
+            Some(new_index) => *self.path.last_mut().unwrap() = new_index,
+            None => { self.path.pop(); },
+        }
+        self.refresh();
+        removed.into_tree()
+    }
+
+    fn remove_child(&mut self, index: usize) -> Result<owned::Tree<T>, EditError> {
+        let index = ChildIndex::check(self.child_count(), index)?;
+        let removed = self.here_mut().children.remove(index);
+        self.refresh();
+        Ok(removed.into_tree())
+    }
+
+    fn remove_sibling(&mut self, offset: isize) -> Option<owned::Tree<T>> {
+        if self.at_root() {
+            return None
+        }
+        if offset == 0 {
+            return Some(self.remove())
+        }
+        let here_index = *self.path.last()?;
+        let sibling_count = self.node(self.path.len() - 1).children.len();
+        SiblingIndex::compute(sibling_count, here_index, offset).map(|index| {
+            let removed = self.parent_mut().unwrap().children.remove(index);
+            if index < here_index {
+                *self.path.last_mut().unwrap() = here_index - 1;
+            }
+            self.refresh();
+            removed.into_tree()
+        })
+    }
+
+    fn swap(&mut self, other: &mut owned::Tree<T>) {
+        let other_children: Vec<Node<T, A>> =
+            mem::take(other.children_vec_mut()).into_iter()
+            .map(|child| self.build(child))
+            .collect();
+        let old_children = {
+            let here = self.here_mut();
+            mem::swap(&mut here.data, other.data_mut());
+            mem::replace(&mut here.children, other_children)
+        };
+        *other.children_vec_mut() = old_children.into_iter().map(Node::into_tree).collect();
+        self.refresh();
+    }
+
+    fn swap_children(&mut self, index_a: usize, index_b: usize) -> bool {
+        let child_count = self.child_count();
+        match (ChildIndex::compute(child_count, index_a),
+               ChildIndex::compute(child_count, index_b)) {
+            (Some(index_a), Some(index_b)) => {
+                self.here_mut().children.swap(index_a, index_b);
+                self.refresh();
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn swap_siblings(&mut self, offset_a: isize, offset_b: isize) -> bool {
+        let here_index = match self.path.last() {
+            None => return false,
+            Some(&here_index) => here_index,
+        };
+        let sibling_count = self.node(self.path.len() - 1).children.len();
+        match (SiblingIndex::compute(sibling_count, here_index, offset_a),
+               SiblingIndex::compute(sibling_count, here_index, offset_b)) {
+            (Some(index_a), Some(index_b)) => {
+                self.parent_mut().unwrap().children.swap(index_a, index_b);
+                if here_index == index_a {
+                    *self.path.last_mut().unwrap() = index_b;
+                } else if here_index == index_b {
+                    *self.path.last_mut().unwrap() = index_a;
+                }
+                self.refresh();
+                true
+            },
+            _ => false,
+        }
+    }
+
+    /// Runs `g` with aggregate updates deferred, then recomputes the aggregate
+    /// of each node changed in the batch once. Aggregates read within the
+    /// batch may be out of date. Nested batches are merged into the outermost.
+    fn batch<R, G>(&mut self, g: G) -> R where G: FnOnce(&mut Self) -> R {
+        if self.batching {
+            return g(self)
+        }
+        self.batching = true;
+        let result = g(self);
+        self.batching = false;
+        let tree = &mut *self.tree;
+        tree.root.normalize(&tree.f);
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::{Editor, Nav, RemoveFocusPolicy};
+    use ::augmented::{Augmented, leaf_count};
+    use ::owned_tree;
+    use ::path::TreePath;
+
+    fn sum(data: &i32, children: &[&i32]) -> i32 {
+        *data + children.iter().map(|c| **c).sum::<i32>()
+    }
+
+    fn size<T>(_: &T, children: &[&usize]) -> usize {
+        1 + children.iter().map(|c| **c).sum::<usize>()
+    }
+
+    #[test]
+    fn computes_initial_aggregates() {
+        let t = Augmented::new(owned_tree![1, [2, [3], [4]], [5]], sum);
+        assert_eq![*t.aggregate(), 15];
+        let mut v = t.view();
+        assert![v.seek_child(0)];
+        assert_eq![*v.aggregate(), 9];
+        assert_eq![v.child_aggregate(1), Some(&4)];
+        assert_eq![v.child_aggregate(2), None];
+    }
+
+    #[test]
+    fn set_data_updates_ancestors() {
+        let mut t = Augmented::new(owned_tree![1, [2, [3], [4]], [5]], sum);
+        {
+            let mut e = t.editor();
+            assert![e.seek_child(0)];
+            assert![e.seek_child(1)];
+            assert_eq![e.set_data(40), 4];
+            assert_eq![*e.aggregate(), 40];
+            e.to_parent();
+            assert_eq![*e.aggregate(), 45];
+            e.update_data(|d| *d *= 10);
+            assert_eq![*e.aggregate(), 63];
+        }
+        assert_eq![*t.aggregate(), 69];
+    }
+
+    #[test]
+    fn structural_edits_update_aggregates() {
+        let mut t = Augmented::new(owned_tree!["a", ["b"], ["c", ["d"]]], size);
+        {
+            let mut e = t.editor();
+            assert![e.seek_child(1)];
+            e.push_child(owned_tree!["e", ["f"], ["g"]]);
+            assert_eq![*e.aggregate(), 3];
+            e.to_root();
+            assert_eq![*e.aggregate(), 7];
+            let removed = e.remove_child(1).unwrap();
+            assert_eq![removed, owned_tree!["c", ["d"], ["e", ["f"], ["g"]]]];
+            assert_eq![*e.aggregate(), 2];
+            assert![! e.insert_leaf(1, "h")];
+            assert![e.insert_leaf(0, "h")];
+            assert_eq![*e, "h"];
+            assert![e.insert_sibling_leaf(0, "i")];
+            e.to_root();
+            assert_eq![*e.aggregate(), 4];
+        }
+        assert_eq![t.into_tree(), owned_tree!["a", ["i"], ["h"], ["b"]]];
+    }
+
+    #[test]
+    fn remove_moves_focus_and_updates_aggregates() {
+        let mut t = Augmented::new(owned_tree![1, [2], [3, [4]], [5]], sum);
+        let mut e = t.editor();
+        assert![e.seek_child(2)];
+        assert_eq![e.remove(), owned_tree![5]];
+        assert_eq![*e, 3];
+        assert_eq![e.remove_sibling(-1), Some(owned_tree![2])];
+        assert_eq![*e, 3];
+        assert![e.seek_child(0)];
+        assert_eq![e.remove(), owned_tree![4]];
+        assert_eq![*e, 3];
+        e.to_root();
+        assert_eq![e.remove_sibling(0), None];
+        assert_eq![*e.aggregate(), 4];
+    }
+
+    #[test]
+    fn views_compare_positions() {
+        let t = Augmented::new(owned_tree![1, [2, [3]], [4]], sum);
+        let mut a = t.view();
+        assert![a.seek_child(0)];
+        let mut b = t.view();
+        assert![b.seek_child(1)];
+        assert![a < b];
+        assert![b.seek_sibling(-1)];
+        assert![a == b];
+        assert![a.seek_child(0)];
+        assert![b < a];
+        let other = Augmented::new(owned_tree![1, [2, [3]], [4]], sum);
+        assert_eq![None, other.view().partial_cmp(&t.view())];
+    }
+
+    #[test]
+    fn enumerate_siblings() {
+        let mut t = Augmented::new(owned_tree![1, [2], [3], [4]], sum);
+        {
+            let mut v = t.view();
+            assert![v.seek_child(1)];
+            assert_eq![v.enumerate_siblings().collect::<Vec<_>>(), vec![(-1, &2), (0, &3), (1, &4)]];
+        }
+        let mut e = t.editor();
+        assert_eq![e.enumerate_siblings().collect::<Vec<_>>(), vec![(0, &1)]];
+        assert![e.seek_child(0)];
+        assert_eq![e.enumerate_siblings().len(), 3];
+    }
+
+    #[test]
+    fn remove_follows_focus_policy() {
+        let mut t = Augmented::new(owned_tree![1, [2], [3], [4, [5]]], sum);
+        let mut e = t.editor();
+        assert![e.set_remove_focus_policy(RemoveFocusPolicy::PreferRight)];
+        assert![e.seek_child(1)];
+        assert_eq![e.remove(), owned_tree![3]];
+        assert_eq![*e, 4];
+        assert_eq![*e.aggregate(), 9];
+        assert![e.set_remove_focus_policy(RemoveFocusPolicy::Parent)];
+        assert![e.seek_child(0)];
+        assert_eq![e.remove(), owned_tree![5]];
+        assert_eq![*e, 4];
+        assert_eq![*e.aggregate(), 4];
+        e.to_root();
+        assert_eq![*e.aggregate(), 7];
+    }
+
+    #[test]
+    fn swap_updates_aggregates() {
+        let mut t = Augmented::new(owned_tree![1, [2, [3]], [4]], sum);
+        {
+            let mut e = t.editor();
+            assert![e.seek_child(0)];
+            let mut other = owned_tree![10, [20], [30]];
+            e.swap(&mut other);
+            assert_eq![other, owned_tree![2, [3]]];
+            assert_eq![*e.aggregate(), 60];
+            assert![e.swap_siblings(0, 1)];
+            assert_eq![*e, 10];
+            e.to_parent();
+            assert![e.swap_children(0, 1)];
+        }
+        assert_eq![*t.aggregate(), 65];
+        assert_eq![t.into_tree(), owned_tree![1, [10, [20], [30]], [4]]];
+    }
+
+    #[test]
+    fn batch_defers_aggregates() {
+        let calls = ::std::cell::Cell::new(0);
+        let count = |data: &i32, children: &[&i32]| {
+            calls.set(calls.get() + 1);
+            sum(data, children)
+        };
+        let mut t = Augmented::new(owned_tree![1, [2, [3]], [4]], count);
+        calls.set(0);
+        {
+            let mut e = t.editor();
+            e.batch(|e| {
+                assert![e.seek_child(0)];
+                for i in 0..10 {
+                    e.push_leaf(i);
+                    e.to_parent();
+                }
+                e.batch(|e| e.set_data(20));
+                assert_eq![*e.aggregate(), 5];
+                e.to_root();
+                assert_eq![e.remove_child(1), Ok(owned_tree![4])];
+            });
+            assert_eq![*e.aggregate(), 69];
+        }
+        // Once for each new leaf as it is built, then once for each changed
+        // node: the new leaves, their parent, and the root.
+        assert_eq![calls.get(), 22];
+        assert_eq![*t.aggregate(), 69];
+        assert_eq![t.view().child_aggregate(0), Some(&68)];
+    }
+
+    #[test]
+    fn batch_normalizes_separate_branches() {
+        let mut t = Augmented::new(owned_tree![1, [2, [3, [4]]], [5], [6, [7]]], sum);
+        {
+            let mut e = t.editor();
+            e.batch(|e| {
+                assert![e.seek_child(0)];
+                assert![e.seek_child(0)];
+                assert![e.seek_child(0)];
+                e.set_data(40);
+                e.to_root();
+                assert![e.seek_child(2)];
+                assert![e.seek_child(0)];
+                e.set_data(70);
+            });
+        }
+        assert_eq![*t.aggregate(), 127];
+        assert_eq![t.view().child_aggregate(0), Some(&45)];
+        assert_eq![t.view().child_aggregate(1), Some(&5)];
+        assert_eq![t.view().child_aggregate(2), Some(&76)];
+    }
+
+    #[test]
+    fn select_leaf_and_rank() {
+        let t = Augmented::new(owned_tree!["r", ["a", ["x"], ["y"]], ["b"], ["c", ["d", ["z"]]]],
+                               leaf_count);
+        assert_eq![t.aggregate().count(), 4];
+        let expected = [vec![0, 0], vec![0, 1], vec![1], vec![2, 0, 0]];
+        for (k, indices) in expected.iter().enumerate() {
+            let path = t.select_leaf(k).unwrap();
+            assert_eq![path, TreePath::from(indices.clone())];
+            assert_eq![t.rank(&path), Some(k)];
+        }
+        assert_eq![t.select_leaf(4), None];
+        assert_eq![t.rank(&TreePath::root()), Some(0)];
+        assert_eq![t.rank(&TreePath::from(vec![2])), Some(3)];
+        assert_eq![t.rank(&TreePath::from(vec![3])), None];
+        assert_eq![t.rank(&TreePath::from(vec![1, 0])), None];
+
+        let wide = Augmented::new(::owned::Tree::new(0, (1..101).map(::owned::Tree::leaf).collect()),
+                                  leaf_count);
+        assert_eq![wide.select_leaf(57), Some(TreePath::from(vec![57]))];
+        assert_eq![wide.rank(&TreePath::from(vec![99])), Some(99)];
+    }
+
+    #[test]
+    fn select_leaf_after_edits() {
+        let mut t = Augmented::new(owned_tree!["r", ["a"], ["b"]], leaf_count);
+        {
+            let mut e = t.editor();
+            assert![e.seek_child(0)];
+            e.push_leaf("c");
+            e.to_parent();
+            e.push_leaf("d");
+        }
+        assert_eq![t.aggregate().count(), 3];
+        assert_eq![t.select_leaf(0), Some(TreePath::from(vec![0, 0]))];
+        assert_eq![t.select_leaf(1), Some(TreePath::from(vec![0, 1]))];
+        assert_eq![t.select_leaf(2), Some(TreePath::from(vec![1]))];
+    }
+}
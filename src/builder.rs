@@ -0,0 +1,241 @@
+use ::Nav;
+use ::owned::Tree;
+
+use std::ops::Deref;
+
+/// Builds a tree in code, for trees whose shape is computed (with loops or
+/// conditions) rather than written out literally with a macro like
+/// `owned_tree!`.
+///
+/// The root's children are added with chained calls. Each call to `child`
+/// takes a closure that adds that child's own children through `Children`.
+/// `build` produces any tree type that can be converted from an `owned::Tree`.
+///
+/// ```
+/// #[macro_use(owned_tree)]
+/// extern crate entmut;
+///
+/// use entmut::builder::TreeBuilder;
+/// use entmut::owned::Tree;
+///
+/// # fn main() {
+/// let t: Tree<u32> = TreeBuilder::root(1)
+///     .child(2, |b| {
+///         for i in 3..5 {
+///             b.leaf(i);
+///         }
+///     })
+///     .leaf(5)
+///     .build();
+/// assert_eq![t, owned_tree![1, [2, [3], [4]], [5]]];
+/// # }
+/// ```
+pub struct TreeBuilder<T> {
+    data: T,
+    children: Children<T>,
+}
+
+impl<T> TreeBuilder<T> {
+    /// Starts building a tree whose root has the given data.
+    pub fn root(data: T) -> Self {
+        TreeBuilder { data, children: Children { trees: Vec::new(), }, }
+    }
+
+    /// Adds a leaf child to the root.
+    pub fn leaf(mut self, data: T) -> Self {
+        self.children.leaf(data);
+        self
+    }
+
+    /// Adds a child to the root, calling `f` to add the child's children.
+    pub fn child<F>(mut self, data: T, f: F) -> Self where F: FnOnce(&mut Children<T>) {
+        self.children.child(data, f);
+        self
+    }
+
+    /// Adds `tree` as a child of the root.
+    pub fn subtree(mut self, tree: Tree<T>) -> Self {
+        self.children.subtree(tree);
+        self
+    }
+
+    /// Finishes building the tree.
+    pub fn build<U>(self) -> U where U: From<Tree<T>> {
+        U::from(Tree::new(self.data, self.children.trees))
+    }
+}
+
+/// The children of a node under construction by a `TreeBuilder`.
+pub struct Children<T> {
+    trees: Vec<Tree<T>>,
+}
+
+impl<T> Children<T> {
+    /// Adds a leaf child.
+    pub fn leaf(&mut self, data: T) -> &mut Self {
+        self.trees.push(Tree::leaf(data));
+        self
+    }
+
+    /// Adds a child, calling `f` to add the child's children.
+    pub fn child<F>(&mut self, data: T, f: F) -> &mut Self where F: FnOnce(&mut Children<T>) {
+        let mut children = Children { trees: Vec::new(), };
+        f(&mut children);
+        self.trees.push(Tree::new(data, children.trees));
+        self
+    }
+
+    /// Adds `tree` as a child.
+    pub fn subtree(&mut self, tree: Tree<T>) -> &mut Self {
+        self.trees.push(tree);
+        self
+    }
+
+    /// Returns the number of children added so far.
+    pub fn len(&self) -> usize {
+        self.trees.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.trees.is_empty()
+    }
+}
+
+/// Copies the subtree rooted at the focus of `nav` into a new tree of any type
+/// that can be converted from an `owned::Tree`, such as another backend.
+///
+/// `nav` itself is not moved; the copy is made through a clone of it.
+pub fn copy_into<N, U>(nav: &N) -> U
+    where N: Nav + Clone + Deref, N::Target: Clone + Sized, U: From<Tree<N::Target>> {
+        U::from(copy_to_depth(nav, usize::MAX))
+    }
+
+/// Copies the focus of `nav` and its ancestors and descendants into a new tree,
+/// as a snapshot for display that borrows nothing from the original tree.
+///
+/// The copy holds each ancestor of the focus, from the root down, each one with
+/// only the next of them (or the focus) as its child, and then the subtree
+/// rooted at the focus down to `depth_limit` levels below it. So the focus of
+/// the copy is reached from its root by seeking child 0 once per ancestor.
+pub fn window<N>(nav: &N, depth_limit: usize) -> Tree<N::Target>
+    where N: Nav + Clone + Deref, N::Target: Clone + Sized {
+        let mut tree = copy_to_depth(nav, depth_limit);
+        let mut nav = nav.clone();
+        while nav.to_parent() {
+            tree = Tree::new((*nav).clone(), vec![tree]);
+        }
+        tree
+    }
+
+// Copies the subtree rooted at the focus of `nav`, down to `max_depth` levels
+// below the focus.
+fn copy_to_depth<N>(nav: &N, max_depth: usize) -> Tree<N::Target>
+    where N: Nav + Clone + Deref, N::Target: Clone + Sized {
+        let mut nav = nav.clone();
+        // Nodes being copied, with the index of the next child to copy and the
+        // children copied so far.
+        let mut stack = vec![((*nav).clone(), 0, Vec::new())];
+        loop {
+            let next = {
+                let top = stack.last_mut().unwrap();
+                let next = top.1;
+                top.1 += 1;
+                next
+            };
+            if stack.len() <= max_depth && next < nav.child_count() {
+                nav.seek_child(next);
+                stack.push(((*nav).clone(), 0, Vec::new()));
+            } else {
+                let (data, _, children) = stack.pop().unwrap();
+                let tree = Tree::new(data, children);
+                match stack.last_mut() {
+                    Some(parent) => {
+                        nav.to_parent();
+                        parent.2.push(tree);
+                    },
+                    None => return tree,
+                }
+            }
+        }
+    }
+
+#[cfg(test)]
+mod test {
+    use ::Nav;
+    use ::builder::{TreeBuilder, copy_into, window};
+    use ::{fixed, linked, owned, shared, sync};
+    use ::{linked_tree, owned_tree, shared_tree, sync_tree};
+
+    fn build<U: From<owned::Tree<u32>>>(depth: u32) -> U {
+        TreeBuilder::root(0)
+            .child(1, |b| {
+                for i in 0..depth {
+                    if i % 2 == 0 {
+                        b.leaf(10 + i);
+                    } else {
+                        b.child(10 + i, |b| { b.leaf(20 + i).leaf(30 + i); });
+                    }
+                }
+            })
+            .leaf(2)
+            .build()
+    }
+
+    #[test]
+    fn builds_each_backend() {
+        assert_eq![build::<owned::Tree<u32>>(3),
+                   owned_tree![0, [1, [10], [11, [21], [31]], [12]], [2]]];
+        assert_eq![build::<shared::Tree<u32>>(3),
+                   shared_tree![0, [1, [10], [11, [21], [31]], [12]], [2]]];
+        assert_eq![build::<sync::Tree<u32>>(2),
+                   sync_tree![0, [1, [10], [11, [21], [31]]], [2]]];
+        assert_eq![format!["{:?}", build::<fixed::Tree<u32>>(1)], "(0 (1 (10)) (2))"];
+    }
+
+    #[test]
+    fn builds_leaf_and_subtrees() {
+        let t: owned::Tree<u32> = TreeBuilder::root(7).build();
+        assert_eq![t, owned_tree![7]];
+        let t: owned::Tree<u32> = TreeBuilder::root(1)
+            .subtree(owned_tree![2, [3]])
+            .child(4, |b| {
+                assert![b.is_empty()];
+                b.subtree(owned_tree![5]);
+                assert_eq![b.len(), 1];
+            })
+            .build();
+        assert_eq![t, owned_tree![1, [2, [3]], [4, [5]]]];
+    }
+
+    #[test]
+    fn copies_subtrees_between_backends() {
+        let t = owned_tree![1, [2, [3], [4, [5]]], [6]];
+        let mut v = t.view();
+        assert![v.seek_child(0)];
+        let copy: fixed::Tree<u32> = copy_into(&v);
+        assert_eq![format!["{:?}", copy], "(2 (3) (4 (5)))"];
+        assert_eq![2, *v];
+
+        let s: shared::Tree<u32> = copy_into(&t.view());
+        assert_eq![s, shared_tree![1, [2, [3], [4, [5]]], [6]]];
+        let l: linked::Tree<u32> = copy_into(&t.clamped_view(1));
+        assert_eq![l, linked_tree![1, [2], [6]]];
+        let mut v = s.view();
+        assert![v.seek_child(1)];
+        assert_eq![owned_tree![6], copy_into::<_, owned::Tree<u32>>(&v)];
+    }
+
+    #[test]
+    fn windows_around_focus() {
+        let t = shared_tree![1, [2, [3, [4, [5]]], [6]], [7]];
+        let mut v = t.view();
+        assert![v.seek_child(0)];
+        assert![v.seek_child(0)];
+        assert_eq![owned_tree![1, [2, [3, [4]]]], window(&v, 1)];
+        assert_eq![owned_tree![1, [2, [3]]], v.window(0)];
+        assert_eq![owned_tree![1, [2, [3, [4, [5]]]]], v.window(usize::MAX)];
+        assert_eq![3, *v];
+        v.to_root();
+        assert_eq![owned_tree![1, [2], [7]], v.window(1)];
+    }
+}
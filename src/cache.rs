@@ -0,0 +1,223 @@
+use ::Nav;
+use ::path::TreePath;
+
+use std::collections::HashMap;
+use std::ops::Deref;
+
+/// Wrapper around a `Nav` that remembers the child count and data of each node
+/// it visits, so that asking again costs a lookup instead of a query of the
+/// wrapped view.
+///
+/// This is meant for views whose nodes are costly to read, such as adapters
+/// over a filesystem or a database, when the same nodes are read over and over,
+/// as a user interface redrawing its tree does. Moves are still forwarded to
+/// the wrapped view, but `child_count`, `at_leaf`, and dereferences of the
+/// focus are answered from the cache once the focus has been visited, and
+/// seeks to children that the cached count rules out fail without reaching the
+/// wrapped view.
+///
+/// The cache is keyed by path, so if the wrapped tree changes, the affected
+/// paths must be passed to `invalidate` before they are read again. A change
+/// that removes the focus's path from the tree also requires moving the focus
+/// to a path that remains, such as the root.
+pub struct CachedNav<N: Deref> where N::Target: Sized {
+    nav: N,
+    path: TreePath,
+    entries: HashMap<TreePath, Entry<N::Target>>,
+}
+
+struct Entry<T> {
+    child_count: usize,
+    data: T,
+}
+
+impl<N: Nav + Deref> CachedNav<N> where N::Target: Clone {
+    /// Wraps `nav`, moving its focus to the root so that paths can be tracked
+    /// from there.
+    pub fn new(mut nav: N) -> Self {
+        nav.to_root();
+        let mut cached = CachedNav { nav, path: TreePath::root(), entries: HashMap::new(), };
+        cached.visit();
+        cached
+    }
+
+    /// Returns the path of the focus.
+    pub fn path(&self) -> &TreePath {
+        &self.path
+    }
+
+    /// Returns true iff the node at `path` has been visited since it was last
+    /// invalidated.
+    pub fn is_cached(&self, path: &TreePath) -> bool {
+        self.entries.contains_key(path)
+    }
+
+    /// Forgets what was cached about the node at `path` and all of its
+    /// descendants, so that they are read from the wrapped view when next
+    /// visited. The focus is read again at once if it is among them.
+    pub fn invalidate(&mut self, path: &TreePath) {
+        self.entries.retain(|cached, _| !path.contains(cached));
+        self.visit();
+    }
+
+    /// Forgets everything that was cached, and reads the focus again.
+    pub fn invalidate_all(&mut self) {
+        self.entries.clear();
+        self.visit();
+    }
+
+    /// Returns the wrapped view.
+    pub fn into_inner(self) -> N {
+        self.nav
+    }
+
+    // Caches the focus if it is not already cached.
+    fn visit(&mut self) {
+        if !self.entries.contains_key(&self.path) {
+            let entry = Entry { child_count: self.nav.child_count(), data: (*self.nav).clone(), };
+            self.entries.insert(self.path.clone(), entry);
+        }
+    }
+
+    fn here(&self) -> &Entry<N::Target> {
+        &self.entries[&self.path]
+    }
+}
+
+impl<N: Nav + Deref> Deref for CachedNav<N> where N::Target: Clone {
+    type Target = N::Target;
+
+    fn deref(&self) -> &N::Target {
+        &self.here().data
+    }
+}
+
+impl<N: Nav + Deref> Nav for CachedNav<N> where N::Target: Clone {
+    fn child_count(&self) -> usize {
+        self.here().child_count
+    }
+
+    fn at_root(&self) -> bool {
+        self.path.is_root()
+    }
+
+    fn index_in_parent(&self) -> Option<usize> {
+        self.path.indices().last().cloned()
+    }
+
+    fn seek_sibling(&mut self, offset: isize) -> bool {
+        if !self.nav.seek_sibling(offset) {
+            return false
+        }
+        let index = self.path.pop().unwrap();
+        self.path.push((index as isize + offset) as usize);
+        self.visit();
+        true
+    }
+
+    fn seek_child(&mut self, index: usize) -> bool {
+        if index >= self.child_count() || !self.nav.seek_child(index) {
+            return false
+        }
+        self.path.push(index);
+        self.visit();
+        true
+    }
+
+    fn to_parent(&mut self) -> bool {
+        if !self.nav.to_parent() {
+            return false
+        }
+        self.path.pop();
+        self.visit();
+        true
+    }
+
+    fn to_root(&mut self) {
+        self.nav.to_root();
+        self.path = TreePath::root();
+        self.visit();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::Nav;
+    use ::cache::CachedNav;
+    use ::owned::TreeView;
+    use ::path::TreePath;
+    use ::owned_tree;
+
+    use std::cell::Cell;
+    use std::ops::Deref;
+
+    // Counts the reads of child counts and data made through it.
+    #[derive(Clone)]
+    struct Counting<'a> {
+        view: TreeView<'a, &'static str>,
+        reads: &'a Cell<usize>,
+    }
+
+    impl<'a> Deref for Counting<'a> {
+        type Target = &'static str;
+
+        fn deref(&self) -> &&'static str {
+            self.reads.set(self.reads.get() + 1);
+            &self.view
+        }
+    }
+
+    impl<'a> Nav for Counting<'a> {
+        fn child_count(&self) -> usize {
+            self.reads.set(self.reads.get() + 1);
+            self.view.child_count()
+        }
+
+        fn at_root(&self) -> bool {
+            self.view.at_root()
+        }
+
+        fn index_in_parent(&self) -> Option<usize> {
+            self.view.index_in_parent()
+        }
+
+        fn seek_sibling(&mut self, offset: isize) -> bool {
+            self.view.seek_sibling(offset)
+        }
+
+        fn seek_child(&mut self, index: usize) -> bool {
+            self.view.seek_child(index)
+        }
+
+        fn to_parent(&mut self) -> bool {
+            self.view.to_parent()
+        }
+    }
+
+    #[test]
+    fn reads_each_node_once() {
+        let t = owned_tree!["a", ["b", ["c"]], ["d"]];
+        let reads = Cell::new(0);
+        let mut n = CachedNav::new(Counting { view: t.view(), reads: &reads, });
+        for _ in 0..3 {
+            assert_eq![2, n.child_count()];
+            assert![n.seek_child(0)];
+            assert_eq!["b", *n];
+            assert![n.seek_child(0)];
+            assert![n.at_leaf()];
+            assert![! n.seek_child(0)];
+            assert![n.to_parent()];
+            assert![n.seek_sibling(1)];
+            assert_eq!["d", *n];
+            assert_eq![&TreePath::new(vec![1]), n.path()];
+            n.to_root();
+            assert_eq!["a", *n];
+        }
+        assert_eq![8, reads.get()];
+        n.invalidate(&TreePath::new(vec![0]));
+        assert![n.is_cached(&TreePath::root())];
+        assert![! n.is_cached(&TreePath::new(vec![0, 0]))];
+        assert![n.seek_child(0)];
+        assert_eq![10, reads.get()];
+    }
+}
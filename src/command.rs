@@ -0,0 +1,471 @@
+use ::Editor;
+use ::path::{ParsePathError, TreePath};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use std::error::Error;
+use std::fmt;
+use std::mem;
+use std::num::ParseIntError;
+use std::ops::DerefMut;
+use std::str::FromStr;
+
+/// A tree edit, described by value so that it can be queued, logged, or sent
+/// elsewhere and applied later with `apply`.
+///
+/// Nodes are addressed by their paths at the time the command is applied.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum EditCommand<T> {
+    /// Adds a leaf with `data` after the last child of the node at `path`.
+    PushChild { path: TreePath, data: T },
+    /// Inserts a leaf with `data` as the child at `index` of the node at
+    /// `path`.
+    InsertChild { path: TreePath, index: usize, data: T },
+    /// Removes the subtree rooted at `path`.
+    Remove { path: TreePath },
+    /// Swaps the children at `a` and `b` of the node at `path`.
+    SwapChildren { path: TreePath, a: usize, b: usize },
+    /// Replaces the data at `path`.
+    SetData { path: TreePath, data: T },
+}
+
+impl<T> EditCommand<T> {
+    /// Returns the path of the node that this command edits.
+    pub fn path(&self) -> &TreePath {
+        match *self {
+            EditCommand::PushChild { ref path, .. } |
+            EditCommand::InsertChild { ref path, .. } |
+            EditCommand::Remove { ref path } |
+            EditCommand::SwapChildren { ref path, .. } |
+            EditCommand::SetData { ref path, .. } => path,
+        }
+    }
+
+    /// Returns the path of the node that this command edits, for changing.
+    pub fn path_mut(&mut self) -> &mut TreePath {
+        match *self {
+            EditCommand::PushChild { ref mut path, .. } |
+            EditCommand::InsertChild { ref mut path, .. } |
+            EditCommand::Remove { ref mut path } |
+            EditCommand::SwapChildren { ref mut path, .. } |
+            EditCommand::SetData { ref mut path, .. } => path,
+        }
+    }
+}
+
+/// The reason a command could not be applied.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ApplyErrorKind {
+    /// There is no node at the command's path.
+    NoSuchNode,
+    /// The node at the command's path has no child at the given index.
+    NoSuchChild(usize),
+    /// The command would remove the tree root.
+    RemoveRoot,
+    /// A replayed script's anchor lies above the tree root.
+    NoSuchAnchor,
+}
+
+/// A failure to apply a command to the node at `path`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ApplyError {
+    pub path: TreePath,
+    pub kind: ApplyErrorKind,
+}
+
+impl fmt::Display for ApplyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            ApplyErrorKind::NoSuchNode => write!(f, "no node at {}", self.path),
+            ApplyErrorKind::NoSuchChild(index) =>
+                write!(f, "node at {} has no child {}", self.path, index),
+            ApplyErrorKind::RemoveRoot => f.write_str("cannot remove the tree root"),
+            ApplyErrorKind::NoSuchAnchor => f.write_str("script anchor lies above the tree root"),
+        }
+    }
+}
+
+impl Error for ApplyError {}
+
+/// Applies `command` to the tree that `editor` edits.
+///
+/// Paths are resolved from the tree root, regardless of where `editor` is
+/// focused. Afterward, focus is wherever the edit leaves it: on the new child
+/// for `PushChild` and `InsertChild`, where `Editor::remove` leaves it for
+/// `Remove`, and on the edited node otherwise. If the command fails, the tree
+/// is unchanged.
+pub fn apply<E>(editor: &mut E, command: &EditCommand<E::Data>) -> Result<(), ApplyError>
+    where E: Editor + DerefMut<Target=<E as Editor>::Data>, E::Data: Clone {
+        apply_undoable(editor, command).map(|_| ())
+    }
+
+/// Applies each of `commands` in turn, as `apply` does, or none of them.
+///
+/// If a command fails, the commands before it are undone in reverse order,
+/// leaving the tree as it was and the focus at the root, and the index of the
+/// failed command is returned with its error. Undoing a removal puts back the
+/// removed subtree itself, so nodes keep their identities.
+pub fn apply_all<E>(editor: &mut E, commands: &[EditCommand<E::Data>])
+                    -> Result<(), (usize, ApplyError)>
+    where E: Editor + DerefMut<Target=<E as Editor>::Data>, E::Data: Clone {
+        let mut undos = Vec::with_capacity(commands.len());
+        for (i, command) in commands.iter().enumerate() {
+            match apply_undoable(editor, command) {
+                Ok(undo) => undos.push(undo),
+                Err(error) => {
+                    while let Some(undo) = undos.pop() {
+                        undo.apply(editor);
+                    }
+                    editor.to_root();
+                    return Err((i, error))
+                },
+            }
+        }
+        Ok(())
+    }
+
+// The edit that reverses an applied command.
+enum Undo<D, T> {
+    Remove { path: TreePath },
+    Reinsert { parent: TreePath, index: usize, tree: T },
+    SwapChildren { path: TreePath, a: usize, b: usize },
+    SetData { path: TreePath, data: D },
+}
+
+impl<D, T> Undo<D, T> {
+    fn apply<E>(self, editor: &mut E) where E: Editor<Data=D, Tree=T> + DerefMut<Target=D> {
+        let path = match self {
+            Undo::Remove { ref path } | Undo::SwapChildren { ref path, .. } |
+            Undo::SetData { ref path, .. } => path.clone(),
+            Undo::Reinsert { ref parent, .. } => parent.clone(),
+        };
+        assert![seek(editor, &path), "undo path {} no longer exists", path];
+        match self {
+            Undo::Remove { .. } => { editor.remove(); },
+            // Editors insert only before an existing child, so a removed last
+            // child is appended instead.
+            Undo::Reinsert { index, tree, .. } if index == editor.child_count() =>
+                editor.push_child(tree),
+            Undo::Reinsert { index, tree, .. } => {
+                assert![editor.insert_child(index, tree), "cannot reinsert child {} at {}",
+                        index, path];
+            },
+            Undo::SwapChildren { a, b, .. } => { editor.swap_children(a, b); },
+            Undo::SetData { data, .. } => **editor = data,
+        }
+    }
+}
+
+// Moves the focus of `editor` to the node at `path`. Returns false if there is
+// no such node.
+fn seek<E: Editor>(editor: &mut E, path: &TreePath) -> bool {
+    editor.to_root();
+    path.indices().iter().all(|&index| editor.seek_child(index))
+}
+
+// Applies `command`, returning the edit that reverses it.
+fn apply_undoable<E>(editor: &mut E, command: &EditCommand<E::Data>)
+                     -> Result<Undo<E::Data, E::Tree>, ApplyError>
+    where E: Editor + DerefMut<Target=<E as Editor>::Data>, E::Data: Clone {
+        let path = command.path();
+        let error = |kind| Err(ApplyError { path: path.clone(), kind, });
+        if !seek(editor, path) {
+            return error(ApplyErrorKind::NoSuchNode)
+        }
+        let undo = match *command {
+            EditCommand::PushChild { ref data, .. } => {
+                let index = editor.child_count();
+                editor.push_leaf(data.clone());
+                Undo::Remove { path: path.child(index), }
+            },
+            EditCommand::InsertChild { index, ref data, .. } => {
+                if !editor.insert_leaf(index, data.clone()) {
+                    return error(ApplyErrorKind::NoSuchChild(index))
+                }
+                Undo::Remove { path: path.child(index), }
+            },
+            EditCommand::Remove { .. } => {
+                let (parent, index) = match path.parent() {
+                    Some(parent) => (parent, *path.indices().last().unwrap()),
+                    None => return error(ApplyErrorKind::RemoveRoot),
+                };
+                Undo::Reinsert { parent, index, tree: editor.remove(), }
+            },
+            EditCommand::SwapChildren { a, b, .. } => {
+                if !editor.swap_children(a, b) {
+                    let invalid = if a >= editor.child_count() { a } else { b };
+                    return error(ApplyErrorKind::NoSuchChild(invalid))
+                }
+                Undo::SwapChildren { path: path.clone(), a, b, }
+            },
+            EditCommand::SetData { ref data, .. } => {
+                let old = mem::replace(&mut **editor, data.clone());
+                Undo::SetData { path: path.clone(), data: old, }
+            },
+        };
+        Ok(undo)
+    }
+
+fn escape(f: &mut fmt::Formatter, text: &str) -> fmt::Result {
+    for c in text.chars() {
+        match c {
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    Ok(())
+}
+
+fn unescape(text: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next()? {
+                '\\' => unescaped.push('\\'),
+                'n' => unescaped.push('\n'),
+                'r' => unescaped.push('\r'),
+                _ => return None,
+            }
+        } else {
+            unescaped.push(c);
+        }
+    }
+    Some(unescaped)
+}
+
+/// Formats a command as a single line of text, such as `push /0 data` or
+/// `swap / 0 2`. Node data is written last, with backslashes, newlines, and
+/// carriage returns escaped.
+impl<T: fmt::Display> fmt::Display for EditCommand<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EditCommand::PushChild { ref path, ref data } => {
+                write!(f, "push {} ", path)?;
+                escape(f, &data.to_string())
+            },
+            EditCommand::InsertChild { ref path, index, ref data } => {
+                write!(f, "insert {} {} ", path, index)?;
+                escape(f, &data.to_string())
+            },
+            EditCommand::Remove { ref path } => write!(f, "remove {}", path),
+            EditCommand::SwapChildren { ref path, a, b } => write!(f, "swap {} {} {}", path, a, b),
+            EditCommand::SetData { ref path, ref data } => {
+                write!(f, "set {} ", path)?;
+                escape(f, &data.to_string())
+            },
+        }
+    }
+}
+
+/// Error returned when parsing a malformed `EditCommand`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ParseCommandError<E> {
+    /// The command name is not recognized.
+    UnknownCommand(String),
+    /// A field is missing.
+    MissingField(&'static str),
+    /// The path is malformed.
+    BadPath(ParsePathError),
+    /// A child index is malformed.
+    BadIndex(ParseIntError),
+    /// The node data contains an invalid escape sequence.
+    BadEscape,
+    /// The node data could not be parsed.
+    BadData(E),
+}
+
+impl<E: fmt::Display> fmt::Display for ParseCommandError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseCommandError::UnknownCommand(ref name) => write!(f, "unknown command {:?}", name),
+            ParseCommandError::MissingField(field) => write!(f, "missing {}", field),
+            ParseCommandError::BadPath(ref e) => e.fmt(f),
+            ParseCommandError::BadIndex(ref e) => write!(f, "invalid child index: {}", e),
+            ParseCommandError::BadEscape => f.write_str("invalid escape sequence in data"),
+            ParseCommandError::BadData(ref e) => write!(f, "invalid data: {}", e),
+        }
+    }
+}
+
+/// Parses a command in the format written by `Display`.
+impl<T: FromStr> FromStr for EditCommand<T> {
+    type Err = ParseCommandError<T::Err>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Splits off the next space-separated field.
+        fn field<'s, E>(rest: &mut &'s str, name: &'static str)
+                        -> Result<&'s str, ParseCommandError<E>> {
+            if rest.is_empty() {
+                return Err(ParseCommandError::MissingField(name))
+            }
+            let (field, remainder) = match rest.find(' ') {
+                Some(i) => (&rest[..i], &rest[i + 1..]),
+                None => (*rest, ""),
+            };
+            *rest = remainder;
+            Ok(field)
+        }
+
+        fn index<E>(rest: &mut &str, name: &'static str) -> Result<usize, ParseCommandError<E>> {
+            field(rest, name)?.parse().map_err(ParseCommandError::BadIndex)
+        }
+
+        fn data<T: FromStr>(rest: &str) -> Result<T, ParseCommandError<T::Err>> {
+            let text = unescape(rest).ok_or(ParseCommandError::BadEscape)?;
+            text.parse().map_err(ParseCommandError::BadData)
+        }
+
+        let mut rest = s;
+        let name = field(&mut rest, "command")?;
+        let path = field(&mut rest, "path")?.parse().map_err(ParseCommandError::BadPath)?;
+        match name {
+            "push" => Ok(EditCommand::PushChild { path, data: data(rest)?, }),
+            "insert" => {
+                let index = index(&mut rest, "index")?;
+                Ok(EditCommand::InsertChild { path, index, data: data(rest)?, })
+            },
+            "remove" => Ok(EditCommand::Remove { path, }),
+            "swap" => {
+                let a = index(&mut rest, "first index")?;
+                let b = index(&mut rest, "second index")?;
+                Ok(EditCommand::SwapChildren { path, a, b, })
+            },
+            "set" => Ok(EditCommand::SetData { path, data: data(rest)?, }),
+            _ => Err(ParseCommandError::UnknownCommand(name.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::Nav;
+    use ::command::{apply, apply_all, ApplyError, ApplyErrorKind, EditCommand, ParseCommandError};
+    use ::owned_tree;
+    use ::path::TreePath;
+    use ::sync_tree;
+
+    fn path(indices: &[usize]) -> TreePath {
+        TreePath::from(indices)
+    }
+
+    fn commands() -> Vec<EditCommand<String>> {
+        vec![EditCommand::PushChild { path: path(&[]), data: "d".to_string(), },
+             EditCommand::InsertChild { path: path(&[1]), index: 0, data: "x y".to_string(), },
+             EditCommand::SwapChildren { path: path(&[]), a: 0, b: 2, },
+             EditCommand::SetData { path: path(&[2]), data: "B\n\\".to_string(), },
+             EditCommand::Remove { path: path(&[1]), }]
+    }
+
+    #[test]
+    fn apply_commands() {
+        let mut t = owned_tree!["a".to_string(), ["b".to_string()], ["c".to_string(), ["e".to_string()]]];
+        {
+            let mut v = t.view_mut();
+            for command in &commands() {
+                apply(&mut v, command).unwrap();
+            }
+        }
+        assert_eq![t, owned_tree!["a".to_string(), ["d".to_string()],
+                                  ["B\n\\".to_string()]]];
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_round_trip() {
+        use serde_json;
+
+        let json = serde_json::to_string(&commands()[1]).unwrap();
+        assert_eq![json, r#"{"InsertChild":{"path":[1],"index":0,"data":"x y"}}"#];
+        let read: Vec<EditCommand<String>> =
+            serde_json::from_str(&serde_json::to_string(&commands()).unwrap()).unwrap();
+        assert_eq![read, commands()];
+    }
+
+    #[test]
+    fn apply_to_other_backends() {
+        let t = sync_tree![1, [2]];
+        {
+            let mut w = t.write();
+            apply(&mut w, &EditCommand::PushChild { path: path(&[0]), data: 3, }).unwrap();
+            apply(&mut w, &EditCommand::SetData { path: path(&[]), data: 0, }).unwrap();
+        }
+        assert_eq![t, sync_tree![0, [2, [3]]]];
+    }
+
+    #[test]
+    fn apply_errors() {
+        let mut t = owned_tree![1, [2]];
+        let mut v = t.view_mut();
+        assert_eq![apply(&mut v, &EditCommand::Remove { path: path(&[1]), }),
+                   Err(ApplyError { path: path(&[1]), kind: ApplyErrorKind::NoSuchNode, })];
+        assert_eq![apply(&mut v, &EditCommand::Remove { path: path(&[]), }),
+                   Err(ApplyError { path: path(&[]), kind: ApplyErrorKind::RemoveRoot, })];
+        assert_eq![apply(&mut v, &EditCommand::SwapChildren { path: path(&[]), a: 0, b: 3, }),
+                   Err(ApplyError { path: path(&[]), kind: ApplyErrorKind::NoSuchChild(3), })];
+        assert_eq![apply(&mut v, &EditCommand::InsertChild { path: path(&[0]), index: 1, data: 5, })
+                   .unwrap_err().to_string(),
+                   "node at /0 has no child 1"];
+    }
+
+    #[test]
+    fn apply_all_or_nothing() {
+        let mut t = owned_tree![1, [2, [3]], [4]];
+        let ids: Vec<_> = t.children().iter().map(|child| child.id()).collect();
+        let mut commands = vec![EditCommand::SetData { path: path(&[]), data: 0, },
+                                EditCommand::Remove { path: path(&[0]), },
+                                EditCommand::PushChild { path: path(&[0]), data: 5, },
+                                EditCommand::PushChild { path: path(&[]), data: 7, },
+                                EditCommand::SwapChildren { path: path(&[]), a: 0, b: 1, },
+                                EditCommand::InsertChild { path: path(&[1]), index: 0, data: 6, },
+                                EditCommand::Remove { path: path(&[2]), }];
+        {
+            let mut v = t.view_mut();
+            assert![v.seek_child(1)];
+            let error = ApplyError { path: path(&[2]), kind: ApplyErrorKind::NoSuchNode, };
+            assert_eq![apply_all(&mut v, &commands), Err((6, error))];
+            assert![v.at_root()];
+        }
+        assert_eq![t, owned_tree![1, [2, [3]], [4]]];
+        assert_eq![ids, t.children().iter().map(|child| child.id()).collect::<Vec<_>>()];
+
+        commands.pop();
+        assert_eq![apply_all(&mut t.view_mut(), &commands), Ok(())];
+        assert_eq![t, owned_tree![0, [7], [4, [6], [5]]]];
+    }
+
+    #[test]
+    fn apply_all_restores_removed_last_children() {
+        let mut t = owned_tree!["r", ["a"], ["b", ["c"]]];
+        let commands: Vec<EditCommand<&str>> = vec![EditCommand::Remove { path: path(&[1, 0]), },
+                                                     EditCommand::Remove { path: path(&[1]), },
+                                                     EditCommand::Remove { path: path(&[5]), }];
+        let error = ApplyError { path: path(&[5]), kind: ApplyErrorKind::NoSuchNode, };
+        assert_eq![apply_all(&mut t.view_mut(), &commands), Err((2, error))];
+        assert_eq![t, owned_tree!["r", ["a"], ["b", ["c"]]]];
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let lines: Vec<String> = commands().iter().map(|c| c.to_string()).collect();
+        assert_eq![lines, vec!["push / d", "insert /1 0 x y", "swap / 0 2", "set /2 B\\n\\\\",
+                               "remove /1"]];
+        let parsed: Vec<EditCommand<String>> = lines.iter().map(|l| l.parse().unwrap()).collect();
+        assert_eq![parsed, commands()];
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!["copy /".parse::<EditCommand<i32>>(),
+                   Err(ParseCommandError::UnknownCommand("copy".to_string()))];
+        assert_eq!["swap / 1".parse::<EditCommand<i32>>(),
+                   Err(ParseCommandError::MissingField("second index"))];
+        assert!["set / x".parse::<EditCommand<i32>>().is_err()];
+        assert!["set 0 1".parse::<EditCommand<i32>>().is_err()];
+        assert_eq!["set / a\\q".parse::<EditCommand<String>>(), Err(ParseCommandError::BadEscape)];
+    }
+}
@@ -0,0 +1,416 @@
+/// Generates tests of an `Editor` implementation in the enclosing module.
+///
+/// `$tree_macro` names a macro that builds trees of `&'static str` data from
+/// the same nested syntax as `owned_tree!`, and `$editor` a method of those
+/// trees that returns an editor focused on the root. The closure-like last
+/// argument maps an editor reference `$e` to the data at its focus.
+///
+/// The generated tests compare trees and removed subtrees with `assert_eq!`,
+/// so the tree type must implement `PartialEq` and `Debug`.
+///
+/// ```ignore
+/// #[macro_use(owned_tree, editor_tests)]
+/// extern crate entmut;
+///
+/// mod owned {
+///     editor_tests!(owned_tree, view_mut, |e| **e);
+/// }
+/// ```
+#[macro_export]
+macro_rules! editor_tests {
+    ($tree_macro:ident, $editor:ident, |$e:ident| $focus_data:expr) => (
+        use $crate::{Editor, Nav, RemoveFocusPolicy};
+        use $crate::error::EditError;
+
+        const POLICIES: [RemoveFocusPolicy; 3] =
+            [RemoveFocusPolicy::PreferLeft, RemoveFocusPolicy::PreferRight,
+             RemoveFocusPolicy::Parent];
+
+        // For each number of children and index of the child removed, the
+        // node focused afterward under each of `POLICIES`.
+        const REMOVE_FOCUS: [(usize, usize, [&'static str; 3]); 6] =
+            [(1, 0, ["r", "r", "r"]),
+             (2, 0, ["b", "b", "r"]),
+             (2, 1, ["a", "a", "r"]),
+             (3, 0, ["b", "b", "r"]),
+             (3, 1, ["a", "c", "r"]),
+             (3, 2, ["b", "b", "r"])];
+
+        const NAMES: [&'static str; 3] = ["a", "b", "c"];
+
+        #[test]
+        fn editor_remove_focus_policy_defaults_to_prefer_left() {
+            let mut t = $tree_macro!["r"];
+            let mut e = t.$editor();
+            assert_eq![RemoveFocusPolicy::PreferLeft, e.remove_focus_policy()];
+            assert![e.set_remove_focus_policy(RemoveFocusPolicy::Parent)];
+            assert_eq![RemoveFocusPolicy::Parent, e.remove_focus_policy()];
+        }
+
+        #[test]
+        fn editor_remove_focus_follows_policy() {
+            for &(count, index, expected) in REMOVE_FOCUS.iter() {
+                for (&policy, &expected_focus) in POLICIES.iter().zip(expected.iter()) {
+                    let mut remaining = $tree_macro!["r"];
+                    {
+                        let mut r = remaining.$editor();
+                        for (i, name) in NAMES[..count].iter().enumerate() {
+                            if i != index {
+                                r.push_leaf(*name);
+                                r.to_parent();
+                            }
+                        }
+                    }
+                    let mut t = $tree_macro!["r"];
+                    let removed = {
+                        let mut e = t.$editor();
+                        for name in NAMES[..count].iter() {
+                            e.push_leaf(*name);
+                            e.to_parent();
+                        }
+                        assert![e.set_remove_focus_policy(policy)];
+                        assert![e.seek_child(index)];
+                        let removed = e.remove();
+                        let $e = &e;
+                        assert_eq![expected_focus, $focus_data,
+                                   "removing child {} of {} with {:?}", index, count, policy];
+                        assert_eq![expected_focus == "r", e.at_root()];
+                        removed
+                    };
+                    assert_eq![$tree_macro![NAMES[index]], removed];
+                    assert_eq![remaining, t];
+                }
+            }
+        }
+
+        #[test]
+        fn editor_remove_focus_keeps_path() {
+            for &policy in POLICIES.iter() {
+                let mut t = $tree_macro!["r", ["a", ["b"], ["c", ["d"]], ["e"]]];
+                {
+                    let mut e = t.$editor();
+                    assert![e.set_remove_focus_policy(policy)];
+                    assert![e.seek_child(0)];
+                    assert![e.seek_child(1)];
+                    e.remove();
+                    assert![! e.at_root()];
+                    if policy != RemoveFocusPolicy::Parent {
+                        assert![e.to_parent()];
+                    }
+                    let $e = &e;
+                    assert_eq!["a", $focus_data];
+                    assert![e.to_parent()];
+                    assert![e.at_root()];
+                }
+                assert_eq![$tree_macro!["r", ["a", ["b"], ["e"]]], t];
+            }
+        }
+
+        #[test]
+        fn editor_remove_child_keeps_focus() {
+            let mut t = $tree_macro!["r", ["a", ["b"], ["c"], ["d"]], ["e"]];
+            let removed = {
+                let mut e = t.$editor();
+                assert![e.seek_child(0)];
+                let removed = e.remove_child(1);
+                let $e = &e;
+                assert_eq!["a", $focus_data];
+                assert_eq![2, e.child_count()];
+                assert![e.remove_child(2).is_err()];
+                assert![e.seek_child(1)];
+                let $e = &e;
+                assert_eq!["d", $focus_data];
+                removed.ok()
+            };
+            assert_eq![Some($tree_macro!["c"]), removed];
+            assert_eq![$tree_macro!["r", ["a", ["b"], ["d"]], ["e"]], t];
+        }
+
+        #[test]
+        fn editor_take_children_keeps_focus() {
+            let mut t = $tree_macro!["r", ["a", ["b", ["c"]], ["d"]], ["e"]];
+            let taken = {
+                let mut e = t.$editor();
+                assert![e.seek_child(0)];
+                let taken = e.take_children();
+                let $e = &e;
+                assert_eq!["a", $focus_data];
+                assert![e.at_leaf()];
+                assert![e.take_children().is_empty()];
+                taken
+            };
+            assert_eq![vec![$tree_macro!["b", ["c"]], $tree_macro!["d"]], taken];
+            assert_eq![$tree_macro!["r", ["a"], ["e"]], t];
+        }
+
+        #[test]
+        fn editor_replace_child_keeps_focus() {
+            let mut t = $tree_macro!["r", ["a", ["b", ["c"]], ["d"]], ["e"]];
+            let replaced = {
+                let mut e = t.$editor();
+                assert![e.seek_child(0)];
+                let replaced = e.replace_child(0, $tree_macro!["x", ["y"]]);
+                let $e = &e;
+                assert_eq!["a", $focus_data];
+                assert![e.seek_child(1)];
+                let $e = &e;
+                assert_eq!["d", $focus_data];
+                replaced
+            };
+            assert_eq![$tree_macro!["b", ["c"]], replaced];
+            assert_eq![$tree_macro!["r", ["a", ["x", ["y"]], ["d"]], ["e"]], t];
+        }
+
+        #[test]
+        #[should_panic(expected = "no child at index 1")]
+        fn editor_replace_child_out_of_range_panics() {
+            let mut t = $tree_macro!["r", ["a"]];
+            let mut e = t.$editor();
+            e.replace_child(1, $tree_macro!["x"]);
+        }
+
+        #[test]
+        fn editor_permute_children() {
+            let mut t = $tree_macro!["r", ["a", ["b"], ["c", ["d"]], ["e"], ["f"]]];
+            {
+                let mut e = t.$editor();
+                assert![e.seek_child(0)];
+                assert_eq![Ok(()), e.permute_children(&[2, 0, 3, 1])];
+                let $e = &e;
+                assert_eq!["a", $focus_data];
+                for bad in &[&[0, 1, 2][..], &[0, 1, 2, 2], &[0, 1, 2, 4]] {
+                    assert_eq![Err(EditError::NotPermutation { child_count: 4, }),
+                               e.permute_children(bad)];
+                }
+                assert![e.seek_child(1)];
+                let $e = &e;
+                assert_eq!["b", $focus_data];
+            }
+            assert_eq![$tree_macro!["r", ["a", ["e"], ["b"], ["f"], ["c", ["d"]]]], t];
+        }
+
+        #[test]
+        fn editor_remove_child_out_of_range_fails() {
+            let mut t = $tree_macro!["r", ["a"]];
+            {
+                let mut e = t.$editor();
+                match e.remove_child(1) {
+                    Err(error) =>
+                        assert_eq![EditError::ChildOutOfRange { index: 1, child_count: 1, }, error],
+                    Ok(_) => panic!["removed a child that does not exist"],
+                }
+                assert![e.seek_child(0)];
+                assert![e.remove_child(0).is_err()];
+            }
+            assert_eq![$tree_macro!["r", ["a"]], t];
+        }
+
+        #[test]
+        fn editor_remove_sibling_keeps_focus_node() {
+            let mut t = $tree_macro!["r", ["a"], ["b"], ["c"], ["d"]];
+            {
+                let mut e = t.$editor();
+                assert![e.seek_child(2)];
+                assert![e.remove_sibling(-2).is_some()];
+                let $e = &e;
+                assert_eq!["c", $focus_data];
+                assert![e.remove_sibling(1).is_some()];
+                assert![e.remove_sibling(1).is_none()];
+                assert![e.seek_sibling(-1)];
+                let $e = &e;
+                assert_eq!["b", $focus_data];
+                assert![! e.seek_sibling(-1)];
+            }
+            assert_eq![$tree_macro!["r", ["b"], ["c"]], t];
+        }
+
+        #[test]
+        fn editor_remove_sibling_at_root_fails() {
+            let mut t = $tree_macro!["r", ["a"]];
+            {
+                let mut e = t.$editor();
+                assert![e.remove_sibling(0).is_none()];
+                assert![e.remove_sibling(1).is_none()];
+                assert![e.at_root()];
+            }
+            assert_eq![$tree_macro!["r", ["a"]], t];
+        }
+
+        #[test]
+        #[should_panic(expected = "focus is at the tree root")]
+        fn editor_remove_root_panics() {
+            let mut t = $tree_macro!["r"];
+            t.$editor().remove();
+        }
+
+        #[test]
+        fn editor_push_focuses_new_child() {
+            let mut t = $tree_macro!["r", ["a"]];
+            {
+                let mut e = t.$editor();
+                e.push_leaf("b");
+                let $e = &e;
+                assert_eq!["b", $focus_data];
+                e.push_child($tree_macro!["c", ["d"]]);
+                let $e = &e;
+                assert_eq!["c", $focus_data];
+                assert_eq![1, e.child_count()];
+                assert![e.to_parent()];
+                assert![e.seek_sibling(-1)];
+                let $e = &e;
+                assert_eq!["a", $focus_data];
+            }
+            assert_eq![$tree_macro!["r", ["a"], ["b", ["c", ["d"]]]], t];
+        }
+
+        #[test]
+        fn editor_insert_focuses_new_child() {
+            let mut t = $tree_macro!["r", ["a"], ["b"]];
+            {
+                let mut e = t.$editor();
+                assert![e.insert_leaf(1, "x")];
+                let $e = &e;
+                assert_eq!["x", $focus_data];
+                assert![e.seek_sibling(1)];
+                let $e = &e;
+                assert_eq!["b", $focus_data];
+                assert![e.to_parent()];
+                assert![e.insert_child(0, $tree_macro!["y", ["z"]])];
+                let $e = &e;
+                assert_eq!["y", $focus_data];
+                assert![e.seek_child(0)];
+                let $e = &e;
+                assert_eq!["z", $focus_data];
+            }
+            assert_eq![$tree_macro!["r", ["y", ["z"]], ["a"], ["x"], ["b"]], t];
+        }
+
+        #[test]
+        fn editor_insert_out_of_range_fails() {
+            let mut t = $tree_macro!["r", ["a"]];
+            {
+                let mut e = t.$editor();
+                assert![! e.insert_leaf(2, "x")];
+                assert![! e.insert_child(2, $tree_macro!["x"])];
+                assert![e.at_root()];
+                assert![e.seek_child(0)];
+                assert![! e.insert_leaf(0, "x")];
+                let $e = &e;
+                assert_eq!["a", $focus_data];
+            }
+            assert_eq![$tree_macro!["r", ["a"]], t];
+        }
+
+        #[test]
+        fn editor_insert_sibling_focuses_new_sibling() {
+            let mut t = $tree_macro!["r", ["a"], ["b"], ["c"]];
+            {
+                let mut e = t.$editor();
+                assert![e.seek_child(1)];
+                assert![e.insert_sibling_leaf(0, "x")];
+                let $e = &e;
+                assert_eq!["x", $focus_data];
+                assert_eq![Some(1), e.index_in_parent()];
+                assert![e.seek_sibling(1)];
+                let $e = &e;
+                assert_eq!["b", $focus_data];
+                assert![e.insert_sibling(-2, $tree_macro!["y", ["z"]])];
+                let $e = &e;
+                assert_eq!["y", $focus_data];
+                assert_eq![Some(0), e.index_in_parent()];
+                assert_eq![1, e.child_count()];
+                assert![e.seek_sibling(1)];
+                let $e = &e;
+                assert_eq!["a", $focus_data];
+            }
+            assert_eq![$tree_macro!["r", ["y", ["z"]], ["a"], ["x"], ["b"], ["c"]], t];
+        }
+
+        #[test]
+        fn editor_insert_sibling_out_of_range_fails() {
+            let mut t = $tree_macro!["r", ["a"], ["b"]];
+            {
+                let mut e = t.$editor();
+                assert![! e.insert_sibling_leaf(0, "x")];
+                assert![! e.insert_sibling(1, $tree_macro!["x"])];
+                assert![e.seek_child(1)];
+                assert![! e.insert_sibling_leaf(-2, "x")];
+                assert![! e.insert_sibling(isize::MIN, $tree_macro!["x"])];
+                let $e = &e;
+                assert_eq!["b", $focus_data];
+            }
+            assert_eq![$tree_macro!["r", ["a"], ["b"]], t];
+        }
+
+        #[test]
+        fn editor_swap_exchanges_focus_subtree() {
+            let mut t = $tree_macro!["r", ["a", ["b"]], ["c"]];
+            let mut other = $tree_macro!["x", ["y"], ["z"]];
+            {
+                let mut e = t.$editor();
+                assert![e.seek_child(0)];
+                e.swap(&mut other);
+                let $e = &e;
+                assert_eq!["x", $focus_data];
+                assert_eq![2, e.child_count()];
+                assert![e.seek_sibling(1)];
+                let $e = &e;
+                assert_eq!["c", $focus_data];
+            }
+            assert_eq![$tree_macro!["a", ["b"]], other];
+            assert_eq![$tree_macro!["r", ["x", ["y"], ["z"]], ["c"]], t];
+        }
+
+        #[test]
+        fn editor_swap_children_keeps_focus() {
+            let mut t = $tree_macro!["r", ["a"], ["b", ["c"]], ["d"]];
+            {
+                let mut e = t.$editor();
+                assert![e.swap_children(0, 1)];
+                assert![e.swap_children(2, 2)];
+                assert![! e.swap_children(0, 3)];
+                assert![! e.swap_children(3, 0)];
+                assert![e.at_root()];
+                assert![e.seek_child(0)];
+                let $e = &e;
+                assert_eq!["b", $focus_data];
+            }
+            assert_eq![$tree_macro!["r", ["b", ["c"]], ["a"], ["d"]], t];
+        }
+
+        #[test]
+        fn editor_swap_siblings_focus_follows() {
+            let mut t = $tree_macro!["r", ["a"], ["b"], ["c"], ["d"]];
+            {
+                let mut e = t.$editor();
+                assert![e.seek_child(1)];
+                // Focus follows the node at offset 0.
+                assert![e.swap_siblings(0, 2)];
+                let $e = &e;
+                assert_eq!["b", $focus_data];
+                assert![! e.seek_sibling(1)];
+                // Swapping other siblings leaves focus in place.
+                assert![e.swap_siblings(-3, -1)];
+                let $e = &e;
+                assert_eq!["b", $focus_data];
+                assert![! e.swap_siblings(-4, 0)];
+                assert![! e.swap_siblings(0, 1)];
+                assert![e.seek_sibling(-3)];
+                let $e = &e;
+                assert_eq!["c", $focus_data];
+            }
+            assert_eq![$tree_macro!["r", ["c"], ["d"], ["a"], ["b"]], t];
+        }
+
+        #[test]
+        fn editor_swap_root_siblings_fails() {
+            let mut t = $tree_macro!["r", ["a"]];
+            {
+                let mut e = t.$editor();
+                assert![! e.swap_siblings(0, 0)];
+                assert![e.at_root()];
+            }
+            assert_eq![$tree_macro!["r", ["a"]], t];
+        }
+    );
+}
@@ -0,0 +1,196 @@
+use ::analysis::{summarize, Subtree};
+use ::owned::Tree;
+use ::path::TreePath;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::hash::Hash;
+
+/// Description of a tree in terms of the subtrees it shares with a base tree,
+/// as made by `encode_delta` and read by `apply_delta`.
+///
+/// A delta is a sequence of operations in pre-order, each of which gives the
+/// next node of the tree it describes. The node is either copied from the base
+/// with its whole subtree, in which case the next operation describes the node
+/// after that subtree, or given with its data and number of children, in which
+/// case the operations after it describe those children in turn.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Delta<T> {
+    ops: Vec<DeltaOp<T>>,
+}
+
+/// An operation in a `Delta`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum DeltaOp<T> {
+    /// The subtree at this path in the base tree.
+    Copy(TreePath),
+    /// A node with this data and this many children, which are described by
+    /// the operations that follow.
+    Node { data: T, child_count: usize },
+}
+
+impl<T> Delta<T> {
+    /// Returns the operations of this delta in order.
+    pub fn ops(&self) -> &[DeltaOp<T>] {
+        &self.ops
+    }
+
+    /// Returns the operations of this delta in order, such as to serialize
+    /// them.
+    pub fn into_ops(self) -> Vec<DeltaOp<T>> {
+        self.ops
+    }
+}
+
+impl<T> From<Vec<DeltaOp<T>>> for Delta<T> {
+    fn from(ops: Vec<DeltaOp<T>>) -> Self {
+        Delta { ops, }
+    }
+}
+
+/// Encodes `new` as a delta against `base`, so that `apply_delta(base, delta)`
+/// rebuilds it. Each subtree of `new` that also occurs anywhere in `base` is
+/// encoded as a reference to it instead of by its data, so the delta is small
+/// when the trees mostly agree, even if shared subtrees have moved.
+///
+/// Shared subtrees are found by hashing each subtree of both trees bottom-up,
+/// as `analysis::find_duplicates` does, so this runs in time roughly linear in
+/// the sizes of the trees; subtrees with equal hashes are compared exactly
+/// before one is used in place of the other.
+pub fn encode_delta<T>(base: &Tree<T>, new: &Tree<T>) -> Delta<T>
+    where T: Clone + Eq + Hash {
+        let mut base_subtrees: HashMap<(u64, usize), Vec<Subtree<'_, T>>> = HashMap::new();
+        for summary in summarize(base) {
+            base_subtrees.entry((summary.hash, summary.size)).or_default().push(summary);
+        }
+        let new_keys: HashMap<TreePath, (u64, usize)> = summarize(new).into_iter()
+            .map(|summary| (summary.path, (summary.hash, summary.size)))
+            .collect();
+        let mut ops = Vec::new();
+        let mut stack = vec![(new, TreePath::root())];
+        while let Some((tree, path)) = stack.pop() {
+            let copy = base_subtrees.get(&new_keys[&path])
+                .and_then(|candidates| candidates.iter().find(|candidate| candidate.tree == tree));
+            if let Some(candidate) = copy {
+                ops.push(DeltaOp::Copy(candidate.path.clone()));
+                continue
+            }
+            ops.push(DeltaOp::Node { data: tree.data().clone(), child_count: tree.children().len(), });
+            for (index, child) in tree.children().iter().enumerate().rev() {
+                stack.push((child, path.child(index)));
+            }
+        }
+        Delta { ops, }
+    }
+
+/// Rebuilds the tree that `delta` describes, copying from `base` the subtrees
+/// that it refers to. Fails if `delta` refers to a path that `base` does not
+/// have, or if its operations do not describe exactly one tree.
+pub fn apply_delta<T: Clone>(base: &Tree<T>, delta: &Delta<T>) -> Result<Tree<T>, ApplyDeltaError> {
+    // Nodes whose children are still being read, with the number of children
+    // each is to have and those read so far.
+    let mut stack: Vec<(T, usize, Vec<Tree<T>>)> = Vec::new();
+    let mut result = None;
+    for op in &delta.ops {
+        if result.is_some() {
+            return Err(ApplyDeltaError::Malformed)
+        }
+        let mut done = match *op {
+            DeltaOp::Copy(ref path) => match base.subtree(path) {
+                Some(subtree) => subtree.clone(),
+                None => return Err(ApplyDeltaError::NoSuchPath(path.clone())),
+            },
+            DeltaOp::Node { ref data, child_count: 0 } => Tree::leaf(data.clone()),
+            DeltaOp::Node { ref data, child_count } => {
+                stack.push((data.clone(), child_count, Vec::with_capacity(child_count)));
+                continue
+            },
+        };
+        // Attach the finished subtree to its parent, finishing the parent in
+        // turn if this was its last child.
+        loop {
+            match stack.last_mut() {
+                None => {
+                    result = Some(done);
+                    break
+                },
+                Some(&mut (_, child_count, ref mut children)) => {
+                    children.push(done);
+                    if children.len() < child_count {
+                        break
+                    }
+                },
+            }
+            let (data, _, children) = stack.pop().unwrap();
+            done = Tree::new(data, children);
+        }
+    }
+    result.ok_or(ApplyDeltaError::Malformed)
+}
+
+/// Error returned when a delta cannot be applied to a base tree.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum ApplyDeltaError {
+    /// The delta copies the subtree at this path, but the base tree has no
+    /// node there.
+    NoSuchPath(TreePath),
+    /// The delta's operations describe no tree, an incomplete one, or more
+    /// than one.
+    Malformed,
+}
+
+impl fmt::Display for ApplyDeltaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ApplyDeltaError::NoSuchPath(ref path) =>
+                write!(f, "base tree has no node at {:?}", path.indices()),
+            ApplyDeltaError::Malformed => f.write_str("delta does not describe exactly one tree"),
+        }
+    }
+}
+
+impl Error for ApplyDeltaError {}
+
+#[cfg(test)]
+mod test {
+    use ::delta::{apply_delta, encode_delta, ApplyDeltaError, Delta, DeltaOp};
+    use ::path::TreePath;
+    use ::owned_tree;
+
+    #[test]
+    fn reuses_unchanged_subtrees() {
+        let base = owned_tree!["r", ["a", ["x"], ["y"]], ["b", ["z"]]];
+        let new = owned_tree!["r", ["b", ["z"]], ["c"], ["a", ["x"], ["y"]]];
+        let delta = encode_delta(&base, &new);
+        assert_eq![&[DeltaOp::Node { data: "r", child_count: 3, },
+                     DeltaOp::Copy(TreePath::new(vec![1])),
+                     DeltaOp::Node { data: "c", child_count: 0, },
+                     DeltaOp::Copy(TreePath::new(vec![0]))],
+                   delta.ops()];
+        assert_eq![Ok(new), apply_delta(&base, &delta)];
+        assert_eq![&[DeltaOp::Copy(TreePath::root())], encode_delta(&base, &base).ops()];
+    }
+
+    #[test]
+    fn rebuilds_unrelated_trees() {
+        let base = owned_tree!["a"];
+        let new = owned_tree!["b", ["c", ["d"]], ["e"]];
+        let delta = encode_delta(&base, &new);
+        assert_eq![4, delta.ops().len()];
+        assert_eq![Ok(new), apply_delta(&base, &delta)];
+    }
+
+    #[test]
+    fn rejects_bad_deltas() {
+        let base = owned_tree!["a"];
+        let missing = Delta::from(vec![DeltaOp::Copy(TreePath::new(vec![0]))]);
+        assert_eq![Err(ApplyDeltaError::NoSuchPath(TreePath::new(vec![0]))),
+                   apply_delta(&base, &missing)];
+        let truncated = Delta::from(vec![DeltaOp::Node { data: "b", child_count: 1, }]);
+        assert_eq![Err(ApplyDeltaError::Malformed), apply_delta(&base, &truncated)];
+        let extra = Delta::from(vec![DeltaOp::Copy(TreePath::root()), DeltaOp::Copy(TreePath::root())]);
+        assert_eq![Err(ApplyDeltaError::Malformed), apply_delta(&base, &extra)];
+        assert_eq![Err(ApplyDeltaError::Malformed), apply_delta(&base, &Delta::from(vec![]))];
+    }
+}
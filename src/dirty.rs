@@ -0,0 +1,524 @@
+use ::{Editor, Nav, RemoveFocusPolicy};
+use ::error::{EditError, NavError};
+use ::path::TreePath;
+use ::util::{self, FocusPath};
+
+use std::ops::{Deref, DerefMut};
+
+/// Wrapper around an `Editor` that records which parts of the tree are
+/// changed through it.
+///
+/// This is meant for consumers that keep a derived copy of a tree up to date,
+/// such as a renderer that should only redraw what changed. Every edit is
+/// forwarded to the wrapped editor, and the path of the root of the smallest
+/// subtree that it changes is recorded: the focus for changes to its data or
+/// children, and its parent for changes to its siblings. Mutable dereferences
+/// count as changes to the focus's data whether or not anything is written.
+///
+/// Callbacks can also be registered with `watch` to be told as soon as a
+/// particular subtree changes.
+pub struct DirtyTracker<E> {
+    editor: E,
+    focus: FocusPath,
+    dirty: Vec<TreePath>,
+    watches: Vec<Watch>,
+    next_watch_id: usize,
+}
+
+/// Identifier of a watch registered with `DirtyTracker::watch`.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct WatchId(usize);
+
+struct Watch {
+    id: WatchId,
+    // Kept up to date as edits move the watched subtree.
+    path: TreePath,
+    callback: Box<dyn FnMut(&TreePath)>,
+}
+
+impl<E: Editor> DirtyTracker<E> {
+    /// Wraps `editor`, moving its focus to the root so that paths can be
+    /// tracked from there.
+    pub fn new(mut editor: E) -> Self {
+        editor.to_root();
+        DirtyTracker { editor, focus: FocusPath::new(), dirty: Vec::new(),
+                       watches: Vec::new(), next_watch_id: 0, }
+    }
+
+    /// Returns the path of the focus.
+    pub fn path(&self) -> &TreePath {
+        self.focus.path()
+    }
+
+    /// Returns the roots of the subtrees changed since the last call, in
+    /// document order. Changes nested inside another changed subtree are
+    /// coalesced into it, so no returned path contains another.
+    ///
+    /// Paths are those of the tree as it is now, so a path recorded before a
+    /// later edit to an ancestor's children, which may shift its indices, is
+    /// subsumed by the ancestor's path rather than returned.
+    pub fn take_dirty_paths(&mut self) -> Vec<TreePath> {
+        let mut dirty = ::std::mem::take(&mut self.dirty);
+        dirty.sort();
+        let mut roots: Vec<TreePath> = Vec::with_capacity(dirty.len());
+        for path in dirty {
+            if !roots.last().is_some_and(|root| root.contains(&path)) {
+                roots.push(path);
+            }
+        }
+        roots
+    }
+
+    /// Registers `callback` to be called after each edit made at or below
+    /// `path`, with the path of the root of the smallest subtree that the edit
+    /// changed. Edits to the siblings of the watched node or its ancestors do
+    /// not trigger it, but do update the watched path so that it follows the
+    /// same node. The watch is dropped without being called if that node is
+    /// removed or replaced.
+    pub fn watch<C>(&mut self, path: TreePath, callback: C) -> WatchId
+        where C: FnMut(&TreePath) + 'static {
+            let id = WatchId(self.next_watch_id);
+            self.next_watch_id += 1;
+            self.watches.push(Watch { id, path, callback: Box::new(callback), });
+            id
+        }
+
+    /// Removes a watch. Returns false if it was already removed.
+    pub fn unwatch(&mut self, id: WatchId) -> bool {
+        let count = self.watches.len();
+        self.watches.retain(|watch| watch.id != id);
+        self.watches.len() != count
+    }
+
+    /// Returns the path currently watched by a watch, or `None` if it has been
+    /// removed.
+    pub fn watched_path(&self, id: WatchId) -> Option<&TreePath> {
+        self.watches.iter().find(|watch| watch.id == id).map(|watch| &watch.path)
+    }
+
+    /// Returns the wrapped editor.
+    pub fn into_inner(self) -> E {
+        self.editor
+    }
+
+    fn mark(&mut self, path: TreePath) {
+        for watch in &mut self.watches {
+            if watch.path.contains(&path) {
+                (watch.callback)(&path);
+            }
+        }
+        if self.dirty.last() != Some(&path) {
+            self.dirty.push(path);
+        }
+    }
+
+    fn mark_here(&mut self) {
+        let path = self.focus.path().clone();
+        self.mark(path);
+    }
+
+    // Records that a child was inserted at `index` among the children of the
+    // node at `parent`.
+    fn mark_inserted(&mut self, parent: TreePath, index: usize) {
+        self.reindex_watches(&parent, util::after_insert(index));
+        self.mark(parent);
+    }
+
+    // Records that the child at `index` was removed from the children of the
+    // node at `parent`.
+    fn mark_removed(&mut self, parent: TreePath, index: usize) {
+        self.reindex_watches(&parent, util::after_remove(index));
+        self.mark(parent);
+    }
+
+    // Records that two children of the node at `parent` were swapped.
+    fn mark_swapped(&mut self, parent: TreePath, index_a: usize, index_b: usize) {
+        self.reindex_watches(&parent, util::after_swap(index_a, index_b));
+        self.mark(parent);
+    }
+
+    // Records that the focus was replaced by another subtree.
+    fn mark_replaced(&mut self) {
+        let here = self.focus.path().clone();
+        self.watches.retain(|watch| watch.path.depth() < here.depth()
+                            || !here.contains(&watch.path));
+        self.mark(here);
+    }
+
+    // Maps the index of each watched path below `parent` at the depth of its
+    // children with `f`, dropping the watches for which `f` returns `None`.
+    fn reindex_watches<G>(&mut self, parent: &TreePath, f: G) where G: Fn(usize) -> Option<usize> {
+        self.watches.retain_mut(|watch| util::reindex_below(&mut watch.path, parent, &f));
+    }
+
+    // Records that a child was inserted at `index` among the children of the
+    // focus, and focuses on it.
+    fn inserted_child(&mut self, index: usize) {
+        let here = self.focus.path().clone();
+        self.mark_inserted(here, index);
+        self.focus.seek_child(index);
+    }
+
+    // Records that a sibling was inserted at `offset` from the focus, and
+    // focuses on it.
+    fn inserted_sibling(&mut self, offset: isize) {
+        let index = self.focus.sibling_index(offset);
+        self.mark_inserted(self.focus.parent().unwrap(), index);
+        self.focus.set_here_index(index);
+    }
+}
+
+impl<E: Editor + Deref> Deref for DirtyTracker<E> {
+    type Target = E::Target;
+
+    fn deref(&self) -> &E::Target {
+        &self.editor
+    }
+}
+
+impl<E: Editor + DerefMut> DerefMut for DirtyTracker<E> {
+    fn deref_mut(&mut self) -> &mut E::Target {
+        self.mark_here();
+        &mut self.editor
+    }
+}
+
+impl<E: Editor> Nav for DirtyTracker<E> {
+    fn child_count(&self) -> usize {
+        self.editor.child_count()
+    }
+
+    fn at_leaf(&self) -> bool {
+        self.editor.at_leaf()
+    }
+
+    fn at_root(&self) -> bool {
+        self.editor.at_root()
+    }
+
+    fn index_in_parent(&self) -> Option<usize> {
+        self.editor.index_in_parent()
+    }
+
+    fn seek_sibling(&mut self, offset: isize) -> bool {
+        let moved = self.editor.seek_sibling(offset);
+        if moved {
+            self.focus.seek_sibling(offset);
+        }
+        moved
+    }
+
+    fn seek_first_sibling(&mut self) {
+        self.editor.seek_first_sibling();
+        if !self.focus.is_start() {
+            self.focus.set_here_index(0);
+        }
+    }
+
+    fn seek_last_sibling(&mut self) {
+        if self.to_parent() {
+            let last_child_index = self.child_count() - 1;
+            self.seek_child(last_child_index);
+        }
+    }
+
+    fn seek_child(&mut self, index: usize) -> bool {
+        let moved = self.editor.seek_child(index);
+        if moved {
+            self.focus.seek_child(index);
+        }
+        moved
+    }
+
+    fn to_parent(&mut self) -> bool {
+        let moved = self.editor.to_parent();
+        if moved {
+            self.focus.pop();
+        }
+        moved
+    }
+
+    fn to_root(&mut self) {
+        self.editor.to_root();
+        self.focus.reset();
+    }
+}
+
+impl<E: Editor> Editor for DirtyTracker<E> {
+    type Data = E::Data;
+    type Tree = E::Tree;
+
+    fn push_leaf(&mut self, data: E::Data) {
+        let index = self.editor.child_count();
+        self.editor.push_leaf(data);
+        self.inserted_child(index);
+    }
+
+    fn push_child(&mut self, child: E::Tree) {
+        let index = self.editor.child_count();
+        self.editor.push_child(child);
+        self.inserted_child(index);
+    }
+
+    fn insert_leaf(&mut self, index: usize, data: E::Data) -> bool {
+        let inserted = self.editor.insert_leaf(index, data);
+        if inserted {
+            self.inserted_child(index);
+        }
+        inserted
+    }
+
+    fn insert_child(&mut self, index: usize, child: E::Tree) -> bool {
+        let inserted = self.editor.insert_child(index, child);
+        if inserted {
+            self.inserted_child(index);
+        }
+        inserted
+    }
+
+    fn insert_sibling_leaf(&mut self, offset: isize, data: E::Data) -> bool {
+        let inserted = self.editor.insert_sibling_leaf(offset, data);
+        if inserted {
+            self.inserted_sibling(offset);
+        }
+        inserted
+    }
+
+    fn insert_sibling(&mut self, offset: isize, sibling: E::Tree) -> bool {
+        let inserted = self.editor.insert_sibling(offset, sibling);
+        if inserted {
+            self.inserted_sibling(offset);
+        }
+        inserted
+    }
+
+    fn remove_focus_policy(&self) -> RemoveFocusPolicy {
+        self.editor.remove_focus_policy()
+    }
+
+    fn set_remove_focus_policy(&mut self, policy: RemoveFocusPolicy) -> bool {
+        self.editor.set_remove_focus_policy(policy)
+    }
+
+    fn remove(&mut self) -> E::Tree {
+        if self.editor.at_root() {
+            panic!["{}", NavError::AtRoot]
+        }
+        let parent = self.focus.parent().unwrap();
+        let (removed, index) = util::remove_focus(&mut self.editor, &mut self.focus);
+        self.mark_removed(parent, index);
+        removed
+    }
+
+    fn remove_child(&mut self, index: usize) -> Result<E::Tree, EditError> {
+        let removed = self.editor.remove_child(index)?;
+        let here = self.focus.path().clone();
+        self.mark_removed(here, index);
+        Ok(removed)
+    }
+
+    fn remove_sibling(&mut self, offset: isize) -> Option<E::Tree> {
+        if offset == 0 {
+            return if self.editor.at_root() { None } else { Some(self.remove()) }
+        }
+        let removed = self.editor.remove_sibling(offset);
+        if removed.is_some() {
+            let index = self.focus.sibling_index(offset);
+            self.mark_removed(self.focus.parent().unwrap(), index);
+            self.focus.sibling_removed(offset);
+        }
+        removed
+    }
+
+    fn swap(&mut self, other: &mut E::Tree) {
+        self.editor.swap(other);
+        self.mark_replaced();
+    }
+
+    fn swap_children(&mut self, index_a: usize, index_b: usize) -> bool {
+        let swapped = self.editor.swap_children(index_a, index_b);
+        if swapped && index_a != index_b {
+            let here = self.focus.path().clone();
+            self.mark_swapped(here, index_a, index_b);
+        }
+        swapped
+    }
+
+    fn swap_siblings(&mut self, offset_a: isize, offset_b: isize) -> bool {
+        let swapped = self.editor.swap_siblings(offset_a, offset_b);
+        if swapped && offset_a != offset_b {
+            let (index_a, index_b) =
+                (self.focus.sibling_index(offset_a), self.focus.sibling_index(offset_b));
+            self.mark_swapped(self.focus.parent().unwrap(), index_a, index_b);
+            self.focus.siblings_swapped(offset_a, offset_b);
+        }
+        swapped
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::{Editor, Nav, RemoveFocusPolicy};
+    use ::dirty::DirtyTracker;
+    use ::owned_tree;
+    use ::path::TreePath;
+
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn paths(paths: &[&str]) -> Vec<TreePath> {
+        paths.iter().map(|p| p.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn records_changed_subtrees() {
+        let mut t = owned_tree!["r", ["a", ["b"], ["c"]], ["d", ["e"]], ["f"]];
+        {
+            let mut e = DirtyTracker::new(t.view_mut());
+            assert![e.take_dirty_paths().is_empty()];
+            assert![e.seek_child(0)];
+            assert![e.seek_child(1)];
+            *e = "C";
+            assert![e.to_parent()];
+            assert![e.seek_sibling(1)];
+            e.push_leaf("g");
+            assert_eq!["/1/1", e.path().to_string()];
+            assert![e.to_parent()];
+            assert![e.seek_sibling(1)];
+            assert_eq!["f", *e];
+            assert_eq![paths(&["/0/1", "/1"]), e.take_dirty_paths()];
+            assert![e.take_dirty_paths().is_empty()];
+
+            assert![e.swap_siblings(0, -2)];
+            assert_eq!["/0", e.path().to_string()];
+            assert_eq!["f", *e];
+            assert_eq![paths(&["/"]), e.take_dirty_paths()];
+        }
+        assert_eq![owned_tree!["r", ["f"], ["d", ["e"], ["g"]], ["a", ["b"], ["C"]]], t];
+    }
+
+    #[test]
+    fn coalesces_nested_changes() {
+        let mut t = owned_tree!["r", ["a", ["b", ["c"]]], ["d"]];
+        let mut e = DirtyTracker::new(t.view_mut());
+        assert![e.seek_child(0)];
+        assert![e.seek_child(0)];
+        assert![e.seek_child(0)];
+        *e = "C";
+        assert![e.to_parent()];
+        e.push_leaf("x");
+        e.to_root();
+        assert![e.seek_child(1)];
+        *e = "D";
+        assert![e.to_parent()];
+        assert![e.seek_child(0)];
+        assert![e.insert_sibling_leaf(0, "y")];
+        assert_eq!["/0", e.path().to_string()];
+        assert_eq![paths(&["/"]), e.take_dirty_paths()];
+    }
+
+    #[test]
+    fn tracks_focus_through_removal() {
+        for &(policy, expected) in [(RemoveFocusPolicy::PreferLeft, "a"),
+                                    (RemoveFocusPolicy::PreferRight, "c"),
+                                    (RemoveFocusPolicy::Parent, "r")].iter() {
+            let mut t = owned_tree!["r", ["a"], ["b", ["x"]], ["c"]];
+            let mut e = DirtyTracker::new(t.view_mut());
+            assert![e.set_remove_focus_policy(policy)];
+            assert![e.seek_child(1)];
+            assert![e.seek_child(0)];
+            assert![e.to_parent()];
+            assert_eq![owned_tree!["b", ["x"]], e.remove()];
+            assert_eq![expected, *e];
+            let moved = expected != "r";
+            assert_eq![moved, e.to_parent()];
+            assert![e.at_root()];
+            assert_eq![TreePath::root(), *e.path()];
+            assert_eq![paths(&["/"]), e.take_dirty_paths()];
+        }
+    }
+
+    #[test]
+    fn tracks_focus_through_sibling_removal() {
+        let mut t = owned_tree!["r", ["a"], ["b"], ["c", ["d"]]];
+        let mut e = DirtyTracker::new(t.view_mut());
+        assert![e.seek_child(2)];
+        assert![e.remove_sibling(-2).is_some()];
+        assert_eq!["/1", e.path().to_string()];
+        assert![e.remove_child(0).is_ok()];
+        assert![e.remove_child(0).is_err()];
+        assert_eq![paths(&["/"]), e.take_dirty_paths()];
+        assert![e.seek_sibling(-1)];
+        assert_eq!["/0", e.path().to_string()];
+        assert_eq!["b", *e];
+    }
+
+    #[test]
+    fn watches_follow_their_subtrees() {
+        let mut t = owned_tree!["r", ["a", ["b"]], ["c", ["d"]]];
+        let mut e = DirtyTracker::new(t.view_mut());
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let c = {
+            let log = log.clone();
+            e.watch("/1".parse().unwrap(), move |path| log.borrow_mut().push(path.to_string()))
+        };
+        let a = e.watch("/0/0".parse().unwrap(), |_| panic!["removed subtree changed"]);
+
+        assert![e.seek_child(0)];
+        assert![e.insert_sibling_leaf(0, "x")];
+        assert_eq![Some(&"/2".parse().unwrap()), e.watched_path(c)];
+        assert_eq![Some(&"/1/0".parse().unwrap()), e.watched_path(a)];
+        assert![log.borrow().is_empty()];
+
+        assert![e.seek_sibling(2)];
+        assert![e.seek_child(0)];
+        *e = "D";
+        assert![e.to_parent()];
+        e.push_leaf("e");
+        assert_eq![vec!["/2/0", "/2"], *log.borrow()];
+
+        assert![e.to_parent()];
+        assert![e.swap_siblings(0, -2)];
+        assert_eq![Some(&"/0".parse().unwrap()), e.watched_path(c)];
+        assert![e.seek_sibling(1)];
+        assert![e.remove_child(0).is_ok()];
+        assert_eq![None, e.watched_path(a)];
+        assert![!e.unwatch(a)];
+        assert_eq![2, log.borrow().len()];
+
+        assert![e.unwatch(c)];
+        e.to_root();
+        assert![e.seek_child(0)];
+        *e = "C";
+        assert_eq![2, log.borrow().len()];
+    }
+
+    #[test]
+    fn watches_end_when_their_node_is_replaced() {
+        let mut t = owned_tree!["r", ["a", ["b"]], ["c"]];
+        let mut e = DirtyTracker::new(t.view_mut());
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let r = {
+            let log = log.clone();
+            e.watch(TreePath::root(), move |path| log.borrow_mut().push(path.to_string()))
+        };
+        let a = e.watch("/0".parse().unwrap(), |_| panic!["replaced subtree changed"]);
+        let b = e.watch("/0/0".parse().unwrap(), |_| panic!["replaced subtree changed"]);
+
+        assert![e.seek_child(0)];
+        let mut other = owned_tree!["x", ["y"]];
+        e.swap(&mut other);
+        assert_eq![owned_tree!["a", ["b"]], other];
+        assert_eq![None, e.watched_path(a)];
+        assert_eq![None, e.watched_path(b)];
+        assert![e.seek_child(0)];
+        *e = "Y";
+        assert![e.to_parent()];
+        assert![e.to_parent()];
+        let c = e.watch("/1".parse().unwrap(), |_| panic!["replaced subtree changed"]);
+        e.replace_child(1, owned_tree!["z"]);
+        assert_eq![None, e.watched_path(c)];
+        assert_eq![vec!["/0", "/0/0", "/1"], *log.borrow()];
+        assert_eq![Some(&TreePath::root()), e.watched_path(r)];
+    }
+}
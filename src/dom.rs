@@ -0,0 +1,334 @@
+use ::owned::Tree;
+
+use quick_xml::Reader;
+use quick_xml::encoding::Decoder;
+use quick_xml::escape::escape;
+use quick_xml::events::{BytesStart, Event};
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Write};
+
+/// A node of a document: an element, whose children are the node's children in
+/// the tree, or a run of text, which is always a leaf.
+///
+/// A document is an `owned::Tree<Node>`, so it is navigated and edited with
+/// the usual views:
+///
+/// ```
+/// use entmut::Editor;
+/// use entmut::dom::{self, Node};
+/// use entmut::owned::Tree;
+///
+/// let mut doc = dom::parse("<ul><li>one</li></ul>").unwrap();
+/// {
+///     let mut e = doc.view_mut();
+///     e.set_attr("class", "list");
+///     e.push_child(Tree::new(Node::element("li"), vec![Tree::leaf(Node::text("two"))]));
+/// }
+/// let mut out = Vec::new();
+/// dom::write(&doc, &mut out).unwrap();
+/// assert_eq![String::from_utf8(out).unwrap(),
+///            r#"<ul class="list"><li>one</li><li>two</li></ul>"#];
+/// ```
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Node {
+    Element(Element),
+    Text(String),
+}
+
+/// The name and attributes of an element.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Element {
+    pub name: String,
+    pub attrs: BTreeMap<String, String>,
+}
+
+impl Node {
+    /// Creates an element with no attributes.
+    pub fn element(name: &str) -> Self {
+        Node::Element(Element { name: name.to_string(), attrs: BTreeMap::new(), })
+    }
+
+    pub fn text(text: &str) -> Self {
+        Node::Text(text.to_string())
+    }
+
+    /// Returns the element name, or `None` for text.
+    pub fn name(&self) -> Option<&str> {
+        match *self {
+            Node::Element(ref e) => Some(&e.name),
+            Node::Text(_) => None,
+        }
+    }
+
+    /// Returns the value of an attribute, or `None` if this is text or does not
+    /// have the attribute.
+    pub fn attr(&self, key: &str) -> Option<&str> {
+        match *self {
+            Node::Element(ref e) => e.attrs.get(key).map(|v| &v[..]),
+            Node::Text(_) => None,
+        }
+    }
+
+    /// Sets an attribute, returning its previous value.
+    ///
+    /// Panics if this is text.
+    pub fn set_attr(&mut self, key: &str, value: &str) -> Option<String> {
+        match *self {
+            Node::Element(ref mut e) => e.attrs.insert(key.to_string(), value.to_string()),
+            Node::Text(_) => panic!["text nodes do not have attributes"],
+        }
+    }
+
+    /// Returns the text, or `None` for an element.
+    pub fn as_text(&self) -> Option<&str> {
+        match *self {
+            Node::Element(_) => None,
+            Node::Text(ref text) => Some(text),
+        }
+    }
+}
+
+/// Returns the concatenated text of all text nodes in `tree`, in document
+/// order.
+pub fn text_content(tree: &Tree<Node>) -> String {
+    let mut text = String::new();
+    let mut stack = vec![tree];
+    while let Some(t) = stack.pop() {
+        if let Some(s) = t.data().as_text() {
+            text.push_str(s);
+        }
+        stack.extend(t.children().iter().rev());
+    }
+    text
+}
+
+/// Error returned when parsing malformed markup.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseDomError {
+    /// Byte offset in the input at which the error was found.
+    pub offset: usize,
+    pub kind: ParseDomErrorKind,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ParseDomErrorKind {
+    /// The markup is not well-formed. Holds `quick-xml`'s description of the
+    /// problem.
+    Xml(String),
+    MissingRoot,
+    MultipleRoots,
+    TextOutsideRoot,
+    UnclosedElement,
+}
+
+impl fmt::Display for ParseDomError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "at byte {}: ", self.offset)?;
+        match self.kind {
+            ParseDomErrorKind::Xml(ref message) => f.write_str(message),
+            ParseDomErrorKind::MissingRoot => f.write_str("missing root element"),
+            ParseDomErrorKind::MultipleRoots => f.write_str("more than one root element"),
+            ParseDomErrorKind::TextOutsideRoot => f.write_str("text outside root element"),
+            ParseDomErrorKind::UnclosedElement => f.write_str("unclosed element"),
+        }
+    }
+}
+
+impl Error for ParseDomError {}
+
+/// Parses a document with a single root element, using `quick-xml`.
+///
+/// Elements, attributes, text, and CDATA sections become nodes, with the
+/// predefined and numeric character references replaced. Comments, processing
+/// instructions, and declarations are skipped, as is text made only of
+/// whitespace. HTML is read only if it is well-formed XML: there are no rules
+/// for implied or void tags.
+pub fn parse(input: &str) -> Result<Tree<Node>, ParseDomError> {
+    let mut reader = Reader::from_str(input);
+    // Open elements and the children parsed for each so far.
+    let mut stack: Vec<(Element, Vec<Tree<Node>>)> = Vec::new();
+    let mut root = None;
+    loop {
+        let offset = reader.buffer_position() as usize;
+        let error = |kind| ParseDomError { offset, kind, };
+        let event = reader.read_event().map_err(|e| {
+            ParseDomError { offset: reader.error_position() as usize,
+                            kind: ParseDomErrorKind::Xml(e.to_string()), }
+        })?;
+        let xml_error = |e: quick_xml::Error| error(ParseDomErrorKind::Xml(e.to_string()));
+        let finished = match event {
+            Event::Start(start) => {
+                stack.push((element(&start, reader.decoder()).map_err(xml_error)?, Vec::new()));
+                None
+            },
+            Event::Empty(start) => {
+                let element = element(&start, reader.decoder()).map_err(xml_error)?;
+                Some(Tree::leaf(Node::Element(element)))
+            },
+            Event::End(_) => {
+                let (element, children) = stack.pop().unwrap();
+                Some(Tree::new(Node::Element(element), children))
+            },
+            Event::Text(text) => {
+                let text = text.unescape().map_err(xml_error)?;
+                if text.trim().is_empty() {
+                    None
+                } else {
+                    Some(Tree::leaf(Node::Text(text.into_owned())))
+                }
+            },
+            Event::CData(text) => {
+                let text = text.decode().map_err(|e| xml_error(e.into()))?;
+                Some(Tree::leaf(Node::Text(text.into_owned())))
+            },
+            Event::Comment(_) | Event::Decl(_) | Event::PI(_) | Event::DocType(_) => None,
+            Event::Eof => {
+                return match (stack.is_empty(), root) {
+                    (true, Some(root)) => Ok(root),
+                    (true, None) => Err(error(ParseDomErrorKind::MissingRoot)),
+                    (false, _) => Err(error(ParseDomErrorKind::UnclosedElement)),
+                }
+            },
+        };
+        if let Some(tree) = finished {
+            match stack.last_mut() {
+                Some(&mut (_, ref mut children)) => children.push(tree),
+                None if tree.data().as_text().is_some() =>
+                    return Err(error(ParseDomErrorKind::TextOutsideRoot)),
+                None if root.is_none() => root = Some(tree),
+                None => return Err(error(ParseDomErrorKind::MultipleRoots)),
+            }
+        }
+    }
+}
+
+// Returns the name and attributes of the element that `start` opens.
+fn element(start: &BytesStart, decoder: Decoder) -> Result<Element, quick_xml::Error> {
+    let mut element = Element { name: decoder.decode(start.name().as_ref())?.into_owned(),
+                                attrs: BTreeMap::new(), };
+    for attr in start.attributes() {
+        let attr = attr?;
+        let key = decoder.decode(attr.key.as_ref())?.into_owned();
+        element.attrs.insert(key, attr.decode_and_unescape_value(decoder)?.into_owned());
+    }
+    Ok(element)
+}
+
+/// Writes `tree` as markup that `parse` reads back into an equal tree, provided
+/// no text node is empty, made only of whitespace, or adjacent to another.
+/// Elements without children are written as self-closing tags.
+pub fn write<W: Write>(tree: &Tree<Node>, w: &mut W) -> io::Result<()> {
+    // Trees whose start tags have been written, with the next child to visit.
+    let mut stack = vec![(tree, 0)];
+    write_start(tree, w)?;
+    while let Some(&mut (t, ref mut next)) = stack.last_mut() {
+        match t.children().get(*next) {
+            Some(child) => {
+                *next += 1;
+                write_start(child, w)?;
+                stack.push((child, 0));
+            },
+            None => {
+                stack.pop();
+                if let Node::Element(ref e) = *t.data() {
+                    if !t.children().is_empty() {
+                        write!(w, "</{}>", e.name)?;
+                    }
+                }
+            },
+        }
+    }
+    Ok(())
+}
+
+fn write_start<W: Write>(tree: &Tree<Node>, w: &mut W) -> io::Result<()> {
+    match *tree.data() {
+        Node::Text(ref text) => w.write_all(escape(&text[..]).as_bytes()),
+        Node::Element(ref e) => {
+            write!(w, "<{}", e.name)?;
+            for (key, value) in e.attrs.iter() {
+                write!(w, " {}=\"{}\"", key, escape(&value[..]))?;
+            }
+            w.write_all(if tree.children().is_empty() { b"/>" } else { b">" })
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::dom::{parse, text_content, write, Node, ParseDomError, ParseDomErrorKind};
+    use ::owned::Tree;
+    use ::{Editor, Nav};
+
+    fn to_string(tree: &Tree<Node>) -> String {
+        let mut out = Vec::new();
+        write(tree, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn parses_elements_attributes_and_text() {
+        let doc = parse(r#"<?xml version="1.0"?>
+            <!DOCTYPE html>
+            <html lang='en'>
+              <!-- comment -->
+              <body class="a &amp; b"><p>x &lt; y&#33;</p><br/></body>
+            </html>"#).unwrap();
+        assert_eq![Some("html"), doc.data().name()];
+        assert_eq![Some("en"), doc.data().attr("lang")];
+        assert_eq![1, doc.children().len()];
+        let body = &doc.children()[0];
+        assert_eq![Some("a & b"), body.data().attr("class")];
+        assert_eq![2, body.children().len()];
+        assert_eq![Some("x < y!"), body.children()[0].children()[0].data().as_text()];
+        assert_eq!["x < y!", text_content(&doc)];
+    }
+
+    #[test]
+    fn edits_with_views() {
+        let mut doc = parse("<ul><li>a</li><li>b</li><li>c</li></ul>").unwrap();
+        {
+            let mut e = doc.view_mut();
+            assert![e.seek_child(1)];
+            e.set_attr("id", "second");
+            e.remove_sibling(1);
+            e.insert_sibling(0, Tree::new(Node::element("li"), vec![Tree::leaf(Node::text("z"))]));
+        }
+        assert_eq![r#"<ul><li>a</li><li>z</li><li id="second">b</li></ul>"#, to_string(&doc)];
+    }
+
+    #[test]
+    fn round_trips() {
+        let text = r#"<a x="1 &quot;2&quot;"><b/>t &amp; u<c><d y="&lt;"/></c></a>"#;
+        let doc = parse(text).unwrap();
+        assert_eq![text, to_string(&doc)];
+        assert_eq![doc, parse(&to_string(&doc)).unwrap()];
+    }
+
+    #[test]
+    fn reads_cdata_as_text() {
+        let doc = parse("<a><![CDATA[x < y]]></a>").unwrap();
+        assert_eq!["x < y", text_content(&doc)];
+        assert_eq!["<a>x &lt; y</a>", to_string(&doc)];
+    }
+
+    #[test]
+    fn rejects_malformed_markup() {
+        let error = |input| parse(input).unwrap_err();
+        let e = error("<a><b></a>");
+        assert_eq![6, e.offset];
+        assert![matches![e.kind, ParseDomErrorKind::Xml(_)]];
+        assert![matches![error("<a>&nope;</a>").kind, ParseDomErrorKind::Xml(_)]];
+        assert![matches![error("<a x=1/>").kind, ParseDomErrorKind::Xml(_)]];
+        assert_eq![ParseDomError { offset: 6, kind: ParseDomErrorKind::UnclosedElement, },
+                   error("<a><b>")];
+        assert_eq![ParseDomError { offset: 4, kind: ParseDomErrorKind::MultipleRoots, },
+                   error("<a/><b/>")];
+        assert_eq![ParseDomError { offset: 0, kind: ParseDomErrorKind::TextOutsideRoot, },
+                   error("hi<a/>")];
+        assert_eq![ParseDomError { offset: 1, kind: ParseDomErrorKind::MissingRoot, }, error(" ")];
+    }
+}
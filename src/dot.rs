@@ -0,0 +1,494 @@
+use ::owned::Tree;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+/// Parses a tree-shaped Graphviz digraph, such as
+///
+/// ```text
+/// digraph {
+///     a -> b -> c;
+///     a -> d [color=red];
+///     d [label="leaf d"];
+/// }
+/// ```
+///
+/// into a tree whose data is each node's `label` attribute, or its name if it
+/// has no label. Children are ordered by the first edge that reaches them.
+///
+/// This accepts the subset of DOT needed to describe trees: node, edge, and
+/// attribute statements, with quoted or unquoted IDs and comments. Attributes
+/// other than node labels are ignored. Subgraphs, ports, HTML strings, and
+/// undirected graphs are not supported. The graph must have exactly one node
+/// without a parent, and every other node must have exactly one parent.
+pub fn parse(input: &str) -> Result<Tree<String>, ParseDotError> {
+    let mut parser = Parser { tokens: tokenize(input)?, next: 0, graph: Graph::new(), };
+    parser.graph_stmt()?;
+    parser.graph.into_tree()
+}
+
+/// Error returned when parsing DOT that is malformed, unsupported, or does not
+/// describe a tree.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ParseDotError {
+    /// A token other than the one expected was found on the given line.
+    Unexpected { line: usize, found: String, expected: &'static str },
+    /// The input ended where `expected` was required.
+    UnexpectedEnd { expected: &'static str },
+    /// A quoted string starting on the given line is not closed.
+    UnterminatedString { line: usize },
+    /// The input uses a DOT feature that is not supported.
+    Unsupported { line: usize, feature: &'static str },
+    /// An edge on the given line gives `node` a second parent.
+    MultipleParents { line: usize, node: String },
+    /// The graph has no nodes.
+    Empty,
+    /// More than one node has no parent.
+    MultipleRoots(Vec<String>),
+    /// The graph contains a cycle through `node`.
+    Cycle { node: String },
+}
+
+impl fmt::Display for ParseDotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseDotError::Unexpected { line, ref found, expected } =>
+                write!(f, "line {}: expected {}, found {:?}", line, expected, found),
+            ParseDotError::UnexpectedEnd { expected } =>
+                write!(f, "expected {}, found end of input", expected),
+            ParseDotError::UnterminatedString { line } =>
+                write!(f, "line {}: unterminated string", line),
+            ParseDotError::Unsupported { line, feature } =>
+                write!(f, "line {}: {} are not supported", line, feature),
+            ParseDotError::MultipleParents { line, ref node } =>
+                write!(f, "line {}: node {:?} has more than one parent", line, node),
+            ParseDotError::Empty => f.write_str("graph has no nodes"),
+            ParseDotError::MultipleRoots(ref roots) =>
+                write!(f, "graph has more than one root: {:?}", roots),
+            ParseDotError::Cycle { ref node } => write!(f, "graph has a cycle through {:?}", node),
+        }
+    }
+}
+
+impl Error for ParseDotError {}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Token {
+    /// An ID, which may be a keyword if it was not quoted.
+    Id { text: String, quoted: bool },
+    Arrow,
+    Punct(char),
+}
+
+impl Token {
+    fn is_keyword(&self, keyword: &str) -> bool {
+        match *self {
+            Token::Id { ref text, quoted: false } => text.eq_ignore_ascii_case(keyword),
+            _ => false,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match *self {
+            Token::Id { ref text, .. } => text.clone(),
+            Token::Arrow => "->".to_string(),
+            Token::Punct(c) => c.to_string(),
+        }
+    }
+}
+
+fn is_id_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.' || !c.is_ascii()
+}
+
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>, ParseDotError> {
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => line += 1,
+            c if c.is_whitespace() => (),
+            '#' => {
+                while chars.peek().is_some_and(|&c| c != '\n') {
+                    chars.next();
+                }
+            },
+            '/' if chars.peek() == Some(&'/') => {
+                while chars.peek().is_some_and(|&c| c != '\n') {
+                    chars.next();
+                }
+            },
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut last = ' ';
+                loop {
+                    match chars.next() {
+                        Some('/') if last == '*' => break,
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            last = c;
+                        },
+                        None => return Err(ParseDotError::UnexpectedEnd { expected: "*/", }),
+                    }
+                }
+            },
+            '-' if chars.peek() == Some(&'>') => {
+                chars.next();
+                tokens.push((Token::Arrow, line));
+            },
+            '-' if chars.peek() == Some(&'-') =>
+                return Err(ParseDotError::Unsupported { line, feature: "undirected edges", }),
+            '"' => {
+                let start = line;
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('"') => text.push('"'),
+                            Some('\\') => text.push('\\'),
+                            Some('\n') => line += 1,
+                            Some(c) => {
+                                text.push('\\');
+                                text.push(c);
+                            },
+                            None => return Err(ParseDotError::UnterminatedString { line: start, }),
+                        },
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            text.push(c);
+                        },
+                        None => return Err(ParseDotError::UnterminatedString { line: start, }),
+                    }
+                }
+                tokens.push((Token::Id { text, quoted: true, }, start));
+            },
+            '<' => return Err(ParseDotError::Unsupported { line, feature: "HTML strings", }),
+            ':' => return Err(ParseDotError::Unsupported { line, feature: "ports", }),
+            '{' | '}' | '[' | ']' | '=' | ';' | ',' => tokens.push((Token::Punct(c), line)),
+            c if is_id_char(c) || c == '-' => {
+                let mut text = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if !is_id_char(c) {
+                        break
+                    }
+                    text.push(c);
+                    chars.next();
+                }
+                tokens.push((Token::Id { text, quoted: false, }, line));
+            },
+            c => return Err(ParseDotError::Unexpected { line, found: c.to_string(),
+                                                        expected: "a DOT token", }),
+        }
+    }
+    Ok(tokens)
+}
+
+struct Node {
+    name: String,
+    label: Option<String>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+}
+
+/// Nodes and edges read so far.
+struct Graph {
+    nodes: Vec<Node>,
+    indices: HashMap<String, usize>,
+}
+
+impl Graph {
+    fn new() -> Self {
+        Graph { nodes: Vec::new(), indices: HashMap::new(), }
+    }
+
+    fn node(&mut self, name: String) -> usize {
+        if let Some(&index) = self.indices.get(&name) {
+            return index
+        }
+        let index = self.nodes.len();
+        self.indices.insert(name.clone(), index);
+        self.nodes.push(Node { name, label: None, parent: None, children: Vec::new(), });
+        index
+    }
+
+    fn edge(&mut self, parent: usize, child: usize, line: usize) -> Result<(), ParseDotError> {
+        if self.nodes[child].parent.is_some() {
+            return Err(ParseDotError::MultipleParents { line, node: self.nodes[child].name.clone(), })
+        }
+        if parent == child {
+            return Err(ParseDotError::Cycle { node: self.nodes[child].name.clone(), })
+        }
+        self.nodes[child].parent = Some(parent);
+        self.nodes[parent].children.push(child);
+        Ok(())
+    }
+
+    fn into_tree(self) -> Result<Tree<String>, ParseDotError> {
+        let roots: Vec<usize> = (0..self.nodes.len())
+            .filter(|&i| self.nodes[i].parent.is_none())
+            .collect();
+        let root = match roots.len() {
+            0 if self.nodes.is_empty() => return Err(ParseDotError::Empty),
+            0 => return Err(ParseDotError::Cycle { node: self.nodes[0].name.clone(), }),
+            1 => roots[0],
+            _ => return Err(ParseDotError::MultipleRoots(
+                roots.iter().map(|&i| self.nodes[i].name.clone()).collect())),
+        };
+        // Every node has one parent except the root, so any node that is not
+        // reachable from the root is on a cycle.
+        let mut reached = 0;
+        let mut nodes: Vec<Option<Node>> = self.nodes.into_iter().map(Some).collect();
+        let mut stack = vec![(nodes[root].take().unwrap(), 0, Vec::new())];
+        loop {
+            let next = {
+                let top = stack.last_mut().unwrap();
+                let next = top.0.children.get(top.1).cloned();
+                top.1 += 1;
+                next
+            };
+            match next {
+                Some(child) => {
+                    let child = nodes[child].take().unwrap();
+                    let count = child.children.len();
+                    stack.push((child, 0, Vec::with_capacity(count)));
+                },
+                None => {
+                    reached += 1;
+                    let (node, _, children) = stack.pop().unwrap();
+                    let tree = Tree::new(node.label.unwrap_or(node.name), children);
+                    match stack.last_mut() {
+                        Some(parent) => parent.2.push(tree),
+                        None => {
+                            return match nodes.into_iter().flatten().next() {
+                                Some(node) => Err(ParseDotError::Cycle { node: node.name, }),
+                                None => {
+                                    debug_assert![reached > 0];
+                                    Ok(tree)
+                                },
+                            }
+                        },
+                    }
+                },
+            }
+        }
+    }
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    next: usize,
+    graph: Graph,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next).map(|(token, _)| token)
+    }
+
+    fn advance(&mut self, expected: &'static str) -> Result<(Token, usize), ParseDotError> {
+        match self.tokens.get(self.next) {
+            Some(token) => {
+                self.next += 1;
+                Ok(token.clone())
+            },
+            None => Err(ParseDotError::UnexpectedEnd { expected, }),
+        }
+    }
+
+    fn unexpected(token: &Token, line: usize, expected: &'static str) -> ParseDotError {
+        ParseDotError::Unexpected { line, found: token.describe(), expected, }
+    }
+
+    fn punct(&mut self, c: char, expected: &'static str) -> Result<(), ParseDotError> {
+        match self.advance(expected)? {
+            (Token::Punct(found), _) if found == c => Ok(()),
+            (token, line) => Err(Parser::unexpected(&token, line, expected)),
+        }
+    }
+
+    fn id(&mut self, expected: &'static str) -> Result<(String, usize), ParseDotError> {
+        match self.advance(expected)? {
+            (Token::Id { text, .. }, line) => Ok((text, line)),
+            (token, line) => Err(Parser::unexpected(&token, line, expected)),
+        }
+    }
+
+    fn graph_stmt(&mut self) -> Result<(), ParseDotError> {
+        if self.peek().is_some_and(|t| t.is_keyword("strict")) {
+            self.next += 1;
+        }
+        match self.advance("digraph")? {
+            (ref token, _) if token.is_keyword("digraph") => (),
+            (ref token, line) if token.is_keyword("graph") =>
+                return Err(ParseDotError::Unsupported { line, feature: "undirected graphs", }),
+            (token, line) => return Err(Parser::unexpected(&token, line, "digraph")),
+        }
+        if let Some(&Token::Id { .. }) = self.peek() {
+            self.next += 1;
+        }
+        self.punct('{', "{")?;
+        loop {
+            match self.peek() {
+                Some(&Token::Punct('}')) => {
+                    self.next += 1;
+                    break
+                },
+                Some(&Token::Punct(';')) => self.next += 1,
+                _ => self.stmt()?,
+            }
+        }
+        match self.tokens.get(self.next) {
+            None => Ok(()),
+            Some(&(ref token, line)) => Err(Parser::unexpected(token, line, "end of input")),
+        }
+    }
+
+    fn stmt(&mut self) -> Result<(), ParseDotError> {
+        let (first, line) = self.advance("a statement")?;
+        if first.is_keyword("subgraph") || first == Token::Punct('{') {
+            return Err(ParseDotError::Unsupported { line, feature: "subgraphs", })
+        }
+        if first.is_keyword("graph") || first.is_keyword("node") || first.is_keyword("edge") {
+            self.attr_list()?;
+            return Ok(())
+        }
+        let name = match first {
+            Token::Id { text, .. } => text,
+            token => return Err(Parser::unexpected(&token, line, "a statement")),
+        };
+        match self.peek() {
+            Some(&Token::Punct('=')) => {
+                self.next += 1;
+                self.id("an attribute value")?;
+            },
+            Some(&Token::Arrow) => {
+                let mut parent = self.graph.node(name);
+                while let Some(&Token::Arrow) = self.peek() {
+                    self.next += 1;
+                    let (child_name, line) = self.id("a node ID")?;
+                    let child = self.graph.node(child_name);
+                    self.graph.edge(parent, child, line)?;
+                    parent = child;
+                }
+                self.attr_list()?;
+            },
+            _ => {
+                let node = self.graph.node(name);
+                if let Some(label) = self.attr_list()?.remove("label") {
+                    self.graph.nodes[node].label = Some(label);
+                }
+            },
+        }
+        Ok(())
+    }
+
+    // Parses zero or more bracketed attribute lists.
+    fn attr_list(&mut self) -> Result<HashMap<String, String>, ParseDotError> {
+        let mut attrs = HashMap::new();
+        while let Some(&Token::Punct('[')) = self.peek() {
+            self.next += 1;
+            loop {
+                match self.peek() {
+                    Some(&Token::Punct(']')) => {
+                        self.next += 1;
+                        break
+                    },
+                    Some(&Token::Punct(',')) | Some(&Token::Punct(';')) => self.next += 1,
+                    _ => {
+                        let (key, _) = self.id("an attribute name")?;
+                        self.punct('=', "=")?;
+                        let (value, _) = self.id("an attribute value")?;
+                        attrs.insert(key, value);
+                    },
+                }
+            }
+        }
+        Ok(attrs)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::dot::{parse, ParseDotError};
+    use ::owned_tree;
+
+    fn tree(data: &[&str]) -> ::owned::Tree<String> {
+        let mut children = data[1..].iter().map(|d| owned_tree![d.to_string()]).collect::<Vec<_>>();
+        let mut t = owned_tree![data[0].to_string()];
+        for child in children.drain(..) {
+            t.push_child(child);
+        }
+        t
+    }
+
+    #[test]
+    fn parses_edges_labels_and_comments() {
+        let t = parse(r#"
+            // A small tree.
+            strict digraph G {
+                graph [rankdir=LR];
+                node [shape=box]
+                a -> b -> c; a -> "d e" [color="red"]
+                /* Labels replace
+                   node names. */
+                "d e" [label="D \"E\""];
+                # More children of a.
+                a -> 1.5
+            }"#).unwrap();
+        assert_eq![t, owned_tree!["a".to_string(),
+                                  ["b".to_string(), ["c".to_string()]],
+                                  ["D \"E\"".to_string()],
+                                  ["1.5".to_string()]]];
+    }
+
+    #[test]
+    fn parses_single_node() {
+        assert_eq![parse("digraph { root }").unwrap(), tree(&["root"])];
+        assert_eq![parse("DiGraph{r->x;r->y;}").unwrap(), tree(&["r", "x", "y"])];
+    }
+
+    #[test]
+    fn round_trips_debug_trees() {
+        let t = parse("digraph { a -> b; a -> c; c -> d; c -> e; }").unwrap();
+        assert_eq![format!["{:?}", t], r#"("a" ("b") ("c" ("d") ("e")))"#];
+    }
+
+    #[test]
+    fn rejects_non_trees() {
+        assert_eq![parse("digraph { a -> b; c -> b; }"),
+                   Err(ParseDotError::MultipleParents { line: 1, node: "b".to_string(), })];
+        assert_eq![parse("digraph { a; b; }"),
+                   Err(ParseDotError::MultipleRoots(vec!["a".to_string(), "b".to_string()]))];
+        assert_eq![parse("digraph { r -> a -> b -> a }"),
+                   Err(ParseDotError::MultipleParents { line: 1, node: "a".to_string(), })];
+        assert_eq![parse("digraph { r; a -> b -> a }"),
+                   Err(ParseDotError::Cycle { node: "a".to_string(), })];
+        assert_eq![parse("digraph { a -> a }"), Err(ParseDotError::Cycle { node: "a".to_string(), })];
+        assert_eq![parse("digraph { }"), Err(ParseDotError::Empty)];
+    }
+
+    #[test]
+    fn rejects_malformed_and_unsupported_input() {
+        assert_eq![parse("graph { a }"),
+                   Err(ParseDotError::Unsupported { line: 1, feature: "undirected graphs", })];
+        assert_eq![parse("digraph { a -- b }"),
+                   Err(ParseDotError::Unsupported { line: 1, feature: "undirected edges", })];
+        assert_eq![parse("digraph {\n a -> b:n }"),
+                   Err(ParseDotError::Unsupported { line: 2, feature: "ports", })];
+        assert_eq![parse("digraph { subgraph x { a } }"),
+                   Err(ParseDotError::Unsupported { line: 1, feature: "subgraphs", })];
+        assert_eq![parse("digraph { a -> }"),
+                   Err(ParseDotError::Unexpected { line: 1, found: "}".to_string(),
+                                                   expected: "a node ID", })];
+        assert_eq![parse("digraph { a -> b"), Err(ParseDotError::UnexpectedEnd { expected: "a statement", })];
+        assert_eq![parse("digraph {\n\"a }"), Err(ParseDotError::UnterminatedString { line: 2, })];
+        assert![parse("digraph { a } b").is_err()];
+    }
+}
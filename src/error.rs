@@ -0,0 +1,186 @@
+use ::dot::ParseDotError;
+use ::indented::ParseIndentedError;
+#[cfg(feature = "dom")]
+use ::dom::ParseDomError;
+use ::path::{ParsePathError, TreePath};
+
+use std::error::Error;
+use std::fmt;
+
+/// Failure of an edit to a tree's topology. The tree is left unchanged.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum EditError {
+    /// There is no child at `index`, since the node has only `child_count`
+    /// children.
+    ChildOutOfRange { index: usize, child_count: usize },
+    /// A guard refused the edit. See `guard::Guarded`.
+    Refused,
+    /// A reordering of `child_count` children did not list each child's index
+    /// exactly once.
+    NotPermutation { child_count: usize },
+    /// The edit would change a frozen subtree. See `freeze::Freezer`.
+    Frozen,
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EditError::ChildOutOfRange { index, child_count } =>
+                write!(f, "no child at index {} (only {} children)", index, child_count),
+            EditError::Refused => f.write_str("edit refused by guard"),
+            EditError::NotPermutation { child_count } =>
+                write!(f, "not a permutation of {} children", child_count),
+            EditError::Frozen => f.write_str("edit inside a frozen subtree"),
+        }
+    }
+}
+
+impl Error for EditError {}
+
+/// A constraint on a tree that an edit would break, or that a tree breaks.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Violation {
+    path: Option<TreePath>,
+    message: String,
+}
+
+impl Violation {
+    pub fn new<S: Into<String>>(message: S) -> Self {
+        Violation { path: None, message: message.into(), }
+    }
+
+    /// Creates a violation of a constraint on the node at `path`.
+    pub fn at<S: Into<String>>(path: TreePath, message: S) -> Self {
+        Violation { path: Some(path), message: message.into(), }
+    }
+
+    /// Returns the path of the node whose constraint is broken, if known.
+    pub fn path(&self) -> Option<&TreePath> {
+        self.path.as_ref()
+    }
+
+    /// Returns a description of the constraint that is broken.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.path {
+            Some(ref path) => write!(f, "{}: {}", path, self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+impl Error for Violation {}
+
+/// Failure to reach a node.
+///
+/// Navigation methods that can fail report it by returning `false`, but
+/// operations that require a node to exist panic with one of these messages
+/// when it does not.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum NavError {
+    /// The operation needs a parent or siblings, but the focus is the root.
+    AtRoot,
+    /// There is no child at `index`, since the node has only `child_count`
+    /// children.
+    NoChild { index: usize, child_count: usize },
+    /// There is no node at the path.
+    NoNode(TreePath),
+    /// Computing the index of a node overflowed.
+    Overflow,
+}
+
+impl fmt::Display for NavError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            NavError::AtRoot => f.write_str("focus is at the tree root"),
+            NavError::NoChild { index, child_count } =>
+                write!(f, "no child at index {} (only {} children)", index, child_count),
+            NavError::NoNode(ref path) => write!(f, "no node at {}", path),
+            NavError::Overflow => f.write_str("numerical overflow computing a node index"),
+        }
+    }
+}
+
+impl Error for NavError {}
+
+/// Failure to assign data to the nodes of a tree because the number of values
+/// given differs from the number of nodes. The tree is left unchanged.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct LengthMismatch {
+    /// The number of nodes in the tree.
+    pub expected: usize,
+    /// The number of values given.
+    pub actual: usize,
+}
+
+impl fmt::Display for LengthMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "expected {} values (one per node) but got {}", self.expected, self.actual)
+    }
+}
+
+impl Error for LengthMismatch {}
+
+/// Failure to parse a tree or path from text, from any of the crate's parsers.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ParseError {
+    Path(ParsePathError),
+    Dot(ParseDotError),
+    Indented(ParseIndentedError),
+    #[cfg(feature = "dom")]
+    Dom(ParseDomError),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseError::Path(ref e) => e.fmt(f),
+            ParseError::Dot(ref e) => e.fmt(f),
+            ParseError::Indented(ref e) => e.fmt(f),
+            #[cfg(feature = "dom")]
+            ParseError::Dom(ref e) => e.fmt(f),
+        }
+    }
+}
+
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            ParseError::Path(ref e) => Some(e),
+            ParseError::Dot(ref e) => Some(e),
+            ParseError::Indented(ref e) => Some(e),
+            #[cfg(feature = "dom")]
+            ParseError::Dom(ref e) => Some(e),
+        }
+    }
+}
+
+impl From<ParsePathError> for ParseError {
+    fn from(e: ParsePathError) -> Self {
+        ParseError::Path(e)
+    }
+}
+
+impl From<ParseDotError> for ParseError {
+    fn from(e: ParseDotError) -> Self {
+        ParseError::Dot(e)
+    }
+}
+
+impl From<ParseIndentedError> for ParseError {
+    fn from(e: ParseIndentedError) -> Self {
+        ParseError::Indented(e)
+    }
+}
+
+#[cfg(feature = "dom")]
+impl From<ParseDomError> for ParseError {
+    fn from(e: ParseDomError) -> Self {
+        ParseError::Dom(e)
+    }
+}
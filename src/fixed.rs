@@ -1,10 +1,20 @@
 use ::Nav;
-use ::traversal::Queue;
-use ::util::{ChildIndex, SiblingIndex};
+use ::algos::range_min::RangeMin;
+use ::error::{LengthMismatch, NavError};
+use ::owned;
+use ::path::TreePath;
+use ::traversal::{EnumerateSiblings, Queue};
+use ::index::{ChildIndex, SiblingIndex};
 
 use std::ops::{Deref, DerefMut};
 use std::clone::Clone;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
 use std::iter::Iterator;
+use std::ops::Range;
+use std::ptr;
+use std::sync::Arc;
 
 /// Fixed-layout tree with good memory locality guarantees.
 ///
@@ -16,8 +26,51 @@ use std::iter::Iterator;
 ///
 /// If the tree is extended with additional children, it may reallocate its
 /// entire structure.
+///
+/// The record of edges is kept apart from the data as a `Shape`, which trees
+/// with the same topology can share, so that many assignments of data to one
+/// tree cost only their data.
+///
+/// When a tree is laid out depth-first (as by `From<owned::Tree<T>>` or the
+/// `static_tree!` macro), each node is stored before its descendants and each
+/// subtree occupies a contiguous range of `nodes()`, which `subtree_slice`
+/// exposes.
 pub struct Tree<T> {
-    data: Vec<T>, offsets: Vec<usize>, children: Vec<usize>,
+    data: Vec<T>, shape: Arc<Shape>,
+}
+
+/// Topology of a fixed tree, without its data.
+///
+/// A shape records each node's children by their indices in storage order, so
+/// it can be paired with any data vector of the same length by
+/// `Tree::with_shape`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Shape {
+    offsets: Vec<usize>, children: Vec<usize>, depth_first: bool,
+}
+
+impl Shape {
+    /// Returns the number of nodes in trees of this shape.
+    pub fn size(&self) -> usize {
+        self.offsets.len()
+    }
+
+    /// Returns `true` iff trees of this shape are laid out depth-first, as
+    /// `Tree::is_depth_first` describes.
+    pub fn is_depth_first(&self) -> bool {
+        self.depth_first
+    }
+}
+
+/// Identifier of a node in a fixed tree.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct NodeId(usize);
+
+impl NodeId {
+    /// Returns the index of the node's data in the tree's `nodes()`.
+    pub fn index(self) -> usize {
+        self.0
+    }
 }
 
 impl<T> Tree<T> {
@@ -27,38 +80,116 @@ impl<T> Tree<T> {
     /// order in which they are visited by the traversal imposed by `queue`.
     pub fn from_traversal<Q, I>(mut queue: Q, data: T, children: I) -> Self
         where Q: Queue<(usize, usize, T, I)>, I: Iterator<Item=(T, I)> {
-            let mut tree = Tree { data: Vec::new(), offsets: Vec::new(), children: Vec::new(), };
-            tree.data.push(data);
-            tree.offsets.push(0);
-            {
-                let mut child_index = 0usize;
-                for (data, children) in children {
-                    queue.unshift((0, child_index, data, children));
-                    child_index += 1;
-                    tree.children.push(0);
-                }
+            let mut tree_data = vec![data];
+            let mut shape = Shape { offsets: vec![0], children: Vec::new(), depth_first: false, };
+            for (child_index, (data, children)) in children.enumerate() {
+                queue.unshift((0, child_index, data, children));
+                shape.children.push(0);
             }
-            loop {
-                match queue.shift() {
-                    None => return tree,
-                    Some((parent_index, index, data, children)) => {
-                        tree.data.push(data);
-                        tree.offsets.push(tree.children.len());
-                        tree.children[tree.offsets[parent_index] + index] = index;
-                        let mut child_index = 0usize;
-                        for (data, children) in children {
-                            queue.unshift((index, child_index, data, children));
-                            child_index += 1;
-                            tree.children.push(0);
-                        }
-                    }
+            while let Some((parent_index, index, data, children)) = queue.shift() {
+                let tree_index = tree_data.len();
+                tree_data.push(data);
+                shape.offsets.push(shape.children.len());
+                shape.children[shape.offsets[parent_index] + index] = tree_index;
+                for (child_index, (data, children)) in children.enumerate() {
+                    queue.unshift((tree_index, child_index, data, children));
+                    shape.children.push(0);
                 }
             }
+            let mut tree = Tree { data: tree_data, shape: Arc::new(shape), };
+            let depth_first = tree.fixed_ref().check_depth_first();
+            Arc::get_mut(&mut tree.shape).unwrap().depth_first = depth_first;
+            tree
         }
 
+    /// Constructs a tree with the topology of `shape` and the given data, in
+    /// storage order. Panics if `data` does not hold one item for each node of
+    /// `shape`.
+    pub fn with_shape(shape: Arc<Shape>, data: Vec<T>) -> Self {
+        assert_eq![shape.size(), data.len(), "data length does not match shape size"];
+        Tree { data, shape, }
+    }
+
     /// Constructs a new tree with no children and the given data.
     pub fn leaf(data: T) -> Self {
-        Tree { data: vec![data], offsets: vec![0], children: Vec::new(), }
+        Tree { data: vec![data],
+               shape: Arc::new(Shape { offsets: vec![0], children: Vec::new(),
+                                       depth_first: true, }), }
+    }
+
+    /// Returns the identifier of the root.
+    pub fn root(&self) -> NodeId {
+        NodeId(0)
+    }
+
+    /// Returns `true` iff each node is stored before its descendants and each
+    /// subtree occupies a contiguous range of `nodes()`.
+    pub fn is_depth_first(&self) -> bool {
+        self.shape.depth_first
+    }
+
+    /// Returns the topology of this tree, to be shared with other trees by
+    /// `with_shape`.
+    pub fn shape(&self) -> &Arc<Shape> {
+        &self.shape
+    }
+
+    /// Returns a tree of the same shape, sharing it, whose data is the result
+    /// of calling `f` on the data of each node in storage order.
+    pub fn map_data<U, F>(&self, f: F) -> Tree<U> where F: FnMut(&T) -> U {
+        Tree { data: self.data.iter().map(f).collect(), shape: self.shape.clone(), }
+    }
+
+    /// Overwrites the data of each node of `out` with the result of calling
+    /// `f` on the data of the corresponding node of this tree, in storage
+    /// order. Because `out` keeps its storage, repeated passes allocate
+    /// nothing. Panics if `out` does not have the same shape as this tree.
+    pub fn map_data_into<U, F>(&self, mut f: F, out: &mut Tree<U>) where F: FnMut(&T) -> U {
+        assert![Arc::ptr_eq(&self.shape, &out.shape) || self.shape == out.shape,
+                "trees do not have the same shape"];
+        for (target, data) in out.data.iter_mut().zip(&self.data) {
+            *target = f(data);
+        }
+    }
+
+    /// Calls `f` on the index and data of each node in storage order, so that
+    /// `f` may update the data in place.
+    ///
+    /// The index of a node is that of its `NodeId`. This touches the data
+    /// vector front to back and nothing else, so it suits bulk numeric
+    /// updates that need no navigation.
+    pub fn map_nodes_in_place<F>(&mut self, mut f: F) where F: FnMut(usize, &mut T) {
+        for (index, data) in self.data.iter_mut().enumerate() {
+            f(index, data);
+        }
+    }
+
+    /// Overwrites the data of every node with a copy of the corresponding
+    /// element of `values`, in storage order. Fails, leaving the tree
+    /// unchanged, if `values` does not hold exactly one element per node.
+    pub fn fill_from_slice(&mut self, values: &[T]) -> Result<(), LengthMismatch>
+        where T: Clone {
+            if values.len() != self.data.len() {
+                return Err(LengthMismatch { expected: self.data.len(), actual: values.len(), })
+            }
+            self.data.clone_from_slice(values);
+            Ok(())
+        }
+
+    /// Returns the data of the subtree rooted at `node`, which is contiguous
+    /// and begins with `node` itself. The order of the remaining nodes depends
+    /// on the traversal that laid out the tree. Panics if the tree is not laid
+    /// out depth-first.
+    pub fn subtree_slice(&self, node: NodeId) -> &[T] {
+        let range = self.fixed_ref().subtree_range(node);
+        &self.data[range]
+    }
+
+    /// Returns the data of the subtree rooted at `node` mutably. Panics if the
+    /// tree is not laid out depth-first.
+    pub fn subtree_slice_mut(&mut self, node: NodeId) -> &mut [T] {
+        let range = self.fixed_ref().subtree_range(node);
+        &mut self.data[range]
     }
 
     /// Returns the number of nodes in this tree.
@@ -78,14 +209,449 @@ impl<T> Tree<T> {
         &mut self.data
     }
 
+    /// Returns a borrowed reference to the layout of this tree.
+    pub fn fixed_ref(&self) -> FixedRef<'_, T> {
+        FixedRef { data: &self.data, offsets: &self.shape.offsets,
+                   children: &self.shape.children, depth_first: self.shape.depth_first, }
+    }
+
+    pub fn view(&self) -> TreeView<'_, T> {
+        self.fixed_ref().view()
+    }
+
+    pub fn view_mut(&mut self) -> TreeViewMut<'_, T> {
+        TreeViewMut { tree: self, path: vec![TreePosition::Root], }
+    }
+
+    /// Returns a view whose path back to the root is stored inline rather than
+    /// on the heap. The view can descend at most `MAX_DEPTH` levels below the
+    /// root.
+    pub fn bounded_view<const MAX_DEPTH: usize>(&self) -> BoundedView<'_, T, MAX_DEPTH> {
+        self.fixed_ref().bounded_view()
+    }
+
+    /// Returns a cleared set of `bits_per_node` flags for each node of this
+    /// tree. Panics if `bits_per_node` is 0 or greater than 64.
+    pub fn bit_flags(&self, bits_per_node: usize) -> BitFlags {
+        BitFlags::new(self.size(), bits_per_node)
+    }
+
+    /// Numbers the nodes of this tree in pre-order and post-order.
+    pub fn number_nodes(&self) -> Numbering {
+        self.fixed_ref().number_nodes()
+    }
+
+    /// Returns the Euler tour of this tree: an event for entering each node,
+    /// and later one for leaving it, in depth-first order with children
+    /// visited in order.
+    pub fn euler_tour(&self) -> Vec<(NodeId, Direction)> {
+        self.fixed_ref().euler_tour()
+    }
+
+    /// Indexes this tree for constant-time lowest common ancestor queries.
+    pub fn lca_index(&self) -> Lca {
+        self.fixed_ref().lca_index()
+    }
+
+    /// Returns the node reached from the root by following the child at each
+    /// index in `path` in turn, or `None` if some index is out of range.
+    ///
+    /// This finds the node by arithmetic on the layout alone, without building
+    /// a view.
+    pub fn node_at_path(&self, path: &[usize]) -> Option<NodeId> {
+        self.fixed_ref().node_at_path(path)
+    }
+
+    /// Returns the data of the node that `node_at_path` finds for `path`.
+    pub fn data_at_path(&self, path: &[usize]) -> Option<&T> {
+        self.node_at_path(path).map(|node| &self.data[node.0])
+    }
+
+    /// Looks up the node reached from the root by following the child equal
+    /// to each of `keys` in turn, in a tree whose children are sorted.
+    ///
+    /// Each level is binary searched, so the children of every node on the
+    /// way must be in ascending order. Returns the node reached, or, if some
+    /// key is missing, `Err` with the node under which it would be inserted.
+    pub fn search_path(&self, keys: &[T]) -> Result<NodeId, NodeId> where T: Ord {
+        self.fixed_ref().search_path(keys)
+    }
+
+    /// Looks up a node as `search_path` does, with `compare` ordering the data
+    /// of a child against the key sought.
+    pub fn search_path_by<K, F>(&self, keys: &[K], compare: F) -> Result<NodeId, NodeId>
+        where F: FnMut(&T, &K) -> Ordering {
+            self.fixed_ref().search_path_by(keys, compare)
+        }
+
+    /// Converts `tree` as `From<owned::Tree<T>>` does, and also returns the
+    /// node in the result that each path in `tree` leads to, so that anything
+    /// keyed by paths in `tree` can be rekeyed by node.
+    pub fn from_owned_with_provenance(tree: owned::Tree<T>) -> (Self, HashMap<TreePath, NodeId>) {
+        let tree = Tree::from(tree);
+        let mut provenance = HashMap::with_capacity(tree.data.len());
+        let fixed = tree.fixed_ref();
+        let mut stack = vec![(0, TreePath::root())];
+        while let Some((index, path)) = stack.pop() {
+            for child in (0..fixed.child_count(index)).rev() {
+                stack.push((fixed.child_of(index, child), path.child(child)));
+            }
+            provenance.insert(path, NodeId(index));
+        }
+        (tree, provenance)
+    }
+}
+
+/// Bit-packed flags for each node of a fixed tree, indexed by `NodeId`.
+///
+/// This keeps per-node marks for algorithms such as mark-and-sweep out of the
+/// node data. Because a fixed tree never changes its node count, flags made by
+/// `Tree::bit_flags` stay sized to it. Methods panic if given a node or bit
+/// that is out of range.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct BitFlags {
+    words: Vec<u64>, node_count: usize, bits_per_node: usize,
+}
+
+impl BitFlags {
+    fn new(node_count: usize, bits_per_node: usize) -> Self {
+        assert![bits_per_node > 0 && bits_per_node <= 64, "bits per node must be from 1 to 64"];
+        let words = vec![0; (node_count * bits_per_node).div_ceil(64)];
+        BitFlags { words, node_count, bits_per_node, }
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.node_count
+    }
+
+    pub fn bits_per_node(&self) -> usize {
+        self.bits_per_node
+    }
+
+    fn position(&self, node: NodeId, bit: usize) -> (usize, u64) {
+        assert![node.0 < self.node_count, "node out of range"];
+        assert![bit < self.bits_per_node, "bit out of range"];
+        let position = node.0 * self.bits_per_node + bit;
+        (position / 64, 1 << (position % 64))
+    }
+
+    /// Returns whether `bit` is set for `node`.
+    pub fn test(&self, node: NodeId, bit: usize) -> bool {
+        let (word, mask) = self.position(node, bit);
+        self.words[word] & mask != 0
+    }
+
+    /// Sets `bit` for `node`, returning whether it was already set.
+    pub fn set(&mut self, node: NodeId, bit: usize) -> bool {
+        let (word, mask) = self.position(node, bit);
+        let was_set = self.words[word] & mask != 0;
+        self.words[word] |= mask;
+        was_set
+    }
+
+    /// Clears `bit` for `node`, returning whether it was set.
+    pub fn clear(&mut self, node: NodeId, bit: usize) -> bool {
+        let (word, mask) = self.position(node, bit);
+        let was_set = self.words[word] & mask != 0;
+        self.words[word] &= !mask;
+        was_set
+    }
+
+    /// Clears every bit of every node.
+    pub fn clear_all(&mut self) {
+        for word in &mut self.words {
+            *word = 0;
+        }
+    }
+
+    /// Iterates over the nodes for which `bit` is set, in the order in which
+    /// they are stored.
+    pub fn nodes_with(&self, bit: usize) -> impl Iterator<Item=NodeId> + '_ {
+        assert![bit < self.bits_per_node, "bit out of range"];
+        (0..self.node_count).map(NodeId).filter(move |&node| self.test(node, bit))
+    }
+}
+
+/// Pre-order and post-order indices of the nodes of a fixed tree, indexed by
+/// `NodeId`.
+///
+/// Children are visited in order, whatever the tree's layout. A node's
+/// descendants are numbered after it in pre-order and before it in post-order,
+/// which lets `is_ancestor` compare two nodes in constant time. Methods panic
+/// if given a node that is out of range.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Numbering {
+    pre: Vec<usize>, post: Vec<usize>, by_pre: Vec<NodeId>,
+}
+
+impl Numbering {
+    pub fn preorder(&self, node: NodeId) -> usize {
+        self.pre[node.0]
+    }
+
+    pub fn postorder(&self, node: NodeId) -> usize {
+        self.post[node.0]
+    }
+
+    /// Returns the node with the given pre-order index, or `None` if there is
+    /// none.
+    pub fn node_at_preorder(&self, index: usize) -> Option<NodeId> {
+        self.by_pre.get(index).cloned()
+    }
+
+    /// Returns `true` iff `a` is a proper ancestor of `b`. A node is not its
+    /// own ancestor.
+    pub fn is_ancestor(&self, a: NodeId, b: NodeId) -> bool {
+        self.pre[a.0] < self.pre[b.0] && self.post[b.0] < self.post[a.0]
+    }
+}
+
+/// Whether an Euler tour event enters or leaves a node.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Direction {
+    Enter,
+    Leave,
+}
+
+/// Index of a fixed tree for answering lowest common ancestor and distance
+/// queries in constant time, after O(n log n) preprocessing.
+///
+/// This is built from the Euler tour: between entering two nodes, the tour
+/// reaches no node shallower than their lowest common ancestor, and reaches
+/// that ancestor itself. Methods panic if given a node that is out of range.
+#[derive(Clone, Debug)]
+pub struct Lca {
+    // The index in `tour` of entering each node.
+    first: Vec<usize>,
+    depths: Vec<usize>,
+    // The node reached by each Euler tour event: the node entered, or the
+    // parent returned to after leaving a node.
+    tour: Vec<NodeId>,
+    // The depth of each node in `tour`.
+    min_depth: RangeMin<usize>,
+}
+
+impl Lca {
+    /// Returns the depth of `node`, which is 0 for the root.
+    pub fn depth(&self, node: NodeId) -> usize {
+        self.depths[node.0]
+    }
+
+    /// Returns the deepest node that is an ancestor of both `a` and `b`,
+    /// counting each node as its own ancestor.
+    pub fn lca(&self, a: NodeId, b: NodeId) -> NodeId {
+        let (a, b) = (self.first[a.0], self.first[b.0]);
+        self.tour[self.min_depth.min_index(a.min(b)..a.max(b) + 1)]
+    }
+
+    /// Returns the number of edges on the path between `a` and `b`.
+    pub fn distance(&self, a: NodeId, b: NodeId) -> usize {
+        self.depth(a) + self.depth(b) - 2 * self.depth(self.lca(a, b))
+    }
+}
+
+/// Borrowed reference to the layout of a fixed tree.
+///
+/// This is obtained either from a `Tree` or from a tree whose layout was
+/// computed at compile time by the `static_tree!` macro, and provides the same
+/// read-only views as a `Tree`.
+pub struct FixedRef<'a, T: 'a> {
+    data: &'a [T], offsets: &'a [usize], children: &'a [usize], depth_first: bool,
+}
+
+impl<'a, T: 'a> FixedRef<'a, T> {
+    /// Returns the number of nodes in this tree.
+    pub fn size(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns a borrowed view of the nodes, in the order in which they are
+    /// stored.
+    pub fn nodes(&self) -> &'a [T] {
+        self.data
+    }
+
+    /// Returns `true` iff each node is stored before its descendants and each
+    /// subtree occupies a contiguous range of `nodes()`.
+    pub fn is_depth_first(&self) -> bool {
+        self.depth_first
+    }
+
+    /// Returns a cleared set of flags for each node, as `Tree::bit_flags`
+    /// does.
+    pub fn bit_flags(&self, bits_per_node: usize) -> BitFlags {
+        BitFlags::new(self.size(), bits_per_node)
+    }
+
+    /// Numbers the nodes of this tree, as `Tree::number_nodes` does.
+    pub fn number_nodes(&self) -> Numbering {
+        let mut pre = vec![0; self.size()];
+        let mut post = vec![0; self.size()];
+        let mut by_pre = Vec::with_capacity(self.size());
+        let mut next_post = 0;
+        // Each stack entry is a node index and whether the node is being
+        // entered (as opposed to left).
+        let mut stack = vec![(0usize, true)];
+        while let Some((index, entering)) = stack.pop() {
+            if !entering {
+                post[index] = next_post;
+                next_post += 1;
+                continue
+            }
+            pre[index] = by_pre.len();
+            by_pre.push(NodeId(index));
+            stack.push((index, false));
+            for i in (0..self.child_count(index)).rev() {
+                stack.push((self.child_of(index, i), true));
+            }
+        }
+        Numbering { pre, post, by_pre, }
+    }
+
+    /// Returns the Euler tour of this tree, as `Tree::euler_tour` does.
+    pub fn euler_tour(&self) -> Vec<(NodeId, Direction)> {
+        let mut tour = Vec::with_capacity(2 * self.size());
+        let mut stack = vec![(0usize, Direction::Enter)];
+        while let Some((index, direction)) = stack.pop() {
+            tour.push((NodeId(index), direction));
+            if direction == Direction::Enter {
+                stack.push((index, Direction::Leave));
+                for i in (0..self.child_count(index)).rev() {
+                    stack.push((self.child_of(index, i), Direction::Enter));
+                }
+            }
+        }
+        tour
+    }
+
+    /// Indexes this tree for lowest common ancestor queries, as
+    /// `Tree::lca_index` does.
+    pub fn lca_index(&self) -> Lca {
+        let mut first = vec![0; self.size()];
+        let mut depths = vec![0; self.size()];
+        let mut tour = Vec::with_capacity(2 * self.size());
+        let mut tour_depths = Vec::with_capacity(2 * self.size());
+        // The nodes entered but not yet left.
+        let mut path: Vec<NodeId> = Vec::new();
+        for (node, direction) in self.euler_tour() {
+            match direction {
+                Direction::Enter => {
+                    first[node.0] = tour.len();
+                    depths[node.0] = path.len();
+                    tour.push(node);
+                    tour_depths.push(path.len());
+                    path.push(node);
+                },
+                Direction::Leave => {
+                    path.pop();
+                    if let Some(&parent) = path.last() {
+                        tour.push(parent);
+                        tour_depths.push(path.len() - 1);
+                    }
+                },
+            }
+        }
+        Lca { first, depths, tour, min_depth: RangeMin::new(tour_depths), }
+    }
+
+    /// Returns the node reached by following `path` from the root, as
+    /// `Tree::node_at_path` does.
+    pub fn node_at_path(&self, path: &[usize]) -> Option<NodeId> {
+        let mut index = 0;
+        for &child in path {
+            if child >= self.child_count(index) {
+                return None
+            }
+            index = self.child_of(index, child);
+        }
+        Some(NodeId(index))
+    }
+
+    /// Returns the data of the node reached by following `path` from the root,
+    /// as `Tree::data_at_path` does.
+    pub fn data_at_path(&self, path: &[usize]) -> Option<&'a T> {
+        self.node_at_path(path).map(|node| &self.data[node.0])
+    }
+
+    /// Looks up a node by the keys on its path, as `Tree::search_path` does.
+    pub fn search_path(&self, keys: &[T]) -> Result<NodeId, NodeId> where T: Ord {
+        self.search_path_by(keys, T::cmp)
+    }
+
+    /// Looks up a node by the keys on its path, as `Tree::search_path_by`
+    /// does.
+    pub fn search_path_by<K, F>(&self, keys: &[K], mut compare: F) -> Result<NodeId, NodeId>
+        where F: FnMut(&T, &K) -> Ordering {
+            let mut index = 0;
+            for key in keys {
+                let start = self.offsets[index];
+                let children = &self.children[start..start + self.child_count(index)];
+                match children.binary_search_by(|&child| compare(&self.data[child], key)) {
+                    Ok(i) => index = children[i],
+                    Err(_) => return Err(NodeId(index)),
+                }
+            }
+            Ok(NodeId(index))
+        }
+
+    /// Returns the data of the subtree rooted at `node`, as
+    /// `Tree::subtree_slice` does.
+    pub fn subtree_slice(&self, node: NodeId) -> &'a [T] {
+        &self.data[self.subtree_range(node)]
+    }
+
+    fn subtree_range(&self, node: NodeId) -> Range<usize> {
+        assert![self.depth_first, "tree is not laid out depth-first"];
+        // In a depth-first layout, the last node of a subtree is the last node
+        // of the subtree of its root's last-stored child.
+        let mut last = node.0;
+        while let Some(child) = (0..self.child_count(last)).map(|i| self.child_of(last, i)).max() {
+            last = child;
+        }
+        node.0..last + 1
+    }
+
+    // Checks that visiting each node's children in the order in which they are
+    // stored visits every node in storage order.
+    fn check_depth_first(&self) -> bool {
+        let mut stack = vec![0];
+        let mut next = 0;
+        let mut children = Vec::new();
+        while let Some(index) = stack.pop() {
+            if index != next {
+                return false
+            }
+            next += 1;
+            children.extend((0..self.child_count(index)).map(|i| self.child_of(index, i)));
+            children.sort_unstable_by(|a, b| b.cmp(a));
+            stack.append(&mut children);
+        }
+        true
+    }
+
+    pub fn view(&self) -> TreeView<'a, T> {
+        TreeView { tree: *self, path: vec![TreePosition::Root], }
+    }
+
+    /// Returns a view whose path back to the root is stored inline rather than
+    /// on the heap. The view can descend at most `MAX_DEPTH` levels below the
+    /// root.
+    pub fn bounded_view<const MAX_DEPTH: usize>(&self) -> BoundedView<'a, T, MAX_DEPTH> {
+        BoundedView {
+            tree: *self,
+            path: [TreePositionData { tree_index: 0, parent_index: 0, }; MAX_DEPTH],
+            depth: 0,
+        }
+    }
+
     fn child_count(&self, index: usize) -> usize {
         match index.checked_add(1) {
             None =>
-                panic!["numerical overflow in computing child count"],
+                panic!["{}", NavError::Overflow],
             Some(x) if x > self.size() =>
                 panic!["no such child {} (only {} nodes in tree)", index, self.size()],
             Some(x) if x == self.size() =>
-                self.size() - self.offsets[index],
+                self.children.len() - self.offsets[index],
             Some(x) =>
                 self.offsets[x] - self.offsets[index],
         }
@@ -95,8 +661,151 @@ impl<T> Tree<T> {
         assert![parent < self.size()];
         match self.offsets[parent].checked_add(index) {
             Some(x) => self.children[x],
-            None => panic!["numerical overflow in computing child offset"],
+            None => panic!["{}", NavError::Overflow],
+        }
+    }
+}
+
+impl<'a, T: 'a> Clone for FixedRef<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T: 'a> Copy for FixedRef<'a, T> {}
+
+/// Node in the nested form of a tree whose layout is computed at compile time.
+/// This is usually written with the `static_tree!` macro.
+pub struct StaticNode<T: 'static> {
+    pub data: T,
+    pub children: &'static [StaticNode<T>],
+}
+
+impl<T> StaticNode<T> {
+    /// Returns the number of nodes in the tree rooted at this node.
+    pub const fn size(&self) -> usize {
+        let mut size = 1;
+        let mut i = 0;
+        while i < self.children.len() {
+            size += self.children[i].size();
+            i += 1;
+        }
+        size
+    }
+}
+
+/// Fixed tree layout with `N` nodes that is computed at compile time. This is
+/// usually written with the `static_tree!` macro.
+pub struct StaticLayout<T, const N: usize> {
+    data: [T; N], offsets: [usize; N], children: [usize; N],
+}
+
+impl<T: Copy, const N: usize> StaticLayout<T, N> {
+    /// Lays out the tree rooted at `root` in depth-first order. `N` must equal
+    /// `root.size()`.
+    pub const fn new(root: &'static StaticNode<T>) -> Self {
+        assert![N == root.size(), "static layout size does not match tree size"];
+        let mut data = [root.data; N];
+        let mut offsets = [0; N];
+        // There is one fewer edge than there are nodes, so the last slot of
+        // `children` is unused.
+        let mut children = [0; N];
+        // Nodes that have yet to be laid out, with the slot in `children` that
+        // records their position.
+        let mut stack: [(&StaticNode<T>, usize); N] = [(root, usize::MAX); N];
+        let mut stack_len = 1;
+        let mut next_node = 0;
+        let mut next_slot = 0;
+        while stack_len > 0 {
+            stack_len -= 1;
+            let (node, slot) = stack[stack_len];
+            data[next_node] = node.data;
+            offsets[next_node] = next_slot;
+            if slot != usize::MAX {
+                children[slot] = next_node;
+            }
+            next_node += 1;
+            let first_slot = next_slot;
+            next_slot += node.children.len();
+            let mut i = node.children.len();
+            while i > 0 {
+                i -= 1;
+                stack[stack_len] = (&node.children[i], first_slot + i);
+                stack_len += 1;
+            }
+        }
+        StaticLayout { data, offsets, children, }
+    }
+
+    /// Returns a borrowed reference to this layout.
+    pub const fn fixed_ref(&'static self) -> FixedRef<'static, T> {
+        FixedRef { data: &self.data,
+                   offsets: &self.offsets,
+                   children: self.children.split_at(N - 1).0,
+                   depth_first: true, }
+    }
+}
+
+/// Lays out the nodes of `tree` in pre-order, as `StaticLayout` does.
+impl<T> From<owned::Tree<T>> for Tree<T> {
+    fn from(tree: owned::Tree<T>) -> Self {
+        let (data, children) = tree.into_nodes();
+        Tree::from_traversal(PreOrderQueue::new(), data, children)
+    }
+}
+
+// Last in, first out queue that returns each batch of items added between
+// shifts in the order they were added, rather than reversed as `DepthQueue`
+// does. `from_traversal` adds a node's children in one batch, so this lays a
+// tree out in pre-order.
+struct PreOrderQueue<T> {
+    stack: Vec<T>,
+    // Items added since the last shift.
+    batch: Vec<T>,
+}
+
+impl<T> PreOrderQueue<T> {
+    fn new() -> Self {
+        PreOrderQueue { stack: Vec::new(), batch: Vec::new(), }
+    }
+}
+
+impl<T> Queue<T> for PreOrderQueue<T> {
+    fn len(&self) -> usize { self.stack.len() + self.batch.len() }
+    fn first(&self) -> Option<&T> { self.batch.first().or_else(|| self.stack.last()) }
+    fn unshift(&mut self, t: T) { self.batch.push(t); }
+    fn shift(&mut self) -> Option<T> {
+        self.stack.extend(self.batch.drain(..).rev());
+        self.stack.pop()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Tree<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        self.fixed_ref().fmt(f)
+    }
+}
+
+impl<'a, T: fmt::Debug> fmt::Debug for FixedRef<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        // Each stack entry is a node index and whether the node is being
+        // entered (as opposed to left).
+        let mut stack = vec![(0usize, true)];
+        let mut first = true;
+        while let Some((index, entering)) = stack.pop() {
+            if ! entering {
+                f.write_str(")")?;
+                continue
+            }
+            f.write_str(if first { "(" } else { " (" })?;
+            first = false;
+            self.data[index].fmt(f)?;
+            stack.push((index, false));
+            for i in (0..self.child_count(index)).rev() {
+                stack.push((self.child_of(index, i), true));
+            }
         }
+        Ok(())
     }
 }
 
@@ -106,6 +815,22 @@ enum TreePosition {
     Nonroot(TreePositionData),
 }
 
+impl TreePosition {
+    fn tree_index(&self) -> usize {
+        match *self {
+            TreePosition::Root => 0,
+            TreePosition::Nonroot(data) => data.tree_index,
+        }
+    }
+
+    fn parent_index(&self) -> Option<usize> {
+        match *self {
+            TreePosition::Root => None,
+            TreePosition::Nonroot(data) => Some(data.parent_index),
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 struct TreePositionData {
     // Index into tree array.
@@ -115,13 +840,34 @@ struct TreePositionData {
 }
 
 pub struct TreeView<'a, T: 'a> {
-    tree: &'a Tree<T>, path: Vec<TreePosition>,
+    tree: FixedRef<'a, T>, path: Vec<TreePosition>,
 }
 
 impl<'a, T: 'a> TreeView<'a, T> {
     fn here(&self) -> TreePosition {
         *self.path.last().unwrap()
-    }    
+    }
+
+    // Returns the index of each node on the path from the root's children to
+    // the focus among its siblings.
+    fn indices(&self) -> impl Iterator<Item=usize> + '_ {
+        self.path.iter().filter_map(|position| match *position {
+            TreePosition::Root => None,
+            TreePosition::Nonroot(data) => Some(data.parent_index),
+        })
+    }
+
+    /// Returns the identifier of the focus node.
+    pub fn id(&self) -> NodeId {
+        NodeId(self.here().tree_index())
+    }
+
+    /// Iterates over the data of the focus and its siblings, in order, with
+    /// each one's offset from the focus.
+    pub fn enumerate_siblings(&self)
+                              -> EnumerateSiblings<impl ExactSizeIterator<Item=&'a T> + 'a> {
+        enumerate_siblings_in(self.tree, &self.path)
+    }
 }
 
 impl<'a, T: 'a> Clone for TreeView<'a, T> {
@@ -130,83 +876,148 @@ impl<'a, T: 'a> Clone for TreeView<'a, T> {
     }
 }
 
+impl<'a, T: 'a> PartialEq for TreeView<'a, T> {
+    fn eq(&self, other: &Self) -> bool {
+        ptr::eq(self.tree.data, other.tree.data)
+            && self.indices().eq(other.indices())
+    }
+}
+
+impl<'a, T: 'a> PartialOrd for TreeView<'a, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if ptr::eq(self.tree.data, other.tree.data) {
+            Some(self.indices().cmp(other.indices()))
+        } else {
+            None
+        }
+    }
+}
+
 impl<'a, T: 'a> Deref for TreeView<'a, T> {
     type Target = T;
     fn deref(&self) -> &<Self as Deref>::Target {
-        match self.here() {
-            TreePosition::Root => &self.tree.data[0],
-            TreePosition::Nonroot(data) => &self.tree.data[data.tree_index],
-        }
+        &self.tree.data[self.here().tree_index()]
+    }
+}
+
+// Moves the last position in `path` by `offset` among its siblings.
+fn seek_sibling_in<T>(tree: FixedRef<T>, path: &mut [TreePosition], offset: isize) -> bool {
+    if offset == 0 {
+        return true
+    }
+    let (here, parent) = match path.len().checked_sub(2) {
+        None => return false,
+        Some(parent_path_index) => (path[parent_path_index + 1], path[parent_path_index]),
+    };
+    let here_data = match here {
+        TreePosition::Root => unreachable!(),
+        TreePosition::Nonroot(data) => data,
+    };
+    let parent_tree_index = parent.tree_index();
+    match SiblingIndex::compute(tree.child_count(parent_tree_index),
+                                here_data.parent_index,
+                                offset) {
+        Some(new_index) => {
+            let tree_index = tree.child_of(parent_tree_index, new_index);
+            *path.last_mut().unwrap() = TreePosition::Nonroot(
+                TreePositionData { tree_index, parent_index: new_index, });
+            true
+        },
+        None => false,
+    }
+}
+
+// Enumerates the siblings of the last position in `path`.
+fn enumerate_siblings_in<'a, T>(tree: FixedRef<'a, T>, path: &[TreePosition])
+                                -> EnumerateSiblings<impl ExactSizeIterator<Item=&'a T> + 'a> {
+    let (parent_tree_index, count, here_index) = match path.len().checked_sub(2) {
+        None => (None, 1, 0),
+        Some(parent_path_index) => {
+            let parent_tree_index = path[parent_path_index].tree_index();
+            let here_index = match path[parent_path_index + 1] {
+                TreePosition::Root => unreachable!(),
+                TreePosition::Nonroot(data) => data.parent_index,
+            };
+            (Some(parent_tree_index), tree.child_count(parent_tree_index), here_index)
+        },
+    };
+    let siblings = (0..count).map(move |i| match parent_tree_index {
+        None => &tree.data[0],
+        Some(parent_tree_index) => &tree.data[tree.child_of(parent_tree_index, i)],
+    });
+    EnumerateSiblings::new(siblings, here_index)
+}
+
+// Extends `path` to the child at `index` of its last position.
+fn seek_child_in<T>(tree: FixedRef<T>, path: &mut Vec<TreePosition>, index: usize) -> bool {
+    let here_tree_index = path.last().unwrap().tree_index();
+    match ChildIndex::compute(tree.child_count(here_tree_index), index) {
+        Some(new_index) => {
+            let tree_index = tree.child_of(here_tree_index, new_index);
+            path.push(TreePosition::Nonroot(
+                TreePositionData { tree_index, parent_index: new_index, }));
+            true
+        },
+        None => false,
+    }
+}
+
+// Extends `path` from its last position down through first or last children
+// to a leaf.
+fn descend_to_leaf_in<T>(tree: FixedRef<T>, path: &mut Vec<TreePosition>, rightmost: bool) {
+    let mut tree_index = path.last().unwrap().tree_index();
+    loop {
+        let parent_index = match tree.child_count(tree_index) {
+            0 => return,
+            count if rightmost => count - 1,
+            _ => 0,
+        };
+        tree_index = tree.child_of(tree_index, parent_index);
+        path.push(TreePosition::Nonroot(TreePositionData { tree_index, parent_index, }));
     }
 }
 
 impl<'a, T: 'a> Nav for TreeView<'a, T> {
     fn seek_sibling(&mut self, offset: isize) -> bool {
-        let new_index_result = match self.path.pop() {
-            None => unreachable!(),
-            Some(TreePosition::Root) => return false,
-            Some(TreePosition::Nonroot(data)) => match self.here() {
-                TreePosition::Root =>
-                    SiblingIndex::compute(self.tree.child_count(0), 0, offset),
-                TreePosition::Nonroot(parent_data) =>
-                    SiblingIndex::compute(self.tree.child_count(parent_data.tree_index),
-                                          data.parent_index,
-                                          offset),
-            },
-        };
-        match new_index_result {
-            Some(new_index) => {
-                let tree_index = match self.here() {
-                    TreePosition::Root =>
-                        self.tree.child_of(0, new_index),
-                    TreePosition::Nonroot(data) =>
-                        self.tree.child_of(data.tree_index, new_index),
-                };
-                self.path.push(TreePosition::Nonroot(
-                    TreePositionData { tree_index: tree_index, parent_index: new_index, }));
-                return true
-            },
-            None => return false,
-        }
+        seek_sibling_in(self.tree, &mut self.path, offset)
     }
 
     fn seek_child(&mut self, index: usize) -> bool {
-        match ChildIndex::compute(self.child_count(), index) {
-            Some(new_index) => {
-                let tree_index = match self.here() {
-                    TreePosition::Root => self.tree.child_of(0, new_index),
-                    TreePosition::Nonroot(data) => self.tree.child_of(data.tree_index, new_index),
-                };
-                self.path.push(TreePosition::Nonroot(
-                    TreePositionData { tree_index: tree_index, parent_index: new_index, }));
-                return true
-            },
-            None => return false,
-        }
+        seek_child_in(self.tree, &mut self.path, index)
     }
 
     fn child_count(&self) -> usize {
-        match self.here() {
-            TreePosition::Root => self.tree.child_count(0),
-            TreePosition::Nonroot(data) => self.tree.child_count(data.tree_index),
-        }
+        self.tree.child_count(self.here().tree_index())
     }
 
     fn at_root(&self) -> bool {
         self.path.len() == 1
     }
 
+    fn index_in_parent(&self) -> Option<usize> {
+        self.here().parent_index()
+    }
+
     fn to_parent(&mut self) -> bool {
-        match self.path.pop() {
-            Some(_) => return true,
-            None => return false,
+        if self.at_root() {
+            return false
         }
+        self.path.pop();
+        true
     }
 
     fn to_root(&mut self) {
         self.path.clear();
         self.path.push(TreePosition::Root);
     }
+
+    fn to_leftmost_leaf(&mut self) {
+        descend_to_leaf_in(self.tree, &mut self.path, false)
+    }
+
+    fn to_rightmost_leaf(&mut self) {
+        descend_to_leaf_in(self.tree, &mut self.path, true)
+    }
 }
 
 pub struct TreeViewMut<'a, T: 'a> {
@@ -218,103 +1029,630 @@ impl<'a, T> TreeViewMut<'a, T> {
     fn here(&self) -> TreePosition {
         *self.path.last().unwrap()
     }
+
+    /// Returns the identifier of the focus node.
+    pub fn id(&self) -> NodeId {
+        NodeId(self.here().tree_index())
+    }
+
+    /// Iterates over the data of the focus and its siblings, in order, with
+    /// each one's offset from the focus.
+    pub fn enumerate_siblings(&self)
+                              -> EnumerateSiblings<impl ExactSizeIterator<Item=&T> + '_> {
+        enumerate_siblings_in(self.tree.fixed_ref(), &self.path)
+    }
 }
 
 impl<'a, T: 'a> Deref for TreeViewMut<'a, T> {
     type Target = T;
 
     fn deref(&self) -> &<Self as Deref>::Target {
-        match self.here() {
-            TreePosition::Root => &self.tree.data[0],
-            TreePosition::Nonroot(data) => &self.tree.data[data.tree_index],
-        }
+        &self.tree.data[self.here().tree_index()]
     }
 }
 
 impl<'a, T: 'a> DerefMut for TreeViewMut<'a, T> {
     fn deref_mut(&mut self) -> &mut <Self as Deref>::Target {
-        match self.here() {
-            TreePosition::Root => &mut self.tree.data[0],
-            TreePosition::Nonroot(data) => &mut self.tree.data[data.tree_index],
-        }
+        let tree_index = self.here().tree_index();
+        &mut self.tree.data[tree_index]
     }
 }
 
 impl<'a, T: 'a> Nav for TreeViewMut<'a, T> {
     fn seek_sibling(&mut self, offset: isize) -> bool {
-        let new_index_result = match self.path.pop() {
-            None => unreachable!(),
-            Some(TreePosition::Root) => return false,
-            Some(TreePosition::Nonroot(data)) => match self.here() {
-                TreePosition::Root =>
-                    SiblingIndex::compute(self.tree.child_count(0), 0, offset),
-                TreePosition::Nonroot(parent_data) =>
-                    SiblingIndex::compute(self.tree.child_count(parent_data.tree_index),
-                                          data.parent_index,
-                                          offset),
-            },
-        };
-        match new_index_result {
+        seek_sibling_in(self.tree.fixed_ref(), &mut self.path, offset)
+    }
+
+    fn seek_child(&mut self, index: usize) -> bool {
+        seek_child_in(self.tree.fixed_ref(), &mut self.path, index)
+    }
+
+    fn child_count(&self) -> usize {
+        self.tree.fixed_ref().child_count(self.here().tree_index())
+    }
+
+    fn at_root(&self) -> bool {
+        self.path.len() == 1
+    }
+
+    fn index_in_parent(&self) -> Option<usize> {
+        self.here().parent_index()
+    }
+
+    fn to_parent(&mut self) -> bool {
+        if self.at_root() {
+            return false
+        }
+        self.path.pop();
+        true
+    }
+
+    fn to_root(&mut self) {
+        self.path.clear();
+        self.path.push(TreePosition::Root);
+    }
+
+    fn to_leftmost_leaf(&mut self) {
+        descend_to_leaf_in(self.tree.fixed_ref(), &mut self.path, false)
+    }
+
+    fn to_rightmost_leaf(&mut self) {
+        descend_to_leaf_in(self.tree.fixed_ref(), &mut self.path, true)
+    }
+}
+
+/// Error returned when a `BoundedView` cannot descend any further because its
+/// path storage is full.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DepthExceeded {
+    /// The maximum depth that the view can reach.
+    pub max_depth: usize,
+}
+
+impl fmt::Display for DepthExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "cannot navigate below maximum depth {}", self.max_depth)
+    }
+}
+
+/// Navigable view of a fixed tree that does not allocate.
+///
+/// The path from the root to the focus is stored in an array of capacity
+/// `MAX_DEPTH` that is kept inline in the view, so navigation never touches the
+/// heap. This is suitable for memory-constrained environments, such as
+/// microcontrollers reading trees baked into flash. Seeking a child that lies
+/// more than `MAX_DEPTH` levels below the root fails; `try_seek_child`
+/// distinguishes this case from seeking a child that does not exist.
+pub struct BoundedView<'a, T: 'a, const MAX_DEPTH: usize> {
+    tree: FixedRef<'a, T>,
+    path: [TreePositionData; MAX_DEPTH],
+    depth: usize,
+}
+
+impl<'a, T: 'a, const MAX_DEPTH: usize> BoundedView<'a, T, MAX_DEPTH> {
+    fn here_tree_index(&self) -> usize {
+        match self.depth {
+            0 => 0,
+            depth => self.path[depth - 1].tree_index,
+        }
+    }
+
+    /// Returns the number of levels between the root and the focus.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Returns the identifier of the focus node.
+    pub fn id(&self) -> NodeId {
+        NodeId(self.here_tree_index())
+    }
+
+    /// Navigates to the child at the given index. Returns `Ok(true)` if the
+    /// focus moves, `Ok(false)` if there is no such child, or an error if the
+    /// child lies below the maximum depth of this view.
+    pub fn try_seek_child(&mut self, index: usize) -> Result<bool, DepthExceeded> {
+        let here_tree_index = self.here_tree_index();
+        match ChildIndex::compute(self.tree.child_count(here_tree_index), index) {
+            Some(_) if self.depth == MAX_DEPTH =>
+                Err(DepthExceeded { max_depth: MAX_DEPTH, }),
             Some(new_index) => {
-                let tree_index = match self.here() {
-                    TreePosition::Root =>
-                        self.tree.child_of(0, new_index),
-                    TreePosition::Nonroot(data) =>
-                        self.tree.child_of(data.tree_index, new_index),
+                self.path[self.depth] = TreePositionData {
+                    tree_index: self.tree.child_of(here_tree_index, new_index),
+                    parent_index: new_index,
                 };
-                self.path.push(TreePosition::Nonroot(
-                    TreePositionData { tree_index: tree_index, parent_index: new_index, }));
-                return true
+                self.depth += 1;
+                Ok(true)
             },
-            None => return false,
+            None => Ok(false),
         }
     }
+}
 
-    fn seek_child(&mut self, index: usize) -> bool {
-        match ChildIndex::compute(self.child_count(), index) {
+impl<'a, T: 'a, const MAX_DEPTH: usize> Clone for BoundedView<'a, T, MAX_DEPTH> {
+    fn clone(&self) -> Self {
+        BoundedView { tree: self.tree, path: self.path, depth: self.depth, }
+    }
+}
+
+impl<'a, T: 'a, const MAX_DEPTH: usize> PartialEq for BoundedView<'a, T, MAX_DEPTH> {
+    fn eq(&self, other: &Self) -> bool {
+        ptr::eq(self.tree.data, other.tree.data)
+            && self.path[..self.depth].iter().map(|p| p.parent_index)
+                .eq(other.path[..other.depth].iter().map(|p| p.parent_index))
+    }
+}
+
+impl<'a, T: 'a, const MAX_DEPTH: usize> PartialOrd for BoundedView<'a, T, MAX_DEPTH> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if ptr::eq(self.tree.data, other.tree.data) {
+            Some(self.path[..self.depth].iter().map(|p| p.parent_index)
+                 .cmp(other.path[..other.depth].iter().map(|p| p.parent_index)))
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, T: 'a, const MAX_DEPTH: usize> Deref for BoundedView<'a, T, MAX_DEPTH> {
+    type Target = T;
+
+    fn deref(&self) -> &<Self as Deref>::Target {
+        &self.tree.data[self.here_tree_index()]
+    }
+}
+
+impl<'a, T: 'a, const MAX_DEPTH: usize> Nav for BoundedView<'a, T, MAX_DEPTH> {
+    fn seek_sibling(&mut self, offset: isize) -> bool {
+        if offset == 0 {
+            return true
+        }
+        if self.depth == 0 {
+            return false
+        }
+        let parent_tree_index = match self.depth {
+            1 => 0,
+            depth => self.path[depth - 2].tree_index,
+        };
+        let here = self.path[self.depth - 1];
+        match SiblingIndex::compute(self.tree.child_count(parent_tree_index),
+                                    here.parent_index,
+                                    offset) {
             Some(new_index) => {
-                let tree_index = match self.here() {
-                    TreePosition::Root => self.tree.child_of(0, new_index),
-                    TreePosition::Nonroot(data) => self.tree.child_of(data.tree_index, new_index),
+                self.path[self.depth - 1] = TreePositionData {
+                    tree_index: self.tree.child_of(parent_tree_index, new_index),
+                    parent_index: new_index,
                 };
-                self.path.push(TreePosition::Nonroot(
-                    TreePositionData { tree_index: tree_index, parent_index: new_index, }));
-                return true
+                true
             },
-            None => return false,
+            None => false,
         }
     }
 
+    /// Navigates to the child at the given index. Returns false if there is no
+    /// such child or if it lies below the maximum depth of this view.
+    fn seek_child(&mut self, index: usize) -> bool {
+        self.try_seek_child(index).unwrap_or(false)
+    }
+
     fn child_count(&self) -> usize {
-        match self.here() {
-            TreePosition::Root => self.tree.child_count(0),
-            TreePosition::Nonroot(data) => self.tree.child_count(data.tree_index),
-        }
+        self.tree.child_count(self.here_tree_index())
     }
 
     fn at_root(&self) -> bool {
-        self.path.len() == 1
+        self.depth == 0
+    }
+
+    fn index_in_parent(&self) -> Option<usize> {
+        self.depth.checked_sub(1).map(|here_depth| self.path[here_depth].parent_index)
     }
 
     fn to_parent(&mut self) -> bool {
-        match self.path.pop() {
-            Some(_) => return true,
-            None => return false,
+        if self.depth == 0 {
+            return false
         }
+        self.depth -= 1;
+        true
     }
 
     fn to_root(&mut self) {
-        self.path.clear();
-        self.path.push(TreePosition::Root);
+        self.depth = 0;
     }
 }
 
+/// Declares a static `FixedRef` whose layout is computed at compile time.
+///
+/// The tree is written in the same nested notation as `owned_tree!`, enclosed
+/// in brackets, and its data type must be `Copy`:
+///
+/// ```
+/// #[macro_use] extern crate entmut;
+/// use entmut::Nav;
+///
+/// static_tree! {
+///     static TAXONOMY: &str = ["animal", ["mammal", ["dog"], ["cat"]], ["bird"]];
+/// }
+///
+/// fn main() {
+///     let mut v = TAXONOMY.view();
+///     assert![v.seek_child(0)];
+///     assert_eq![*v, "mammal"];
+/// }
+/// ```
+#[macro_export]
+macro_rules! static_tree {
+    ($vis:vis static $name:ident : $t:ty = [$($tree:tt)*];) => (
+        $vis static $name: $crate::fixed::FixedRef<'static, $t> = {
+            const ROOT: $crate::fixed::StaticNode<$t> = $crate::static_node![$($tree)*];
+            const SIZE: usize = ROOT.size();
+            static LAYOUT: $crate::fixed::StaticLayout<$t, SIZE> =
+                $crate::fixed::StaticLayout::new(&ROOT);
+            LAYOUT.fixed_ref()
+        };
+    );
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! static_node {
+    ($data:expr) => ($crate::fixed::StaticNode { data: $data, children: &[], });
+    ($data:expr, $([$($child:tt)*]),*) =>
+        ($crate::fixed::StaticNode { data: $data,
+                                     children: &[$($crate::static_node![$($child)*]),*], });
+}
+
+#[macro_export]
+macro_rules! fixed_tree {
+    ($($t:tt)*) => ($crate::fixed::Tree::from($crate::owned_tree![$($t)*]));
+}
+
 #[cfg(test)]
 mod tests {
-    use ::fixed::Tree;
-    
+    use ::Nav;
+    use ::fixed::{DepthExceeded, Direction, NodeId, Shape, Tree};
+    use ::owned_tree;
+    use ::traversal::{BreadthQueue, PriorityQueue};
+
+    use std::sync::Arc;
+
+    #[test]
+    fn conversion_maps_paths_to_nodes() {
+        let original = owned_tree!["a", ["b", ["c"], ["d"]], ["e"]];
+        let (t, ids) = Tree::from_owned_with_provenance(original.clone());
+        assert_eq![5, ids.len()];
+        for (path, &id) in &ids {
+            assert_eq![original.subtree(path).unwrap().data(), &t.nodes()[id.index()]];
+            assert_eq![Some(id), t.node_at_path(path.indices())];
+        }
+    }
+
     #[test]
     fn can_instantiate_zero_depth_tree() {
-        Tree { data: vec![0], offsets: vec![0], children: vec![], };
+        Tree { data: vec![0],
+               shape: Arc::new(Shape { offsets: vec![0], children: vec![], depth_first: true, }), };
+    }
+
+    #[test]
+    fn depth_first_layout() {
+        let t = Tree::from(owned_tree![1, [2, [3], [4]], [5, [6]]]);
+        assert_eq![t.size(), 6];
+        assert_eq![t.nodes(), &[1, 2, 3, 4, 5, 6]];
+        assert_eq![t.nodes(), NUMBERS.nodes()];
+        assert_eq![format!["{:?}", t], "(1 (2 (3) (4)) (5 (6)))"];
+    }
+
+    #[test]
+    fn trees_share_shape() {
+        let t = Tree::from(owned_tree![1, [2, [3], [4]], [5, [6]]]);
+        let u = Tree::with_shape(t.shape().clone(), vec!["a", "b", "c", "d", "e", "f"]);
+        assert![Arc::ptr_eq(t.shape(), u.shape())];
+        assert_eq![t.shape().size(), 6];
+        assert![u.is_depth_first()];
+        assert_eq![format!["{:?}", u], "(\"a\" (\"b\" (\"c\") (\"d\")) (\"e\" (\"f\")))"];
+    }
+
+    #[test]
+    fn map_data_into_reuses_output() {
+        let t = Tree::from(owned_tree![1, [2, [3], [4]], [5, [6]]]);
+        let mut out = t.map_data(|d| d * 10);
+        assert![Arc::ptr_eq(t.shape(), out.shape())];
+        assert_eq![out.nodes(), &[10, 20, 30, 40, 50, 60]];
+        let before = out.nodes().as_ptr();
+        for k in 1..4 {
+            t.map_data_into(|d| d * k, &mut out);
+        }
+        assert_eq![out.nodes(), &[3, 6, 9, 12, 15, 18]];
+        assert_eq![out.nodes().as_ptr(), before];
+        // Equal shapes need not be shared.
+        let mut other = Tree::from(owned_tree![0, [0, [0], [0]], [0, [0]]]);
+        t.map_data_into(|d| d + 1, &mut other);
+        assert_eq![format!["{:?}", other], "(2 (3 (4) (5)) (6 (7)))"];
+    }
+
+    #[test]
+    #[should_panic(expected = "trees do not have the same shape")]
+    fn map_data_into_checks_shape() {
+        let t = Tree::from(owned_tree![1, [2]]);
+        t.map_data_into(|d| *d, &mut Tree::from(owned_tree![1, [2], [3]]));
+    }
+
+    #[test]
+    fn data_at_path_follows_child_indices() {
+        let t = Tree::from(owned_tree![1, [2, [3], [4]], [5, [6]]]);
+        assert_eq![t.node_at_path(&[]), Some(t.root())];
+        assert_eq![t.data_at_path(&[]), Some(&1)];
+        assert_eq![t.data_at_path(&[0, 1]), Some(&4)];
+        assert_eq![t.data_at_path(&[1, 0]), Some(&6)];
+        assert_eq![t.node_at_path(&[1, 0]).map(|node| t.nodes()[node.index()]), Some(6)];
+        assert_eq![t.data_at_path(&[2]), None];
+        assert_eq![t.data_at_path(&[1, 0, 0]), None];
+        assert_eq![t.fixed_ref().data_at_path(&[0, 0]), Some(&3)];
+    }
+
+    #[test]
+    fn map_nodes_in_place_visits_storage_order() {
+        let mut t = Tree::from(owned_tree![1, [2, [3], [4]], [5, [6]]]);
+        let mut seen = Vec::new();
+        t.map_nodes_in_place(|index, data| {
+            seen.push(index);
+            *data *= 10;
+        });
+        assert_eq![seen, vec![0, 1, 2, 3, 4, 5]];
+        assert_eq![format!["{:?}", t], "(10 (20 (30) (40)) (50 (60)))"];
+    }
+
+    #[test]
+    fn fill_from_slice_checks_length() {
+        use ::error::LengthMismatch;
+
+        let mut t = Tree::from(owned_tree![1, [2, [3], [4]], [5, [6]]]);
+        assert_eq![t.fill_from_slice(&[0, 0]), Err(LengthMismatch { expected: 6, actual: 2, })];
+        assert_eq![t.nodes(), &[1, 2, 3, 4, 5, 6]];
+        assert_eq![t.fill_from_slice(&[7, 8, 9, 10, 11, 12]), Ok(())];
+        assert_eq![format!["{:?}", t], "(7 (8 (9) (10)) (11 (12)))"];
+    }
+
+    #[test]
+    #[should_panic(expected = "data length does not match shape size")]
+    fn with_shape_checks_length() {
+        Tree::with_shape(Tree::leaf(0).shape().clone(), vec![1, 2]);
+    }
+
+    #[test]
+    fn breadth_first_layout() {
+        let (data, children) = owned_tree![1, [2, [3], [4]], [5, [6]]].into_nodes();
+        let t = Tree::from_traversal(BreadthQueue::new(), data, children);
+        assert_eq![t.nodes(), &[1, 2, 5, 3, 4, 6]];
+        assert_eq![format!["{:?}", t], "(1 (2 (3) (4)) (5 (6)))"];
+    }
+
+    #[test]
+    fn best_first_layout() {
+        let (data, children) = owned_tree![5, [7, [1]], [3, [6], [2]]].into_nodes();
+        let t = Tree::from_traversal(PriorityQueue::new(|c: &(_, _, i32, _)| c.2), data, children);
+        assert_eq![t.nodes(), &[5, 3, 2, 6, 7, 1]];
+        assert_eq![format!["{:?}", t], "(5 (7 (1)) (3 (6) (2)))"];
+    }
+
+    #[test]
+    fn numbering() {
+        // Laid out breadth-first, so storage order differs from pre-order.
+        let (data, children) = owned_tree![1, [2, [3], [4]], [5, [6]]].into_nodes();
+        let t = Tree::from_traversal(BreadthQueue::new(), data, children);
+        let n = t.number_nodes();
+        let id = |d| NodeId(t.nodes().iter().position(|&x| x == d).unwrap());
+        let pre: Vec<usize> = (1..7).map(|d| n.preorder(id(d))).collect();
+        let post: Vec<usize> = (1..7).map(|d| n.postorder(id(d))).collect();
+        assert_eq![vec![0, 1, 2, 3, 4, 5], pre];
+        assert_eq![vec![5, 2, 0, 1, 4, 3], post];
+        assert_eq![Some(id(4)), n.node_at_preorder(3)];
+        assert_eq![None, n.node_at_preorder(6)];
+        assert![n.is_ancestor(id(1), id(6))];
+        assert![n.is_ancestor(id(2), id(4))];
+        assert![! n.is_ancestor(id(2), id(6))];
+        assert![! n.is_ancestor(id(4), id(2))];
+        assert![! n.is_ancestor(id(2), id(2))];
+    }
+
+    #[test]
+    fn euler_tour_and_lca() {
+        let t = Tree::from(owned_tree![0, [1, [2], [3]], [4, [5, [6]]]]);
+        let id = |d| NodeId(t.nodes().iter().position(|&x| x == d).unwrap());
+        let tour: Vec<(i32, Direction)> = t.euler_tour().into_iter()
+            .map(|(node, direction)| (t.nodes()[node.0], direction))
+            .collect();
+        let (enter, leave) = (Direction::Enter, Direction::Leave);
+        assert_eq![vec![(0, enter), (1, enter), (2, enter), (2, leave), (3, enter), (3, leave),
+                        (1, leave), (4, enter), (5, enter), (6, enter), (6, leave), (5, leave),
+                        (4, leave), (0, leave)],
+                   tour];
+
+        let lca = t.lca_index();
+        assert_eq![id(1), lca.lca(id(2), id(3))];
+        assert_eq![id(0), lca.lca(id(3), id(6))];
+        assert_eq![id(4), lca.lca(id(6), id(4))];
+        assert_eq![id(5), lca.lca(id(5), id(5))];
+        assert_eq![3, lca.depth(id(6))];
+        assert_eq![5, lca.distance(id(2), id(6))];
+        assert_eq![0, lca.distance(id(3), id(3))];
+    }
+
+    #[test]
+    fn search_path() {
+        let t = Tree::from(owned_tree!["", ["animal", ["bird"], ["cat"], ["dog"]],
+                                       ["plant", ["fern"], ["oak"]]]);
+        let found = t.search_path(&["animal", "cat"]).unwrap();
+        assert_eq![t.nodes()[found.index()], "cat"];
+        assert_eq![t.search_path(&[]), Ok(t.root())];
+        let frontier = t.search_path(&["plant", "moss", "spore"]).unwrap_err();
+        assert_eq![t.nodes()[frontier.index()], "plant"];
+        assert_eq![t.search_path(&["fungus"]), Err(t.root())];
+        let leaf = t.search_path_by(&["DOG"], |d, k| d.to_uppercase().as_str().cmp(k));
+        assert_eq![leaf, Err(t.root())];
+        let dog = t.search_path_by(&["ANIMAL", "DOG"], |d, k| d.to_uppercase().as_str().cmp(k));
+        assert_eq![t.nodes()[dog.unwrap().index()], "dog"];
+    }
+
+    #[test]
+    fn bit_flags() {
+        let t = Tree::from(owned_tree![1, [2, [3], [4]], [5, [6]]]);
+        let mut flags = t.bit_flags(3);
+        assert_eq![flags.node_count(), 6];
+        let (root, five) = {
+            let mut v = t.view();
+            assert![v.seek_child(1)];
+            (t.root(), v.id())
+        };
+        assert![! flags.set(five, 2)];
+        assert![flags.set(five, 2)];
+        flags.set(root, 0);
+        for i in 0..t.size() {
+            flags.set(NodeId(i), 1);
+        }
+        assert![flags.test(five, 2)];
+        assert![! flags.test(five, 0)];
+        assert![! flags.test(root, 2)];
+        assert_eq![flags.nodes_with(1).count(), 6];
+        assert![flags.clear(five, 2)];
+        assert![! flags.clear(five, 2)];
+        assert_eq![flags.nodes_with(0).collect::<Vec<_>>(), vec![root]];
+        flags.clear_all();
+        assert_eq![flags, t.fixed_ref().bit_flags(3)];
+    }
+
+    #[test]
+    fn depth_first_subtrees_are_contiguous() {
+        let mut t = Tree::from(owned_tree![1, [2, [3], [4]], [5, [6]]]);
+        assert![t.is_depth_first()];
+        assert_eq![t.subtree_slice(t.root()), t.nodes()];
+        let (two, four) = {
+            let mut v = t.view();
+            assert![v.seek_child(0)];
+            let two = v.id();
+            assert![v.seek_child(1)];
+            (two, v.id())
+        };
+        assert_eq![t.subtree_slice(two), &[2, 3, 4]];
+        assert_eq![t.subtree_slice(four), &[4]];
+        t.subtree_slice_mut(two)[1..].reverse();
+        assert_eq![format!["{:?}", t], "(1 (2 (4) (3)) (5 (6)))"];
+        assert_eq![NUMBERS.subtree_slice(NUMBERS.view().id()), &[1, 2, 3, 4, 5, 6]];
+        let mut v = NUMBERS.bounded_view::<1>();
+        assert![v.seek_child(0)];
+        assert_eq![NUMBERS.subtree_slice(v.id()), &[2, 3, 4]];
+    }
+
+    #[test]
+    #[should_panic]
+    fn breadth_first_subtrees_are_not_sliced() {
+        let (data, children) = owned_tree![1, [2, [3], [4]], [5, [6]]].into_nodes();
+        let t = Tree::from_traversal(BreadthQueue::new(), data, children);
+        assert![! t.is_depth_first()];
+        t.subtree_slice(t.root());
+    }
+
+    #[test]
+    fn view_mut_updates_data() {
+        let mut t = Tree::from(owned_tree![1, [2], [3]]);
+        {
+            let mut v = t.view_mut();
+            assert![v.seek_child(1)];
+            *v = 30;
+            assert![v.seek_sibling(-1)];
+            *v = 20;
+        }
+        assert_eq![format!["{:?}", t], "(1 (20) (30))"];
+    }
+
+    #[test]
+    fn enumerate_siblings() {
+        let t = Tree::from(owned_tree![1, [2, [3], [4]], [5], [6]]);
+        let mut v = t.view();
+        assert_eq![v.enumerate_siblings().collect::<Vec<_>>(), vec![(0, &1)]];
+        assert![v.seek_child(1)];
+        assert_eq![v.enumerate_siblings().collect::<Vec<_>>(), vec![(-1, &2), (0, &5), (1, &6)]];
+        assert![v.seek_sibling(-1)];
+        assert![v.seek_child(1)];
+        assert_eq![v.enumerate_siblings().collect::<Vec<_>>(), vec![(-1, &3), (0, &4)]];
+    }
+
+    static_tree! {
+        static LEAF: u32 = [7];
+    }
+
+    static_tree! {
+        static NUMBERS: u32 = [1, [2, [3], [4]], [5, [6]]];
+    }
+
+    #[test]
+    fn static_tree_leaf() {
+        assert_eq![LEAF.size(), 1];
+        let v = LEAF.view();
+        assert_eq![*v, 7];
+        assert![v.at_leaf()];
+    }
+
+    #[test]
+    fn static_tree_layout() {
+        assert_eq![NUMBERS.size(), 6];
+        assert_eq![NUMBERS.nodes(), &[1, 2, 3, 4, 5, 6]];
+        assert_eq![format!["{:?}", NUMBERS], "(1 (2 (3) (4)) (5 (6)))"];
+    }
+
+    #[test]
+    fn static_tree_navigates() {
+        let mut v = NUMBERS.view();
+        assert_eq![v.child_count(), 2];
+        assert![v.seek_child(1)];
+        assert![v.seek_child(0)];
+        assert_eq![*v, 6];
+        let mut b = NUMBERS.bounded_view::<1>();
+        assert![b.seek_child(0)];
+        assert![! b.seek_child(0)];
+        assert_eq![*b, 2];
+    }
+
+    #[test]
+    fn bounded_view_navigates() {
+        let t = Tree::from(owned_tree![1, [2, [3], [4]], [5, [6]]]);
+        let mut v = t.bounded_view::<2>();
+        assert![v.seek_child(0)];
+        assert![v.seek_child(1)];
+        assert_eq![*v, 4];
+        assert_eq![v.depth(), 2];
+        assert![v.seek_sibling(-1)];
+        assert_eq![*v, 3];
+        assert![v.to_parent()];
+        assert![v.seek_sibling(1)];
+        assert_eq![*v, 5];
+        v.to_root();
+        assert_eq![*v, 1];
+        assert![! v.to_parent()];
+    }
+
+    #[test]
+    fn bounded_view_reports_depth_exceeded() {
+        let t = Tree::from(owned_tree![1, [2, [3, [4]]]]);
+        let mut v = t.bounded_view::<1>();
+        assert_eq![v.try_seek_child(0), Ok(true)];
+        assert_eq![v.try_seek_child(0), Err(DepthExceeded { max_depth: 1, })];
+        assert_eq![v.try_seek_child(1), Ok(false)];
+        assert![! v.seek_child(0)];
+        assert_eq![*v, 2];
+    }
+
+    #[test]
+    fn bounded_views_compare_positions() {
+        let t = Tree::from(owned_tree![1, [2, [3]], [4]]);
+        let mut a = t.bounded_view::<2>();
+        let mut b = t.bounded_view::<2>();
+        assert![a.seek_child(0)];
+        assert![b.seek_child(1)];
+        assert![a < b];
+        assert![b.seek_sibling(-1)];
+        assert![a == b];
+        assert![a.seek_child(0)];
+        assert![b < a];
+        let other = Tree::from(owned_tree![1, [2, [3]], [4]]);
+        assert_eq![None, other.bounded_view::<2>().partial_cmp(&t.bounded_view::<2>())];
     }
 }
@@ -0,0 +1,185 @@
+use ::Nav;
+use ::path::TreePath;
+use ::util::FocusPath;
+
+use std::collections::BTreeSet;
+use std::ops::Deref;
+use std::rc::Rc;
+
+/// Wrapper around a `Nav` that treats folded nodes as leaves.
+///
+/// This gives tree widgets the folding they need without keeping parallel
+/// state: a folded node reports no children and cannot be descended into, so
+/// traversals over the wrapper skip its subtree. Nodes are folded either by
+/// path with `fold` or by a predicate on the view focused on them, and `unfold`
+/// exposes a node's children again whichever way it was folded.
+///
+/// Folds are recorded by path, so they are not updated if the tree's shape
+/// changes. Folding an ancestor of the focus does not move the focus.
+pub struct FoldedNav<N> {
+    nav: N,
+    focus: FocusPath,
+    folded: BTreeSet<TreePath>,
+    unfolded: BTreeSet<TreePath>,
+    predicate: Option<Predicate<N>>,
+}
+
+type Predicate<N> = Rc<dyn Fn(&N) -> bool>;
+
+impl<N: Nav> FoldedNav<N> {
+    /// Wraps `nav` with no nodes folded, moving its focus to the root so that
+    /// paths can be tracked from there.
+    pub fn new(mut nav: N) -> Self {
+        nav.to_root();
+        FoldedNav { nav, focus: FocusPath::new(), folded: BTreeSet::new(),
+                    unfolded: BTreeSet::new(), predicate: None, }
+    }
+
+    /// Wraps `nav`, folding each node for which `predicate` returns true when
+    /// given a view focused on it.
+    pub fn with_predicate<P>(nav: N, predicate: P) -> Self where P: Fn(&N) -> bool + 'static {
+        let mut folded = FoldedNav::new(nav);
+        folded.predicate = Some(Rc::new(predicate));
+        folded
+    }
+
+    /// Returns the path of the focus.
+    pub fn path(&self) -> &TreePath {
+        self.focus.path()
+    }
+
+    /// Folds the node at `path`.
+    pub fn fold(&mut self, path: TreePath) {
+        self.unfolded.remove(&path);
+        self.folded.insert(path);
+    }
+
+    /// Unfolds the node at `path`, even if the predicate would fold it.
+    pub fn unfold(&mut self, path: TreePath) {
+        self.folded.remove(&path);
+        self.unfolded.insert(path);
+    }
+
+    /// Returns `true` iff the focus is folded.
+    pub fn is_folded(&self) -> bool {
+        if self.folded.contains(self.focus.path()) {
+            return true
+        }
+        if self.unfolded.contains(self.focus.path()) {
+            return false
+        }
+        self.predicate.as_ref().is_some_and(|predicate| predicate(&self.nav))
+    }
+
+    /// Returns the number of children the focus has in the underlying tree,
+    /// whether or not it is folded.
+    pub fn hidden_child_count(&self) -> usize {
+        self.nav.child_count()
+    }
+
+    /// Returns the wrapped view.
+    pub fn into_inner(self) -> N {
+        self.nav
+    }
+}
+
+impl<N: Clone> Clone for FoldedNav<N> {
+    fn clone(&self) -> Self {
+        FoldedNav { nav: self.nav.clone(), focus: self.focus.clone(), folded: self.folded.clone(),
+                    unfolded: self.unfolded.clone(), predicate: self.predicate.clone(), }
+    }
+}
+
+impl<N: Deref> Deref for FoldedNav<N> {
+    type Target = N::Target;
+
+    fn deref(&self) -> &N::Target {
+        &self.nav
+    }
+}
+
+impl<N: Nav> Nav for FoldedNav<N> {
+    fn child_count(&self) -> usize {
+        if self.is_folded() { 0 } else { self.nav.child_count() }
+    }
+
+    fn at_root(&self) -> bool {
+        self.nav.at_root()
+    }
+
+    fn index_in_parent(&self) -> Option<usize> {
+        self.nav.index_in_parent()
+    }
+
+    fn seek_sibling(&mut self, offset: isize) -> bool {
+        let moved = self.nav.seek_sibling(offset);
+        if moved {
+            self.focus.seek_sibling(offset);
+        }
+        moved
+    }
+
+    fn seek_child(&mut self, index: usize) -> bool {
+        if self.is_folded() {
+            return false
+        }
+        let moved = self.nav.seek_child(index);
+        if moved {
+            self.focus.seek_child(index);
+        }
+        moved
+    }
+
+    fn to_parent(&mut self) -> bool {
+        let moved = self.nav.to_parent();
+        if moved {
+            self.focus.pop();
+        }
+        moved
+    }
+
+    fn to_root(&mut self) {
+        self.nav.to_root();
+        self.focus.reset();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::Nav;
+    use ::fold::FoldedNav;
+    use ::owned_tree;
+    use ::traversal::preorder;
+
+    #[test]
+    fn hides_folded_subtrees() {
+        let t = owned_tree!["r", ["a", ["b"], ["c"]], ["d", ["e"]]];
+        let mut v = FoldedNav::new(t.view());
+        v.fold("/0".parse().unwrap());
+        let labels: Vec<&str> = preorder(v.clone()).map(|visit| *visit.node).collect();
+        assert_eq![vec!["r", "a", "d", "e"], labels];
+
+        assert![v.seek_child(0)];
+        assert![v.is_folded()];
+        assert![v.at_leaf()];
+        assert_eq![2, v.hidden_child_count()];
+        assert![! v.seek_child(0)];
+        v.unfold("/0".parse().unwrap());
+        assert![v.seek_child(1)];
+        assert_eq!["c", *v];
+        assert_eq!["/0/1", v.path().to_string()];
+    }
+
+    #[test]
+    fn folds_by_predicate() {
+        let t = owned_tree!["r", ["a", ["b"]], ["+d", ["e"]], ["+f"]];
+        let mut v = FoldedNav::with_predicate(t.view(), |v| v.starts_with('+'));
+        v.unfold("/2".parse().unwrap());
+        let labels: Vec<&str> = preorder(v.clone()).map(|visit| *visit.node).collect();
+        assert_eq![vec!["r", "a", "b", "+d", "+f"], labels];
+        v.unfold("/1".parse().unwrap());
+        assert_eq![6, preorder(v.clone()).count()];
+        v.fold("/1".parse().unwrap());
+        assert_eq![5, preorder(v).count()];
+    }
+}
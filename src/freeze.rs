@@ -0,0 +1,446 @@
+use ::{Editor, Nav, RemoveFocusPolicy};
+use ::error::{EditError, NavError};
+use ::path::TreePath;
+use ::util::{self, FocusPath};
+
+use std::ops::{Deref, DerefMut};
+
+/// Wrapper around an `Editor` that makes chosen subtrees read-only.
+///
+/// This is meant for editors shared by several components, each of which owns
+/// some region of the tree that the others must leave alone. A subtree frozen
+/// with `freeze_subtree` cannot be changed through the wrapper until it is
+/// unfrozen.
+///
+/// The wrapper navigates like the wrapped editor, but edits through methods of
+/// its own, named after those of `Editor`. Each returns `Err(EditError::Frozen)`,
+/// leaving the tree unchanged, if the edit would add, remove, replace, or
+/// reorder nodes within a frozen subtree, or remove it whole, and otherwise
+/// returns what the wrapped editor's method does. Data is changed through
+/// `data_mut`, which fails the same way for a frozen node. `is_frozen` tells
+/// whether a node is frozen.
+///
+/// Edits elsewhere are forwarded to the wrapped editor, and frozen subtrees
+/// may still be moved among their siblings, so the wrapper tracks the focus's
+/// path and keeps the path of each frozen subtree up to date.
+pub struct Freezer<E> {
+    editor: E,
+    focus: FocusPath,
+    frozen: Vec<TreePath>,
+}
+
+impl<E: Editor> Freezer<E> {
+    /// Wraps `editor` with nothing frozen, moving its focus to the root so that
+    /// paths can be tracked from there.
+    pub fn new(mut editor: E) -> Self {
+        editor.to_root();
+        Freezer { editor, focus: FocusPath::new(), frozen: Vec::new(), }
+    }
+
+    /// Returns the path of the focus.
+    pub fn path(&self) -> &TreePath {
+        self.focus.path()
+    }
+
+    /// Freezes the subtree at `path`, which should address a node of the
+    /// tree.
+    pub fn freeze_subtree(&mut self, path: TreePath) {
+        if !self.frozen.contains(&path) {
+            self.frozen.push(path);
+        }
+    }
+
+    /// Unfreezes the subtree at `path`, which must have been frozen with
+    /// `freeze_subtree`; parts of it may remain frozen if they were frozen
+    /// separately or lie within another frozen subtree. Returns false if
+    /// `path` was not frozen.
+    pub fn unfreeze(&mut self, path: &TreePath) -> bool {
+        let count = self.frozen.len();
+        self.frozen.retain(|frozen| frozen != path);
+        self.frozen.len() != count
+    }
+
+    /// Returns the paths of the frozen subtrees, which follow the subtrees as
+    /// edits move them.
+    pub fn frozen_paths(&self) -> &[TreePath] {
+        &self.frozen
+    }
+
+    /// Returns true iff the node at `path` is in a frozen subtree.
+    pub fn is_frozen(&self, path: &TreePath) -> bool {
+        self.frozen.iter().any(|frozen| frozen.contains(path))
+    }
+
+    /// Returns the wrapped editor.
+    pub fn into_inner(self) -> E {
+        self.editor
+    }
+
+    // Returns true iff the focus is in a frozen subtree.
+    fn here_frozen(&self) -> bool {
+        self.is_frozen(self.focus.path())
+    }
+
+    // Returns true iff the parent of the focus is in a frozen subtree.
+    fn parent_frozen(&self) -> bool {
+        self.focus.parent().is_some_and(|parent| self.is_frozen(&parent))
+    }
+
+    // Returns true iff removing or replacing the subtree at `path` would
+    // change or remove a frozen subtree.
+    fn overlaps_frozen(&self, path: &TreePath) -> bool {
+        self.frozen.iter().any(|frozen| frozen.contains(path) || path.contains(frozen))
+    }
+
+    // Fails if `frozen`, which tells whether an edit would change a frozen
+    // subtree.
+    fn refuse_if(frozen: bool) -> Result<(), EditError> {
+        if frozen {
+            Err(EditError::Frozen)
+        } else {
+            Ok(())
+        }
+    }
+
+    // Returns the path of the sibling at `offset` from the focus, if the focus
+    // is not the root.
+    fn sibling_path(&self, offset: isize) -> Option<TreePath> {
+        let parent = self.focus.parent()?;
+        let index = (self.focus.here_index() as isize).checked_add(offset)?;
+        if index < 0 {
+            return None
+        }
+        Some(parent.child(index as usize))
+    }
+
+    // Maps the index of each frozen path below `parent` at the depth of its
+    // children with `f`. Edits that would remove a frozen subtree are refused,
+    // so `f` never drops one.
+    fn reindex_frozen<G>(&mut self, parent: &TreePath, f: G)
+        where G: Fn(usize) -> Option<usize> {
+            self.frozen.retain_mut(|frozen| util::reindex_below(frozen, parent, &f));
+        }
+
+    // Records that a child was inserted at `index` among the children of the
+    // focus, and focuses on it.
+    fn inserted_child(&mut self, index: usize) {
+        let here = self.focus.path().clone();
+        self.reindex_frozen(&here, util::after_insert(index));
+        self.focus.seek_child(index);
+    }
+
+    // Records that a sibling was inserted at `offset` from the focus, and
+    // focuses on it.
+    fn inserted_sibling(&mut self, offset: isize) {
+        let index = self.focus.sibling_index(offset);
+        self.reindex_frozen(&self.focus.parent().unwrap(), util::after_insert(index));
+        self.focus.set_here_index(index);
+    }
+}
+
+impl<E: Editor + Deref> Deref for Freezer<E> {
+    type Target = E::Target;
+
+    fn deref(&self) -> &E::Target {
+        &self.editor
+    }
+}
+
+impl<E: Editor> Nav for Freezer<E> {
+    fn child_count(&self) -> usize {
+        self.editor.child_count()
+    }
+
+    fn at_leaf(&self) -> bool {
+        self.editor.at_leaf()
+    }
+
+    fn at_root(&self) -> bool {
+        self.editor.at_root()
+    }
+
+    fn index_in_parent(&self) -> Option<usize> {
+        self.editor.index_in_parent()
+    }
+
+    fn seek_sibling(&mut self, offset: isize) -> bool {
+        let moved = self.editor.seek_sibling(offset);
+        if moved {
+            self.focus.seek_sibling(offset);
+        }
+        moved
+    }
+
+    fn seek_child(&mut self, index: usize) -> bool {
+        let moved = self.editor.seek_child(index);
+        if moved {
+            self.focus.seek_child(index);
+        }
+        moved
+    }
+
+    fn to_parent(&mut self) -> bool {
+        let moved = self.editor.to_parent();
+        if moved {
+            self.focus.pop();
+        }
+        moved
+    }
+
+    fn to_root(&mut self) {
+        self.editor.to_root();
+        self.focus.reset();
+    }
+}
+
+impl<E: Editor> Freezer<E> {
+    /// Like `Editor::push_leaf`.
+    pub fn push_leaf(&mut self, data: E::Data) -> Result<(), EditError> {
+        Self::refuse_if(self.here_frozen())?;
+        let index = self.editor.child_count();
+        self.editor.push_leaf(data);
+        self.focus.seek_child(index);
+        Ok(())
+    }
+
+    /// Like `Editor::push_child`.
+    pub fn push_child(&mut self, child: E::Tree) -> Result<(), EditError> {
+        Self::refuse_if(self.here_frozen())?;
+        let index = self.editor.child_count();
+        self.editor.push_child(child);
+        self.focus.seek_child(index);
+        Ok(())
+    }
+
+    /// Like `Editor::insert_leaf`.
+    pub fn insert_leaf(&mut self, index: usize, data: E::Data) -> Result<bool, EditError> {
+        Self::refuse_if(self.here_frozen())?;
+        let inserted = self.editor.insert_leaf(index, data);
+        if inserted {
+            self.inserted_child(index);
+        }
+        Ok(inserted)
+    }
+
+    /// Like `Editor::insert_child`.
+    pub fn insert_child(&mut self, index: usize, child: E::Tree) -> Result<bool, EditError> {
+        Self::refuse_if(self.here_frozen())?;
+        let inserted = self.editor.insert_child(index, child);
+        if inserted {
+            self.inserted_child(index);
+        }
+        Ok(inserted)
+    }
+
+    /// Like `Editor::insert_sibling_leaf`.
+    pub fn insert_sibling_leaf(&mut self, offset: isize, data: E::Data)
+                               -> Result<bool, EditError> {
+        Self::refuse_if(self.parent_frozen())?;
+        let inserted = self.editor.insert_sibling_leaf(offset, data);
+        if inserted {
+            self.inserted_sibling(offset);
+        }
+        Ok(inserted)
+    }
+
+    /// Like `Editor::insert_sibling`.
+    pub fn insert_sibling(&mut self, offset: isize, sibling: E::Tree) -> Result<bool, EditError> {
+        Self::refuse_if(self.parent_frozen())?;
+        let inserted = self.editor.insert_sibling(offset, sibling);
+        if inserted {
+            self.inserted_sibling(offset);
+        }
+        Ok(inserted)
+    }
+
+    pub fn remove_focus_policy(&self) -> RemoveFocusPolicy {
+        self.editor.remove_focus_policy()
+    }
+
+    pub fn set_remove_focus_policy(&mut self, policy: RemoveFocusPolicy) -> bool {
+        self.editor.set_remove_focus_policy(policy)
+    }
+
+    /// Like `Editor::remove`, which panics if the focus is the root.
+    pub fn remove(&mut self) -> Result<E::Tree, EditError> {
+        if self.editor.at_root() {
+            panic!["{}", NavError::AtRoot]
+        }
+        Self::refuse_if(self.overlaps_frozen(self.focus.path()))?;
+        let parent = self.focus.parent().unwrap();
+        let (removed, index) = util::remove_focus(&mut self.editor, &mut self.focus);
+        self.reindex_frozen(&parent, util::after_remove(index));
+        Ok(removed)
+    }
+
+    /// Like `Editor::remove_child`.
+    pub fn remove_child(&mut self, index: usize) -> Result<E::Tree, EditError> {
+        Self::refuse_if(self.overlaps_frozen(&self.focus.path().child(index)))?;
+        let removed = self.editor.remove_child(index)?;
+        let here = self.focus.path().clone();
+        self.reindex_frozen(&here, util::after_remove(index));
+        Ok(removed)
+    }
+
+    /// Like `Editor::replace_child`, which panics if there is no child at
+    /// `index`.
+    pub fn replace_child(&mut self, index: usize, child: E::Tree) -> Result<E::Tree, EditError> {
+        Self::refuse_if(self.overlaps_frozen(&self.focus.path().child(index)))?;
+        Ok(self.editor.replace_child(index, child))
+    }
+
+    /// Like `Editor::remove_sibling`.
+    pub fn remove_sibling(&mut self, offset: isize) -> Result<Option<E::Tree>, EditError> {
+        if offset == 0 {
+            return if self.editor.at_root() { Ok(None) } else { self.remove().map(Some) }
+        }
+        let sibling = self.sibling_path(offset);
+        Self::refuse_if(sibling.is_some_and(|sibling| self.overlaps_frozen(&sibling)))?;
+        let removed = self.editor.remove_sibling(offset);
+        if removed.is_some() {
+            let index = self.focus.sibling_index(offset);
+            self.reindex_frozen(&self.focus.parent().unwrap(), util::after_remove(index));
+            self.focus.sibling_removed(offset);
+        }
+        Ok(removed)
+    }
+
+    /// Like `Editor::take_children`.
+    pub fn take_children(&mut self) -> Result<Vec<E::Tree>, EditError> {
+        Self::refuse_if(self.overlaps_frozen(self.focus.path()))?;
+        Ok(self.editor.take_children())
+    }
+
+    /// Like `Editor::swap`.
+    pub fn swap(&mut self, other: &mut E::Tree) -> Result<(), EditError> {
+        Self::refuse_if(self.overlaps_frozen(self.focus.path()))?;
+        self.editor.swap(other);
+        Ok(())
+    }
+
+    /// Like `Editor::swap_children`.
+    pub fn swap_children(&mut self, index_a: usize, index_b: usize) -> Result<bool, EditError> {
+        Self::refuse_if(self.here_frozen())?;
+        let swapped = self.editor.swap_children(index_a, index_b);
+        if swapped {
+            let here = self.focus.path().clone();
+            self.reindex_frozen(&here, util::after_swap(index_a, index_b));
+        }
+        Ok(swapped)
+    }
+
+    /// Like `Editor::swap_siblings`.
+    pub fn swap_siblings(&mut self, offset_a: isize, offset_b: isize) -> Result<bool, EditError> {
+        Self::refuse_if(self.parent_frozen())?;
+        let swapped = self.editor.swap_siblings(offset_a, offset_b);
+        if let (true, Some(parent)) = (swapped, self.focus.parent()) {
+            let (index_a, index_b) =
+                (self.focus.sibling_index(offset_a), self.focus.sibling_index(offset_b));
+            self.reindex_frozen(&parent, util::after_swap(index_a, index_b));
+            self.focus.siblings_swapped(offset_a, offset_b);
+        }
+        Ok(swapped)
+    }
+
+    /// Like `Editor::permute_children`.
+    pub fn permute_children(&mut self, perm: &[usize]) -> Result<(), EditError> {
+        Self::refuse_if(self.here_frozen())?;
+        self.editor.permute_children(perm)?;
+        // The child now at `i` was at `perm[i]`.
+        let mut new_index = vec![0; perm.len()];
+        for (i, &old) in perm.iter().enumerate() {
+            new_index[old] = i;
+        }
+        let here = self.focus.path().clone();
+        self.reindex_frozen(&here, |i| Some(new_index[i]));
+        Ok(())
+    }
+}
+
+impl<E: Editor + DerefMut> Freezer<E> {
+    /// Returns the focus's data for writing, or `Err(EditError::Frozen)` if the
+    /// focus is frozen.
+    pub fn data_mut(&mut self) -> Result<&mut E::Target, EditError> {
+        Self::refuse_if(self.here_frozen())?;
+        Ok(&mut self.editor)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::Nav;
+    use ::error::EditError;
+    use ::freeze::Freezer;
+    use ::owned_tree;
+    use ::path::TreePath;
+
+    fn path(indices: &[usize]) -> TreePath {
+        TreePath::new(indices.to_vec())
+    }
+
+    #[test]
+    fn refuses_edits_inside_frozen_subtrees() {
+        let mut t = owned_tree!["r", ["a", ["b"]], ["c"]];
+        {
+            let mut e = Freezer::new(t.view_mut());
+            e.freeze_subtree(path(&[0]));
+            assert_eq![Err(EditError::Frozen), e.remove_child(0)];
+            assert_eq![Err(EditError::Frozen), e.replace_child(0, owned_tree!["x"])];
+            assert_eq![Err(EditError::Frozen), e.take_children()];
+            assert![e.seek_child(0)];
+            assert![e.is_frozen(e.path())];
+            assert_eq![Err(EditError::Frozen), e.push_leaf("x")];
+            assert_eq![Err(EditError::Frozen), e.insert_leaf(0, "x")];
+            assert_eq![Err(EditError::Frozen), e.remove_child(0)];
+            assert_eq![Err(EditError::Frozen), e.permute_children(&[0])];
+            assert_eq![Err(EditError::Frozen), e.remove()];
+            assert_eq![Err(EditError::Frozen), e.swap(&mut owned_tree!["x"])];
+            assert_eq![Some(EditError::Frozen), e.data_mut().err()];
+            assert![e.seek_child(0)];
+            assert_eq![Err(EditError::Frozen), e.insert_sibling_leaf(1, "x")];
+            assert_eq![Err(EditError::Frozen), e.swap_siblings(0, 0)];
+            assert![e.to_parent()];
+            assert![e.seek_sibling(1)];
+            assert_eq![Err(EditError::Frozen), e.remove_sibling(-1)];
+            *e.data_mut().unwrap() = "d";
+            assert_eq![Ok(()), e.push_leaf("e")];
+        }
+        assert_eq![owned_tree!["r", ["a", ["b"]], ["d", ["e"]]], t];
+    }
+
+    #[test]
+    fn passes_on_other_failures() {
+        let mut t = owned_tree!["r", ["a"]];
+        let mut e = Freezer::new(t.view_mut());
+        e.freeze_subtree(path(&[0]));
+        assert_eq![Ok(false), e.insert_leaf(2, "x")];
+        assert_eq![Ok(false), e.swap_children(0, 1)];
+        assert_eq![Err(EditError::ChildOutOfRange { index: 1, child_count: 1, }),
+                   e.remove_child(1)];
+        assert_eq![Ok(None), e.remove_sibling(0)];
+    }
+
+    #[test]
+    fn frozen_paths_follow_edits() {
+        let mut t = owned_tree!["r", ["a"], ["b", ["c"]], ["d"]];
+        {
+            let mut e = Freezer::new(t.view_mut());
+            e.freeze_subtree(path(&[1, 0]));
+            assert_eq![Ok(true), e.insert_leaf(0, "x")];
+            assert_eq![&[path(&[2, 0])], e.frozen_paths()];
+            assert![e.to_parent()];
+            assert![e.remove_child(1).is_ok()];
+            assert_eq![&[path(&[1, 0])], e.frozen_paths()];
+            assert_eq![Ok(true), e.swap_children(0, 1)];
+            assert_eq![&[path(&[0, 0])], e.frozen_paths()];
+            assert_eq![Ok(()), e.permute_children(&[2, 1, 0])];
+            assert_eq![&[path(&[2, 0])], e.frozen_paths()];
+            assert![e.seek_child(2)];
+            assert![e.unfreeze(&path(&[2, 0]))];
+            assert![! e.unfreeze(&path(&[2, 0]))];
+            assert![e.seek_child(0)];
+            *e.data_mut().unwrap() = "C";
+        }
+        assert_eq![owned_tree!["r", ["d"], ["x"], ["b", ["C"]]], t];
+    }
+}
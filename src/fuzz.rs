@@ -0,0 +1,374 @@
+use ::{Editor, RemoveFocusPolicy};
+use ::augmented::Augmented;
+use ::{linked_tree, owned_tree, shared_tree, sync_tree};
+
+use std::borrow::Borrow;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+
+/// A navigation or editing operation.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Op {
+    SeekChild(usize),
+    SeekSibling(isize),
+    SeekFirstSibling,
+    SeekLastSibling,
+    ToParent,
+    ToRoot,
+    PushLeaf(u32),
+    InsertLeaf(usize, u32),
+    InsertSiblingLeaf(isize, u32),
+    SetRemoveFocusPolicy(RemoveFocusPolicy),
+    Remove,
+    RemoveChild(usize),
+    RemoveSibling(isize),
+    SwapChildren(usize, usize),
+    SwapSiblings(isize, isize),
+}
+
+/// Small, deterministic pseudo-random number generator (xorshift64*), so that
+/// failing runs can be reproduced from their seed.
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // The generator never leaves the zero state, so avoid it.
+        Rng { state: seed ^ 0x9e37_79b9_7f4a_7c15, }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Returns a number in `0..n`.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// Returns a number in `-max..=max`.
+    pub fn offset(&mut self, max: usize) -> isize {
+        self.below(2 * max + 1) as isize - max as isize
+    }
+}
+
+/// The kinds of operation to generate.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct OpMix {
+    pub navigation: bool,
+    pub insertion: bool,
+    pub removal: bool,
+    pub swapping: bool,
+}
+
+impl OpMix {
+    pub fn all() -> Self {
+        OpMix { navigation: true, insertion: true, removal: true, swapping: true, }
+    }
+}
+
+const POLICIES: [RemoveFocusPolicy; 3] =
+    [RemoveFocusPolicy::PreferLeft, RemoveFocusPolicy::PreferRight, RemoveFocusPolicy::Parent];
+
+/// Generates `count` random operations of the kinds in `mix`.
+///
+/// Indices and offsets are drawn from small ranges so that both valid and
+/// out-of-range arguments are common. New leaves get increasing data values, so
+/// that each node in a run can be told apart.
+pub fn generate(rng: &mut Rng, mix: OpMix, count: usize) -> Vec<Op> {
+    let mut kinds: Vec<fn(&mut Rng, u32) -> Op> = Vec::new();
+    if mix.navigation {
+        kinds.push(|rng, _| Op::SeekChild(rng.below(4)));
+        kinds.push(|rng, _| Op::SeekSibling(rng.offset(3)));
+        kinds.push(|_, _| Op::SeekFirstSibling);
+        kinds.push(|_, _| Op::SeekLastSibling);
+        kinds.push(|_, _| Op::ToParent);
+        kinds.push(|_, _| Op::ToRoot);
+    }
+    if mix.insertion {
+        kinds.push(|_, data| Op::PushLeaf(data));
+        kinds.push(|rng, data| Op::InsertLeaf(rng.below(4), data));
+        kinds.push(|rng, data| Op::InsertSiblingLeaf(rng.offset(3), data));
+    }
+    if mix.removal {
+        kinds.push(|rng, _| Op::SetRemoveFocusPolicy(POLICIES[rng.below(POLICIES.len())]));
+        kinds.push(|_, _| Op::Remove);
+        kinds.push(|rng, _| Op::RemoveChild(rng.below(4)));
+        kinds.push(|rng, _| Op::RemoveSibling(rng.offset(3)));
+    }
+    if mix.swapping {
+        kinds.push(|rng, _| Op::SwapChildren(rng.below(4), rng.below(4)));
+        kinds.push(|rng, _| Op::SwapSiblings(rng.offset(3), rng.offset(3)));
+    }
+    assert![!kinds.is_empty(), "no kinds of operation to generate"];
+    (0..count).map(|i| {
+        let kind = kinds[rng.below(kinds.len())];
+        kind(rng, INITIAL_SIZE + i as u32)
+    }).collect()
+}
+
+/// The result of an operation, in a form that can be compared across backends.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Outcome {
+    Unit,
+    Bool(bool),
+    /// The subtree returned by a removal, formatted with `Debug`.
+    Removed(Option<String>),
+    Panicked,
+}
+
+/// Everything observable after one step of a run.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Observation {
+    pub outcome: Outcome,
+    /// The focus data, child count, and whether the focus is at the root, or
+    /// `None` if the operation panicked.
+    pub focus: Option<(u32, usize, bool)>,
+}
+
+/// The observations of a run in one backend, followed by the final tree
+/// (formatted with `Debug`) unless an operation panicked.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Trace {
+    pub steps: Vec<Observation>,
+    pub tree: Option<String>,
+}
+
+/// Applies `ops` through `editor`, stopping after any operation that panics.
+/// `data` reads the focus data.
+///
+/// Removed subtrees are formatted once `editor` is dropped, since formatting a
+/// sync tree while holding a writer on another violates lock ordering.
+pub fn run<E, F>(mut editor: E, data: F, ops: &[Op]) -> Vec<Observation>
+    where E: Editor<Data=u32>, E::Tree: fmt::Debug, F: Fn(&E) -> u32 {
+        let mut steps = Vec::with_capacity(ops.len());
+        let mut removed = Vec::new();
+        for &op in ops {
+            let outcome =
+                panic::catch_unwind(AssertUnwindSafe(|| apply(&mut editor, op, &mut removed)))
+                .unwrap_or(Outcome::Panicked);
+            if outcome == Outcome::Panicked {
+                steps.push(Observation { outcome, focus: None, });
+                break
+            }
+            let focus = Some((data(&editor), editor.child_count(), editor.at_root()));
+            steps.push(Observation { outcome, focus, });
+        }
+        drop(editor);
+        let mut removed = removed.into_iter();
+        for step in &mut steps {
+            if let Outcome::Removed(Some(ref mut tree)) = step.outcome {
+                *tree = format!["{:?}", removed.next().unwrap()];
+            }
+        }
+        steps
+    }
+
+// Applies `op`, moving any removed subtree to `removed`. The outcome of a
+// removal holds an empty string in place of the subtree until `run` formats
+// it.
+fn apply<E: Editor<Data=u32>>(e: &mut E, op: Op, removed: &mut Vec<E::Tree>) -> Outcome {
+    let mut remove = |tree: Option<E::Tree>| Outcome::Removed(tree.map(|t| {
+        removed.push(t);
+        String::new()
+    }));
+    match op {
+        Op::SeekChild(index) => Outcome::Bool(e.seek_child(index)),
+        Op::SeekSibling(offset) => Outcome::Bool(e.seek_sibling(offset)),
+        Op::SeekFirstSibling => { e.seek_first_sibling(); Outcome::Unit },
+        Op::SeekLastSibling => { e.seek_last_sibling(); Outcome::Unit },
+        Op::ToParent => Outcome::Bool(e.to_parent()),
+        Op::ToRoot => { e.to_root(); Outcome::Unit },
+        Op::PushLeaf(data) => { e.push_leaf(data); Outcome::Unit },
+        Op::InsertLeaf(index, data) => Outcome::Bool(e.insert_leaf(index, data)),
+        Op::InsertSiblingLeaf(offset, data) => Outcome::Bool(e.insert_sibling_leaf(offset, data)),
+        Op::SetRemoveFocusPolicy(policy) => Outcome::Bool(e.set_remove_focus_policy(policy)),
+        Op::Remove => remove(Some(e.remove())),
+        Op::RemoveChild(index) => remove(e.remove_child(index).ok()),
+        Op::RemoveSibling(offset) => remove(e.remove_sibling(offset)),
+        Op::SwapChildren(a, b) => Outcome::Bool(e.swap_children(a, b)),
+        Op::SwapSiblings(a, b) => Outcome::Bool(e.swap_siblings(a, b)),
+    }
+}
+
+/// Number of nodes in the tree that each run starts from.
+const INITIAL_SIZE: u32 = 5;
+
+macro_rules! initial_tree {
+    ($tree_macro:ident) => ($tree_macro![0, [1, [3], [4]], [2]]);
+}
+
+fn subtree_size(_: &u32, children: &[&usize]) -> usize {
+    1 + children.iter().map(|c| **c).sum::<usize>()
+}
+
+fn trace(steps: Vec<Observation>, tree: String) -> Trace {
+    let completed = steps.last().is_none_or(|s| s.outcome != Outcome::Panicked);
+    Trace { steps, tree: if completed { Some(tree) } else { None }, }
+}
+
+/// Runs `ops` in each backend, returning each backend's name and trace.
+pub fn traces(ops: &[Op]) -> Vec<(&'static str, Trace)> {
+    let mut traces = Vec::new();
+
+    let mut t = initial_tree!(owned_tree);
+    let steps = run(t.view_mut(), |e| **e, ops);
+    traces.push(("owned", trace(steps, format!["{:?}", t])));
+
+    let mut t = initial_tree!(shared_tree);
+    let steps = run(t.view_mut(), |e| *e.borrow(), ops);
+    traces.push(("shared", trace(steps, format!["{:?}", t])));
+
+    let t = initial_tree!(sync_tree);
+    let steps = run(t.write(), |e| **e, ops);
+    traces.push(("sync", trace(steps, format!["{:?}", t])));
+
+    let mut t = Augmented::new(initial_tree!(owned_tree), subtree_size);
+    let steps = run(t.editor(), |e| **e, ops);
+    traces.push(("augmented", trace(steps, format!["{:?}", t.into_tree()])));
+
+    let mut t = initial_tree!(linked_tree);
+    let steps = run(t.view_mut(), |e| **e, ops);
+    traces.push(("linked", trace(steps, format!["{:?}", t])));
+
+    traces
+}
+
+/// A sequence of operations on which two backends disagree.
+#[derive(Clone, Debug)]
+pub struct Divergence {
+    pub ops: Vec<Op>,
+    pub expected: (&'static str, Trace),
+    pub actual: (&'static str, Trace),
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (expected_name, ref expected) = self.expected;
+        let (actual_name, ref actual) = self.actual;
+        writeln!(f, "{} and {} backends diverge", expected_name, actual_name)?;
+        for (i, op) in self.ops.iter().enumerate() {
+            let a = expected.steps.get(i);
+            let b = actual.steps.get(i);
+            writeln!(f, "  {:?}: {:?} / {:?}", op, a, b)?;
+            if a != b {
+                return Ok(())
+            }
+        }
+        write!(f, "  final trees: {:?} / {:?}", expected.tree, actual.tree)
+    }
+}
+
+/// Checks that every backend behaves identically on `ops`.
+///
+/// Everything observable about each step is compared: the operation's return
+/// value, the focus data, and the shape of the tree around the focus, as well
+/// as the final tree.
+pub fn compare(ops: &[Op]) -> Result<(), Box<Divergence>> {
+    let mut traces = traces(ops).into_iter();
+    let expected = traces.next().unwrap();
+    for actual in traces {
+        if actual.1 != expected.1 {
+            return Err(Box::new(Divergence { ops: ops.to_vec(), expected, actual, }))
+        }
+    }
+    Ok(())
+}
+
+/// Shortens a divergent sequence of operations while it still diverges, to
+/// make failures easier to read.
+pub fn shrink(mut ops: Vec<Op>) -> Vec<Op> {
+    let mut i = 0;
+    while i < ops.len() {
+        let mut shorter = ops.clone();
+        shorter.remove(i);
+        if compare(&shorter).is_err() {
+            ops = shorter;
+        } else {
+            i += 1;
+        }
+    }
+    ops
+}
+
+/// Compares backends on `runs` random sequences of `len` operations each,
+/// returning the first (shrunk) divergence found.
+pub fn fuzz(seed: u64, mix: OpMix, runs: usize, len: usize) -> Result<(), Box<Divergence>> {
+    let mut rng = Rng::new(seed);
+    for _ in 0..runs {
+        let ops = generate(&mut rng, mix, len);
+        if compare(&ops).is_err() {
+            return compare(&shrink(ops))
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use ::fuzz::{self, Op, OpMix, Outcome, Rng};
+
+    #[test]
+    fn rng_is_deterministic() {
+        let mut a = Rng::new(7);
+        let mut b = Rng::new(7);
+        for _ in 0..10 {
+            assert_eq![a.next_u64(), b.next_u64()];
+        }
+        for _ in 0..100 {
+            let x = a.offset(3);
+            assert![(-3..=3).contains(&x)];
+        }
+    }
+
+    #[test]
+    fn traces_record_outcomes() {
+        let traces = fuzz::traces(&[Op::SeekChild(0), Op::SeekChild(5), Op::PushLeaf(9)]);
+        let (name, ref trace) = traces[0];
+        assert_eq![name, "owned"];
+        assert_eq![trace.steps[0].outcome, Outcome::Bool(true)];
+        assert_eq![trace.steps[0].focus, Some((1, 2, false))];
+        assert_eq![trace.steps[1].outcome, Outcome::Bool(false)];
+        assert_eq![trace.steps[2].focus, Some((9, 0, false))];
+        assert_eq![trace.tree, Some("(0 (1 (3) (4) (9)) (2))".to_string())];
+    }
+
+    #[test]
+    fn backends_agree_on_navigation_and_insertion() {
+        let mix = OpMix { navigation: true, insertion: true, removal: false, swapping: true, };
+        if let Err(divergence) = fuzz::fuzz(1, mix, 200, 40) {
+            panic!["{}", divergence];
+        }
+    }
+
+    #[test]
+    fn backends_agree_on_all_operations() {
+        if let Err(divergence) = fuzz::fuzz(2, OpMix::all(), 200, 40) {
+            panic!["{}", divergence];
+        }
+    }
+
+    #[test]
+    fn backends_agree_on_remove_focus() {
+        for &policy in fuzz::POLICIES.iter() {
+            let prefix = [Op::SetRemoveFocusPolicy(policy), Op::SeekChild(0)];
+            let sequences: [&[Op]; 4] =
+                [&[Op::Remove, Op::Remove, Op::Remove],
+                 &[Op::SeekChild(0), Op::Remove, Op::Remove, Op::Remove],
+                 &[Op::SeekChild(1), Op::Remove, Op::Remove],
+                 &[Op::PushLeaf(5), Op::SeekFirstSibling, Op::SeekSibling(1), Op::Remove,
+                   Op::Remove, Op::Remove]];
+            for ops in sequences.iter() {
+                let ops: Vec<Op> = prefix.iter().chain(ops.iter()).cloned().collect();
+                if let Err(divergence) = fuzz::compare(&ops) {
+                    panic!["{}", divergence];
+                }
+            }
+        }
+    }
+}
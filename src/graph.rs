@@ -0,0 +1,160 @@
+use ::owned::Tree;
+use ::traversal::Queue;
+
+use std::error::Error;
+use std::fmt;
+use std::mem;
+
+/// What `from_graph_spanning_tree` does with an edge to a vertex that is
+/// already in the tree.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum BackEdgePolicy {
+    /// Leaves the edge out of the tree.
+    Ignore,
+    /// Fails with the edge, as a `BackEdge`.
+    Error,
+    /// Adds a `SpanningNode::BackEdge` leaf for the edge.
+    Annotate,
+}
+
+/// Node of a spanning tree made by `from_graph_spanning_tree`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum SpanningNode {
+    /// A vertex of the graph, reached by a tree edge from its parent.
+    Vertex(usize),
+    /// A leaf recording an edge from its parent to a vertex that is already
+    /// in the tree.
+    BackEdge(usize),
+}
+
+/// Error returned by `from_graph_spanning_tree` for an edge to a vertex that
+/// is already in the tree.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct BackEdge {
+    pub from: usize,
+    pub to: usize,
+}
+
+impl fmt::Display for BackEdge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "edge from vertex {} to vertex {} is not a tree edge", self.from, self.to)
+    }
+}
+
+impl Error for BackEdge {}
+
+/// Makes a spanning tree of the vertices reachable from `root` in a directed
+/// graph, given as the list of each vertex's successors.
+///
+/// Vertices are taken from `queue` and their edges followed in order, so a
+/// `BreadthQueue` gives a breadth-first spanning tree and a `DepthQueue` a
+/// depth-first one. The first edge found to each vertex is a tree edge, and
+/// `policy` says what to do with each later edge to it, whether it closes a
+/// cycle or joins two paths. An edge from a vertex back to its own parent is
+/// taken to be the reverse of the tree edge, as in an undirected graph that
+/// lists each edge both ways, and is skipped once.
+///
+/// Panics if `root` or any successor is not a vertex of the graph.
+pub fn from_graph_spanning_tree<A, Q>(adjacency: &[A], root: usize, mut queue: Q,
+                                      policy: BackEdgePolicy)
+                                      -> Result<Tree<SpanningNode>, BackEdge>
+    where A: AsRef<[usize]>, Q: Queue<usize> {
+        assert![root < adjacency.len(), "no vertex {} (only {} vertices)", root, adjacency.len()];
+        let mut parents = vec![None; adjacency.len()];
+        let mut discovered = vec![false; adjacency.len()];
+        let mut children: Vec<Vec<SpanningNode>> = vec![Vec::new(); adjacency.len()];
+        discovered[root] = true;
+        queue.unshift(root);
+        while let Some(from) = queue.shift() {
+            let mut parent = parents[from];
+            for &to in adjacency[from].as_ref() {
+                assert![to < adjacency.len(), "no vertex {} (only {} vertices)",
+                        to, adjacency.len()];
+                if !discovered[to] {
+                    discovered[to] = true;
+                    parents[to] = Some(from);
+                    children[from].push(SpanningNode::Vertex(to));
+                    queue.unshift(to);
+                } else if parent == Some(to) {
+                    parent = None;
+                } else {
+                    match policy {
+                        BackEdgePolicy::Ignore => (),
+                        BackEdgePolicy::Error => return Err(BackEdge { from, to, }),
+                        BackEdgePolicy::Annotate => children[from].push(SpanningNode::BackEdge(to)),
+                    }
+                }
+            }
+        }
+        // Each stack entry is a node, its remaining children, and its subtrees
+        // so far.
+        let root_children = mem::take(&mut children[root]);
+        let mut stack = vec![(SpanningNode::Vertex(root), root_children.into_iter(), Vec::new())];
+        loop {
+            let next = stack.last_mut().unwrap().1.next();
+            match next {
+                Some(SpanningNode::Vertex(vertex)) => {
+                    let grandchildren = mem::take(&mut children[vertex]).into_iter();
+                    stack.push((SpanningNode::Vertex(vertex), grandchildren, Vec::new()));
+                },
+                Some(back_edge) => stack.last_mut().unwrap().2.push(Tree::leaf(back_edge)),
+                None => {
+                    let (node, _, subtrees) = stack.pop().unwrap();
+                    let tree = Tree::new(node, subtrees);
+                    match stack.last_mut() {
+                        Some(parent) => parent.2.push(tree),
+                        None => return Ok(tree),
+                    }
+                },
+            }
+        }
+    }
+
+#[cfg(test)]
+mod test {
+    use ::graph::{from_graph_spanning_tree, BackEdge, BackEdgePolicy, SpanningNode};
+    use ::owned_tree;
+    use ::traversal::{BreadthQueue, DepthQueue};
+
+    use self::SpanningNode::{BackEdge as B, Vertex as V};
+
+    // 0 -> 1 -> 3 and 0 -> 2 -> 3, with 3 -> 0 closing a cycle and 4
+    // unreachable.
+    fn diamond() -> Vec<Vec<usize>> {
+        vec![vec![1, 2], vec![3], vec![3], vec![0], vec![0]]
+    }
+
+    #[test]
+    fn breadth_first_spanning_tree() {
+        let g = diamond();
+        assert_eq![Ok(owned_tree![V(0), [V(1), [V(3)]], [V(2)]]),
+                   from_graph_spanning_tree(&g, 0, BreadthQueue::new(), BackEdgePolicy::Ignore)];
+        assert_eq![Ok(owned_tree![V(0), [V(1), [V(3), [B(0)]]], [V(2), [B(3)]]]),
+                   from_graph_spanning_tree(&g, 0, BreadthQueue::new(), BackEdgePolicy::Annotate)];
+        assert_eq![Err(BackEdge { from: 2, to: 3, }),
+                   from_graph_spanning_tree(&g, 0, BreadthQueue::new(), BackEdgePolicy::Error)];
+    }
+
+    #[test]
+    fn depth_first_spanning_tree() {
+        // The depth-first queue takes the last successor first.
+        let g = diamond();
+        assert_eq![Ok(owned_tree![V(0), [V(1), [B(3)]], [V(2), [V(3), [B(0)]]]]),
+                   from_graph_spanning_tree(&g, 0, DepthQueue::new(), BackEdgePolicy::Annotate)];
+    }
+
+    #[test]
+    fn undirected_tree_has_no_back_edges() {
+        // The path 0 - 1 - 2, with each edge listed both ways.
+        let g = vec![vec![1], vec![0, 2], vec![1]];
+        assert_eq![Ok(owned_tree![V(1), [V(0)], [V(2)]]),
+                   from_graph_spanning_tree(&g, 1, BreadthQueue::new(), BackEdgePolicy::Error)];
+    }
+
+    #[test]
+    #[should_panic(expected = "no vertex 5 (only 5 vertices)")]
+    fn root_must_be_a_vertex() {
+        let _ = from_graph_spanning_tree(&diamond(), 5, BreadthQueue::new(),
+                                         BackEdgePolicy::Ignore);
+    }
+}
@@ -0,0 +1,333 @@
+use ::{Editor, Nav, RemoveFocusPolicy};
+use ::error::{EditError, Violation};
+
+use std::ops::{Deref, DerefMut};
+
+/// An edit about to be made through a `Guarded` editor, described relative to
+/// the focus at the time it is made.
+#[derive(Debug)]
+pub enum EditOp<'a, D: 'a, T: 'a> {
+    /// Inserts `child` at `index` among the focus's children. Pushing a child
+    /// inserts it at the current child count.
+    InsertChild { index: usize, child: NewNode<'a, D, T> },
+    /// Inserts `sibling` at `offset` from the focus.
+    InsertSibling { offset: isize, sibling: NewNode<'a, D, T> },
+    /// Removes the focus.
+    Remove,
+    /// Removes the focus's child at `index`.
+    RemoveChild { index: usize },
+    /// Removes the sibling at `offset` from the focus.
+    RemoveSibling { offset: isize },
+    /// Replaces the focus's child at `index` with `child`.
+    ReplaceChild { index: usize, child: &'a T },
+    /// Removes all of the focus's children.
+    TakeChildren,
+    /// Replaces the focus with `other`.
+    Swap { other: &'a T },
+    /// Swaps the focus's children at `a` and `b`.
+    SwapChildren { a: usize, b: usize },
+    /// Swaps the siblings at offsets `a` and `b` from the focus.
+    SwapSiblings { a: isize, b: isize },
+}
+
+/// A node that an `EditOp` would add to the tree.
+#[derive(Debug)]
+pub enum NewNode<'a, D: 'a, T: 'a> {
+    /// A leaf with the given data.
+    Leaf(&'a D),
+    /// The root of the given subtree.
+    Tree(&'a T),
+}
+
+type Guard<E> = Box<dyn Fn(&EditOp<<E as Editor>::Data, <E as Editor>::Tree>, &E)
+                           -> Result<(), Violation>>;
+
+/// Wrapper around an `Editor` that consults a guard before each edit, so that
+/// constraints on the tree's shape can be enforced in one place.
+///
+/// The guard is called with a description of the edit and the wrapped editor,
+/// still focused where the edit is to be made. If it returns a violation, the
+/// edit is not made: methods that can report failure do so (through `false`,
+/// `None`, or `EditError::Refused`), and the others panic with the violation.
+/// The violation is kept until it is retrieved with `take_violation`.
+///
+/// Changes to data through `DerefMut` are not edits to the tree's shape, and
+/// are not guarded.
+pub struct Guarded<E: Editor> {
+    editor: E,
+    guard: Option<Guard<E>>,
+    violation: Option<Violation>,
+}
+
+impl<E: Editor> Guarded<E> {
+    /// Wraps `editor` with no guard, so that all edits are allowed.
+    pub fn new(editor: E) -> Self {
+        Guarded { editor, guard: None, violation: None, }
+    }
+
+    /// Sets the guard consulted before each edit, replacing any previous one.
+    pub fn set_guard<G>(&mut self, guard: G)
+        where G: Fn(&EditOp<E::Data, E::Tree>, &E) -> Result<(), Violation> + 'static {
+            self.guard = Some(Box::new(guard));
+        }
+
+    /// Removes the guard, so that all edits are allowed.
+    pub fn clear_guard(&mut self) {
+        self.guard = None;
+    }
+
+    /// Returns the violation that caused the most recent refused edit, if it
+    /// has not already been taken.
+    pub fn take_violation(&mut self) -> Option<Violation> {
+        self.violation.take()
+    }
+
+    /// Returns the wrapped editor.
+    pub fn into_inner(self) -> E {
+        self.editor
+    }
+
+    // Consults the guard about `op`, recording any violation. Returns true iff
+    // the edit is allowed.
+    fn allows(&mut self, op: &EditOp<E::Data, E::Tree>) -> bool {
+        let result = match self.guard {
+            Some(ref guard) => guard(op, &self.editor),
+            None => Ok(()),
+        };
+        match result {
+            Ok(()) => true,
+            Err(violation) => {
+                self.violation = Some(violation);
+                false
+            },
+        }
+    }
+
+    // Like `allows`, but panics if the edit is refused.
+    fn require(&mut self, op: &EditOp<E::Data, E::Tree>) {
+        if !self.allows(op) {
+            panic!["{}", self.violation.as_ref().unwrap()]
+        }
+    }
+}
+
+impl<E: Editor + Deref> Deref for Guarded<E> {
+    type Target = E::Target;
+
+    fn deref(&self) -> &E::Target {
+        &self.editor
+    }
+}
+
+impl<E: Editor + DerefMut> DerefMut for Guarded<E> {
+    fn deref_mut(&mut self) -> &mut E::Target {
+        &mut self.editor
+    }
+}
+
+impl<E: Editor> Nav for Guarded<E> {
+    fn child_count(&self) -> usize {
+        self.editor.child_count()
+    }
+
+    fn at_leaf(&self) -> bool {
+        self.editor.at_leaf()
+    }
+
+    fn at_root(&self) -> bool {
+        self.editor.at_root()
+    }
+
+    fn index_in_parent(&self) -> Option<usize> {
+        self.editor.index_in_parent()
+    }
+
+    fn seek_sibling(&mut self, offset: isize) -> bool {
+        self.editor.seek_sibling(offset)
+    }
+
+    fn seek_first_sibling(&mut self) {
+        self.editor.seek_first_sibling()
+    }
+
+    fn seek_last_sibling(&mut self) {
+        self.editor.seek_last_sibling()
+    }
+
+    fn seek_child(&mut self, index: usize) -> bool {
+        self.editor.seek_child(index)
+    }
+
+    fn to_parent(&mut self) -> bool {
+        self.editor.to_parent()
+    }
+
+    fn to_root(&mut self) {
+        self.editor.to_root()
+    }
+}
+
+impl<E: Editor> Editor for Guarded<E> {
+    type Data = E::Data;
+    type Tree = E::Tree;
+
+    fn push_leaf(&mut self, data: E::Data) {
+        let index = self.editor.child_count();
+        self.require(&EditOp::InsertChild { index, child: NewNode::Leaf(&data), });
+        self.editor.push_leaf(data)
+    }
+
+    fn push_child(&mut self, child: E::Tree) {
+        let index = self.editor.child_count();
+        self.require(&EditOp::InsertChild { index, child: NewNode::Tree(&child), });
+        self.editor.push_child(child)
+    }
+
+    fn insert_leaf(&mut self, index: usize, data: E::Data) -> bool {
+        self.allows(&EditOp::InsertChild { index, child: NewNode::Leaf(&data), })
+            && self.editor.insert_leaf(index, data)
+    }
+
+    fn insert_child(&mut self, index: usize, child: E::Tree) -> bool {
+        self.allows(&EditOp::InsertChild { index, child: NewNode::Tree(&child), })
+            && self.editor.insert_child(index, child)
+    }
+
+    fn insert_sibling_leaf(&mut self, offset: isize, data: E::Data) -> bool {
+        self.allows(&EditOp::InsertSibling { offset, sibling: NewNode::Leaf(&data), })
+            && self.editor.insert_sibling_leaf(offset, data)
+    }
+
+    fn insert_sibling(&mut self, offset: isize, sibling: E::Tree) -> bool {
+        self.allows(&EditOp::InsertSibling { offset, sibling: NewNode::Tree(&sibling), })
+            && self.editor.insert_sibling(offset, sibling)
+    }
+
+    fn remove_focus_policy(&self) -> RemoveFocusPolicy {
+        self.editor.remove_focus_policy()
+    }
+
+    fn set_remove_focus_policy(&mut self, policy: RemoveFocusPolicy) -> bool {
+        self.editor.set_remove_focus_policy(policy)
+    }
+
+    fn remove(&mut self) -> E::Tree {
+        self.require(&EditOp::Remove);
+        self.editor.remove()
+    }
+
+    fn remove_child(&mut self, index: usize) -> Result<E::Tree, EditError> {
+        if !self.allows(&EditOp::RemoveChild { index, }) {
+            return Err(EditError::Refused)
+        }
+        self.editor.remove_child(index)
+    }
+
+    fn remove_sibling(&mut self, offset: isize) -> Option<E::Tree> {
+        if !self.allows(&EditOp::RemoveSibling { offset, }) {
+            return None
+        }
+        self.editor.remove_sibling(offset)
+    }
+
+    fn replace_child(&mut self, index: usize, child: E::Tree) -> E::Tree {
+        self.require(&EditOp::ReplaceChild { index, child: &child, });
+        self.editor.replace_child(index, child)
+    }
+
+    fn take_children(&mut self) -> Vec<E::Tree> {
+        self.require(&EditOp::TakeChildren);
+        self.editor.take_children()
+    }
+
+    fn swap(&mut self, other: &mut E::Tree) {
+        self.require(&EditOp::Swap { other, });
+        self.editor.swap(other)
+    }
+
+    fn swap_children(&mut self, index_a: usize, index_b: usize) -> bool {
+        self.allows(&EditOp::SwapChildren { a: index_a, b: index_b, })
+            && self.editor.swap_children(index_a, index_b)
+    }
+
+    fn swap_siblings(&mut self, offset_a: isize, offset_b: isize) -> bool {
+        self.allows(&EditOp::SwapSiblings { a: offset_a, b: offset_b, })
+            && self.editor.swap_siblings(offset_a, offset_b)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::{Editor, Nav};
+    use ::error::{EditError, Violation};
+    use ::guard::{EditOp, Guarded, NewNode};
+    use ::owned::{Tree, TreeViewMut};
+    use ::owned_tree;
+
+    // Allows at most two children per node, and no "x" under an "a".
+    fn guard(op: &EditOp<&'static str, Tree<&'static str>>, context: &TreeViewMut<&'static str>)
+             -> Result<(), Violation> {
+        let child = match *op {
+            EditOp::InsertChild { ref child, .. } => child,
+            _ => return Ok(()),
+        };
+        if context.child_count() >= 2 {
+            return Err(Violation::new("at most two children are allowed"))
+        }
+        let data = match *child {
+            NewNode::Leaf(data) => *data,
+            NewNode::Tree(tree) => *tree.data(),
+        };
+        if **context == "a" && data == "x" {
+            return Err(Violation::new("\"x\" is not allowed under \"a\""))
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn guard_refuses_edits() {
+        let mut t = owned_tree!["r", ["a"]];
+        {
+            let mut e = Guarded::new(t.view_mut());
+            e.set_guard(guard);
+            e.push_leaf("b");
+            assert![e.to_parent()];
+            assert![! e.insert_leaf(0, "c")];
+            assert_eq![Some(Violation::new("at most two children are allowed")),
+                       e.take_violation()];
+            assert![e.seek_child(0)];
+            assert![! e.insert_child(0, owned_tree!["x"])];
+            assert_eq!["\"x\" is not allowed under \"a\"", e.take_violation().unwrap().message()];
+            e.push_leaf("y");
+            assert_eq![None, e.take_violation()];
+            e.clear_guard();
+            e.to_root();
+            assert![e.insert_leaf(0, "x")];
+        }
+        assert_eq![owned_tree!["r", ["x"], ["a", ["y"]], ["b"]], t];
+    }
+
+    #[test]
+    fn refused_removal_fails() {
+        let mut t = owned_tree!["r", ["a"]];
+        {
+            let mut e = Guarded::new(t.view_mut());
+            e.set_guard(|op, _| match *op {
+                EditOp::RemoveChild { .. } => Err(Violation::new("removal is not allowed")),
+                _ => Ok(()),
+            });
+            assert_eq![Err(EditError::Refused), e.remove_child(0)];
+            assert![e.take_violation().is_some()];
+        }
+        assert_eq![owned_tree!["r", ["a"]], t];
+    }
+
+    #[test]
+    #[should_panic(expected = "at most two children are allowed")]
+    fn refused_push_panics() {
+        let mut t = owned_tree!["r", ["a"], ["b"]];
+        let mut e = Guarded::new(t.view_mut());
+        e.set_guard(guard);
+        e.push_leaf("c");
+    }
+}
@@ -0,0 +1,199 @@
+use ::owned::Tree;
+use ::path::TreePath;
+
+use std::mem;
+
+/// Cached value computed from every node of an `owned::Tree`.
+///
+/// Like an `augmented::Augmented` aggregate, the value at each node is
+/// computed from the node's data and the values at its children, but it is
+/// kept apart from the tree, so the tree can be edited by any means. After
+/// editing, pass the changed paths to `invalidate` (a `dirty::DirtyTracker`
+/// reports exactly these) and `update` recomputes only the values of the
+/// changed subtrees and their ancestors.
+///
+/// Invalidating a path discards the values of its whole subtree, since its
+/// children may have been inserted, removed, or reordered.
+#[derive(Clone, Debug)]
+pub struct Derived<V> {
+    root: Slot<V>,
+}
+
+#[derive(Clone, Debug)]
+struct Slot<V> {
+    // `None` if not yet computed or invalidated.
+    value: Option<V>,
+    // Empty if the node's children have not been computed since its subtree
+    // was last invalidated.
+    children: Vec<Slot<V>>,
+}
+
+impl<V> Slot<V> {
+    fn empty() -> Self {
+        Slot { value: None, children: Vec::new(), }
+    }
+
+    fn invalidate(&mut self, path: &[usize]) {
+        let mut slot = self;
+        for &index in path {
+            slot.value = None;
+            slot = match slot.children.get_mut(index) {
+                Some(child) => child,
+                None => return,
+            };
+        }
+        slot.value = None;
+        slot.children.clear();
+    }
+
+    fn update<T, F>(&mut self, tree: &Tree<T>, f: &F) -> usize where F: Fn(&T, &[&V]) -> V {
+        if self.value.is_some() {
+            return 0
+        }
+        let mut count = 0;
+        // The child slots of the slots being computed, each taken out of its
+        // slot, which is the last child visited in the entry below (or
+        // `self`), with the slot's node and the index of the next child to
+        // visit. A slot gets its children back and is computed once they are
+        // all done.
+        let mut stack = vec![(self.take_children(tree), tree, 0)];
+        loop {
+            let next_missing = {
+                let &mut (ref mut slots, tree, ref mut next) = stack.last_mut().unwrap();
+                match (*next..slots.len()).find(|&i| slots[i].value.is_none()) {
+                    Some(index) => {
+                        *next = index + 1;
+                        let child = &tree.children()[index];
+                        Some((slots[index].take_children(child), child))
+                    },
+                    None => None,
+                }
+            };
+            if let Some((grandchildren, child)) = next_missing {
+                stack.push((grandchildren, child, 0));
+                continue
+            }
+            let (children, tree, _) = stack.pop().unwrap();
+            let slot = match stack.last_mut() {
+                Some(&mut (ref mut siblings, _, next)) => &mut siblings[next - 1],
+                None => &mut *self,
+            };
+            slot.children = children;
+            let child_values: Vec<&V> =
+                slot.children.iter().map(|c| c.value.as_ref().unwrap()).collect();
+            slot.value = Some(f(tree.data(), &child_values));
+            count += 1;
+            if stack.is_empty() {
+                return count
+            }
+        }
+    }
+
+    // Takes the child slots out of this slot, first making a fresh slot for
+    // each child of `tree` if they do not match its children.
+    fn take_children<T>(&mut self, tree: &Tree<T>) -> Vec<Slot<V>> {
+        if self.children.len() != tree.children().len() {
+            self.children = tree.children().iter().map(|_| Slot::empty()).collect();
+        }
+        mem::take(&mut self.children)
+    }
+}
+
+impl<V> Derived<V> {
+    /// Creates a cache with no values computed yet.
+    pub fn new() -> Self {
+        Derived { root: Slot::empty(), }
+    }
+
+    /// Marks the subtree at `path` as changed, discarding its values and those
+    /// of its ancestors. Paths that reach below the computed part of the tree
+    /// only discard the values that exist along them.
+    pub fn invalidate(&mut self, path: &TreePath) {
+        self.root.invalidate(path.indices());
+    }
+
+    /// Discards every value.
+    pub fn invalidate_all(&mut self) {
+        self.root = Slot::empty();
+    }
+
+    /// Computes the values that are missing for `tree` with `f`, returning the
+    /// number of nodes whose value was computed.
+    ///
+    /// `tree` must be the tree the cache was last updated for, changed only at
+    /// the paths invalidated since then, and `f` must be the same each time.
+    pub fn update<T, F>(&mut self, tree: &Tree<T>, f: F) -> usize
+        where F: Fn(&T, &[&V]) -> V {
+            self.root.update(tree, &f)
+        }
+
+    /// Returns the value for the whole tree, if it is up to date.
+    pub fn value(&self) -> Option<&V> {
+        self.root.value.as_ref()
+    }
+
+    /// Returns the value for the subtree at `path`, if it is up to date.
+    pub fn value_at(&self, path: &TreePath) -> Option<&V> {
+        let mut slot = &self.root;
+        for &index in path.indices() {
+            slot = slot.children.get(index)?;
+        }
+        slot.value.as_ref()
+    }
+}
+
+impl<V> Default for Derived<V> {
+    fn default() -> Self {
+        Derived::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::{Editor, Nav};
+    use ::dirty::DirtyTracker;
+    use ::incremental::Derived;
+    use ::owned_tree;
+    use ::path::TreePath;
+
+    fn sum(data: &i32, children: &[&i32]) -> i32 {
+        *data + children.iter().map(|c| **c).sum::<i32>()
+    }
+
+    #[test]
+    fn recomputes_changed_subtrees() {
+        let mut t = owned_tree![1, [2, [3], [4]], [5, [6]], [7]];
+        let mut derived = Derived::new();
+        assert_eq![None, derived.value()];
+        assert_eq![7, derived.update(&t, sum)];
+        assert_eq![Some(&28), derived.value()];
+        assert_eq![0, derived.update(&t, sum)];
+
+        let dirty = {
+            let mut e = DirtyTracker::new(t.view_mut());
+            assert![e.seek_child(0)];
+            assert![e.seek_child(1)];
+            *e = 40;
+            e.to_root();
+            assert![e.seek_child(1)];
+            e.push_leaf(8);
+            e.take_dirty_paths()
+        };
+        for path in &dirty {
+            derived.invalidate(path);
+        }
+        assert_eq![Some(&7), derived.value_at(&"/2".parse().unwrap())];
+        assert_eq![None, derived.value_at(&"/0".parse().unwrap())];
+        // The root, /0, /0/1, /1, and its two children.
+        assert_eq![6, derived.update(&t, sum)];
+        assert_eq![Some(&72), derived.value()];
+        assert_eq![Some(&19), derived.value_at(&"/1".parse().unwrap())];
+        assert_eq![Some(&3), derived.value_at(&"/0/0".parse().unwrap())];
+        assert_eq![None, derived.value_at(&"/3".parse().unwrap())];
+
+        derived.invalidate(&TreePath::from(vec![5, 5]));
+        assert_eq![1, derived.update(&t, sum)];
+        derived.invalidate_all();
+        assert_eq![8, derived.update(&t, sum)];
+    }
+}
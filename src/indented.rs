@@ -0,0 +1,280 @@
+use ::arena::Tree;
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Write};
+
+/// Indentation that marks one level of depth in indented text.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Indent {
+    /// Each level is indented this many spaces more than its parent. Functions
+    /// given `Spaces(0)` panic.
+    Spaces(usize),
+    /// Each level is indented one tab more than its parent.
+    Tabs,
+    /// Each level is indented by the four-column line-drawing guides of the
+    /// `tree(1)` command, such as `"│   "` and `"├── "`.
+    Guides,
+}
+
+// The units of indentation in `Indent::Guides`: passing a later sibling,
+// passing the end of a list of siblings, and branching to a node that does or
+// does not have later siblings.
+const GUIDE_CONTINUE: &str = "│   ";
+const GUIDE_BLANK: &str = "    ";
+const GUIDE_BRANCH: &str = "├── ";
+const GUIDE_LAST_BRANCH: &str = "└── ";
+
+impl Indent {
+    // Splits `line` into its depth and the text after its indentation, or
+    // returns `None` if the indentation is not a whole number of levels.
+    #[allow(clippy::manual_is_multiple_of)]
+    fn split<'a>(&self, line: &'a str) -> Option<(usize, &'a str)> {
+        match *self {
+            Indent::Spaces(width) => {
+                assert![width > 0, "indent width must be positive"];
+                let text = line.trim_start_matches(' ');
+                let indent = line.len() - text.len();
+                if text.starts_with(char::is_whitespace) || indent % width != 0 {
+                    None
+                } else {
+                    Some((indent / width, text))
+                }
+            },
+            Indent::Tabs => {
+                let text = line.trim_start_matches('\t');
+                if text.starts_with(char::is_whitespace) {
+                    None
+                } else {
+                    Some((line.len() - text.len(), text))
+                }
+            },
+            Indent::Guides => {
+                let mut text = line;
+                let mut depth = 0;
+                loop {
+                    let rest = [GUIDE_CONTINUE, GUIDE_BLANK, GUIDE_BRANCH, GUIDE_LAST_BRANCH]
+                        .iter().find_map(|guide| text.strip_prefix(guide));
+                    match rest {
+                        Some(rest) => {
+                            text = rest;
+                            depth += 1;
+                        },
+                        None if text.starts_with(char::is_whitespace) => return None,
+                        None => return Some((depth, text)),
+                    }
+                }
+            },
+        }
+    }
+}
+
+/// Parses indented text, such as
+///
+/// ```text
+/// animal
+///   mammal
+///     dog
+///     cat
+///   bird
+/// ```
+///
+/// into a tree whose data is the text of each line after its indentation. A
+/// line's parent is the closest line before it that is indented one level
+/// less, so each line may be indented at most one level more than the line
+/// before it. The first line is the root, and it is the only line without
+/// indentation.
+///
+/// Blank lines are skipped, and trailing whitespace (including the carriage
+/// return of a Windows line ending) is removed from each line.
+pub fn parse(input: &str, indent: Indent) -> Result<Tree<String>, ParseIndentedError> {
+    let mut tree: Option<Tree<String>> = None;
+    // The last node seen at each depth down to the previous line's.
+    let mut stack = Vec::new();
+    for (index, line) in input.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.trim_end();
+        if line.is_empty() {
+            continue
+        }
+        let (depth, text) = match indent.split(line) {
+            Some(split) => split,
+            None => return Err(ParseIndentedError::BadIndent { line: line_number, }),
+        };
+        if depth > stack.len() {
+            return Err(ParseIndentedError::SkippedLevel { line: line_number, })
+        }
+        let tree = match tree {
+            None => {
+                let root = Tree::new(text.to_string());
+                stack.push(root.root());
+                tree = Some(root);
+                continue
+            },
+            Some(ref mut tree) => tree,
+        };
+        if depth == 0 {
+            return Err(ParseIndentedError::MultipleRoots { line: line_number, })
+        }
+        stack.truncate(depth);
+        let id = tree.push_child(stack[depth - 1], text.to_string());
+        stack.push(id);
+    }
+    tree.ok_or(ParseIndentedError::Empty)
+}
+
+/// Writes `tree` as indented text that `parse` reads back into an equal tree,
+/// with each node's data on its own line, indented one level per level of
+/// depth. This holds provided that the data of no node, as displayed, is
+/// empty, contains a line break, or begins or ends with whitespace.
+///
+/// Fails with an error of kind `InvalidInput`, before writing anything, if the
+/// tree has placeholders. Panics if `indent` is `Spaces(0)`.
+pub fn write<T, W>(tree: &Tree<T>, w: &mut W, indent: Indent) -> io::Result<()>
+    where T: fmt::Display, W: Write {
+        if let Indent::Spaces(width) = indent {
+            assert![width > 0, "indent width must be positive"];
+        }
+        tree.validate().map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        // Nodes to write, with their depths and whether each is the last of its
+        // siblings.
+        let mut stack = vec![(tree.root(), 0usize, true)];
+        // Whether each ancestor of the next node below the root is the last of
+        // its siblings, which `Indent::Guides` draws.
+        let mut last_ancestors: Vec<bool> = Vec::new();
+        while let Some((id, depth, last)) = stack.pop() {
+            last_ancestors.truncate(depth.saturating_sub(1));
+            match indent {
+                Indent::Spaces(width) => write!(w, "{:1$}", "", depth * width)?,
+                Indent::Tabs => {
+                    for _ in 0..depth {
+                        w.write_all(b"\t")?;
+                    }
+                },
+                Indent::Guides if depth > 0 => {
+                    for &ancestor_last in &last_ancestors {
+                        let guide = if ancestor_last { GUIDE_BLANK } else { GUIDE_CONTINUE };
+                        w.write_all(guide.as_bytes())?;
+                    }
+                    let guide = if last { GUIDE_LAST_BRANCH } else { GUIDE_BRANCH };
+                    w.write_all(guide.as_bytes())?;
+                    last_ancestors.push(last);
+                },
+                Indent::Guides => (),
+            }
+            writeln!(w, "{}", tree.data(id).unwrap())?;
+            let children = tree.children(id);
+            for (index, &child) in children.iter().enumerate().rev() {
+                stack.push((child, depth + 1, index + 1 == children.len()));
+            }
+        }
+        Ok(())
+    }
+
+/// Error returned when parsing indented text that does not describe a tree.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum ParseIndentedError {
+    /// The input has no lines that are not blank.
+    Empty,
+    /// The indentation of the given line is not a whole number of levels.
+    BadIndent { line: usize },
+    /// The given line is indented more than one level more than the line
+    /// before it, or it is the first line and is indented.
+    SkippedLevel { line: usize },
+    /// The given line is not indented, but it is not the first line.
+    MultipleRoots { line: usize },
+}
+
+impl fmt::Display for ParseIndentedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseIndentedError::Empty => f.write_str("input has no lines"),
+            ParseIndentedError::BadIndent { line } =>
+                write!(f, "line {}: indentation is not a whole number of levels", line),
+            ParseIndentedError::SkippedLevel { line } =>
+                write!(f, "line {}: indented more than one level below its parent", line),
+            ParseIndentedError::MultipleRoots { line } =>
+                write!(f, "line {}: second line without indentation", line),
+        }
+    }
+}
+
+impl Error for ParseIndentedError {}
+
+#[cfg(test)]
+mod test {
+    use ::arena::Tree;
+    use ::indented::{parse, write, Indent, ParseIndentedError};
+
+    use std::io;
+    use ::owned_tree;
+
+    fn owned(input: &str, indent: Indent) -> ::owned::Tree<String> {
+        parse(input, indent).unwrap().into_owned().unwrap()
+    }
+
+    #[test]
+    fn parses_each_indent() {
+        let expected = owned_tree!["animal".to_string(),
+                                   ["mammal".to_string(), ["dog".to_string()], ["cat".to_string()]],
+                                   ["bird".to_string()]];
+        let spaces = "animal\n  mammal\n    dog\n\n    cat\r\n  bird\n";
+        assert_eq![expected, owned(spaces, Indent::Spaces(2))];
+        let tabs = "animal\n\tmammal\n\t\tdog\n\t\tcat\n\tbird";
+        assert_eq![expected, owned(tabs, Indent::Tabs)];
+        let guides = "animal\n├── mammal\n│   ├── dog\n│   └── cat\n└── bird\n";
+        assert_eq![expected, owned(guides, Indent::Guides)];
+    }
+
+    #[test]
+    fn keeps_inner_whitespace() {
+        assert_eq![owned_tree!["a b".to_string(), ["c  d".to_string()]],
+                   owned("a b  \n    c  d", Indent::Spaces(4))];
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq![Some(ParseIndentedError::Empty), parse(" \n\n", Indent::Tabs).err()];
+        assert_eq![Some(ParseIndentedError::BadIndent { line: 3, }),
+                   parse("a\n  b\n   c", Indent::Spaces(2)).err()];
+        assert_eq![Some(ParseIndentedError::BadIndent { line: 2, }),
+                   parse("a\n\t b", Indent::Tabs).err()];
+        assert_eq![Some(ParseIndentedError::SkippedLevel { line: 2, }),
+                   parse("a\n\t\tb", Indent::Tabs).err()];
+        assert_eq![Some(ParseIndentedError::SkippedLevel { line: 1, }),
+                   parse("  a", Indent::Spaces(2)).err()];
+        assert_eq![Some(ParseIndentedError::MultipleRoots { line: 4, }),
+                   parse("a\n├── b\n\nc", Indent::Guides).err()];
+    }
+
+    fn written(tree: &Tree<String>, indent: Indent) -> String {
+        let mut out = Vec::new();
+        write(tree, &mut out, indent).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn writes_what_it_parses() {
+        let guides = "a\n├── b\n│   ├── c\n│   │   └── d\n│   └── e\n└── f\n    └── g\n";
+        let tree = parse(guides, Indent::Guides).unwrap();
+        assert_eq![guides, written(&tree, Indent::Guides)];
+        let spaces = written(&tree, Indent::Spaces(3));
+        assert_eq!["a\n   b\n      c\n         d\n      e\n   f\n      g\n", spaces];
+        let tabs = written(&tree, Indent::Tabs);
+        assert_eq!["a\n\tb\n\t\tc\n\t\t\td\n\t\te\n\tf\n\t\tg\n", tabs];
+        for &(ref text, indent) in &[(spaces, Indent::Spaces(3)), (tabs, Indent::Tabs)] {
+            assert_eq![guides, written(&parse(text, indent).unwrap(), Indent::Guides)];
+        }
+    }
+
+    #[test]
+    fn write_rejects_placeholders() {
+        let mut tree = Tree::new("a".to_string());
+        let root = tree.root();
+        tree.push_placeholder(root);
+        let mut out = Vec::new();
+        let error = write(&tree, &mut out, Indent::Tabs).unwrap_err();
+        assert_eq![io::ErrorKind::InvalidInput, error.kind()];
+        assert![out.is_empty()];
+    }
+}
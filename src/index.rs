@@ -0,0 +1,196 @@
+use ::error::EditError;
+
+use std::error::Error;
+use std::fmt;
+
+/// Failure to compute the index of a child or sibling.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum IndexError {
+    /// The offset reaches to the left of the first sibling.
+    Underflow,
+    /// Adding the offset to the index overflowed.
+    Overflow,
+    /// There is no node at `index`, since there are only `count`.
+    OutOfRange { index: usize, count: usize },
+}
+
+impl fmt::Display for IndexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IndexError::Underflow => f.write_str("offset reaches before the first sibling"),
+            IndexError::Overflow => f.write_str("numerical overflow computing a node index"),
+            IndexError::OutOfRange { index, count } =>
+                write!(f, "no node at index {} (only {} nodes)", index, count),
+        }
+    }
+}
+
+impl Error for IndexError {}
+
+/// The result of computing the index of a nonroot tree node's sibling.
+///
+/// Backends use this to implement `Nav::seek_sibling` and the sibling edits of
+/// `Editor`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum SiblingIndex {
+    /// Numerical underflow in computing the index (i.e., the offset reaches
+    /// to the left of the first sibling).
+    Underflow,
+    /// Numerical overflow in computing the index.
+    Overflow,
+    /// The computed index is out of range, with the second value giving the
+    /// number of siblings.
+    OutOfRange(usize, usize),
+    /// A successfully computed index value.
+    Valid(usize),
+}
+
+impl SiblingIndex {
+    pub const fn of(sibling_count: usize,
+                    here_index: usize,
+                    offset: isize) -> Self {
+        if offset < 0 {
+            match here_index.checked_sub(offset.unsigned_abs()) {
+                Some(new_index) => SiblingIndex::Valid(new_index),
+                None => SiblingIndex::Underflow,
+            }
+        } else if offset == 0 {
+            SiblingIndex::Valid(here_index)
+        } else {
+            let new_index = match here_index.checked_add(offset as usize) {
+                Some(x) => x,
+                None => return SiblingIndex::Overflow,
+            };
+            if new_index >= sibling_count {
+                return SiblingIndex::OutOfRange(new_index, sibling_count);
+            }
+            SiblingIndex::Valid(new_index)
+        }
+    }
+
+    /// Safely computes the index of a tree node's sibling.
+    ///
+    /// For `sibling_count` siblings and the current node at `here_index`, the
+    /// index of the node that is the given offset from `here_index` is computed
+    /// using checked arithmetic. Returns `None` if there is no such sibling.
+    pub const fn compute(sibling_count: usize,
+                         here_index: usize,
+                         offset: isize) -> Option<usize> {
+        match SiblingIndex::of(sibling_count, here_index, offset) {
+            SiblingIndex::Valid(new_index) => Some(new_index),
+            _ => None,
+        }
+    }
+
+    /// Converts this into the index it computed, or an error saying why there
+    /// is no such sibling.
+    pub const fn into_result(self) -> Result<usize, IndexError> {
+        match self {
+            SiblingIndex::Underflow => Err(IndexError::Underflow),
+            SiblingIndex::Overflow => Err(IndexError::Overflow),
+            SiblingIndex::OutOfRange(index, count) => Err(IndexError::OutOfRange { index, count, }),
+            SiblingIndex::Valid(new_index) => Ok(new_index),
+        }
+    }
+}
+
+impl From<SiblingIndex> for Option<usize> {
+    /// Unwraps the index to get its value. Returns `None` if there is no such
+    /// sibling, including on numerical overflow.
+    fn from(index: SiblingIndex) -> Self {
+        index.into_result().ok()
+    }
+}
+
+impl From<SiblingIndex> for Result<usize, IndexError> {
+    fn from(index: SiblingIndex) -> Self {
+        index.into_result()
+    }
+}
+
+/// The result of computing the index of a child.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ChildIndex {
+    /// The computed index is out of range, with the second value giving the
+    /// actual number of children.
+    OutOfRange(usize, usize),
+    /// A successfully computed index value.
+    Valid(usize),
+}
+
+impl ChildIndex {
+    /// Validates that a tree node has a child at the given index.
+    pub const fn of(child_count: usize, index: usize) -> Self {
+        if index >= child_count {
+            ChildIndex::OutOfRange(index, child_count)
+        } else {
+            ChildIndex::Valid(index)
+        }
+    }
+
+    pub const fn compute(child_count: usize, index: usize) -> Option<usize> {
+        if index < child_count { Some(index) } else { None }
+    }
+
+    /// Validates a child index, returning an error that describes it if it is
+    /// out of range.
+    pub const fn check(child_count: usize, index: usize) -> Result<usize, EditError> {
+        match ChildIndex::of(child_count, index) {
+            ChildIndex::OutOfRange(index, child_count) =>
+                Err(EditError::ChildOutOfRange { index, child_count, }),
+            ChildIndex::Valid(index) => Ok(index),
+        }
+    }
+
+    /// Converts this into the index it computed, or an error saying why there
+    /// is no such child.
+    pub const fn into_result(self) -> Result<usize, IndexError> {
+        match self {
+            ChildIndex::OutOfRange(index, count) => Err(IndexError::OutOfRange { index, count, }),
+            ChildIndex::Valid(index) => Ok(index),
+        }
+    }
+}
+
+impl From<ChildIndex> for Option<usize> {
+    /// Unwraps the index to get its value, or returns `None` if it is out of
+    /// range.
+    fn from(index: ChildIndex) -> Self {
+        index.into_result().ok()
+    }
+}
+
+impl From<ChildIndex> for Result<usize, IndexError> {
+    fn from(index: ChildIndex) -> Self {
+        index.into_result()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::index::{ChildIndex, IndexError, SiblingIndex};
+
+    // Computable at compile time.
+    const THIRD: Option<usize> = SiblingIndex::compute(4, 1, 1);
+
+    #[test]
+    fn computes_sibling_indices() {
+        assert_eq![Some(2), THIRD];
+        assert_eq![Ok(0), SiblingIndex::of(4, 2, -2).into_result()];
+        assert_eq![Err(IndexError::Underflow), SiblingIndex::of(4, 2, -3).into_result()];
+        assert_eq![Err(IndexError::OutOfRange { index: 4, count: 4, }),
+                   SiblingIndex::of(4, 2, 2).into_result()];
+        let overflow = SiblingIndex::of(4, usize::MAX, 1);
+        assert_eq![SiblingIndex::Overflow, overflow];
+        assert_eq![None, Option::<usize>::from(overflow)];
+        assert_eq![Err(IndexError::Overflow), Result::<usize, IndexError>::from(overflow)];
+    }
+
+    #[test]
+    fn computes_child_indices() {
+        assert_eq![Some(1), ChildIndex::compute(2, 1)];
+        assert_eq![None, ChildIndex::compute(2, 2)];
+        assert_eq![Err(IndexError::OutOfRange { index: 2, count: 2, }),
+                   ChildIndex::of(2, 2).into_result()];
+    }
+}
@@ -0,0 +1,147 @@
+use ::owned::Tree;
+
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::rc::Rc;
+
+/// Compact handle to a string stored in a `StringInterner`.
+///
+/// Symbols are cheap to copy, compare, and hash, which makes `Tree<Sym>` a
+/// good fit for large trees whose labels repeat often, such as markup tags or
+/// opcodes. A symbol is only meaningful to the interner that produced it.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Sym(u32);
+
+/// Stores each distinct string once and hands out `Sym`s for them.
+#[derive(Clone, Debug, Default)]
+pub struct StringInterner {
+    strings: Vec<Rc<str>>,
+    syms: HashMap<Rc<str>, Sym>,
+}
+
+impl StringInterner {
+    pub fn new() -> Self {
+        StringInterner { strings: Vec::new(), syms: HashMap::new(), }
+    }
+
+    /// Returns the symbol for `s`, storing `s` if it has not been seen before.
+    pub fn intern(&mut self, s: &str) -> Sym {
+        if let Some(&sym) = self.syms.get(s) {
+            return sym
+        }
+        assert![self.strings.len() < u32::MAX as usize, "too many interned strings"];
+        let sym = Sym(self.strings.len() as u32);
+        let s: Rc<str> = Rc::from(s);
+        self.strings.push(s.clone());
+        self.syms.insert(s, sym);
+        sym
+    }
+
+    /// Returns the symbol for `s` if it has been interned.
+    pub fn get(&self, s: &str) -> Option<Sym> {
+        self.syms.get(s).cloned()
+    }
+
+    /// Returns the string for `sym`.
+    ///
+    /// Panics if `sym` was not produced by this interner.
+    pub fn resolve(&self, sym: Sym) -> &str {
+        &self.strings[sym.0 as usize]
+    }
+
+    /// Returns the number of distinct strings interned.
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Creates a leaf whose data is the symbol for `data`.
+    pub fn leaf(&mut self, data: &str) -> Tree<Sym> {
+        Tree::leaf(self.intern(data))
+    }
+
+    /// Creates a tree whose root data is the symbol for `data`.
+    pub fn tree(&mut self, data: &str, children: Vec<Tree<Sym>>) -> Tree<Sym> {
+        Tree::new(self.intern(data), children)
+    }
+
+    /// Converts a tree of strings into a tree of symbols.
+    pub fn intern_tree<S: AsRef<str>>(&mut self, tree: Tree<S>) -> Tree<Sym> {
+        tree.fold(|data, children| Tree::new(self.intern(data.as_ref()), children))
+    }
+
+    /// Converts a tree of symbols back into a tree of strings.
+    pub fn resolve_tree(&self, tree: Tree<Sym>) -> Tree<String> {
+        tree.fold(|data, children| Tree::new(self.resolve(data).to_string(), children))
+    }
+}
+
+/// Looks up the string for the symbol a view is focused on.
+///
+/// This is implemented for anything that dereferences to a `Sym`, which
+/// includes the views and editors of every backend.
+pub trait Resolve {
+    /// Returns the string for the focused symbol.
+    ///
+    /// Panics if the symbol was not produced by `interner`.
+    fn resolve<'i>(&self, interner: &'i StringInterner) -> &'i str;
+}
+
+impl<D> Resolve for D where D: Deref<Target=Sym> {
+    fn resolve<'i>(&self, interner: &'i StringInterner) -> &'i str {
+        interner.resolve(**self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::intern::{Resolve, StringInterner};
+    use ::owned_tree;
+    use ::Nav;
+
+    #[test]
+    fn interns_each_string_once() {
+        let mut interner = StringInterner::new();
+        let div = interner.intern("div");
+        let span = interner.intern("span");
+        assert![div != span];
+        assert_eq![div, interner.intern("div")];
+        assert_eq![Some(span), interner.get("span")];
+        assert_eq![None, interner.get("p")];
+        assert_eq!["div", interner.resolve(div)];
+        assert_eq![2, interner.len()];
+    }
+
+    #[test]
+    fn builds_and_converts_trees() {
+        let mut interner = StringInterner::new();
+        let t = {
+            let li = interner.leaf("li");
+            let li2 = interner.leaf("li");
+            interner.tree("ul", vec![li, li2])
+        };
+        assert_eq![2, interner.len()];
+        assert_eq![t, interner.intern_tree(owned_tree!["ul", ["li"], ["li"]])];
+        assert_eq![owned_tree!["ul".to_string(), ["li".to_string()], ["li".to_string()]],
+                   interner.resolve_tree(t)];
+    }
+
+    #[test]
+    fn resolves_views() {
+        let mut interner = StringInterner::new();
+        let t = interner.intern_tree(owned_tree!["html", ["head"], ["body", ["p"]]]);
+        let mut v = t.view();
+        assert_eq!["html", v.resolve(&interner)];
+        assert![v.seek_child(1)];
+        assert![v.seek_child(0)];
+        assert_eq!["p", v.resolve(&interner)];
+
+        let shared = ::shared::Tree::from(t.clone());
+        let mut v = shared.view();
+        assert![v.seek_child(0)];
+        assert_eq!["head", v.resolve(&interner)];
+    }
+}
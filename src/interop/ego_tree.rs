@@ -0,0 +1,91 @@
+use ::owned::Tree;
+
+use ego_tree;
+
+use std::collections::HashMap;
+
+/// Moves the data of a tree into an `ego_tree::Tree` of the same shape.
+impl<T> From<Tree<T>> for ego_tree::Tree<T> {
+    fn from(tree: Tree<T>) -> Self {
+        let (data, children) = tree.into_parts();
+        let mut ego = ego_tree::Tree::new(data);
+        let root = ego.root().id();
+        let mut stack = vec![(root, children.into_iter())];
+        while let Some(&mut (parent, ref mut children)) = stack.last_mut() {
+            match children.next() {
+                Some(child) => {
+                    let (data, grandchildren) = child.into_parts();
+                    let id = ego.get_mut(parent).unwrap().append(data).id();
+                    stack.push((id, grandchildren.into_iter()));
+                },
+                None => { stack.pop(); },
+            }
+        }
+        ego
+    }
+}
+
+/// Moves the data of the nodes reachable from the root of an `ego_tree::Tree`
+/// into a tree of the same shape. Orphaned nodes are dropped.
+impl<T> From<ego_tree::Tree<T>> for Tree<T> {
+    fn from(ego: ego_tree::Tree<T>) -> Self {
+        // Nodes are numbered in insertion order, which is the order in which
+        // `into_iter` yields their data.
+        let numbers: HashMap<ego_tree::NodeId, usize> =
+            ego.nodes().enumerate().map(|(number, node)| (node.id(), number)).collect();
+        let children: Vec<Vec<usize>> = ego.nodes()
+            .map(|node| node.children().map(|child| numbers[&child.id()]).collect())
+            .collect();
+        let root = numbers[&ego.root().id()];
+        let mut data: Vec<Option<T>> = ego.into_iter().map(Some).collect();
+        // Each stack entry is a node, the index of its next child, and its
+        // subtrees so far.
+        let mut stack = vec![(root, 0, Vec::new())];
+        loop {
+            let next = {
+                let top = stack.last_mut().unwrap();
+                let next = children[top.0].get(top.1).cloned();
+                top.1 += 1;
+                next
+            };
+            match next {
+                Some(child) => stack.push((child, 0, Vec::with_capacity(children[child].len()))),
+                None => {
+                    let (node, _, subtrees) = stack.pop().unwrap();
+                    let tree = Tree::new(data[node].take().unwrap(), subtrees);
+                    match stack.last_mut() {
+                        Some(parent) => parent.2.push(tree),
+                        None => return tree,
+                    }
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::owned::Tree;
+    use ::owned_tree;
+
+    use ego_tree;
+
+    #[test]
+    fn round_trips_trees() {
+        let t = owned_tree!["a", ["b", ["c"], ["d"]], ["e"]];
+        let ego = ego_tree::Tree::from(t.clone());
+        assert_eq![vec!["a", "b", "c", "d", "e"],
+                   ego.root().descendants().map(|n| *n.value()).collect::<Vec<_>>()];
+        assert_eq![t, Tree::from(ego)];
+    }
+
+    #[test]
+    fn follows_sibling_order_and_drops_orphans() {
+        let mut ego = ego_tree::Tree::new("r");
+        let b = ego.root_mut().append("b").id();
+        ego.get_mut(b).unwrap().insert_before("a");
+        ego.get_mut(b).unwrap().append("c");
+        ego.orphan("x");
+        assert_eq![owned_tree!["r", ["a"], ["b", ["c"]]], Tree::from(ego)];
+    }
+}
@@ -0,0 +1,80 @@
+use ::owned::Tree;
+
+use indextree::{Arena, NodeId};
+
+/// Moves the data of `tree` into `arena` as a new tree, returning the ID of its
+/// root.
+pub fn into_arena<T>(tree: Tree<T>, arena: &mut Arena<T>) -> NodeId {
+    let (data, children) = tree.into_parts();
+    let root = arena.new_node(data);
+    let mut stack = vec![(root, children.into_iter())];
+    while let Some(&mut (parent, ref mut children)) = stack.last_mut() {
+        match children.next() {
+            Some(child) => {
+                let (data, grandchildren) = child.into_parts();
+                let id = arena.new_node(data);
+                parent.append(id, arena);
+                stack.push((id, grandchildren.into_iter()));
+            },
+            None => { stack.pop(); },
+        }
+    }
+    root
+}
+
+/// Copies the subtree of `arena` rooted at `root` into a tree. `root` need not
+/// be the root of a tree in the arena.
+///
+/// Panics if `root` has been removed from the arena.
+pub fn from_arena<T: Clone>(arena: &Arena<T>, root: NodeId) -> Tree<T> {
+    assert![!root.is_removed(arena), "node {} has been removed", root];
+    // Each stack entry is a node, its remaining children, and its subtrees so
+    // far.
+    let mut stack = vec![(root, root.children(arena), Vec::new())];
+    loop {
+        let next = stack.last_mut().unwrap().1.next();
+        match next {
+            Some(child) => stack.push((child, child.children(arena), Vec::new())),
+            None => {
+                let (node, _, subtrees) = stack.pop().unwrap();
+                let tree = Tree::new(arena[node].get().clone(), subtrees);
+                match stack.last_mut() {
+                    Some(parent) => parent.2.push(tree),
+                    None => return tree,
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::interop::indextree::{from_arena, into_arena};
+    use ::owned_tree;
+
+    use indextree::Arena;
+
+    #[test]
+    fn round_trips_trees() {
+        let t = owned_tree!["a", ["b", ["c"], ["d"]], ["e"]];
+        let mut arena = Arena::new();
+        let other = into_arena(owned_tree!["x", ["y"]], &mut arena);
+        let root = into_arena(t.clone(), &mut arena);
+        assert_eq![7, arena.len()];
+        assert_eq![vec!["a", "b", "c", "d", "e"],
+                   root.descendants(&arena).map(|n| *arena[n].get()).collect::<Vec<_>>()];
+        assert_eq![t, from_arena(&arena, root)];
+        assert_eq![owned_tree!["x", ["y"]], from_arena(&arena, other)];
+        let b = root.children(&arena).next().unwrap();
+        assert_eq![owned_tree!["b", ["c"], ["d"]], from_arena(&arena, b)];
+    }
+
+    #[test]
+    #[should_panic(expected = "has been removed")]
+    fn rejects_removed_nodes() {
+        let mut arena = Arena::new();
+        let root = into_arena(owned_tree![1, [2]], &mut arena);
+        root.remove(&mut arena);
+        from_arena(&arena, root);
+    }
+}
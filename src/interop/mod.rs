@@ -0,0 +1,9 @@
+/// Conversions between trees and `ego_tree` trees.
+#[cfg(feature = "ego-tree")]
+pub mod ego_tree;
+/// Conversions between trees and trees in `indextree` arenas.
+#[cfg(feature = "indextree")]
+pub mod indextree;
+/// Conversions between trees and `petgraph` graphs.
+#[cfg(feature = "petgraph")]
+pub mod petgraph;
@@ -0,0 +1,213 @@
+use ::Nav;
+use ::owned::Tree;
+
+use petgraph::Direction;
+use petgraph::graph::{DiGraph, NodeIndex};
+
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::ops::Deref;
+
+/// Makes a directed graph with a node for each node of the tree under `nav`,
+/// holding a copy of its data, and an edge from each node to each of its
+/// children, weighted with the child's index.
+///
+/// The nodes are added in pre-order, so the root is at index 0.
+pub fn to_graph<N>(mut nav: N) -> DiGraph<N::Target, usize>
+    where N: Nav + Deref, N::Target: Clone + Sized {
+        nav.to_root();
+        let mut graph = DiGraph::new();
+        let root = graph.add_node((*nav).clone());
+        // Each stack entry is a node and the index of its next child.
+        let mut stack = vec![(root, 0)];
+        while let Some((parent, index)) = stack.pop() {
+            if nav.seek_child(index) {
+                let child = graph.add_node((*nav).clone());
+                graph.add_edge(parent, child, index);
+                stack.push((parent, index + 1));
+                stack.push((child, 0));
+            } else if !stack.is_empty() {
+                nav.to_parent();
+            }
+        }
+        graph
+    }
+
+/// Moves the data of a tree into a graph, laid out as `to_graph` does.
+impl<T> From<Tree<T>> for DiGraph<T, usize> {
+    fn from(tree: Tree<T>) -> Self {
+        let (data, children) = tree.into_parts();
+        let mut graph = DiGraph::new();
+        let root = graph.add_node(data);
+        let mut stack = vec![(root, children.into_iter().enumerate())];
+        while let Some(&mut (parent, ref mut children)) = stack.last_mut() {
+            match children.next() {
+                Some((index, child)) => {
+                    let (data, grandchildren) = child.into_parts();
+                    let id = graph.add_node(data);
+                    graph.add_edge(parent, id, index);
+                    stack.push((id, grandchildren.into_iter().enumerate()));
+                },
+                None => { stack.pop(); },
+            }
+        }
+        graph
+    }
+}
+
+/// Error returned when converting a graph that is not a tree.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum NotATree {
+    /// The graph has no nodes.
+    Empty,
+    /// Every node has an incoming edge.
+    NoRoot,
+    /// Both nodes have no incoming edges.
+    MultipleRoots(NodeIndex, NodeIndex),
+    /// The node has more than one incoming edge.
+    MultipleParents(NodeIndex),
+    /// The node is on a cycle, or is reached only from one.
+    Cycle(NodeIndex),
+}
+
+impl fmt::Display for NotATree {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            NotATree::Empty => f.write_str("graph has no nodes"),
+            NotATree::NoRoot => f.write_str("graph has no node without a parent"),
+            NotATree::MultipleRoots(a, b) =>
+                write!(f, "nodes {} and {} both have no parent", a.index(), b.index()),
+            NotATree::MultipleParents(n) =>
+                write!(f, "node {} has more than one parent", n.index()),
+            NotATree::Cycle(n) => write!(f, "node {} is not reachable from the root", n.index()),
+        }
+    }
+}
+
+impl Error for NotATree {}
+
+/// Moves the data of a tree-shaped graph into a tree. The root is the one node
+/// with no incoming edges, and the children of each node are ordered by the
+/// weights of the edges to them, ties keeping the order in which the edges
+/// were added.
+impl<T> TryFrom<DiGraph<T, usize>> for Tree<T> {
+    type Error = NotATree;
+
+    fn try_from(graph: DiGraph<T, usize>) -> Result<Self, NotATree> {
+        let mut root = None;
+        for node in graph.node_indices() {
+            let mut parents = graph.neighbors_directed(node, Direction::Incoming);
+            match (parents.next(), parents.next()) {
+                (None, _) => match root {
+                    None => root = Some(node),
+                    Some(other) => return Err(NotATree::MultipleRoots(other, node)),
+                },
+                (Some(_), None) => (),
+                (Some(_), Some(_)) => return Err(NotATree::MultipleParents(node)),
+            }
+        }
+        let root = match root {
+            Some(root) => root,
+            None if graph.node_count() == 0 => return Err(NotATree::Empty),
+            None => return Err(NotATree::NoRoot),
+        };
+        let mut children: Vec<Vec<(usize, usize)>> = vec![Vec::new(); graph.node_count()];
+        let (nodes, edges) = graph.into_nodes_edges();
+        for (order, edge) in edges.iter().enumerate() {
+            children[edge.source().index()].push((edge.weight, order));
+        }
+        for c in &mut children {
+            c.sort();
+        }
+        let mut data: Vec<Option<T>> = nodes.into_iter().map(|n| Some(n.weight)).collect();
+        let mut built = 0;
+        // Each stack entry is a node, its remaining children, and its subtrees
+        // so far.
+        let mut stack = vec![(root.index(), 0, Vec::new())];
+        loop {
+            let next = {
+                let top = stack.last_mut().unwrap();
+                let next = children[top.0].get(top.1).map(|&(_, order)| edges[order].target());
+                top.1 += 1;
+                next
+            };
+            match next {
+                Some(child) => stack.push((child.index(), 0, Vec::new())),
+                None => {
+                    let (node, _, subtrees) = stack.pop().unwrap();
+                    let tree = Tree::new(data[node].take().unwrap(), subtrees);
+                    built += 1;
+                    match stack.last_mut() {
+                        Some(parent) => parent.2.push(tree),
+                        None if built == data.len() => return Ok(tree),
+                        None => {
+                            let unreached = data.iter().position(Option::is_some).unwrap();
+                            return Err(NotATree::Cycle(NodeIndex::new(unreached)))
+                        },
+                    }
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::Nav;
+    use ::interop::petgraph::{to_graph, NotATree};
+    use ::owned::Tree;
+    use ::owned_tree;
+
+    use petgraph::graph::{DiGraph, NodeIndex};
+
+    use std::convert::TryFrom;
+
+    #[test]
+    fn round_trips_trees() {
+        let t = owned_tree!["a", ["b", ["c"], ["d"]], ["e"]];
+        let g = DiGraph::from(t.clone());
+        assert_eq![5, g.node_count()];
+        assert_eq![Some(&"a"), g.node_weight(NodeIndex::new(0))];
+        assert_eq![Ok(t.clone()), Tree::try_from(g)];
+        let mut v = t.view();
+        v.seek_child(1);
+        let g = to_graph(v);
+        assert_eq![vec!["a", "b", "c", "d", "e"], g.node_weights().cloned().collect::<Vec<_>>()];
+        assert_eq![Ok(t), Tree::try_from(g)];
+    }
+
+    #[test]
+    fn orders_children_by_edge_weight() {
+        let mut g = DiGraph::new();
+        let r = g.add_node("r");
+        let a = g.add_node("a");
+        let b = g.add_node("b");
+        g.add_edge(r, b, 1);
+        g.add_edge(r, a, 0);
+        assert_eq![Ok(owned_tree!["r", ["a"], ["b"]]), Tree::try_from(g)];
+    }
+
+    #[test]
+    fn rejects_graphs_that_are_not_trees() {
+        assert_eq![Err(NotATree::Empty), Tree::try_from(DiGraph::<(), usize>::new())];
+        let mut g = DiGraph::new();
+        let r = g.add_node(0);
+        let a = g.add_node(1);
+        let b = g.add_node(2);
+        g.add_edge(r, a, 0);
+        g.add_edge(r, b, 1);
+        let mut cycle = g.clone();
+        let c = cycle.add_node(3);
+        cycle.add_edge(c, c, 0);
+        assert_eq![Err(NotATree::Cycle(c)), Tree::try_from(cycle)];
+        let mut roots = g.clone();
+        let c = roots.add_node(3);
+        assert_eq![Err(NotATree::MultipleRoots(r, c)), Tree::try_from(roots)];
+        let mut parents = g.clone();
+        parents.add_edge(a, b, 0);
+        assert_eq![Err(NotATree::MultipleParents(b)), Tree::try_from(parents)];
+        g.add_edge(b, r, 0);
+        assert_eq![Err(NotATree::NoRoot), Tree::try_from(g)];
+    }
+}
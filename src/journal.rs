@@ -0,0 +1,302 @@
+use ::Editor;
+use ::command::{self, ApplyError, EditCommand, ParseCommandError};
+use ::owned::Tree;
+use ::path::TreePath;
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::ops::DerefMut;
+use std::str::FromStr;
+
+/// Line that separates the snapshot from the tail in a written journal.
+const TAIL_MARKER: &str = "tail";
+
+/// Record of the edits made to a tree, kept as a snapshot of the tree and the
+/// commands applied since the snapshot was taken.
+///
+/// Replaying the tail onto the snapshot reproduces the current tree, so a tree
+/// can be persisted by writing the journal once and then appending each new
+/// command as it is applied. Compaction folds the tail into the snapshot.
+///
+/// # Format
+///
+/// `write` produces one command per line, in the format of `EditCommand`'s
+/// `Display` impl. The snapshot is written as a `set /` command for the root
+/// followed by `push` commands that rebuild the rest of the tree in pre-order,
+/// then a line reading `tail`, then the tail. Commands written after the tail
+/// (such as by `append_to`) become part of the tail when the journal is read
+/// back.
+#[derive(Clone, Debug)]
+pub struct Journal<T> {
+    snapshot: Tree<T>,
+    tail: Vec<EditCommand<T>>,
+}
+
+impl<T: Clone> Journal<T> {
+    /// Creates a journal whose snapshot is `tree`, with an empty tail.
+    pub fn new(tree: Tree<T>) -> Self {
+        Journal { snapshot: tree, tail: Vec::new(), }
+    }
+
+    pub fn snapshot(&self) -> &Tree<T> {
+        &self.snapshot
+    }
+
+    /// Returns the commands recorded since the snapshot was taken.
+    pub fn tail(&self) -> &[EditCommand<T>] {
+        &self.tail
+    }
+
+    /// Appends `command` to the tail without applying it.
+    pub fn record(&mut self, command: EditCommand<T>) {
+        self.tail.push(command);
+    }
+
+    /// Applies `command` with `editor` and, if it succeeds, appends it to the
+    /// tail.
+    pub fn apply<E>(&mut self, editor: &mut E, command: EditCommand<T>) -> Result<(), ApplyError>
+        where E: Editor<Data=T> + DerefMut<Target=T> {
+            command::apply(editor, &command)?;
+            self.tail.push(command);
+            Ok(())
+        }
+
+    /// Reproduces the current tree by applying the tail to a copy of the
+    /// snapshot.
+    pub fn replay(&self) -> Result<Tree<T>, ApplyError> {
+        let mut tree = self.snapshot.clone();
+        apply_all(&mut tree, &self.tail)?;
+        Ok(tree)
+    }
+
+    /// Applies the tail to the snapshot and clears it. If a command fails, the
+    /// journal is left unchanged.
+    pub fn compact(&mut self) -> Result<(), ApplyError> {
+        self.snapshot = self.replay()?;
+        self.tail.clear();
+        Ok(())
+    }
+
+    /// Returns commands that rebuild the snapshot, starting from a leaf.
+    fn snapshot_commands(&self) -> Vec<EditCommand<T>> {
+        let mut commands = vec![EditCommand::SetData { path: TreePath::root(),
+                                                       data: self.snapshot.data().clone(), }];
+        let mut stack = vec![(&self.snapshot, TreePath::root())];
+        while let Some((tree, path)) = stack.pop() {
+            for (index, child) in tree.children().iter().enumerate().rev() {
+                stack.push((child, path.child(index)));
+            }
+            if let Some(parent) = path.parent() {
+                commands.push(EditCommand::PushChild { path: parent, data: tree.data().clone(), });
+            }
+        }
+        commands
+    }
+
+    /// Writes the snapshot and tail to `w`.
+    pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> where T: fmt::Display {
+        for command in self.snapshot_commands() {
+            writeln!(w, "{}", command)?;
+        }
+        writeln!(w, "{}", TAIL_MARKER)?;
+        for command in &self.tail {
+            writeln!(w, "{}", command)?;
+        }
+        Ok(())
+    }
+
+    /// Applies `command` with `editor`, appends it to the tail, and writes it
+    /// to `w`, which should hold this journal as written by `write`. Nothing
+    /// is written if the command fails.
+    pub fn append_to<E, W>(&mut self, editor: &mut E, command: EditCommand<T>, w: &mut W)
+                           -> Result<(), AppendError>
+        where E: Editor<Data=T> + DerefMut<Target=T>, W: Write, T: fmt::Display {
+            command::apply(editor, &command).map_err(AppendError::Apply)?;
+            writeln!(w, "{}", command).map_err(AppendError::Io)?;
+            self.tail.push(command);
+            Ok(())
+        }
+
+    /// Reads a journal written by `write`.
+    pub fn read<R: BufRead>(r: R) -> Result<Self, ReadError<T::Err>> where T: FromStr {
+        let mut snapshot: Option<Tree<T>> = None;
+        let mut tail: Option<Vec<EditCommand<T>>> = None;
+        for (index, line) in r.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.map_err(ReadError::Io)?;
+            if tail.is_none() && line == TAIL_MARKER {
+                if snapshot.is_none() {
+                    return Err(ReadError::MissingRoot)
+                }
+                tail = Some(Vec::new());
+                continue
+            }
+            let command: EditCommand<T> = line.parse()
+                .map_err(|error| ReadError::Parse { line: line_number, error, })?;
+            match (&mut snapshot, &mut tail) {
+                (_, &mut Some(ref mut tail)) => tail.push(command),
+                (&mut None, &mut None) => match command {
+                    EditCommand::SetData { ref path, ref data } if path.is_root() =>
+                        snapshot = Some(Tree::leaf(data.clone())),
+                    _ => return Err(ReadError::MissingRoot),
+                },
+                (&mut Some(ref mut tree), &mut None) =>
+                    apply_all(tree, &[command])
+                        .map_err(|error| ReadError::Apply { line: line_number, error, })?,
+            }
+        }
+        match snapshot {
+            Some(snapshot) => Ok(Journal { snapshot, tail: tail.unwrap_or_default(), }),
+            None => Err(ReadError::MissingRoot),
+        }
+    }
+}
+
+fn apply_all<T: Clone>(tree: &mut Tree<T>, commands: &[EditCommand<T>]) -> Result<(), ApplyError> {
+    let mut v = tree.view_mut();
+    for command in commands {
+        command::apply(&mut v, command)?;
+    }
+    Ok(())
+}
+
+/// Failure to append a command to a journal.
+#[derive(Debug)]
+pub enum AppendError {
+    Apply(ApplyError),
+    Io(io::Error),
+}
+
+impl fmt::Display for AppendError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AppendError::Apply(ref e) => e.fmt(f),
+            AppendError::Io(ref e) => e.fmt(f),
+        }
+    }
+}
+
+impl Error for AppendError {}
+
+/// Failure to read a journal.
+#[derive(Debug)]
+pub enum ReadError<E> {
+    Io(io::Error),
+    /// The journal does not begin with a command that sets the root data.
+    MissingRoot,
+    /// A line could not be parsed as a command.
+    Parse { line: usize, error: ParseCommandError<E> },
+    /// A snapshot command could not be applied.
+    Apply { line: usize, error: ApplyError },
+}
+
+impl<E: fmt::Display> fmt::Display for ReadError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ReadError::Io(ref e) => e.fmt(f),
+            ReadError::MissingRoot => f.write_str("journal does not begin with the root data"),
+            ReadError::Parse { line, ref error } => write!(f, "line {}: {}", line, error),
+            ReadError::Apply { line, ref error } => write!(f, "line {}: {}", line, error),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> Error for ReadError<E> {}
+
+#[cfg(test)]
+mod test {
+    use ::command::{ApplyErrorKind, EditCommand};
+    use ::journal::{Journal, ReadError};
+    use ::owned_tree;
+    use ::path::TreePath;
+
+    fn edit(journal: &mut Journal<i32>, tree: &mut ::owned::Tree<i32>) {
+        let mut v = tree.view_mut();
+        journal.apply(&mut v, EditCommand::PushChild { path: TreePath::from(vec![0]), data: 4, })
+            .unwrap();
+        journal.apply(&mut v, EditCommand::SetData { path: TreePath::root(), data: 10, }).unwrap();
+        journal.apply(&mut v, EditCommand::Remove { path: TreePath::from(vec![1]), }).unwrap();
+    }
+
+    #[test]
+    fn replay_and_compact() {
+        let mut tree = owned_tree![1, [2], [3]];
+        let mut journal = Journal::new(tree.clone());
+        edit(&mut journal, &mut tree);
+        assert_eq![journal.tail().len(), 3];
+        assert_eq![*journal.snapshot(), owned_tree![1, [2], [3]]];
+        assert_eq![journal.replay().unwrap(), tree];
+        journal.compact().unwrap();
+        assert![journal.tail().is_empty()];
+        assert_eq![*journal.snapshot(), tree];
+    }
+
+    #[test]
+    fn failed_commands_are_not_recorded() {
+        let mut tree = owned_tree![1];
+        let mut journal = Journal::new(tree.clone());
+        let mut v = tree.view_mut();
+        let error = journal.apply(&mut v, EditCommand::Remove { path: TreePath::root(), })
+            .unwrap_err();
+        assert_eq![error.kind, ApplyErrorKind::RemoveRoot];
+        assert![journal.tail().is_empty()];
+    }
+
+    #[test]
+    fn compaction_failure_leaves_journal_unchanged() {
+        let mut journal = Journal::new(owned_tree![1]);
+        journal.record(EditCommand::PushChild { path: TreePath::root(), data: 2, });
+        journal.record(EditCommand::Remove { path: TreePath::from(vec![5]), });
+        assert![journal.compact().is_err()];
+        assert_eq![journal.tail().len(), 2];
+        assert_eq![*journal.snapshot(), owned_tree![1]];
+    }
+
+    #[test]
+    fn write_and_read() {
+        let mut tree = owned_tree![1, [2, [5], [6]], [3]];
+        let mut journal = Journal::new(tree.clone());
+        let mut out = Vec::new();
+        journal.write(&mut out).unwrap();
+        {
+            let mut v = tree.view_mut();
+            journal.append_to(&mut v, EditCommand::PushChild { path: TreePath::from(vec![1]),
+                                                               data: 7, },
+                              &mut out).unwrap();
+            assert![journal.append_to(&mut v, EditCommand::Remove { path: TreePath::from(vec![9]), },
+                                      &mut out).is_err()];
+        }
+        let text = String::from_utf8(out).unwrap();
+        assert_eq![text, "set / 1\npush / 2\npush /0 5\npush /0 6\npush / 3\ntail\npush /1 7\n"];
+        let read: Journal<i32> = Journal::read(text.as_bytes()).unwrap();
+        assert_eq![*read.snapshot(), owned_tree![1, [2, [5], [6]], [3]]];
+        assert_eq![read.tail(), journal.tail()];
+        assert_eq![read.replay().unwrap(), tree];
+    }
+
+    #[test]
+    fn line_breaks_in_data_round_trip() {
+        let tree = owned_tree!["a\r".to_string(), ["b\r\n".to_string()], ["\\r".to_string()]];
+        let mut out = Vec::new();
+        Journal::new(tree.clone()).write(&mut out).unwrap();
+        let read: Journal<String> = Journal::read(&out[..]).unwrap();
+        assert_eq![*read.snapshot(), tree];
+    }
+
+    #[test]
+    fn read_errors() {
+        match Journal::<i32>::read("push / 1\n".as_bytes()) {
+            Err(ReadError::MissingRoot) => (),
+            other => panic!["unexpected result {:?}", other],
+        }
+        match Journal::<i32>::read("set / 1\npush /3 2\n".as_bytes()) {
+            Err(ReadError::Apply { line: 2, .. }) => (),
+            other => panic!["unexpected result {:?}", other],
+        }
+        match Journal::<i32>::read("set / 1\ntail\nset / x\n".as_bytes()) {
+            Err(ReadError::Parse { line: 3, .. }) => (),
+            other => panic!["unexpected result {:?}", other],
+        }
+    }
+}
@@ -0,0 +1,296 @@
+use ::owned::Tree;
+use ::path::TreePath;
+
+use std::fmt;
+use std::io::{self, Write};
+
+/// Left and right extents of a subtree at each depth below its root, relative
+/// to the root's x coordinate.
+struct Contour {
+    left: Vec<f32>,
+    right: Vec<f32>,
+}
+
+impl Contour {
+    fn leaf() -> Self {
+        Contour { left: vec![0.0], right: vec![0.0], }
+    }
+
+    /// Places `next` to the right of `self` with at least `separation` between
+    /// them at every shared depth, merges it in, and returns its x coordinate.
+    fn place(&mut self, next: Contour, separation: f32) -> f32 {
+        let shared = self.right.len().min(next.left.len());
+        let x = (0..shared)
+            .map(|d| self.right[d] - next.left[d] + separation)
+            .fold(f32::NEG_INFINITY, f32::max);
+        for (d, (left, right)) in next.left.into_iter().zip(next.right).enumerate() {
+            if d < self.right.len() {
+                self.right[d] = right + x;
+            } else {
+                self.left.push(left + x);
+                self.right.push(right + x);
+            }
+        }
+        x
+    }
+
+    /// Returns this contour of a parent's children as seen from a parent at
+    /// `x`, with the parent itself added as the top level.
+    fn under(self, x: f32) -> Self {
+        let mut left = vec![0.0];
+        left.extend(self.left.into_iter().map(|l| l - x));
+        let mut right = vec![0.0];
+        right.extend(self.right.into_iter().map(|r| r - x));
+        Contour { left, right, }
+    }
+}
+
+/// Computes tidy drawing coordinates for every node of `tree`, in the manner
+/// of Reingold and Tilford.
+///
+/// Each node is given the center of a box of `node_size`, as `(width,
+/// height)`. Nodes at depth `d` have y coordinate `d * height`, so y grows
+/// downward as in most drawing surfaces. Subtrees are packed as closely as
+/// possible from left to right so that no two boxes at the same depth overlap,
+/// and each parent is centered over its first and last children. The leftmost
+/// node has x coordinate 0. Callers wanting space between nodes should pad
+/// `node_size`.
+///
+/// The result lists nodes in pre-order with their paths. Laying out a tree
+/// takes time proportional to its size times its height in the worst case.
+pub fn tidy<T>(tree: &Tree<T>, node_size: (f32, f32)) -> Vec<(TreePath, (f32, f32))> {
+    struct Frame<'a, T: 'a> {
+        tree: &'a Tree<T>,
+        next_child: usize,
+        index: usize,
+        children: Vec<(usize, Contour)>,
+    }
+
+    let (width, height) = node_size;
+    // Indexed by pre-order position: each node's path, its parent's position,
+    // and its x coordinate relative to its parent.
+    let mut paths = vec![TreePath::root()];
+    let mut parents = vec![0];
+    let mut offsets = vec![0.0];
+    let mut path = TreePath::root();
+    let mut stack = vec![Frame { tree, next_child: 0, index: 0, children: Vec::new(), }];
+    loop {
+        let child = {
+            let top = stack.last_mut().unwrap();
+            let child = top.tree.children().get(top.next_child);
+            if child.is_some() {
+                path.push(top.next_child);
+                top.next_child += 1;
+            }
+            child.map(|child| (child, top.index))
+        };
+        match child {
+            Some((child, parent)) => {
+                let index = paths.len();
+                paths.push(path.clone());
+                parents.push(parent);
+                offsets.push(0.0);
+                stack.push(Frame { tree: child, next_child: 0, index, children: Vec::new(), });
+            },
+            None => {
+                let done = stack.pop().unwrap();
+                path.pop();
+                let contour = {
+                    let mut children = done.children.into_iter();
+                    match children.next() {
+                        None => Contour::leaf(),
+                        Some((first, mut contour)) => {
+                            let mut placed = vec![(first, 0.0)];
+                            for (child, next) in children {
+                                let x = contour.place(next, width);
+                                placed.push((child, x));
+                            }
+                            let x = (placed[0].1 + placed[placed.len() - 1].1) / 2.0;
+                            for (child, child_x) in placed {
+                                offsets[child] = child_x - x;
+                            }
+                            contour.under(x)
+                        },
+                    }
+                };
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push((done.index, contour)),
+                    None => break,
+                }
+            },
+        }
+    }
+
+    let mut xs = Vec::with_capacity(offsets.len());
+    for (index, offset) in offsets.into_iter().enumerate() {
+        let x = if index == 0 { 0.0 } else { xs[parents[index]] + offset };
+        xs.push(x);
+    }
+    let min_x = xs.iter().cloned().fold(f32::INFINITY, f32::min);
+    paths.into_iter().zip(xs)
+        .map(|(path, x)| {
+            let y = path.depth() as f32 * height;
+            (path, (x - min_x, y))
+        })
+        .collect()
+}
+
+/// Appearance of trees drawn by `render_svg`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SvgStyle {
+    /// Width and height of the box drawn for each node.
+    pub node_size: (f32, f32),
+    /// Horizontal space between adjacent boxes and vertical space between
+    /// levels.
+    pub spacing: (f32, f32),
+    /// Space around the whole drawing.
+    pub margin: f32,
+    /// Size of node labels.
+    pub font_size: f32,
+    /// SVG paint used to fill node boxes.
+    pub fill: String,
+    /// SVG paint used for box outlines, edges, and labels.
+    pub stroke: String,
+}
+
+impl Default for SvgStyle {
+    fn default() -> Self {
+        SvgStyle { node_size: (80.0, 30.0),
+                   spacing: (10.0, 20.0),
+                   margin: 10.0,
+                   font_size: 12.0,
+                   fill: "white".to_string(),
+                   stroke: "black".to_string(), }
+    }
+}
+
+/// Writes a standalone SVG document drawing `tree` with the coordinates of
+/// `tidy`, as boxes labeled with each node's data and lines from each parent
+/// to its children. Labels are not clipped to their boxes.
+pub fn render_svg<T, W>(tree: &Tree<T>, writer: &mut W, style: &SvgStyle) -> io::Result<()>
+    where T: fmt::Display, W: Write {
+    let (width, height) = style.node_size;
+    let (gap_x, gap_y) = style.spacing;
+    let layout = tidy(tree, (width + gap_x, height + gap_y));
+    let center = |(x, y): (f32, f32)| {
+        (x + style.margin + width / 2.0, y + style.margin + height / 2.0)
+    };
+    let max = layout.iter().fold((0.0f32, 0.0f32), |(mx, my), &(_, (x, y))| (mx.max(x), my.max(y)));
+    let total = (max.0 + width + 2.0 * style.margin, max.1 + height + 2.0 * style.margin);
+    writeln!(writer, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+                      viewBox=\"0 0 {} {}\">", total.0, total.1, total.0, total.1)?;
+
+    writeln!(writer, "<g stroke=\"{}\" fill=\"none\">", escape(&style.stroke))?;
+    // In pre-order, a node's parent is the last node seen one level up.
+    let mut ancestors: Vec<(f32, f32)> = Vec::new();
+    for &(ref path, xy) in layout.iter() {
+        let (x, y) = center(xy);
+        ancestors.truncate(path.depth());
+        if let Some(&(px, py)) = ancestors.last() {
+            writeln!(writer, "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"/>",
+                     px, py + height / 2.0, x, y - height / 2.0)?;
+        }
+        ancestors.push((x, y));
+    }
+    writeln!(writer, "</g>")?;
+
+    writeln!(writer, "<g font-family=\"sans-serif\" font-size=\"{}\" text-anchor=\"middle\" \
+                      dominant-baseline=\"central\">", style.font_size)?;
+    for (path, xy) in layout {
+        let (x, y) = center(xy);
+        let data = tree.subtree(&path).unwrap().data();
+        writeln!(writer,
+                 "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" stroke=\"{}\"/>",
+                 x - width / 2.0, y - height / 2.0, width, height,
+                 escape(&style.fill), escape(&style.stroke))?;
+        writeln!(writer, "<text x=\"{}\" y=\"{}\" fill=\"{}\">{}</text>",
+                 x, y, escape(&style.stroke), escape(&data.to_string()))?;
+    }
+    writeln!(writer, "</g>")?;
+    writeln!(writer, "</svg>")
+}
+
+/// Escapes `text` for use in SVG character data or attribute values.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use ::layout::{render_svg, tidy, SvgStyle};
+    use ::owned_tree;
+    use ::path::TreePath;
+
+    fn coordinates<T>(tree: &::owned::Tree<T>) -> Vec<(String, (f32, f32))> {
+        tidy(tree, (2.0, 3.0)).into_iter()
+            .map(|(path, xy)| (path.to_string(), xy))
+            .collect()
+    }
+
+    #[test]
+    fn lays_out_leaf() {
+        assert_eq![tidy(&owned_tree![()], (1.0, 1.0)), vec![(TreePath::root(), (0.0, 0.0))]];
+    }
+
+    #[test]
+    fn centers_parents_over_children() {
+        let t = owned_tree![0, [1, [2], [3], [4]], [5]];
+        let expected: Vec<(String, (f32, f32))> =
+            vec![("/".to_string(), (3.0, 0.0)),
+                 ("/0".to_string(), (2.0, 3.0)),
+                 ("/0/0".to_string(), (0.0, 6.0)),
+                 ("/0/1".to_string(), (2.0, 6.0)),
+                 ("/0/2".to_string(), (4.0, 6.0)),
+                 ("/1".to_string(), (4.0, 3.0))];
+        assert_eq![coordinates(&t), expected];
+    }
+
+    #[test]
+    fn separates_deep_contours() {
+        // The right subtree must clear the left one's wide bottom level, not
+        // just its root.
+        let t = owned_tree![0, [1, [2, [3], [4], [5]]], [6, [7, [8]]]];
+        let layout = tidy(&t, (1.0, 1.0));
+        for (i, &(ref a, (ax, ay))) in layout.iter().enumerate() {
+            for &(ref b, (bx, by)) in layout[i + 1..].iter() {
+                if ay == by {
+                    assert![(ax - bx).abs() >= 1.0, "{} and {} overlap", a, b];
+                }
+            }
+        }
+        let x = |path: &[usize]| layout.iter()
+            .find(|&(p, _)| p.indices() == path)
+            .map(|&(_, (x, _))| x)
+            .unwrap();
+        assert_eq![x(&[0, 0, 2]) + 1.0, x(&[1, 0, 0])];
+        assert_eq![x(&[]), (x(&[0]) + x(&[1])) / 2.0];
+    }
+
+    #[test]
+    fn renders_svg() {
+        let t = owned_tree!["a", ["<b>"], ["c", ["d & e"]]];
+        let style = SvgStyle { node_size: (10.0, 10.0), spacing: (2.0, 4.0), margin: 1.0,
+                               ..SvgStyle::default() };
+        let mut svg = Vec::new();
+        render_svg(&t, &mut svg, &style).unwrap();
+        let svg = String::from_utf8(svg).unwrap();
+        assert![svg.starts_with(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"24\" height=\"40\"")];
+        assert![svg.trim_end().ends_with("</svg>")];
+        assert_eq![4, svg.matches("<rect").count()];
+        assert_eq![3, svg.matches("<line").count()];
+        assert![svg.contains("<line x1=\"12\" y1=\"11\" x2=\"18\" y2=\"15\"/>")];
+        assert![svg.contains(">&lt;b&gt;</text>")];
+        assert![svg.contains(">d &amp; e</text>")];
+    }
+}
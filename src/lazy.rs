@@ -0,0 +1,306 @@
+use ::Nav;
+use ::index::{ChildIndex, SiblingIndex};
+
+use std::cell::{OnceCell, RefCell};
+use std::clone::Clone;
+use std::cmp::Ordering;
+use std::mem;
+use std::ops::Deref;
+use std::ptr;
+
+/// Source of lazily loaded tree nodes.
+///
+/// A loader maps a key identifying a node (such as a file offset or a database
+/// row id) to the node's data and the keys of its children. Closures of type
+/// `Fn(&K) -> (T, Vec<K>)` are loaders.
+pub trait Loader<K> {
+    /// The type of tree node data.
+    type Data;
+
+    /// Loads the data at the node identified by `key` and the keys of its
+    /// children.
+    fn load(&self, key: &K) -> (Self::Data, Vec<K>);
+}
+
+impl<K, T, F> Loader<K> for F where F: Fn(&K) -> (T, Vec<K>) {
+    type Data = T;
+
+    fn load(&self, key: &K) -> (T, Vec<K>) {
+        self(key)
+    }
+}
+
+struct Node<K, T> {
+    data: T,
+    // Keys of children that have not been loaded yet. Emptied when `children`
+    // is initialized.
+    pending: RefCell<Vec<K>>,
+    children: OnceCell<Vec<Node<K, T>>>,
+}
+
+impl<K, T> Node<K, T> {
+    fn load<L>(loader: &L, key: &K) -> Self where L: Loader<K, Data=T> {
+        let (data, keys) = loader.load(key);
+        Node { data, pending: RefCell::new(keys), children: OnceCell::new(), }
+    }
+
+    fn child_count(&self) -> usize {
+        match self.children.get() {
+            Some(children) => children.len(),
+            None => self.pending.borrow().len(),
+        }
+    }
+
+    fn children<L>(&self, loader: &L) -> &[Node<K, T>] where L: Loader<K, Data=T> {
+        self.children.get_or_init(|| {
+            let keys = mem::take(&mut *self.pending.borrow_mut());
+            keys.iter().map(|key| Node::load(loader, key)).collect()
+        })
+    }
+
+    fn loaded_size(&self) -> usize {
+        let mut size = 0;
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            size += 1;
+            if let Some(children) = node.children.get() {
+                stack.extend(children.iter());
+            }
+        }
+        size
+    }
+}
+
+/// Tree whose subtrees are loaded on first navigation.
+///
+/// Only the root and a fixed number of levels below it are loaded when the tree
+/// is created. Deeper nodes are materialized by the tree's `Loader` the first
+/// time a view navigates to them, after which they remain in memory. This makes
+/// it possible to work with very large persisted trees without reading them in
+/// their entirety.
+pub struct Tree<K, L> where L: Loader<K> {
+    loader: L,
+    root: Node<K, L::Data>,
+}
+
+impl<K, L> Tree<K, L> where L: Loader<K> {
+    /// Loads the node at `root_key` with `loader`, along with the
+    /// `eager_depth` levels of the tree beneath it.
+    pub fn load(loader: L, root_key: K, eager_depth: usize) -> Self {
+        let root = Node::load(&loader, &root_key);
+        let tree = Tree { loader, root, };
+        let mut frontier = vec![(&tree.root, 0usize)];
+        while let Some((node, depth)) = frontier.pop() {
+            if depth < eager_depth {
+                for child in node.children(&tree.loader) {
+                    frontier.push((child, depth + 1));
+                }
+            }
+        }
+        tree
+    }
+
+    /// Returns the number of nodes that have been loaded so far.
+    pub fn loaded_size(&self) -> usize {
+        self.root.loaded_size()
+    }
+
+    pub fn view(&self) -> TreeView<'_, K, L> {
+        TreeView { tree: self, here: &self.root, path: Vec::new(), }
+    }
+}
+
+/// Navigable view of a lazily loaded tree. Seeking a child loads the children
+/// of the focus if they have not been loaded yet.
+pub struct TreeView<'a, K: 'a, L: 'a> where L: Loader<K> {
+    tree: &'a Tree<K, L>,
+    here: &'a Node<K, L::Data>,
+    path: Vec<(&'a Node<K, L::Data>, usize)>,
+}
+
+impl<'a, K: 'a, L: 'a> TreeView<'a, K, L> where L: Loader<K> {
+    /// Returns `true` iff the children of the focus have been loaded.
+    pub fn children_loaded(&self) -> bool {
+        self.here.children.get().is_some()
+    }
+}
+
+impl<'a, K: 'a, L: 'a> Clone for TreeView<'a, K, L> where L: Loader<K> {
+    fn clone(&self) -> Self {
+        TreeView { tree: self.tree, here: self.here, path: self.path.clone(), }
+    }
+}
+
+impl<'a, K: 'a, L: 'a> PartialEq for TreeView<'a, K, L> where L: Loader<K> {
+    fn eq(&self, other: &Self) -> bool {
+        ptr::eq(self.tree, other.tree)
+            && self.path.iter().map(|&(_, i)| i).eq(other.path.iter().map(|&(_, i)| i))
+    }
+}
+
+impl<'a, K: 'a, L: 'a> PartialOrd for TreeView<'a, K, L> where L: Loader<K> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if ptr::eq(self.tree, other.tree) {
+            Some(self.path.iter().map(|&(_, i)| i).cmp(other.path.iter().map(|&(_, i)| i)))
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, K: 'a, L: 'a> Deref for TreeView<'a, K, L> where L: Loader<K> {
+    type Target = L::Data;
+
+    fn deref(&self) -> &<Self as Deref>::Target {
+        &self.here.data
+    }
+}
+
+impl<'a, K: 'a, L: 'a> Nav for TreeView<'a, K, L> where L: Loader<K> {
+    fn seek_sibling(&mut self, offset: isize) -> bool {
+        if offset == 0 {
+            return true
+        }
+        let (parent, here_index) = match self.path.last() {
+            None => return false,
+            Some(&(parent, here_index)) => (parent, here_index),
+        };
+        match SiblingIndex::compute(parent.child_count(), here_index, offset) {
+            Some(new_index) => {
+                self.path.pop();
+                self.path.push((parent, new_index));
+                self.here = &parent.children(&self.tree.loader)[new_index];
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn seek_child(&mut self, index: usize) -> bool {
+        match ChildIndex::compute(self.child_count(), index) {
+            Some(new_index) => {
+                self.path.push((self.here, new_index));
+                self.here = &self.here.children(&self.tree.loader)[new_index];
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn child_count(&self) -> usize {
+        self.here.child_count()
+    }
+
+    fn at_root(&self) -> bool {
+        self.path.is_empty()
+    }
+
+    fn index_in_parent(&self) -> Option<usize> {
+        self.path.last().map(|&(_, index)| index)
+    }
+
+    fn to_parent(&mut self) -> bool {
+        match self.path.pop() {
+            Some((parent, _)) => {
+                self.here = parent;
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn to_root(&mut self) {
+        self.here = &self.tree.root;
+        self.path.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::Nav;
+    use ::lazy::Tree;
+
+    use std::cell::Cell;
+
+    // Loads a complete binary tree in which node `n` has children `2n + 1` and
+    // `2n + 2`, up to `limit`.
+    fn binary_loader<'a>(calls: &'a Cell<usize>, limit: usize)
+                         -> impl Fn(&usize) -> (usize, Vec<usize>) + 'a {
+        move |&n| {
+            calls.set(calls.get() + 1);
+            let children = vec![2 * n + 1, 2 * n + 2].into_iter()
+                .filter(|&c| c < limit)
+                .collect();
+            (n, children)
+        }
+    }
+
+    #[test]
+    fn loads_root_only() {
+        let calls = Cell::new(0);
+        let t = Tree::load(binary_loader(&calls, 15), 0, 0);
+        assert_eq![calls.get(), 1];
+        assert_eq![t.loaded_size(), 1];
+        let v = t.view();
+        assert_eq![*v, 0];
+        assert_eq![v.child_count(), 2];
+        assert![! v.children_loaded()];
+    }
+
+    #[test]
+    fn loads_eager_levels() {
+        let calls = Cell::new(0);
+        let t = Tree::load(binary_loader(&calls, 15), 0, 2);
+        assert_eq![calls.get(), 7];
+        assert_eq![t.loaded_size(), 7];
+    }
+
+    #[test]
+    fn navigation_loads_on_demand() {
+        let calls = Cell::new(0);
+        let t = Tree::load(binary_loader(&calls, 15), 0, 0);
+        let mut v = t.view();
+        assert![v.seek_child(1)];
+        assert_eq![*v, 2];
+        assert_eq![calls.get(), 3];
+        assert![v.seek_child(0)];
+        assert_eq![*v, 5];
+        assert_eq![calls.get(), 5];
+        assert![v.seek_sibling(1)];
+        assert_eq![*v, 6];
+        assert![v.seek_child(0)];
+        assert_eq![*v, 13];
+        assert![v.at_leaf()];
+        v.to_root();
+        assert![v.seek_child(1)];
+        assert_eq![calls.get(), 7];
+        assert_eq![t.loaded_size(), 7];
+    }
+
+    #[test]
+    fn seek_past_end_does_not_load() {
+        let calls = Cell::new(0);
+        let t = Tree::load(binary_loader(&calls, 3), 0, 0);
+        let mut v = t.view();
+        assert![! v.seek_child(2)];
+        assert_eq![calls.get(), 1];
+        assert![! v.seek_sibling(1)];
+    }
+
+    #[test]
+    fn views_compare_positions() {
+        let calls = Cell::new(0);
+        let t = Tree::load(binary_loader(&calls, 7), 0, 0);
+        let mut a = t.view();
+        let mut b = t.view();
+        assert![a.seek_child(0)];
+        assert![b.seek_child(1)];
+        assert![a < b];
+        assert![b.seek_sibling(-1)];
+        assert![a == b];
+        assert![a.seek_child(0)];
+        assert![b < a];
+        let other = Tree::load(binary_loader(&calls, 7), 0, 0);
+        assert_eq![None, other.view().partial_cmp(&t.view())];
+    }
+}
@@ -8,17 +8,122 @@
 //  - Shared-topology tree (data fixed).
 //  - Shared-data, shared-topology tree.
 
+#[cfg(feature = "ego-tree")]
+extern crate ego_tree;
+#[cfg(feature = "derive")]
+extern crate entmut_derive;
+#[cfg(feature = "indextree")]
+extern crate indextree;
+#[cfg(feature = "petgraph")]
+extern crate petgraph;
+#[cfg(feature = "dom")]
+extern crate quick_xml;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
+
+/// Data structures and algorithms built on the tree backends.
+pub mod algos;
+/// Structural analyses of trees.
+pub mod analysis;
+/// Trees stored in a single arena and addressed by node IDs.
+pub mod arena;
+/// Trees that cache an aggregate of each subtree.
+pub mod augmented;
+/// Programmatic construction of trees.
+pub mod builder;
+/// Navigation that remembers what it reads from costly views.
+pub mod cache;
+/// Tree edits described by value.
+pub mod command;
+/// Tests that editors of any backend should pass, exported as the
+/// `editor_tests!` macro.
+mod conformance;
+/// Compact encodings of trees as changes to other trees.
+pub mod delta;
+/// Tracking of the parts of a tree changed by edits.
+pub mod dirty;
+/// Documents of elements and text, edited as trees.
+#[cfg(feature = "dom")]
+pub mod dom;
+/// Reading trees from the Graphviz DOT language.
+pub mod dot;
+/// Errors returned by tree edits.
+pub mod error;
 /// Fixed-layout trees with good memory locality guarantees.
 pub mod fixed;
+/// Views that hide the children of collapsed nodes.
+pub mod fold;
+/// Randomized differential testing of the tree backends.
+#[cfg(feature = "fuzz")]
+pub mod fuzz;
+/// Editor wrapper that makes chosen subtrees read-only.
+pub mod freeze;
+/// Spanning trees of graphs.
+pub mod graph;
+/// Editor wrapper that checks each edit against a guard.
+pub mod guard;
+/// Values derived from trees and recomputed as they change.
+pub mod incremental;
+/// Checked arithmetic on the indices of children and siblings.
+pub mod index;
+/// Reading and writing trees as indented text.
+pub mod indented;
+/// Interning of string node data.
+pub mod intern;
+/// Conversions to and from the tree and graph types of other crates.
+pub mod interop;
+/// Persistent records of tree edits.
+pub mod journal;
+/// Trees whose subtrees are loaded on first navigation.
+pub mod lazy;
+/// Coordinates and pictures for drawing trees.
+pub mod layout;
+/// Trees whose children are kept in doubly-linked lists.
+pub mod linked;
+/// Conversion of trees of loosely typed data into typed trees.
+pub mod migrate;
+/// Navigation over values that hold their own children.
+pub mod node;
 /// Single-ownership trees wherein a parent owns its children.
 pub mod owned;
+/// Addresses of tree nodes.
+pub mod path;
+/// Editor wrapper that keeps children ordered by priority.
+pub mod priority;
+/// Instrumentation of tree navigation.
+pub mod profile;
+/// Error placeholder nodes in trees built with error recovery.
+pub mod recover;
+/// Recording of edits as scripts to replay on other trees.
+pub mod record;
+/// Rewriting of trees by pattern and template rules.
+pub mod rewrite;
+/// Declared node kinds and child patterns, for validating trees.
+pub mod schema;
+/// Incremental serialization of trees.
+pub mod serialize;
 /// Heap-allocated, reference-counted trees that can be shared freely.
 pub mod shared;
+/// Source spans of node data, and lookup of nodes by source position.
+pub mod span;
+/// Thread-safe trees guarded by reader-writer locks.
+pub mod sync;
+/// Trees with named holes to be filled by substitution.
+pub mod template;
 /// Tree traversal methods and interfaces.
 pub mod traversal;
 /// Internal utilities.
 mod util;
 
+use ::command::{ApplyError, EditCommand};
+use ::error::EditError;
+use ::path::{RelativePath, Step};
+use ::record::{EditScript, Recorder};
+
+use std::ops::{Deref, DerefMut};
+
 /// Navigable, focus-based view of a tree.
 ///
 /// This trait defines a view of a tree that is focused on a node and can be
@@ -48,6 +153,12 @@ mod util;
 /// of `std::clone::Clone` when this is possible. For mutable types that also
 /// implement `std::borrow::DerefMut`, which may require a read-write borrow of
 /// an underlying structure, this may not be possible.
+///
+/// The read-only views of the backends also implement `PartialEq` and
+/// `PartialOrd` by position: two views are equal iff they are over the same
+/// tree and focused on the same node, and views over the same tree are ordered
+/// by the pre-order position of their focus. Views over different trees, even
+/// equal ones, are unequal and unordered.
 pub trait Nav {
     /// Returns the number of children of the current node.
     fn child_count(&self) -> usize;
@@ -62,12 +173,35 @@ pub trait Nav {
     /// parent).
     fn at_root(&self) -> bool;
 
+    /// Returns the index of the current node among its siblings, or `None` if
+    /// the current node is the tree root.
+    fn index_in_parent(&self) -> Option<usize>;
+
     /// Navigates to the sibling at `offset`, for which negative values indicate
     /// navigating to the left of this node's location and positive value to the
     /// right. (An offset of 0 is a no-op.) Returns true iff `offset` resolves
     /// to an extant sibling.
     fn seek_sibling(&mut self, offset: isize) -> bool;
 
+    /// Navigates as far as possible toward the sibling at `offset`, stopping
+    /// at the first or last sibling if `offset` reaches past it. Returns the
+    /// part of `offset` that was not moved, which is 0 iff the sibling at
+    /// `offset` exists.
+    ///
+    /// The default implementation steps one sibling at a time once a direct
+    /// seek fails.
+    fn seek_sibling_clamped(&mut self, offset: isize) -> isize {
+        if self.seek_sibling(offset) {
+            return 0
+        }
+        let step = offset.signum();
+        let mut remaining = offset;
+        while remaining != 0 && self.seek_sibling(step) {
+            remaining -= step;
+        }
+        remaining
+    }
+
     // Navigates to the leftmost sibling. This is a no-op if the focus is
     // already at the leftmost sibling.
     fn seek_first_sibling(&mut self) {
@@ -89,10 +223,55 @@ pub trait Nav {
     /// resolves to an extant child.
     fn seek_child(&mut self, index: usize) -> bool;
 
+    /// Returns the number of pages of `page_size` children each needed to hold
+    /// the children of the current node. The last page may be partial.
+    ///
+    /// Panics if `page_size` is 0.
+    fn child_page_count(&self, page_size: usize) -> usize {
+        assert![page_size > 0, "page size must be positive"];
+        self.child_count().div_ceil(page_size)
+    }
+
+    /// Navigates to the child at `offset` within page `page` of the current
+    /// node's children, where each page holds `page_size` children. Returns
+    /// true iff `offset` is within the page and resolves to an extant child.
+    ///
+    /// Panics if `page_size` is 0.
+    fn seek_child_page(&mut self, page: usize, page_size: usize, offset: usize) -> bool {
+        assert![page_size > 0, "page size must be positive"];
+        if offset >= page_size {
+            return false
+        }
+        match page.checked_mul(page_size).and_then(|start| start.checked_add(offset)) {
+            Some(index) => self.seek_child(index),
+            None => false,
+        }
+    }
+
     /// Navigates to this node's parent. Returns true iff the focus changes
     /// (i.e., if `self` was not already pointing to the tree root).
     fn to_parent(&mut self) -> bool;
 
+    /// Follows each step of `path` in turn. Returns true iff every step
+    /// resolves to an extant node. If a step fails, the focus is left where
+    /// the steps before it led.
+    fn seek_relative(&mut self, path: &RelativePath) -> bool {
+        path.steps().iter().all(|step| match *step {
+            Step::Up => self.to_parent(),
+            Step::Child(index) => self.seek_child(index),
+            Step::Sibling(offset) => self.seek_sibling(offset),
+        })
+    }
+
+    /// Copies the focus with its ancestors and with its descendants down to
+    /// `depth_limit` levels below it, as `builder::window` does. The copy
+    /// borrows nothing from this tree, so it can be kept while the tree
+    /// changes.
+    fn window(&self, depth_limit: usize) -> owned::Tree<<Self as Deref>::Target>
+        where Self: Sized + Clone + Deref, <Self as Deref>::Target: Clone + Sized {
+            builder::window(self, depth_limit)
+        }
+
     /// Navigates to the tree's root. If this navigator is already pointing at
     /// the tree root, this is a no-op.
     ///
@@ -103,6 +282,56 @@ pub trait Nav {
             self.to_parent();
         }
     }
+
+    /// Navigates to the first leaf at or below the current node, following
+    /// each node's first child. This is a no-op if the current node is a leaf.
+    ///
+    /// The default implementation repeatedly calls `seek_child`.
+    fn to_leftmost_leaf(&mut self) {
+        while self.seek_child(0) {}
+    }
+
+    /// Navigates to the last leaf at or below the current node, following each
+    /// node's last child. This is a no-op if the current node is a leaf.
+    ///
+    /// The default implementation repeatedly calls `seek_child`.
+    fn to_rightmost_leaf(&mut self) {
+        while let Some(last) = self.child_count().checked_sub(1) {
+            self.seek_child(last);
+        }
+    }
+
+    /// Navigates to the node after the current one in pre-order, which is its
+    /// first child if it has one, or else the next sibling of the nearest of
+    /// it and its ancestors to have one. Returns false, leaving the focus
+    /// unchanged, iff the current node is the last in the tree.
+    fn to_next_in_document_order(&mut self) -> bool {
+        if self.seek_child(0) {
+            return true
+        }
+        while ! self.seek_sibling(1) {
+            if ! self.to_parent() {
+                // Only the tree's last node has no successor, and it is the
+                // root's rightmost leaf.
+                self.to_rightmost_leaf();
+                return false
+            }
+        }
+        true
+    }
+
+    /// Navigates to the node before the current one in pre-order, which is the
+    /// rightmost leaf under its previous sibling if it has one, or else its
+    /// parent. Returns false, leaving the focus unchanged, iff the current node
+    /// is the root.
+    fn to_prev_in_document_order(&mut self) -> bool {
+        if self.seek_sibling(-1) {
+            self.to_rightmost_leaf();
+            true
+        } else {
+            self.to_parent()
+        }
+    }
 }
 
 /// Navigable view of a tree, with support for modifying the tree's topology.
@@ -146,20 +375,74 @@ pub trait Editor: Nav {
     fn insert_sibling(
         &mut self, offset: isize, sibling: <Self as Editor>::Tree) -> bool;
 
-    /// Removes the focus node and returns the subtree rooted at it. Focus
-    /// changes to (in order of preference) the focus's left sibling, its right
-    /// sibling (if there is no left sibling), or its parent (if there are no
-    /// siblings).
+    /// Returns the policy that determines where focus moves after `remove`.
+    ///
+    /// The default implementation returns `RemoveFocusPolicy::default()`.
+    fn remove_focus_policy(&self) -> RemoveFocusPolicy {
+        RemoveFocusPolicy::default()
+    }
+
+    /// Sets the policy that determines where focus moves after `remove`.
+    /// Returns `false`, leaving the policy unchanged, if the editor does not
+    /// support `policy`.
+    ///
+    /// The default implementation supports only the default policy.
+    fn set_remove_focus_policy(&mut self, policy: RemoveFocusPolicy) -> bool {
+        policy == RemoveFocusPolicy::default()
+    }
+
+    /// Removes the focus node and returns the subtree rooted at it. Focus moves
+    /// to a sibling or the parent of the removed node, as determined by the
+    /// editor's [RemoveFocusPolicy](enum.RemoveFocusPolicy.html). Panics if
+    /// the focus is the root.
     fn remove(&mut self) -> <Self as Editor>::Tree;
 
-    /// Removes the child at the given index and returns the subtree rooted at
-    /// it.
-    fn remove_child(&mut self, index: usize) -> Option<<Self as Editor>::Tree>;
+    /// Removes the child of the focus at the given index and returns the
+    /// subtree rooted at it. Focus does not move. Fails, leaving the tree
+    /// unchanged, if there is no child at the index.
+    fn remove_child(&mut self, index: usize) -> Result<<Self as Editor>::Tree, EditError>;
+
+    /// Replaces the child of the focus at the given index with `tree` and
+    /// returns the subtree rooted at the child that was there. Focus does not
+    /// move, and the other children keep their indices. Panics if there is no
+    /// child at the index.
+    ///
+    /// The default implementation focuses on the child, swaps `tree` into its
+    /// place, and returns to the parent.
+    fn replace_child(&mut self, index: usize, mut tree: <Self as Editor>::Tree)
+                     -> <Self as Editor>::Tree {
+        let child_count = self.child_count();
+        if !self.seek_child(index) {
+            panic!["{}", EditError::ChildOutOfRange { index, child_count, }]
+        }
+        self.swap(&mut tree);
+        self.to_parent();
+        tree
+    }
 
     /// Removes the sibling at the given offset and returns the subtree rooted
-    /// at it.
+    /// at it. Focus stays on the same node, so its index among its siblings
+    /// decreases by one if the removed sibling preceded it. An offset of 0
+    /// removes the focus, as `remove` does. Returns `None`, leaving the tree
+    /// unchanged, if there is no sibling at the offset or the focus is the
+    /// root, whatever the offset.
     fn remove_sibling(&mut self, offset: isize) -> Option<<Self as Editor>::Tree>;
 
+    /// Removes all children of the focus and returns them in order. Focus does
+    /// not move.
+    ///
+    /// The default implementation removes the children one at a time, from
+    /// last to first. Implementors that can detach a child list at once may
+    /// wish to provide a more efficient method.
+    fn take_children(&mut self) -> Vec<<Self as Editor>::Tree> {
+        let mut children = Vec::with_capacity(self.child_count());
+        while let Some(last) = self.child_count().checked_sub(1) {
+            children.push(self.remove_child(last).expect("child index in bounds"));
+        }
+        children.reverse();
+        children
+    }
+
     /// Swaps the focus node and `other`.
     fn swap(&mut self, other: &mut <Self as Editor>::Tree);
 
@@ -172,6 +455,98 @@ pub trait Editor: Nav {
     /// this is a no-op. If either offset is 0 (corresponding to the focus),
     /// focus follows it after the swap.
     fn swap_siblings(&mut self, offset_a: isize, offset_b: isize) -> bool;
+
+    /// Reorders the focus's children so that the child at `i` afterward is
+    /// the one that was at `perm[i]`. Fails, leaving the tree unchanged, if
+    /// `perm` does not hold each child's index exactly once.
+    ///
+    /// The default implementation follows each cycle of `perm` with
+    /// `swap_children`, making fewer swaps than there are children.
+    fn permute_children(&mut self, perm: &[usize]) -> Result<(), EditError> {
+        util::check_permutation(perm, self.child_count())?;
+        let mut placed = vec![false; perm.len()];
+        for start in 0..perm.len() {
+            let mut i = start;
+            while !placed[i] {
+                placed[i] = true;
+                if perm[i] != start {
+                    self.swap_children(i, perm[i]);
+                }
+                i = perm[i];
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs `f` on this editor as a single batch of edits and returns its
+    /// result.
+    ///
+    /// Editors that maintain invariants beyond the tree's structure, such as
+    /// cached aggregates, may defer that work until `f` returns and then do it
+    /// in one pass, so a run of edits costs no more than rebuilding once.
+    /// Within the batch those invariants may not hold. The default
+    /// implementation just calls `f`.
+    fn batch<R, F>(&mut self, f: F) -> R
+        where Self: Sized, F: FnOnce(&mut Self) -> R {
+            f(self)
+        }
+
+    /// Applies each of `commands` in turn, or none of them, as
+    /// `command::apply_all` does.
+    fn apply_all(&mut self, commands: &[EditCommand<<Self as Editor>::Data>])
+                 -> Result<(), (usize, ApplyError)>
+        where Self: Sized + DerefMut<Target=<Self as Editor>::Data>,
+              <Self as Editor>::Data: Clone {
+            command::apply_all(self, commands)
+        }
+
+    /// Runs `f` on a `Recorder` wrapping this editor, and returns the edits it
+    /// makes as a script that can be replayed on other trees. The script
+    /// addresses nodes relative to the focus when recording starts; see
+    /// `record::Recorder` for which edits can be recorded.
+    fn record<F>(&mut self, f: F) -> EditScript<<Self as Editor>::Data>
+        where Self: Sized + DerefMut<Target=<Self as Editor>::Data>,
+              <Self as Editor>::Data: Clone, F: FnOnce(&mut Recorder<Self>) {
+            let mut recorder = Recorder::new(self);
+            f(&mut recorder);
+            recorder.finish()
+        }
+}
+
+/// Where an [Editor](trait.Editor.html) moves focus after removing the focus
+/// node.
+///
+/// Each policy falls back to the parent of the removed node when it has no
+/// remaining siblings. The default is `PreferLeft`.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum RemoveFocusPolicy {
+    /// Focus the left sibling of the removed node, or its right sibling if it
+    /// was the leftmost child.
+    #[default]
+    PreferLeft,
+    /// Focus the right sibling of the removed node, or its left sibling if it
+    /// was the rightmost child.
+    PreferRight,
+    /// Focus the parent of the removed node.
+    Parent,
+}
+
+impl RemoveFocusPolicy {
+    /// Returns the index of the sibling to focus after removing the child at
+    /// `removed_index`, leaving `remaining` children. Returns `None` if focus
+    /// should move to the parent.
+    pub fn focus_after_remove(self, removed_index: usize, remaining: usize) -> Option<usize> {
+        if remaining == 0 {
+            return None
+        }
+        match self {
+            RemoveFocusPolicy::PreferLeft =>
+                Some(removed_index.saturating_sub(1)),
+            RemoveFocusPolicy::PreferRight =>
+                Some(if removed_index < remaining { removed_index } else { remaining - 1 }),
+            RemoveFocusPolicy::Parent => None,
+        }
+    }
 }
 
 // #[cfg(test)]
@@ -0,0 +1,806 @@
+use ::{Editor, Nav, RemoveFocusPolicy};
+use ::error::{EditError, NavError};
+use ::owned;
+use ::index::{ChildIndex, SiblingIndex};
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::ptr;
+
+#[derive(Clone)]
+struct Node<T> {
+    // `None` for a free slot.
+    data: Option<T>,
+    parent: Option<usize>,
+    prev: Option<usize>,
+    next: Option<usize>,
+    first_child: Option<usize>,
+    last_child: Option<usize>,
+    child_count: usize,
+}
+
+impl<T> Node<T> {
+    fn new(data: T) -> Self {
+        Node { data: Some(data), parent: None, prev: None, next: None,
+               first_child: None, last_child: None, child_count: 0, }
+    }
+}
+
+/// Tree whose children are kept in doubly-linked lists.
+///
+/// Nodes are stored together in one vector and linked to their parents and
+/// siblings by index. A `TreeViewMut` can therefore insert or remove a node
+/// next to its focus in constant time, where the vector-backed trees shift
+/// every later sibling, which suits heavy editing in the middle of long child
+/// lists. In exchange, seeking a child by index or a sibling by offset walks
+/// the list (from whichever end is closer, for children).
+///
+/// Subtrees inserted into or removed from a tree are moved between vectors one
+/// node at a time. Slots freed by removals are reused by later insertions.
+#[derive(Clone)]
+pub struct Tree<T> {
+    // The root is always at index 0.
+    nodes: Vec<Node<T>>,
+    free: Vec<usize>,
+}
+
+impl<T> Tree<T> {
+    pub fn new(data: T, children: Vec<Tree<T>>) -> Self {
+        let mut tree = Tree::leaf(data);
+        for child in children {
+            tree.attach(0, None, child);
+        }
+        tree
+    }
+
+    pub fn leaf(data: T) -> Self {
+        Tree { nodes: vec![Node::new(data)], free: Vec::new(), }
+    }
+
+    pub fn data(&self) -> &T {
+        self.data_at(0)
+    }
+
+    pub fn data_mut(&mut self) -> &mut T {
+        self.data_at_mut(0)
+    }
+
+    /// Returns the number of children of the root.
+    pub fn child_count(&self) -> usize {
+        self.nodes[0].child_count
+    }
+
+    /// Returns the number of nodes in the tree.
+    pub fn size(&self) -> usize {
+        self.nodes.len() - self.free.len()
+    }
+
+    pub fn view(&self) -> TreeView<'_, T> {
+        TreeView { tree: self, here: 0, path: Vec::new(), }
+    }
+
+    pub fn view_mut(&mut self) -> TreeViewMut<'_, T> {
+        TreeViewMut { tree: self, here: 0, path: Vec::new(),
+                      remove_focus_policy: RemoveFocusPolicy::default(), }
+    }
+
+    /// Converts this into an owned tree.
+    pub fn into_owned(mut self) -> owned::Tree<T> {
+        let root = self.take_data(0);
+        let mut stack = vec![(root, self.nodes[0].first_child, Vec::new())];
+        loop {
+            let next = stack.last().unwrap().1;
+            match next {
+                Some(id) => {
+                    stack.last_mut().unwrap().1 = self.nodes[id].next;
+                    let data = self.take_data(id);
+                    stack.push((data, self.nodes[id].first_child, Vec::new()));
+                },
+                None => {
+                    let (data, _, children) = stack.pop().unwrap();
+                    let tree = owned::Tree::new(data, children);
+                    match stack.last_mut() {
+                        Some(parent) => parent.2.push(tree),
+                        None => return tree,
+                    }
+                },
+            }
+        }
+    }
+
+    fn data_at(&self, id: usize) -> &T {
+        self.nodes[id].data.as_ref().unwrap()
+    }
+
+    fn data_at_mut(&mut self, id: usize) -> &mut T {
+        self.nodes[id].data.as_mut().unwrap()
+    }
+
+    fn take_data(&mut self, id: usize) -> T {
+        self.nodes[id].data.take().unwrap()
+    }
+
+    fn alloc(&mut self, data: T) -> usize {
+        match self.free.pop() {
+            Some(id) => {
+                self.nodes[id] = Node::new(data);
+                id
+            },
+            None => {
+                self.nodes.push(Node::new(data));
+                self.nodes.len() - 1
+            },
+        }
+    }
+
+    fn release(&mut self, id: usize) {
+        self.nodes[id].data = None;
+        self.free.push(id);
+    }
+
+    // Links the unlinked node `id` into the children of `parent`, before
+    // `before` or at the end if it is `None`.
+    fn link(&mut self, parent: usize, before: Option<usize>, id: usize) {
+        let prev = match before {
+            Some(before) => self.nodes[before].prev,
+            None => self.nodes[parent].last_child,
+        };
+        {
+            let node = &mut self.nodes[id];
+            node.parent = Some(parent);
+            node.prev = prev;
+            node.next = before;
+        }
+        match prev {
+            Some(prev) => self.nodes[prev].next = Some(id),
+            None => self.nodes[parent].first_child = Some(id),
+        }
+        match before {
+            Some(before) => self.nodes[before].prev = Some(id),
+            None => self.nodes[parent].last_child = Some(id),
+        }
+        self.nodes[parent].child_count += 1;
+    }
+
+    // Unlinks the nonroot node `id` from its parent's children.
+    fn unlink(&mut self, id: usize) {
+        let (parent, prev, next) = {
+            let node = &mut self.nodes[id];
+            let links = (node.parent.unwrap(), node.prev, node.next);
+            node.parent = None;
+            node.prev = None;
+            node.next = None;
+            links
+        };
+        match prev {
+            Some(prev) => self.nodes[prev].next = next,
+            None => self.nodes[parent].first_child = next,
+        }
+        match next {
+            Some(next) => self.nodes[next].prev = prev,
+            None => self.nodes[parent].last_child = prev,
+        }
+        self.nodes[parent].child_count -= 1;
+    }
+
+    // Moves the nodes of `tree` into this tree, linking its root into the
+    // children of `parent` before `before`. Returns the new ID of its root.
+    fn attach(&mut self, parent: usize, before: Option<usize>, mut tree: Tree<T>) -> usize {
+        let root = self.alloc(tree.take_data(0));
+        self.link(parent, before, root);
+        let mut stack = vec![(0, root)];
+        while let Some((from, to)) = stack.pop() {
+            let mut child = tree.nodes[from].first_child;
+            while let Some(id) = child {
+                let new_id = self.alloc(tree.take_data(id));
+                self.link(to, None, new_id);
+                stack.push((id, new_id));
+                child = tree.nodes[id].next;
+            }
+        }
+        root
+    }
+
+    // Unlinks the nonroot node `id` and moves its subtree out into a new tree.
+    fn detach(&mut self, id: usize) -> Tree<T> {
+        self.unlink(id);
+        let mut tree = Tree::leaf(self.take_data(id));
+        let mut stack = vec![(id, 0)];
+        while let Some((from, to)) = stack.pop() {
+            let mut child = self.nodes[from].first_child;
+            while let Some(child_id) = child {
+                let new_id = tree.alloc(self.take_data(child_id));
+                tree.link(to, None, new_id);
+                stack.push((child_id, new_id));
+                child = self.nodes[child_id].next;
+            }
+            self.release(from);
+        }
+        tree
+    }
+
+    // Returns the child of `parent` at `index`, which must be in range.
+    fn nth_child(&self, parent: usize, index: usize) -> usize {
+        let node = &self.nodes[parent];
+        if index < node.child_count / 2 {
+            self.walk(node.first_child.unwrap(), index as isize)
+        } else {
+            self.walk(node.last_child.unwrap(), index as isize - (node.child_count as isize - 1))
+        }
+    }
+
+    // Returns the sibling `offset` positions from `id`, which must exist.
+    fn walk(&self, mut id: usize, offset: isize) -> usize {
+        for _ in 0..offset.unsigned_abs() {
+            id = if offset < 0 { self.nodes[id].prev } else { self.nodes[id].next }.unwrap();
+        }
+        id
+    }
+
+    // Iterates over the IDs of the children of `parent`.
+    fn child_ids(&self, parent: usize) -> ChildIds<'_, T> {
+        ChildIds { tree: self, next: self.nodes[parent].first_child, }
+    }
+}
+
+struct ChildIds<'a, T: 'a> {
+    tree: &'a Tree<T>,
+    next: Option<usize>,
+}
+
+impl<'a, T: 'a> Iterator for ChildIds<'a, T> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let id = self.next?;
+        self.next = self.tree.nodes[id].next;
+        Some(id)
+    }
+}
+
+impl<T> From<owned::Tree<T>> for Tree<T> {
+    fn from(tree: owned::Tree<T>) -> Self {
+        let (data, children) = tree.into_parts();
+        let mut linked = Tree::leaf(data);
+        let mut stack = vec![(0, children.into_iter())];
+        while let Some(&mut (parent, ref mut children)) = stack.last_mut() {
+            match children.next() {
+                Some(child) => {
+                    let (data, grandchildren) = child.into_parts();
+                    let id = linked.alloc(data);
+                    linked.link(parent, None, id);
+                    stack.push((id, grandchildren.into_iter()));
+                },
+                None => {
+                    stack.pop();
+                },
+            }
+        }
+        linked
+    }
+}
+
+impl<T: PartialEq> PartialEq<Tree<T>> for Tree<T> {
+    fn eq(&self, other: &Tree<T>) -> bool {
+        let mut stack = vec![(0, 0)];
+        while let Some((x, y)) = stack.pop() {
+            if self.data_at(x) != other.data_at(y)
+                || self.nodes[x].child_count != other.nodes[y].child_count {
+                    return false
+                }
+            stack.extend(self.child_ids(x).zip(other.child_ids(y)));
+        }
+        true
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Tree<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        // Each stack entry is a node ID and whether the node is being entered
+        // (as opposed to left).
+        let mut stack = vec![(0, true)];
+        let mut first = true;
+        while let Some((id, entering)) = stack.pop() {
+            if ! entering {
+                f.write_str(")")?;
+                continue
+            }
+            f.write_str(if first { "(" } else { " (" })?;
+            first = false;
+            self.data_at(id).fmt(f)?;
+            stack.push((id, false));
+            let children: Vec<usize> = self.child_ids(id).collect();
+            stack.extend(children.into_iter().rev().map(|child| (child, true)));
+        }
+        Ok(())
+    }
+}
+
+// Navigation shared by both views. `path` holds the index of each node from
+// the root's children to the focus among its siblings.
+fn seek_sibling<T>(tree: &Tree<T>, here: &mut usize, path: &mut [usize], offset: isize) -> bool {
+    if offset == 0 {
+        return true
+    }
+    let here_index = match path.last_mut() {
+        None => return false,
+        Some(here_index) => here_index,
+    };
+    let sibling_count = tree.nodes[tree.nodes[*here].parent.unwrap()].child_count;
+    match SiblingIndex::compute(sibling_count, *here_index, offset) {
+        Some(new_index) => {
+            *here = tree.walk(*here, offset);
+            *here_index = new_index;
+            true
+        },
+        None => false,
+    }
+}
+
+fn seek_child<T>(tree: &Tree<T>, here: &mut usize, path: &mut Vec<usize>, index: usize) -> bool {
+    match ChildIndex::compute(tree.nodes[*here].child_count, index) {
+        Some(new_index) => {
+            *here = tree.nth_child(*here, new_index);
+            path.push(new_index);
+            true
+        },
+        None => false,
+    }
+}
+
+fn to_parent<T>(tree: &Tree<T>, here: &mut usize, path: &mut Vec<usize>) -> bool {
+    match path.pop() {
+        Some(_) => {
+            *here = tree.nodes[*here].parent.unwrap();
+            true
+        },
+        None => false,
+    }
+}
+
+/// Read-only view of a linked tree.
+pub struct TreeView<'a, T: 'a> {
+    tree: &'a Tree<T>,
+    here: usize,
+    path: Vec<usize>,
+}
+
+impl<'a, T: 'a> TreeView<'a, T> {
+    /// Returns the data at the focus, borrowed for as long as the tree is.
+    pub fn data(&self) -> &'a T {
+        self.tree.data_at(self.here)
+    }
+}
+
+impl<'a, T: 'a> Clone for TreeView<'a, T> {
+    fn clone(&self) -> Self {
+        TreeView { tree: self.tree, here: self.here, path: self.path.clone(), }
+    }
+}
+
+impl<'a, T: 'a> PartialEq for TreeView<'a, T> {
+    fn eq(&self, other: &Self) -> bool {
+        ptr::eq(self.tree, other.tree)
+            && self.path.iter().eq(other.path.iter())
+    }
+}
+
+impl<'a, T: 'a> PartialOrd for TreeView<'a, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if ptr::eq(self.tree, other.tree) {
+            Some(self.path.iter().cmp(other.path.iter()))
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, T: 'a> Deref for TreeView<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.tree.data_at(self.here)
+    }
+}
+
+impl<'a, T: 'a> Nav for TreeView<'a, T> {
+    fn seek_sibling(&mut self, offset: isize) -> bool {
+        seek_sibling(self.tree, &mut self.here, &mut self.path, offset)
+    }
+
+    fn seek_child(&mut self, index: usize) -> bool {
+        seek_child(self.tree, &mut self.here, &mut self.path, index)
+    }
+
+    fn child_count(&self) -> usize {
+        self.tree.nodes[self.here].child_count
+    }
+
+    fn at_root(&self) -> bool {
+        self.path.is_empty()
+    }
+
+    fn index_in_parent(&self) -> Option<usize> {
+        self.path.last().cloned()
+    }
+
+    fn to_parent(&mut self) -> bool {
+        to_parent(self.tree, &mut self.here, &mut self.path)
+    }
+
+    fn to_root(&mut self) {
+        self.here = 0;
+        self.path.clear();
+    }
+}
+
+/// Mutable view of a linked tree.
+///
+/// Inserting or removing the focus or a sibling at offset -1, 0, or 1 takes
+/// constant time, apart from moving the nodes of the subtree inserted or
+/// removed.
+pub struct TreeViewMut<'a, T: 'a> {
+    tree: &'a mut Tree<T>,
+    here: usize,
+    path: Vec<usize>,
+    remove_focus_policy: RemoveFocusPolicy,
+}
+
+impl<'a, T: 'a> TreeViewMut<'a, T> {
+    fn sibling_count(&self) -> usize {
+        self.tree.nodes[self.tree.nodes[self.here].parent.unwrap()].child_count
+    }
+}
+
+impl<'a, T: 'a> Deref for TreeViewMut<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.tree.data_at(self.here)
+    }
+}
+
+impl<'a, T: 'a> DerefMut for TreeViewMut<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.tree.data_at_mut(self.here)
+    }
+}
+
+impl<'a, T: 'a> Nav for TreeViewMut<'a, T> {
+    fn seek_sibling(&mut self, offset: isize) -> bool {
+        seek_sibling(self.tree, &mut self.here, &mut self.path, offset)
+    }
+
+    fn seek_child(&mut self, index: usize) -> bool {
+        seek_child(self.tree, &mut self.here, &mut self.path, index)
+    }
+
+    fn child_count(&self) -> usize {
+        self.tree.nodes[self.here].child_count
+    }
+
+    fn at_root(&self) -> bool {
+        self.path.is_empty()
+    }
+
+    fn index_in_parent(&self) -> Option<usize> {
+        self.path.last().cloned()
+    }
+
+    fn to_parent(&mut self) -> bool {
+        to_parent(self.tree, &mut self.here, &mut self.path)
+    }
+
+    fn to_root(&mut self) {
+        self.here = 0;
+        self.path.clear();
+    }
+}
+
+impl<'a, T: 'a> Editor for TreeViewMut<'a, T> {
+    type Data = T;
+    type Tree = Tree<T>;
+
+    fn push_leaf(&mut self, data: T) {
+        self.push_child(Tree::leaf(data));
+    }
+
+    fn push_child(&mut self, child: Tree<T>) {
+        let index = self.child_count();
+        self.here = self.tree.attach(self.here, None, child);
+        self.path.push(index);
+    }
+
+    fn insert_leaf(&mut self, index: usize, data: T) -> bool {
+        self.insert_child(index, Tree::leaf(data))
+    }
+
+    fn insert_child(&mut self, index: usize, child: Tree<T>) -> bool {
+        let child_count = self.child_count();
+        match ChildIndex::compute(child_count, index) {
+            Some(new_index) => {
+                let before = self.tree.nth_child(self.here, new_index);
+                self.here = self.tree.attach(self.here, Some(before), child);
+                self.path.push(new_index);
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn insert_sibling_leaf(&mut self, offset: isize, data: T) -> bool {
+        self.insert_sibling(offset, Tree::leaf(data))
+    }
+
+    fn insert_sibling(&mut self, offset: isize, sibling: Tree<T>) -> bool {
+        let here_index = match self.path.last() {
+            None => return false,
+            Some(&here_index) => here_index,
+        };
+        match SiblingIndex::compute(self.sibling_count(), here_index, offset) {
+            Some(new_index) => {
+                let parent = self.tree.nodes[self.here].parent.unwrap();
+                let before = self.tree.walk(self.here, offset);
+                self.here = self.tree.attach(parent, Some(before), sibling);
+                *self.path.last_mut().unwrap() = new_index;
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn remove_focus_policy(&self) -> RemoveFocusPolicy {
+        self.remove_focus_policy
+    }
+
+    fn set_remove_focus_policy(&mut self, policy: RemoveFocusPolicy) -> bool {
+        self.remove_focus_policy = policy;
+        true
+    }
+
+    fn remove(&mut self) -> Tree<T> {
+        let here_index = *self.path.last().unwrap_or_else(|| panic!["{}", NavError::AtRoot]);
+        let remaining = self.sibling_count() - 1;
+        let (parent, prev, next) = {
+            let node = &self.tree.nodes[self.here];
+            (node.parent.unwrap(), node.prev, node.next)
+        };
+        let removed = self.tree.detach(self.here);
+        match self.remove_focus_policy.focus_after_remove(here_index, remaining) {
+            Some(new_index) => {
+                self.here = if new_index < here_index {
+                    self.tree.walk(prev.unwrap(), new_index as isize + 1 - here_index as isize)
+                } else {
+                    self.tree.walk(next.unwrap(), (new_index - here_index) as isize)
+                };
+                *self.path.last_mut().unwrap() = new_index;
+            },
+            None => {
+                self.here = parent;
+                self.path.pop();
+            },
+        }
+        removed
+    }
+
+    fn remove_child(&mut self, index: usize) -> Result<Tree<T>, EditError> {
+        let index = ChildIndex::check(self.child_count(), index)?;
+        let child = self.tree.nth_child(self.here, index);
+        Ok(self.tree.detach(child))
+    }
+
+    fn remove_sibling(&mut self, offset: isize) -> Option<Tree<T>> {
+        if self.at_root() {
+            return None
+        }
+        if offset == 0 {
+            return Some(self.remove())
+        }
+        let here_index = *self.path.last()?;
+        SiblingIndex::compute(self.sibling_count(), here_index, offset).map(|index| {
+            let sibling = self.tree.walk(self.here, offset);
+            if index < here_index {
+                *self.path.last_mut().unwrap() = here_index - 1;
+            }
+            self.tree.detach(sibling)
+        })
+    }
+
+    fn swap(&mut self, other: &mut Tree<T>) {
+        mem::swap(self.tree.data_at_mut(self.here), other.data_mut());
+        let here = self.here;
+        let old_children: Vec<usize> = self.tree.child_ids(here).collect();
+        let new_children: Vec<usize> = other.child_ids(0).collect();
+        for child in new_children {
+            let child = other.detach(child);
+            self.tree.attach(here, None, child);
+        }
+        for child in old_children {
+            let child = self.tree.detach(child);
+            other.attach(0, None, child);
+        }
+    }
+
+    fn swap_children(&mut self, index_a: usize, index_b: usize) -> bool {
+        let child_count = self.child_count();
+        match (ChildIndex::compute(child_count, index_a),
+               ChildIndex::compute(child_count, index_b)) {
+            (Some(index_a), Some(index_b)) => {
+                let a = self.tree.nth_child(self.here, index_a);
+                let b = self.tree.nth_child(self.here, index_b);
+                swap_links(self.tree, a, b);
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn swap_siblings(&mut self, offset_a: isize, offset_b: isize) -> bool {
+        let here_index = match self.path.last() {
+            None => return false,
+            Some(&here_index) => here_index,
+        };
+        let sibling_count = self.sibling_count();
+        match (SiblingIndex::compute(sibling_count, here_index, offset_a),
+               SiblingIndex::compute(sibling_count, here_index, offset_b)) {
+            (Some(index_a), Some(index_b)) => {
+                let a = self.tree.walk(self.here, offset_a);
+                let b = self.tree.walk(self.here, offset_b);
+                swap_links(self.tree, a, b);
+                if here_index == index_a {
+                    *self.path.last_mut().unwrap() = index_b;
+                } else if here_index == index_b {
+                    *self.path.last_mut().unwrap() = index_a;
+                }
+                true
+            },
+            _ => false,
+        }
+    }
+}
+
+// Exchanges the positions of the siblings `a` and `b`.
+fn swap_links<T>(tree: &mut Tree<T>, a: usize, b: usize) {
+    if a == b {
+        return
+    }
+    // Order the pair so that `x` comes first.
+    let b_follows_a = {
+        let mut next = tree.nodes[a].next;
+        loop {
+            match next {
+                Some(id) if id == b => break true,
+                Some(id) => next = tree.nodes[id].next,
+                None => break false,
+            }
+        }
+    };
+    let (x, y) = if b_follows_a { (a, b) } else { (b, a) };
+    let parent = tree.nodes[x].parent.unwrap();
+    if tree.nodes[x].next == Some(y) {
+        tree.unlink(y);
+        tree.link(parent, Some(x), y);
+    } else {
+        let y_prev = tree.nodes[y].prev.unwrap();
+        let x_next = tree.nodes[x].next.unwrap();
+        tree.unlink(x);
+        let after_y_prev = tree.nodes[y_prev].next;
+        tree.link(parent, after_y_prev, x);
+        tree.unlink(y);
+        tree.link(parent, Some(x_next), y);
+    }
+}
+
+#[macro_export]
+macro_rules! linked_tree {
+    ($($t:tt)*) => ($crate::linked::Tree::from($crate::owned_tree![$($t)*]));
+}
+
+#[cfg(test)]
+mod test {
+    use ::{Editor, Nav, RemoveFocusPolicy};
+    use ::linked::Tree;
+    use ::owned_tree;
+
+    #[test]
+    fn converts_owned_trees() {
+        let owned = owned_tree![1, [2, [3], [4]], [5, [6, [7]]]];
+        let t = Tree::from(owned.clone());
+        assert_eq![7, t.size()];
+        assert_eq![2, t.child_count()];
+        assert_eq!["(1 (2 (3) (4)) (5 (6 (7))))", format!["{:?}", t]];
+        assert_eq![t, linked_tree![1, [2, [3], [4]], [5, [6, [7]]]]];
+        assert![t != linked_tree![1, [2, [3], [4]], [5, [6]]]];
+        assert_eq![owned, t.into_owned()];
+    }
+
+    #[test]
+    fn navigates() {
+        let t = linked_tree!["r", ["a"], ["b", ["c"], ["d"], ["e"]], ["f"]];
+        let mut v = t.view();
+        assert![v.seek_child(1)];
+        assert![v.seek_child(2)];
+        assert_eq!["e", *v];
+        assert![v.seek_sibling(-2)];
+        assert_eq!["c", *v];
+        assert![! v.seek_sibling(-1)];
+        assert![! v.seek_sibling(3)];
+        assert![v.to_parent()];
+        assert![v.seek_sibling(1)];
+        assert_eq!["f", *v];
+        assert![! v.seek_child(0)];
+        v.to_root();
+        assert![v.at_root()];
+        assert![! v.seek_sibling(1)];
+    }
+
+    #[test]
+    fn inserts_next_to_focus() {
+        let mut t = linked_tree!["r", ["a"], ["c"]];
+        {
+            let mut e = t.view_mut();
+            assert![e.seek_child(1)];
+            assert![e.insert_sibling_leaf(0, "b")];
+            assert_eq!["b", *e];
+            assert![e.insert_sibling_leaf(1, "b2")];
+            assert![e.seek_sibling(1)];
+            assert_eq!["c", *e];
+            e.to_root();
+            assert![e.insert_child(3, linked_tree!["x", ["y"]])];
+            assert_eq!["x", *e];
+            assert![e.to_parent()];
+            e.push_leaf("z");
+            assert![! e.insert_leaf(6, "w")];
+            *e = "Z";
+        }
+        assert_eq![t, linked_tree!["r", ["a"], ["b"], ["b2"], ["x", ["y"]], ["c"], ["Z"]]];
+    }
+
+    #[test]
+    fn removes_and_reuses_slots() {
+        for &(policy, expected) in [(RemoveFocusPolicy::PreferLeft, "a"),
+                                    (RemoveFocusPolicy::PreferRight, "c"),
+                                    (RemoveFocusPolicy::Parent, "r")].iter() {
+            let mut t = linked_tree!["r", ["a"], ["b", ["x"], ["y"]], ["c"]];
+            {
+                let mut e = t.view_mut();
+                assert![e.set_remove_focus_policy(policy)];
+                assert![e.seek_child(1)];
+                assert_eq![linked_tree!["b", ["x"], ["y"]], e.remove()];
+                assert_eq![expected, *e];
+            }
+            assert_eq![3, t.size()];
+            let mut e = t.view_mut();
+            e.push_child(linked_tree!["d", ["e"]]);
+            e.to_root();
+            assert_eq![linked_tree!["a"], e.remove_child(0).unwrap()];
+            assert![e.remove_child(2).is_err()];
+            assert![e.seek_child(1)];
+            assert_eq![Some(linked_tree!["c"]), e.remove_sibling(-1)];
+            assert_eq!["d", *e];
+            assert![e.to_parent()];
+            assert_eq![1, e.child_count()];
+        }
+    }
+
+    #[test]
+    fn swaps() {
+        let mut t = linked_tree!["r", ["a"], ["b"], ["c"], ["d"]];
+        {
+            let mut e = t.view_mut();
+            assert![e.swap_children(0, 3)];
+            assert![e.swap_children(1, 2)];
+            assert![e.seek_child(0)];
+            assert![e.swap_siblings(0, 1)];
+            assert_eq!["d", *e];
+            assert![e.seek_sibling(-1)];
+            assert_eq!["c", *e];
+            let mut other = linked_tree!["x", ["y"], ["z"]];
+            e.swap(&mut other);
+            assert_eq![linked_tree!["c"], other];
+        }
+        assert_eq![t, linked_tree!["r", ["x", ["y"], ["z"]], ["d"], ["b"], ["a"]]];
+    }
+}
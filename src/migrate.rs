@@ -0,0 +1,195 @@
+use ::owned::Tree;
+use ::path::TreePath;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+/// The reason a node could not be migrated.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MigrationErrorKind<K, E> {
+    /// No converter is registered for the node's kind.
+    UnknownKind(K),
+    /// The converter registered for the node's kind failed.
+    Failed(E),
+}
+
+/// A failure to migrate the node at `path`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MigrationError<K, E> {
+    pub path: TreePath,
+    pub kind: MigrationErrorKind<K, E>,
+}
+
+impl<K: fmt::Debug, E: fmt::Display> fmt::Display for MigrationError<K, E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            MigrationErrorKind::UnknownKind(ref kind) =>
+                write!(f, "no converter for node kind {:?} at {}", kind, self.path),
+            MigrationErrorKind::Failed(ref e) =>
+                write!(f, "conversion failed at {}: {}", self.path, e),
+        }
+    }
+}
+
+type Converter<'f, R, T, E> = Box<dyn Fn(&R) -> Result<T, E> + 'f>;
+
+/// Converts trees of loosely typed data (such as freshly deserialized values of
+/// an older format version) into trees of typed data.
+///
+/// Each raw node is classified by a kind function, which typically reads a
+/// version or type field, and is converted by the converter registered for its
+/// kind.
+pub struct Migrator<'f, R, T, K, E> {
+    kind_of: Box<dyn Fn(&R) -> K + 'f>,
+    converters: HashMap<K, Converter<'f, R, T, E>>,
+}
+
+impl<'f, R, T, K, E> Migrator<'f, R, T, K, E> where K: Eq + Hash {
+    /// Creates a migrator that classifies nodes with `kind_of`.
+    pub fn new<F>(kind_of: F) -> Self where F: Fn(&R) -> K + 'f {
+        Migrator { kind_of: Box::new(kind_of), converters: HashMap::new(), }
+    }
+
+    /// Registers `converter` for nodes of the given kind, replacing any
+    /// converter that was previously registered for it.
+    pub fn register<F>(&mut self, kind: K, converter: F) -> &mut Self
+        where F: Fn(&R) -> Result<T, E> + 'f {
+            self.converters.insert(kind, Box::new(converter));
+            self
+        }
+
+    fn convert(&self, data: &R) -> Result<T, MigrationErrorKind<K, E>> {
+        let kind = (self.kind_of)(data);
+        match self.converters.get(&kind) {
+            Some(converter) => converter(data).map_err(MigrationErrorKind::Failed),
+            None => Err(MigrationErrorKind::UnknownKind(kind)),
+        }
+    }
+
+    /// Converts every node in `tree`.
+    ///
+    /// Conversion continues past failures so that all of them can be reported
+    /// at once. If any node fails to convert, the errors are returned in
+    /// pre-order of the nodes at which they occurred.
+    pub fn migrate(&self, tree: &Tree<R>) -> Result<Tree<T>, Vec<MigrationError<K, E>>> {
+        struct Frame<'a, R: 'a, T> {
+            node: &'a Tree<R>,
+            next_child: usize,
+            data: Option<T>,
+            children: Vec<Tree<T>>,
+        }
+
+        let mut errors = Vec::new();
+        let mut path = TreePath::root();
+        let mut stack = Vec::new();
+        let root_data = match self.convert(tree.data()) {
+            Ok(data) => Some(data),
+            Err(kind) => {
+                errors.push(MigrationError { path: path.clone(), kind, });
+                None
+            },
+        };
+        stack.push(Frame { node: tree, next_child: 0, data: root_data, children: Vec::new(), });
+        loop {
+            let child = {
+                let top = stack.last_mut().unwrap();
+                let child = top.node.children().get(top.next_child);
+                if child.is_some() {
+                    path.push(top.next_child);
+                    top.next_child += 1;
+                }
+                child
+            };
+            match child {
+                Some(node) => {
+                    let data = match self.convert(node.data()) {
+                        Ok(data) => Some(data),
+                        Err(kind) => {
+                            errors.push(MigrationError { path: path.clone(), kind, });
+                            None
+                        },
+                    };
+                    stack.push(Frame { node, next_child: 0, data, children: Vec::new(), });
+                },
+                None => {
+                    let frame = stack.pop().unwrap();
+                    path.pop();
+                    let converted = match (frame.data, errors.is_empty()) {
+                        (Some(data), true) => Some(Tree::new(data, frame.children)),
+                        _ => None,
+                    };
+                    match stack.last_mut() {
+                        Some(parent) => {
+                            if let Some(t) = converted {
+                                parent.children.push(t);
+                            }
+                        },
+                        None => {
+                            return match converted {
+                                Some(t) => Ok(t),
+                                None => Err(errors),
+                            }
+                        },
+                    }
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::migrate::{MigrationError, MigrationErrorKind, Migrator};
+    use ::owned_tree;
+    use ::path::TreePath;
+
+    #[derive(Debug, PartialEq)]
+    enum Node {
+        Add,
+        Num(i64),
+    }
+
+    // Raw nodes are (version, payload) pairs. Version 1 stored numbers as
+    // decimal strings; version 2 stores them in hexadecimal.
+    fn migrator<'f>() -> Migrator<'f, (u32, &'static str), Node, u32, String> {
+        let mut m = Migrator::new(|raw: &(u32, &str)| raw.0);
+        m.register(1, |raw| match raw.1 {
+            "+" => Ok(Node::Add),
+            s => s.parse().map(Node::Num).map_err(|_| format!["bad number {:?}", s]),
+        });
+        m.register(2, |raw| match raw.1 {
+            "+" => Ok(Node::Add),
+            s => i64::from_str_radix(s, 16).map(Node::Num)
+                .map_err(|_| format!["bad hex number {:?}", s]),
+        });
+        m
+    }
+
+    #[test]
+    fn migrates_mixed_versions() {
+        let raw = owned_tree![(1, "+"), [(1, "10")], [(2, "+"), [(2, "ff")], [(1, "3")]]];
+        let migrated = migrator().migrate(&raw).unwrap();
+        assert_eq![migrated, owned_tree![Node::Add, [Node::Num(10)],
+                                         [Node::Add, [Node::Num(255)], [Node::Num(3)]]]];
+    }
+
+    #[test]
+    fn collects_errors_by_path() {
+        let raw = owned_tree![(1, "+"), [(3, "10")], [(2, "+"), [(2, "zz")], [(1, "3")]]];
+        let errors = migrator().migrate(&raw).unwrap_err();
+        assert_eq![errors, vec![
+            MigrationError { path: TreePath::from(vec![0]),
+                             kind: MigrationErrorKind::UnknownKind(3), },
+            MigrationError { path: TreePath::from(vec![1, 0]),
+                             kind: MigrationErrorKind::Failed("bad hex number \"zz\"".to_string()), }]];
+        assert_eq![format!["{}", errors[1]], "conversion failed at /1/0: bad hex number \"zz\""];
+    }
+
+    #[test]
+    fn root_error() {
+        let raw = owned_tree![(9, "+")];
+        let errors = migrator().migrate(&raw).unwrap_err();
+        assert_eq![errors[0].path, TreePath::root()];
+    }
+}
@@ -0,0 +1,199 @@
+use ::Nav;
+use ::index::{ChildIndex, SiblingIndex};
+
+use std::cmp::Ordering;
+use std::ops::Deref;
+use std::ptr;
+
+#[cfg(feature = "derive")]
+pub use entmut_derive::TreeNode;
+
+/// A type whose values hold their own children, such as the nodes of a syntax
+/// tree, so that `NodeView` can navigate them.
+///
+/// With the `derive` feature, `#[derive(TreeNode)]` implements this for enums
+/// and structs whose children are marked with attributes on their fields:
+///
+/// - `#[child]` on a field that holds one child, such as a `Box<Self>`.
+/// - `#[children]` on a field that holds any number of children, such as a
+///   `Vec<Self>`, a `Vec<Box<Self>>`, or an `Option<Box<Self>>`.
+///
+/// The children of a value are the children of its marked fields, in the
+/// order in which the fields are declared.
+pub trait TreeNode {
+    fn child_count(&self) -> usize;
+
+    /// Returns the child at `index`, or `None` if there is no such child.
+    fn child(&self, index: usize) -> Option<&Self>;
+}
+
+/// Navigator over a tree of values that implement `TreeNode`. It dereferences
+/// to the focus value itself.
+pub struct NodeView<'a, N: 'a> {
+    here: &'a N,
+    path: Vec<(&'a N, usize)>,
+}
+
+impl<'a, N: 'a + TreeNode> NodeView<'a, N> {
+    /// Creates a navigator focused on `root`, which is taken to be the root of
+    /// the tree.
+    pub fn new(root: &'a N) -> Self {
+        NodeView { here: root, path: Vec::new(), }
+    }
+
+    /// Returns the focus value, borrowed for as long as the tree.
+    pub fn node(&self) -> &'a N {
+        self.here
+    }
+
+    fn root(&self) -> &'a N {
+        self.path.first().map_or(self.here, |&(root, _)| root)
+    }
+}
+
+impl<'a, N: 'a> Clone for NodeView<'a, N> {
+    fn clone(&self) -> Self {
+        NodeView { here: self.here, path: self.path.clone(), }
+    }
+}
+
+impl<'a, N: 'a + TreeNode> PartialEq for NodeView<'a, N> {
+    fn eq(&self, other: &Self) -> bool {
+        ptr::eq(self.root(), other.root())
+            && self.path.iter().map(|&(_, i)| i).eq(other.path.iter().map(|&(_, i)| i))
+    }
+}
+
+impl<'a, N: 'a + TreeNode> PartialOrd for NodeView<'a, N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if ptr::eq(self.root(), other.root()) {
+            Some(self.path.iter().map(|&(_, i)| i).cmp(other.path.iter().map(|&(_, i)| i)))
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, N: 'a> Deref for NodeView<'a, N> {
+    type Target = N;
+
+    fn deref(&self) -> &N {
+        self.here
+    }
+}
+
+impl<'a, N: 'a + TreeNode> Nav for NodeView<'a, N> {
+    fn seek_sibling(&mut self, offset: isize) -> bool {
+        let (parent, here_index) = match self.path.last() {
+            Some(&last) => last,
+            None => return offset == 0,
+        };
+        match SiblingIndex::compute(parent.child_count(), here_index, offset) {
+            Some(new_index) => {
+                self.path.last_mut().unwrap().1 = new_index;
+                self.here = parent.child(new_index).unwrap();
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn seek_child(&mut self, index: usize) -> bool {
+        match ChildIndex::compute(self.here.child_count(), index) {
+            Some(index) => {
+                self.path.push((self.here, index));
+                self.here = self.here.child(index).unwrap();
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn child_count(&self) -> usize {
+        self.here.child_count()
+    }
+
+    fn at_root(&self) -> bool {
+        self.path.is_empty()
+    }
+
+    fn index_in_parent(&self) -> Option<usize> {
+        self.path.last().map(|&(_, index)| index)
+    }
+
+    fn to_parent(&mut self) -> bool {
+        match self.path.pop() {
+            Some((parent, _)) => {
+                self.here = parent;
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn to_root(&mut self) {
+        self.here = self.root();
+        self.path.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::Nav;
+    use ::node::{NodeView, TreeNode};
+
+    // An expression tree that implements `TreeNode` by hand, as the derive
+    // would.
+    enum Expr {
+        Num(i32),
+        Neg(Box<Expr>),
+        Sum(Vec<Expr>),
+    }
+
+    impl TreeNode for Expr {
+        fn child_count(&self) -> usize {
+            match *self {
+                Expr::Num(_) => 0,
+                Expr::Neg(_) => 1,
+                Expr::Sum(ref terms) => terms.len(),
+            }
+        }
+
+        fn child(&self, index: usize) -> Option<&Expr> {
+            match *self {
+                Expr::Num(_) => None,
+                Expr::Neg(ref e) => if index == 0 { Some(e) } else { None },
+                Expr::Sum(ref terms) => terms.get(index),
+            }
+        }
+    }
+
+    fn value(e: &Expr) -> Option<i32> {
+        match *e {
+            Expr::Num(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn navigates_nodes() {
+        let e = Expr::Sum(vec![Expr::Num(1), Expr::Neg(Box::new(Expr::Num(2))), Expr::Num(3)]);
+        let mut v = NodeView::new(&e);
+        assert_eq![3, v.child_count()];
+        assert![!v.seek_sibling(1)];
+        assert![v.seek_child(1)];
+        assert_eq![Some(1), v.index_in_parent()];
+        assert![v.seek_child(0)];
+        assert_eq![Some(2), value(&v)];
+        let inner = v.clone();
+        assert![!v.seek_child(0)];
+        assert![v.to_parent()];
+        assert![v < inner];
+        assert![v.seek_sibling(1)];
+        assert_eq![Some(3), value(v.node())];
+        assert![!v.seek_sibling(1)];
+        v.to_root();
+        assert![v.at_root()];
+        assert![v == NodeView::new(&e)];
+    }
+}
@@ -1,11 +1,51 @@
-use ::{Editor, Nav};
-use ::util::{ChildIndex, SiblingIndex};
+use ::{Editor, Nav, RemoveFocusPolicy};
+use ::error::{EditError, LengthMismatch, NavError};
+use ::path::TreePath;
+use ::traversal::{EnumerateSiblings, Order};
+use ::index::{ChildIndex, SiblingIndex};
+use ::util::check_permutation;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 use std::ops::{Deref, DerefMut};
 use std::clone::Clone;
+use std::cmp::Ordering;
+use std::collections::VecDeque;
 use std::fmt;
 use std::iter::Iterator;
+use std::mem;
 use std::ptr;
+use std::rc::Rc;
+use std::slice;
+use std::sync::atomic::{self, AtomicU64};
+
+static NEXT_CHILD_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Stable identity of a tree node.
+///
+/// Each node is assigned an identity when it is created. Identities are unique
+/// within a process and travel with their nodes when children are moved,
+/// swapped, or reordered, so external state keyed by `ChildId` survives
+/// reordering operations that would invalidate child indices.
+///
+/// Only owned trees have identities. They are read with `id` on a `Tree`, a
+/// `TreeRef`, or the owned views `TreeView`, `TreeViewMut`, and `ForestView`,
+/// each of which returns the identity of its root or focus. Other backends
+/// address nodes by position, or, in the arena backend, by `NodeId`.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ChildId(u64);
+
+impl ChildId {
+    fn next() -> Self {
+        ChildId(NEXT_CHILD_ID.fetch_add(1, atomic::Ordering::Relaxed))
+    }
+
+    /// Returns the raw value of this identity.
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+}
 
 /// Single-ownership trees wherein a parent owns its children.
 ///
@@ -13,46 +53,631 @@ use std::ptr;
 /// appending children is a cheap operation. References into the tree cannot be
 /// retained when modifying it, however, and subtrees cannot be shared between
 /// parents.
+///
+/// The order of a node's children is only changed by operations that insert,
+/// remove, or explicitly reorder them (such as `sort_children_by` or
+/// `Editor::swap_children`). Reordering preserves each child's `ChildId`.
 pub struct Tree<T> {
-    data: T, children: Vec<Tree<T>>,
+    id: ChildId, data: T, children: Vec<Tree<T>>,
 }
 
 impl<T> Tree<T> {
     pub fn new(data: T, children: Vec<Tree<T>>) -> Self {
-        Tree { data: data, children: children, }
+        Tree { id: ChildId::next(), data: data, children: children, }
     }
 
     pub fn leaf(data: T) -> Self {
-        Tree { data: data, children: Vec::new(), }
+        Tree { id: ChildId::next(), data: data, children: Vec::new(), }
+    }
+
+    /// Generates a tree from `seed`. Calling `f` on a seed gives the data of
+    /// its node and the seeds of the node's children, which are unfolded in
+    /// turn.
+    ///
+    /// Generation does not recurse, so deep trees do not overflow the stack,
+    /// but it does not stop unless `f` eventually gives no seeds. See
+    /// `unfold_bounded` to stop at a depth or size.
+    pub fn unfold<S, F>(seed: S, f: F) -> Self where F: FnMut(&S) -> (T, Vec<S>) {
+        Tree::unfold_bounded(seed, usize::MAX, usize::MAX, f)
+    }
+
+    /// Generates a tree from `seed` as `unfold` does, but with at most
+    /// `max_depth` levels below the root and at most `max_nodes` nodes.
+    ///
+    /// Nodes are generated in pre-order, and `f` is called once for each.
+    /// Seeds that would exceed either limit are dropped without being passed
+    /// to `f`, so the nodes at the edge of the tree become leaves. Panics if
+    /// `max_nodes` is 0.
+    pub fn unfold_bounded<S, F>(seed: S, max_depth: usize, max_nodes: usize, mut f: F) -> Self
+        where F: FnMut(&S) -> (T, Vec<S>) {
+            assert![max_nodes > 0, "a tree has at least one node"];
+            let mut nodes = 1;
+            let (data, seeds) = f(&seed);
+            // Each stack entry is a node's data, its remaining child seeds, and
+            // its children so far.
+            let mut stack = vec![(data, seeds.into_iter(), Vec::new())];
+            loop {
+                let depth = stack.len();
+                let next = if depth > max_depth || nodes == max_nodes {
+                    None
+                } else {
+                    stack.last_mut().unwrap().1.next()
+                };
+                match next {
+                    Some(seed) => {
+                        nodes += 1;
+                        let (data, seeds) = f(&seed);
+                        stack.push((data, seeds.into_iter(), Vec::new()));
+                    },
+                    None => {
+                        let (data, _, children) = stack.pop().unwrap();
+                        let tree = Tree::new(data, children);
+                        match stack.last_mut() {
+                            Some(parent) => parent.2.push(tree),
+                            None => return tree,
+                        }
+                    },
+                }
+            }
+        }
+
+    /// Returns the stable identity of this tree's root node.
+    pub fn id(&self) -> ChildId {
+        self.id
+    }
+
+    /// Returns the data at this tree's root.
+    pub fn data(&self) -> &T {
+        &self.data
+    }
+
+    /// Returns the data at this tree's root, mutably.
+    pub fn data_mut(&mut self) -> &mut T {
+        &mut self.data
+    }
+
+    /// Replaces the data at this tree's root, returning the old data.
+    pub fn set_root_data(&mut self, data: T) -> T {
+        ::std::mem::replace(&mut self.data, data)
+    }
+
+    /// Returns the children of this tree's root.
+    pub fn children(&self) -> &[Tree<T>] {
+        &self.children
+    }
+
+    /// Returns page `page` of the children of this tree's root, where each page
+    /// holds `page_size` children. The last page may be partial, and pages
+    /// past it are empty.
+    ///
+    /// Panics if `page_size` is 0.
+    pub fn children_page(&self, page: usize, page_size: usize) -> &[Tree<T>] {
+        assert![page_size > 0, "page size must be positive"];
+        let count = self.children.len();
+        let start = page.saturating_mul(page_size).min(count);
+        let end = start.saturating_add(page_size).min(count);
+        &self.children[start..end]
+    }
+
+    /// Returns the children of this tree's root, mutably. The number of
+    /// children cannot be changed through the returned slice, but children may
+    /// be reordered or replaced with other subtrees.
+    pub fn children_mut(&mut self) -> &mut [Tree<T>] {
+        &mut self.children
     }
 
+    pub(crate) fn children_vec_mut(&mut self) -> &mut Vec<Tree<T>> {
+        &mut self.children
+    }
+
+    /// Returns the subtree rooted at the node addressed by `path`, or `None` if
+    /// there is no such node.
+    pub fn subtree(&self, path: &TreePath) -> Option<&Tree<T>> {
+        let mut here = self;
+        for &index in path.indices() {
+            here = here.children.get(index)?;
+        }
+        Some(here)
+    }
+
+    /// Returns the subtree rooted at the node addressed by `path` mutably, or
+    /// `None` if there is no such node.
+    pub fn subtree_mut(&mut self, path: &TreePath) -> Option<&mut Tree<T>> {
+        let mut here = self;
+        for &index in path.indices() {
+            here = here.children.get_mut(index)?;
+        }
+        Some(here)
+    }
+
+    /// Returns the index of the child with the given identity, if it is a
+    /// child of this node.
+    pub fn child_position(&self, id: ChildId) -> Option<usize> {
+        self.children.iter().position(|child| child.id == id)
+    }
+
+    /// Sorts this node's children by comparing their data with `compare`. The
+    /// sort is stable, and each child keeps its `ChildId`.
+    pub fn sort_children_by<F>(&mut self, mut compare: F)
+        where F: FnMut(&T, &T) -> Ordering {
+            self.children.sort_by(|a, b| compare(&a.data, &b.data));
+        }
+
+    /// Reallocates the children of every node in the order in which `order`
+    /// visits them.
+    ///
+    /// A tree built up by many separate edits can have its children scattered
+    /// across the heap. Rebuilding it in the order in which it will be
+    /// traversed tends to place nodes that are visited together near each
+    /// other in memory. The structure, data, and `ChildId`s of the tree are
+    /// unchanged.
+    pub fn optimize_layout(&mut self, order: Order) {
+        let mut queue: VecDeque<&mut Tree<T>> = VecDeque::new();
+        queue.push_back(self);
+        loop {
+            let tree = match order {
+                Order::DepthFirst => queue.pop_back(),
+                Order::BreadthFirst => queue.pop_front(),
+            };
+            let tree = match tree {
+                Some(tree) => tree,
+                None => return,
+            };
+            let mut children = Vec::with_capacity(tree.children.len());
+            children.append(&mut tree.children);
+            tree.children = children;
+            match order {
+                Order::DepthFirst => queue.extend(tree.children.iter_mut().rev()),
+                Order::BreadthFirst => queue.extend(tree.children.iter_mut()),
+            }
+        }
+    }
+
+    /// Replaces the data of every node with the next of `values`, taking the
+    /// nodes in the order in which `order` visits them. Fails, leaving the tree
+    /// unchanged, if `values` does not yield exactly one value per node.
+    pub fn overlay_data<I>(&mut self, order: Order, values: I) -> Result<(), LengthMismatch>
+        where I: IntoIterator<Item=T> {
+            let values: Vec<T> = values.into_iter().collect();
+            let expected = self.iter().count();
+            if values.len() != expected {
+                return Err(LengthMismatch { expected, actual: values.len(), })
+            }
+            let mut values = values.into_iter();
+            let mut queue: VecDeque<&mut Tree<T>> = VecDeque::new();
+            queue.push_back(self);
+            loop {
+                let tree = match order {
+                    Order::DepthFirst => queue.pop_back(),
+                    Order::BreadthFirst => queue.pop_front(),
+                };
+                let tree = match tree {
+                    Some(tree) => tree,
+                    None => return Ok(()),
+                };
+                tree.data = values.next().unwrap();
+                match order {
+                    Order::DepthFirst => queue.extend(tree.children.iter_mut().rev()),
+                    Order::BreadthFirst => queue.extend(tree.children.iter_mut()),
+                }
+            }
+        }
+
     pub fn push_child(&mut self, child: Tree<T>) {
         self.children.push(child);
     }
 
-    pub fn remove_child(&mut self, index: usize) {
-        assert![index < self.children.len(),
-                "cannot remove child at index {} (only {} children)", index, self.children.len()];
-        self.children.remove(index);
+    /// Removes the child at `index` and returns it.
+    pub fn remove_child(&mut self, index: usize) -> Result<Tree<T>, EditError> {
+        let index = ChildIndex::check(self.children.len(), index)?;
+        Ok(self.children.remove(index))
     }
 
     pub fn insert_child(&mut self, index: usize, child: Tree<T>) {
         self.children.insert(index, child);
     }
 
+    /// Replaces the child at `index` with `child` and returns the one that was
+    /// there. Panics if there is no child at `index`.
+    pub fn replace_child(&mut self, index: usize, child: Tree<T>) -> Tree<T> {
+        let index = ChildIndex::check(self.children.len(), index)
+            .unwrap_or_else(|e| panic!["{}", e]);
+        mem::replace(&mut self.children[index], child)
+    }
+
+    /// Removes all children of this tree's root and returns them in order,
+    /// without copying them.
+    pub fn take_children(&mut self) -> Forest<T> {
+        mem::take(&mut self.children)
+    }
+
     pub fn into_parts(self) -> (T, Vec<Tree<T>>) {
         (self.data, self.children)
     }
 
+    /// Consumes this tree bottom-up, replacing each node with the result of
+    /// calling `f` on its data and the results for its children.
+    pub fn fold<U, F>(self, mut f: F) -> U where F: FnMut(T, Vec<U>) -> U {
+        let mut stack = vec![(self.data, self.children.into_iter(), Vec::new())];
+        loop {
+            let next = stack.last_mut().unwrap().1.next();
+            match next {
+                Some(child) => {
+                    let results = Vec::with_capacity(child.children.len());
+                    stack.push((child.data, child.children.into_iter(), results));
+                },
+                None => {
+                    let (data, _, results) = stack.pop().unwrap();
+                    let result = f(data, results);
+                    match stack.last_mut() {
+                        Some(parent) => parent.2.push(result),
+                        None => return result,
+                    }
+                },
+            }
+        }
+    }
+
+    /// Copies this tree into a `Nested` value, which is built only of tuples
+    /// and `Vec`s.
+    pub fn to_nested(&self) -> Nested<T> where T: Clone {
+        let mut stack = vec![(self, 0, Vec::with_capacity(self.children.len()))];
+        loop {
+            let next = {
+                let top = stack.last_mut().unwrap();
+                let next = top.0.children.get(top.1);
+                top.1 += 1;
+                next
+            };
+            match next {
+                Some(child) => stack.push((child, 0, Vec::with_capacity(child.children.len()))),
+                None => {
+                    let (tree, _, children) = stack.pop().unwrap();
+                    let copy = Nested(tree.data.clone(), children);
+                    match stack.last_mut() {
+                        Some(parent) => parent.2.push(copy),
+                        None => return copy,
+                    }
+                },
+            }
+        }
+    }
+
+    /// Converts this tree into a `Nested` value without copying its data.
+    pub fn into_nested(self) -> Nested<T> {
+        self.fold(Nested)
+    }
+
+    /// Builds a tree from a `Nested` value, such as one made by `to_nested`.
+    /// Each node of the tree gets a new `ChildId`.
+    pub fn from_nested(nested: Nested<T>) -> Self {
+        let Nested(data, children) = nested;
+        let mut stack = vec![(data, children.into_iter(), Vec::new())];
+        loop {
+            let next = stack.last_mut().unwrap().1.next();
+            match next {
+                Some(Nested(data, children)) => {
+                    let trees = Vec::with_capacity(children.len());
+                    stack.push((data, children.into_iter(), trees));
+                },
+                None => {
+                    let (data, _, children) = stack.pop().unwrap();
+                    let tree = Tree::new(data, children);
+                    match stack.last_mut() {
+                        Some(parent) => parent.2.push(tree),
+                        None => return tree,
+                    }
+                },
+            }
+        }
+    }
+
+    /// Decomposes this tree into its root data and an iterator over its
+    /// children, each of which is likewise decomposed. This is the form taken
+    /// by `fixed::Tree::from_traversal`.
+    pub fn into_nodes(self) -> (T, IntoNodes<T>) {
+        (self.data, IntoNodes { children: self.children.into_iter(), })
+    }
+
+    /// Iterates over the data of this tree's nodes in pre-order (document
+    /// order).
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { stack: vec![self], }
+    }
+
+    /// Iterates over mutable references to the data of this tree's nodes in
+    /// pre-order (document order).
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut { stack: vec![self], }
+    }
+
+    /// Returns a borrowed view of this tree as a `TreeRef`.
+    pub fn tree_ref(&self) -> TreeRef<'_, T> {
+        TreeRef { tree: self, }
+    }
+
+    /// Removes every node more than `depth` levels below the root and returns
+    /// the removed subtrees, whose roots were `depth + 1` levels below the
+    /// root, in pre-order.
+    pub fn truncate_depth(&mut self, depth: usize) -> Vec<Tree<T>> {
+        let mut removed = Vec::new();
+        let mut stack = vec![(self, 0)];
+        while let Some((tree, tree_depth)) = stack.pop() {
+            if tree_depth == depth {
+                removed.append(&mut tree.children);
+            } else {
+                stack.extend(tree.children.iter_mut().rev().map(|child| (child, tree_depth + 1)));
+            }
+        }
+        removed
+    }
+
+    /// Removes subtrees until the total weight of the remaining nodes is at
+    /// most `budget`, and returns the removed subtrees in the order in which
+    /// they were chosen.
+    ///
+    /// Subtrees are chosen by the priority of their roots, lowest first, with
+    /// ties going to the node that comes later in pre-order. A subtree inside
+    /// one already removed is not considered again. The root is never removed,
+    /// so if its own weight exceeds `budget`, every other node is removed.
+    pub fn prune_to_budget<W, P>(&mut self, budget: usize, weight: W, priority: P) -> Vec<Tree<T>>
+        where W: Fn(&T) -> usize, P: Fn(&T) -> u64 {
+            // The parent, priority, size, and total weight of each subtree,
+            // indexed by the pre-order position of its root.
+            let mut parents = Vec::new();
+            let mut priorities = Vec::new();
+            let mut weights = Vec::new();
+            let mut stack = vec![(self as &Tree<T>, None)];
+            while let Some((tree, parent)) = stack.pop() {
+                let index = parents.len();
+                parents.push(parent);
+                priorities.push(priority(&tree.data));
+                weights.push(weight(&tree.data));
+                stack.extend(tree.children.iter().rev().map(|child| (child, Some(index))));
+            }
+            let mut sizes = vec![1; parents.len()];
+            for index in (1..parents.len()).rev() {
+                let parent = parents[index].unwrap();
+                sizes[parent] += sizes[index];
+                weights[parent] += weights[index];
+            }
+
+            let mut order: Vec<usize> = (1..parents.len()).collect();
+            order.sort_by_key(|&index| (priorities[index], ::std::cmp::Reverse(index)));
+            // The position in `order` at which each removed subtree was chosen.
+            let mut ranks: Vec<Option<usize>> = vec![None; parents.len()];
+            for (rank, &index) in order.iter().enumerate() {
+                if weights[0] <= budget {
+                    break
+                }
+                let mut ancestor = parents[index];
+                let mut inside_removed = false;
+                while let Some(a) = ancestor {
+                    inside_removed |= ranks[a].is_some();
+                    ancestor = parents[a];
+                }
+                if inside_removed {
+                    continue
+                }
+                ranks[index] = Some(rank);
+                let removed_weight = weights[index];
+                let mut ancestor = parents[index];
+                while let Some(a) = ancestor {
+                    weights[a] -= removed_weight;
+                    ancestor = parents[a];
+                }
+            }
+
+            // A subtree chosen after some of its descendants still holds them,
+            // so each detached subtree is searched in turn.
+            let mut removed = Vec::new();
+            let mut pending = self.detach_ranked(0, &ranks, &sizes);
+            while let Some((rank, mut tree, index)) = pending.pop() {
+                pending.extend(tree.detach_ranked(index, &ranks, &sizes));
+                removed.push((rank, tree));
+            }
+            removed.sort_by_key(|&(rank, _)| rank);
+            removed.into_iter().map(|(_, tree)| tree).collect()
+        }
+
+    /// Detaches every descendant with a rank, given pre-order `index` of this
+    /// node and the pre-order subtree `sizes`, returning each with its rank
+    /// and index.
+    fn detach_ranked(&mut self, index: usize, ranks: &[Option<usize>], sizes: &[usize])
+                     -> Vec<(usize, Tree<T>, usize)> {
+        let mut detached = Vec::new();
+        let mut stack = vec![(self, index)];
+        while let Some((tree, index)) = stack.pop() {
+            let mut child_index = index + 1;
+            let mut kept = Vec::with_capacity(tree.children.len());
+            for child in mem::take(&mut tree.children) {
+                match ranks[child_index] {
+                    Some(rank) => detached.push((rank, child, child_index)),
+                    None => kept.push((child, child_index)),
+                }
+                child_index += sizes[child_index];
+            }
+            let indices: Vec<usize> = kept.iter().map(|&(_, i)| i).collect();
+            tree.children = kept.into_iter().map(|(child, _)| child).collect();
+            stack.extend(tree.children.iter_mut().zip(indices));
+        }
+        detached
+    }
+
+    /// Returns the same tree rooted at the node addressed by `path`.
+    ///
+    /// Each former ancestor of that node becomes the last child of the node
+    /// that was its child on `path`, so the ancestors form a chain below the
+    /// new root and every edge is kept but reversed along `path`. Each node
+    /// keeps its `ChildId`. Panics if there is no node at `path`.
+    pub fn reroot(self, path: &TreePath) -> Tree<T> {
+        let mut ancestors = Vec::with_capacity(path.depth());
+        let mut here = self;
+        for &index in path.indices() {
+            if index >= here.children.len() {
+                panic!["{}", NavError::NoNode(path.clone())]
+            }
+            let child = here.children.remove(index);
+            ancestors.push(here);
+            here = child;
+        }
+        let mut chain = None;
+        for mut ancestor in ancestors {
+            ancestor.children.extend(chain);
+            chain = Some(ancestor);
+        }
+        here.children.extend(chain);
+        here
+    }
+
     pub fn view<'s>(&'s self) -> TreeView<'s, T> {
         TreeView::new(self)
     }
 
+    /// Returns a view in which nodes `max_depth` levels below the root appear
+    /// to be leaves, as if the tree had been truncated by `truncate_depth`.
+    pub fn clamped_view(&self, max_depth: usize) -> ClampedView<'_, T> {
+        ClampedView { view: self.view(), max_depth, }
+    }
+
     pub fn view_mut<'s>(&'s mut self) -> TreeViewMut<'s, T> {
         TreeViewMut::new(self)
     }
 }
 
+/// Iterator over the children of a tree, yielding the data of each child along
+/// with an iterator over the child's own children.
+pub struct IntoNodes<T> {
+    children: ::std::vec::IntoIter<Tree<T>>,
+}
+
+impl<T> Iterator for IntoNodes<T> {
+    type Item = (T, IntoNodes<T>);
+
+    fn next(&mut self) -> Option<(T, IntoNodes<T>)> {
+        self.children.next().map(Tree::into_nodes)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.children.size_hint()
+    }
+}
+
+/// Iterator over the data of a tree's nodes in pre-order, returned by
+/// `Tree::iter`.
+pub struct Iter<'a, T: 'a> {
+    // Subtrees yet to be visited, the next one last.
+    stack: Vec<&'a Tree<T>>,
+}
+
+impl<'a, T: 'a> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let tree = self.stack.pop()?;
+        self.stack.extend(tree.children.iter().rev());
+        Some(&tree.data)
+    }
+}
+
+/// Iterator over mutable references to the data of a tree's nodes in
+/// pre-order, returned by `Tree::iter_mut`.
+pub struct IterMut<'a, T: 'a> {
+    // Subtrees yet to be visited, the next one last.
+    stack: Vec<&'a mut Tree<T>>,
+}
+
+impl<'a, T: 'a> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        let Tree { ref mut data, ref mut children, .. } = *self.stack.pop()?;
+        self.stack.extend(children.iter_mut().rev());
+        Some(data)
+    }
+}
+
+impl<'a, T: 'a> IntoIterator for &'a Tree<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T: 'a> IntoIterator for &'a mut Tree<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+/// Tree made only of tuples and `Vec`s, for passing trees to code that does not
+/// depend on this crate's tree types.
+///
+/// The fields are a node's data and its children, as in the tuple
+/// `(T, Vec<Nested<T>>)`. (A type alias cannot name itself, so this is a
+/// struct, but it converts to and from that tuple.) Since comparing, copying,
+/// and dropping a `Nested` value recurse, very deep trees may overflow the
+/// stack in these operations.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Nested<T>(pub T, pub Vec<Nested<T>>);
+
+impl<T> From<(T, Vec<Nested<T>>)> for Nested<T> {
+    fn from((data, children): (T, Vec<Nested<T>>)) -> Self {
+        Nested(data, children)
+    }
+}
+
+impl<T> From<Nested<T>> for (T, Vec<Nested<T>>) {
+    fn from(nested: Nested<T>) -> Self {
+        (nested.0, nested.1)
+    }
+}
+
+impl<T> From<Nested<T>> for Tree<T> {
+    fn from(nested: Nested<T>) -> Self {
+        Tree::from_nested(nested)
+    }
+}
+
+impl<T> From<Tree<T>> for Nested<T> {
+    fn from(tree: Tree<T>) -> Self {
+        tree.into_nested()
+    }
+}
+
+/// Deep copy of a tree. Each node of the copy is a new node with its own
+/// `ChildId`.
+impl<T: Clone> Clone for Tree<T> {
+    fn clone(&self) -> Self {
+        let mut stack = vec![(self, 0, Vec::with_capacity(self.children.len()))];
+        loop {
+            let next = {
+                let top = stack.last_mut().unwrap();
+                let next = top.0.children.get(top.1);
+                top.1 += 1;
+                next
+            };
+            match next {
+                Some(child) => stack.push((child, 0, Vec::with_capacity(child.children.len()))),
+                None => {
+                    let (tree, _, children) = stack.pop().unwrap();
+                    let copy = Tree::new(tree.data.clone(), children);
+                    match stack.last_mut() {
+                        Some(parent) => parent.2.push(copy),
+                        None => return copy,
+                    }
+                },
+            }
+        }
+    }
+}
+
 impl<T: PartialEq> PartialEq<Tree<T>> for Tree<T> {
     fn eq(&self, other: &Tree<T>) -> bool {
         let mut x_stack = vec![self];
@@ -60,7 +685,7 @@ impl<T: PartialEq> PartialEq<Tree<T>> for Tree<T> {
         loop {
             match (x_stack.pop(), y_stack.pop()) {
                 (None, None) => return true,
-                (Some(x), Some(y)) if x.data == y.data => {
+                (Some(x), Some(y)) if x.data == y.data && x.children.len() == y.children.len() => {
                     for child in x.children.iter() {
                         x_stack.push(child);
                     }
@@ -107,6 +732,122 @@ impl<T: fmt::Debug> fmt::Debug for Tree<T> {
     }
 }
 
+/// Borrowed tree, which may be all of a `Tree` or one of its subtrees.
+///
+/// This has the read-only API of `Tree`, except that children are yielded as
+/// `TreeRef` values as they are visited. Functions that take a `TreeRef` accept
+/// whole trees (via `Tree::tree_ref` or `From`) and parts of trees (via
+/// `children`, `subtree`, or `TreeView::tree_ref`) alike.
+pub struct TreeRef<'a, T: 'a> {
+    tree: &'a Tree<T>,
+}
+
+impl<'a, T: 'a> TreeRef<'a, T> {
+    /// Returns the stable identity of this tree's root node.
+    pub fn id(&self) -> ChildId {
+        self.tree.id
+    }
+
+    /// Returns the data at this tree's root.
+    pub fn data(&self) -> &'a T {
+        &self.tree.data
+    }
+
+    /// Returns the number of children of this tree's root.
+    pub fn child_count(&self) -> usize {
+        self.tree.children.len()
+    }
+
+    /// Returns the child at `index`, if there is one.
+    pub fn child(&self, index: usize) -> Option<TreeRef<'a, T>> {
+        self.tree.children.get(index).map(|tree| TreeRef { tree, })
+    }
+
+    /// Iterates over the children of this tree's root.
+    pub fn children(&self) -> ChildRefs<'a, T> {
+        ChildRefs { children: self.tree.children.iter(), }
+    }
+
+    /// Returns the subtree rooted at the node addressed by `path`, or `None` if
+    /// there is no such node.
+    pub fn subtree(&self, path: &TreePath) -> Option<TreeRef<'a, T>> {
+        self.tree.subtree(path).map(|tree| TreeRef { tree, })
+    }
+
+    /// Returns the index of the child with the given identity, if it is a
+    /// child of this node.
+    pub fn child_position(&self, id: ChildId) -> Option<usize> {
+        self.tree.child_position(id)
+    }
+
+    pub fn view(&self) -> TreeView<'a, T> {
+        TreeView::new(self.tree)
+    }
+
+    /// Copies this tree into a new `Tree`. As with `Tree::clone`, each node of
+    /// the copy has a new `ChildId`.
+    pub fn to_owned(&self) -> Tree<T> where T: Clone {
+        self.tree.clone()
+    }
+}
+
+impl<'a, T: 'a> Clone for TreeRef<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T: 'a> Copy for TreeRef<'a, T> {}
+
+impl<'a, T: 'a> From<&'a Tree<T>> for TreeRef<'a, T> {
+    fn from(tree: &'a Tree<T>) -> Self {
+        TreeRef { tree, }
+    }
+}
+
+impl<'a, 'b, T: PartialEq> PartialEq<TreeRef<'b, T>> for TreeRef<'a, T> {
+    fn eq(&self, other: &TreeRef<'b, T>) -> bool {
+        self.tree == other.tree
+    }
+}
+
+impl<'a, T: PartialEq> PartialEq<Tree<T>> for TreeRef<'a, T> {
+    fn eq(&self, other: &Tree<T>) -> bool {
+        self.tree == other
+    }
+}
+
+impl<'a, T: fmt::Debug> fmt::Debug for TreeRef<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.tree.fmt(f)
+    }
+}
+
+/// Iterator over the children of a `TreeRef`.
+pub struct ChildRefs<'a, T: 'a> {
+    children: ::std::slice::Iter<'a, Tree<T>>,
+}
+
+impl<'a, T: 'a> Iterator for ChildRefs<'a, T> {
+    type Item = TreeRef<'a, T>;
+
+    fn next(&mut self) -> Option<TreeRef<'a, T>> {
+        self.children.next().map(|tree| TreeRef { tree, })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.children.size_hint()
+    }
+}
+
+impl<'a, T: 'a> DoubleEndedIterator for ChildRefs<'a, T> {
+    fn next_back(&mut self) -> Option<TreeRef<'a, T>> {
+        self.children.next_back().map(|tree| TreeRef { tree, })
+    }
+}
+
+impl<'a, T: 'a> ExactSizeIterator for ChildRefs<'a, T> {}
+
 pub struct TreeView<'a, T: 'a> {
     here: &'a Tree<T>,
     path: Vec<(&'a Tree<T>, usize)>,
@@ -116,6 +857,37 @@ impl<'a, T: 'a> TreeView<'a, T> {
     fn new(tree: &'a Tree<T>) -> Self {
         TreeView { here: tree, path: Vec::new(), }
     }
+
+    fn root(&self) -> &'a Tree<T> {
+        self.path.first().map_or(self.here, |&(root, _)| root)
+    }
+
+    /// Returns the stable identity of the focus node.
+    pub fn id(&self) -> ChildId {
+        self.here.id
+    }
+
+    /// Returns the subtree rooted at the focus.
+    pub fn tree_ref(&self) -> TreeRef<'a, T> {
+        TreeRef { tree: self.here, }
+    }
+
+    /// Returns page `page` of the focus's children, as with
+    /// `Tree::children_page`.
+    pub fn children_page(&self, page: usize, page_size: usize) -> &'a [Tree<T>] {
+        self.here.children_page(page, page_size)
+    }
+
+    /// Iterates over the data of the focus and its siblings, in order, with
+    /// each one's offset from the focus.
+    pub fn enumerate_siblings(&self)
+                              -> EnumerateSiblings<impl ExactSizeIterator<Item=&'a T> + 'a> {
+        let (siblings, here_index) = match self.path.last() {
+            None => (slice::from_ref(self.here), 0),
+            Some(&(parent, here_index)) => (&parent.children[..], here_index),
+        };
+        EnumerateSiblings::new(siblings.iter().map(|c| &c.data), here_index)
+    }
 }
 
 impl<'a, T: 'a> Clone for TreeView<'a, T> {
@@ -124,6 +896,23 @@ impl<'a, T: 'a> Clone for TreeView<'a, T> {
     }
 }
 
+impl<'a, T: 'a> PartialEq for TreeView<'a, T> {
+    fn eq(&self, other: &Self) -> bool {
+        ptr::eq(self.root(), other.root())
+            && self.path.iter().map(|&(_, i)| i).eq(other.path.iter().map(|&(_, i)| i))
+    }
+}
+
+impl<'a, T: 'a> PartialOrd for TreeView<'a, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if ptr::eq(self.root(), other.root()) {
+            Some(self.path.iter().map(|&(_, i)| i).cmp(other.path.iter().map(|&(_, i)| i)))
+        } else {
+            None
+        }
+    }
+}
+
 impl<'a, T: 'a> Deref for TreeView<'a, T> {
     type Target = T;
 
@@ -171,6 +960,10 @@ impl<'a, T: 'a> Nav for TreeView<'a, T> {
         self.path.is_empty()
     }
 
+    fn index_in_parent(&self) -> Option<usize> {
+        self.path.last().map(|&(_, index)| index)
+    }
+
     fn to_parent(&mut self) -> bool {
         match self.path.pop() {
             Some((parent, _)) => {
@@ -190,24 +983,345 @@ impl<'a, T: 'a> Nav for TreeView<'a, T> {
     }
 }
 
-pub struct TreeViewMut<'a, T: 'a> {
-    tree: &'a mut Tree<T>,
-    here_ptr: *mut Tree<T>,
-    path: Vec<(*mut Tree<T>, usize)>,
+/// View of a tree that hides nodes below a maximum depth. This is returned by
+/// `Tree::clamped_view`.
+pub struct ClampedView<'a, T: 'a> {
+    view: TreeView<'a, T>,
+    max_depth: usize,
 }
 
-impl<'a, T: 'a> TreeViewMut<'a, T> {
-    fn new(tree: &'a mut Tree<T>) -> Self {
-        let tree_ptr: *mut Tree<T> = tree;
-        TreeViewMut { tree: tree,
+impl<'a, T: 'a> Clone for ClampedView<'a, T> {
+    fn clone(&self) -> Self {
+        ClampedView { view: self.view.clone(), max_depth: self.max_depth, }
+    }
+}
+
+// Views compare by focus alone, whatever their depth limits.
+impl<'a, T: 'a> PartialEq for ClampedView<'a, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.view == other.view
+    }
+}
+
+impl<'a, T: 'a> PartialOrd for ClampedView<'a, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.view.partial_cmp(&other.view)
+    }
+}
+
+impl<'a, T: 'a> ClampedView<'a, T> {
+    fn at_max_depth(&self) -> bool {
+        self.view.path.len() >= self.max_depth
+    }
+}
+
+impl<'a, T: 'a> Deref for ClampedView<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.view
+    }
+}
+
+impl<'a, T: 'a> Nav for ClampedView<'a, T> {
+    fn seek_sibling(&mut self, offset: isize) -> bool {
+        self.view.seek_sibling(offset)
+    }
+
+    fn seek_child(&mut self, index: usize) -> bool {
+        !self.at_max_depth()
+            && self.view.seek_child(index)
+    }
+
+    fn child_count(&self) -> usize {
+        if self.at_max_depth() { 0 } else { self.view.child_count() }
+    }
+
+    fn at_root(&self) -> bool {
+        self.view.at_root()
+    }
+
+    fn index_in_parent(&self) -> Option<usize> {
+        self.view.index_in_parent()
+    }
+
+    fn to_parent(&mut self) -> bool {
+        self.view.to_parent()
+    }
+
+    fn to_root(&mut self) {
+        self.view.to_root()
+    }
+}
+
+/// Sequence of trees without a common root, such as the children taken from a
+/// node by `Tree::take_children`.
+pub type Forest<T> = Vec<Tree<T>>;
+
+/// Navigable view of a sequence of trees.
+///
+/// The root level of this view is the sequence itself: `seek_sibling` at a
+/// root moves between the trees in the sequence, and `to_parent` at a root
+/// fails. This is useful for parse forests or lists of document fragments,
+/// which cannot be wrapped in a synthetic root without changing their meaning.
+pub struct ForestView<'a, T: 'a> {
+    roots: &'a [Tree<T>],
+    root_index: usize,
+    here: &'a Tree<T>,
+    path: Vec<(&'a Tree<T>, usize)>,
+}
+
+impl<'a, T: 'a> ForestView<'a, T> {
+    /// Creates a view focused on the first tree in `roots`, or `None` if
+    /// `roots` is empty.
+    pub fn new(roots: &'a [Tree<T>]) -> Option<Self> {
+        roots.first().map(|first| {
+            ForestView { roots, root_index: 0, here: first, path: Vec::new(), }
+        })
+    }
+
+    /// Returns the stable identity of the focus node.
+    pub fn id(&self) -> ChildId {
+        self.here.id
+    }
+
+    /// Returns the index of the tree that contains the focus.
+    pub fn root_index(&self) -> usize {
+        self.root_index
+    }
+
+    /// Returns the number of trees in the sequence.
+    pub fn root_count(&self) -> usize {
+        self.roots.len()
+    }
+
+    /// Iterates over the data of the focus and its siblings, in order, with
+    /// each one's offset from the focus. At the root level, the siblings are
+    /// the other trees in the sequence.
+    pub fn enumerate_siblings(&self)
+                              -> EnumerateSiblings<impl ExactSizeIterator<Item=&'a T> + 'a> {
+        let (siblings, here_index) = match self.path.last() {
+            None => (self.roots, self.root_index),
+            Some(&(parent, here_index)) => (&parent.children[..], here_index),
+        };
+        EnumerateSiblings::new(siblings.iter().map(|c| &c.data), here_index)
+    }
+
+    /// Navigates to the root of the tree at `index` in the sequence. Returns
+    /// true iff `index` resolves to an extant tree.
+    pub fn seek_root(&mut self, index: usize) -> bool {
+        match ChildIndex::compute(self.roots.len(), index) {
+            Some(new_index) => {
+                self.root_index = new_index;
+                self.here = &self.roots[new_index];
+                self.path.clear();
+                true
+            },
+            None => false,
+        }
+    }
+}
+
+impl<'a, T: 'a> Clone for ForestView<'a, T> {
+    fn clone(&self) -> Self {
+        ForestView { roots: self.roots,
+                     root_index: self.root_index,
+                     here: self.here,
+                     path: self.path.clone(), }
+    }
+}
+
+impl<'a, T: 'a> PartialEq for ForestView<'a, T> {
+    fn eq(&self, other: &Self) -> bool {
+        ptr::eq(self.roots, other.roots) && self.root_index == other.root_index
+            && self.path.iter().map(|&(_, i)| i).eq(other.path.iter().map(|&(_, i)| i))
+    }
+}
+
+impl<'a, T: 'a> PartialOrd for ForestView<'a, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if ptr::eq(self.roots, other.roots) {
+            Some(self.root_index.cmp(&other.root_index).then_with(|| {
+                self.path.iter().map(|&(_, i)| i).cmp(other.path.iter().map(|&(_, i)| i))
+            }))
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, T: 'a> Deref for ForestView<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &<Self as Deref>::Target {
+        &self.here.data
+    }
+}
+
+impl<'a, T: 'a> Nav for ForestView<'a, T> {
+    fn seek_sibling(&mut self, offset: isize) -> bool {
+        if offset == 0 {
+            return true
+        }
+        match self.path.last().cloned() {
+            None => {
+                match SiblingIndex::compute(self.roots.len(), self.root_index, offset) {
+                    Some(new_index) => self.seek_root(new_index),
+                    None => false,
+                }
+            },
+            Some((parent, here_index)) => {
+                match SiblingIndex::compute(parent.children.len(), here_index, offset) {
+                    Some(new_index) => {
+                        self.path.pop();
+                        self.path.push((parent, new_index));
+                        self.here = &parent.children[new_index];
+                        true
+                    },
+                    None => false,
+                }
+            },
+        }
+    }
+
+    fn seek_first_sibling(&mut self) {
+        if self.at_root() {
+            self.seek_root(0);
+        } else {
+            self.to_parent();
+            self.seek_child(0);
+        }
+    }
+
+    fn seek_last_sibling(&mut self) {
+        if self.at_root() {
+            let last_root_index = self.roots.len() - 1;
+            self.seek_root(last_root_index);
+        } else {
+            self.to_parent();
+            let last_child_index = self.child_count() - 1;
+            self.seek_child(last_child_index);
+        }
+    }
+
+    fn seek_child(&mut self, index: usize) -> bool {
+        match ChildIndex::compute(self.child_count(), index) {
+            Some(new_index) => {
+                self.path.push((self.here, new_index));
+                self.here = &self.here.children[new_index];
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn child_count(&self) -> usize {
+        self.here.children.len()
+    }
+
+    fn at_root(&self) -> bool {
+        self.path.is_empty()
+    }
+
+    fn index_in_parent(&self) -> Option<usize> {
+        self.path.last().map(|&(_, index)| index)
+    }
+
+    fn to_parent(&mut self) -> bool {
+        match self.path.pop() {
+            Some((parent, _)) => {
+                self.here = parent;
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Navigates to the root of the tree that contains the focus. This does
+    /// not change which tree in the sequence is focused.
+    fn to_root(&mut self) {
+        self.here = &self.roots[self.root_index];
+        self.path.clear();
+    }
+}
+
+pub struct TreeViewMut<'a, T: 'a> {
+    tree: &'a mut Tree<T>,
+    here_ptr: *mut Tree<T>,
+    path: Vec<(*mut Tree<T>, usize)>,
+    remove_focus_policy: RemoveFocusPolicy,
+}
+
+impl<'a, T: 'a> TreeViewMut<'a, T> {
+    fn new(tree: &'a mut Tree<T>) -> Self {
+        let tree_ptr: *mut Tree<T> = tree;
+        TreeViewMut { tree: tree,
                       here_ptr: tree_ptr,
-                      path: vec![], }
+                      path: vec![],
+                      remove_focus_policy: RemoveFocusPolicy::default(), }
     }
 
     fn here(&self) -> &Tree<T> {
         unsafe { &*self.here_ptr }
     }
 
+    /// Returns the stable identity of the focus node.
+    pub fn id(&self) -> ChildId {
+        self.here().id
+    }
+
+    /// Returns the subtree rooted at the focus.
+    pub fn tree_ref(&self) -> TreeRef<'_, T> {
+        TreeRef { tree: self.here(), }
+    }
+
+    /// Returns the subtree rooted at the focus's parent, or `None` if the
+    /// focus is the root.
+    pub fn parent_ref(&self) -> Option<TreeRef<'_, T>> {
+        self.path.last().map(|&(parent_ptr, _)| TreeRef { tree: unsafe { &*parent_ptr }, })
+    }
+
+    /// Iterates over the data of the focus and its siblings, in order, with
+    /// each one's offset from the focus.
+    pub fn enumerate_siblings(&self)
+                              -> EnumerateSiblings<impl ExactSizeIterator<Item=&T> + '_> {
+        let (siblings, here_index) = match self.path.last() {
+            None => (slice::from_ref(self.here()), 0),
+            Some(&(parent_ptr, here_index)) => {
+                let parent: &Tree<T> = unsafe { &*parent_ptr };
+                (&parent.children[..], here_index)
+            },
+        };
+        EnumerateSiblings::new(siblings.iter().map(|c| &c.data), here_index)
+    }
+
+    /// Focuses on the child at `index`, calls `f`, and then returns focus to
+    /// the node that was focused before, however `f` left it. Panics if there
+    /// is no child at `index`.
+    ///
+    /// Focus is restored by position, so if `f` moves the original focus by
+    /// editing its ancestors' children, the node now at its former position is
+    /// focused instead. Panics if `f` leaves no node at that position.
+    pub fn with_subtree<R, F>(&mut self, index: usize, f: F) -> R
+        where F: FnOnce(&mut Self) -> R {
+            let origin: Vec<usize> = self.path.iter().map(|&(_, i)| i).collect();
+            if !self.seek_child(index) {
+                let child_count = self.child_count();
+                panic!["{}", NavError::NoChild { index, child_count, }]
+            }
+            let result = f(self);
+            let common = self.path.iter().zip(origin.iter())
+                .take_while(|&(&(_, i), &o)| i == o)
+                .count();
+            while self.path.len() > common {
+                self.to_parent();
+            }
+            for &i in origin[common..].iter() {
+                assert![self.seek_child(i), "focus moved out of the tree by with_subtree"];
+            }
+            result
+        }
+
     fn here_mut(&mut self) -> &mut Tree<T> {
         unsafe { &mut *self.here_ptr }
     }
@@ -234,9 +1348,13 @@ impl<'a, T: 'a> Nav for TreeViewMut<'a, T> {
 
     fn at_root(&self) -> bool { self.path.is_empty() }
 
+    fn index_in_parent(&self) -> Option<usize> {
+        self.path.last().map(|&(_, index)| index)
+    }
+
     fn seek_sibling(&mut self, offset: isize) -> bool {
         if self.at_root() {
-            return false
+            return offset == 0
         }
         let (parent_ptr, here_index) = self.path[self.path.len() - 1];
         let parent: &Tree<T> = unsafe { &*parent_ptr };
@@ -264,163 +1382,459 @@ impl<'a, T: 'a> Nav for TreeViewMut<'a, T> {
         }
     }
 
-    fn to_parent(&mut self) -> bool {
-        match self.path.pop() {
-            Some((parent_ptr, _)) => {
-                self.here_ptr = parent_ptr;
-                return true
-            },
-            None => return false,
+    fn to_parent(&mut self) -> bool {
+        match self.path.pop() {
+            Some((parent_ptr, _)) => {
+                self.here_ptr = parent_ptr;
+                return true
+            },
+            None => return false,
+        }
+    }
+
+    fn to_root(&mut self) {
+        if ! self.at_root() {
+            self.path.clear();
+            self.here_ptr = self.tree;
+        }
+    }
+}
+
+impl<'a, T: 'a> Editor for TreeViewMut<'a, T> {
+    type Data = T;
+    type Tree = Tree<T>;
+
+    fn push_leaf(&mut self, data: T) {
+        self.push_child(Tree::leaf(data));
+    }
+
+    fn push_child(&mut self, child: Tree<T>) {
+        self.here_mut().children.push(child);
+        let new_child_index = self.here().children.len() - 1;
+        self.path.push((self.here_ptr, new_child_index));
+        self.here_ptr = &mut self.here_mut().children[new_child_index];
+    }
+
+    fn insert_leaf(&mut self, index: usize, data: T) -> bool {
+        self.insert_child(index, Tree::leaf(data))
+    }
+    
+    fn insert_child(&mut self, index: usize, child: Tree<T>) -> bool {
+        match ChildIndex::compute(self.here().children.len(), index) {
+            Some(new_index) => {
+                self.here_mut().children.insert(new_index, child);
+                self.path.push((self.here_ptr, new_index));
+                self.here_ptr = &mut self.here_mut().children[new_index];
+                return true
+            },
+            None => return false,
+        }
+    }
+
+    fn insert_sibling_leaf(&mut self, offset: isize, data: T) -> bool {
+        self.insert_sibling(offset, Tree::leaf(data))
+    }
+
+    fn insert_sibling(&mut self, offset: isize, sibling: Tree<T>) -> bool {
+        if self.at_root() {
+            return false
+        }
+        let (parent_ptr, here_index) = self.path[self.path.len() - 1];
+        let parent: &Tree<T> = unsafe { &*parent_ptr };
+        match SiblingIndex::compute(parent.children.len(), here_index, offset) {
+            Some(new_index) => {
+                let (parent_ptr, _) = self.path.pop().unwrap();
+                let parent: &mut Tree<T> = unsafe { &mut *parent_ptr };
+                parent.children.insert(new_index, sibling);
+                self.path.push((parent_ptr, new_index));
+                self.here_ptr = &mut parent.children[new_index];
+                return true
+            },
+            None => return false,
+        }
+    }
+
+    fn remove_focus_policy(&self) -> RemoveFocusPolicy {
+        self.remove_focus_policy
+    }
+
+    fn set_remove_focus_policy(&mut self, policy: RemoveFocusPolicy) -> bool {
+        self.remove_focus_policy = policy;
+        true
+    }
+
+    fn remove(&mut self) -> Tree<T> {
+        let (parent_ptr, here_index) =
+            self.path.pop().unwrap_or_else(|| panic!["{}", NavError::AtRoot]);
+        let parent: &mut Tree<T> = unsafe { &mut *parent_ptr };
+        let removed = parent.children.remove(here_index);
+        match self.remove_focus_policy.focus_after_remove(here_index, parent.children.len()) {
+            Some(new_index) => {
+                self.path.push((parent_ptr, new_index));
+                self.here_ptr = &mut parent.children[new_index];
+            },
+            None => self.here_ptr = parent_ptr,
+        }
+        removed
+    }
+
+    fn remove_child(&mut self, index: usize) -> Result<Tree<T>, EditError> {
+        self.here_mut().remove_child(index)
+    }
+
+    fn replace_child(&mut self, index: usize, child: Tree<T>) -> Tree<T> {
+        self.here_mut().replace_child(index, child)
+    }
+
+    fn take_children(&mut self) -> Forest<T> {
+        self.here_mut().take_children()
+    }
+
+    fn permute_children(&mut self, perm: &[usize]) -> Result<(), EditError> {
+        check_permutation(perm, self.child_count())?;
+        let here = self.here_mut();
+        let mut children: Vec<Option<Tree<T>>> =
+            mem::take(&mut here.children).into_iter().map(Some).collect();
+        here.children = perm.iter().map(|&i| children[i].take().unwrap()).collect();
+        Ok(())
+    }
+
+    fn remove_sibling(&mut self, offset: isize) -> Option<Tree<T>> {
+        if self.at_root() {
+            return None
+        }
+        if offset == 0 {
+            return Some(self.remove())
+        }
+        let (parent_ptr, here_index) = *self.path.last()?;
+        let parent: &mut Tree<T> = unsafe { &mut *parent_ptr };
+        SiblingIndex::compute(parent.children.len(), here_index, offset).map(|index| {
+            let removed = parent.children.remove(index);
+            if index < here_index {
+                self.path.last_mut().unwrap().1 = here_index - 1;
+                self.here_ptr = &mut parent.children[here_index - 1];
+            }
+            removed
+        })
+    }
+
+    fn swap(&mut self, other: &mut Tree<T>) {
+        unsafe { ptr::swap(self.here_ptr, other) };
+    }
+
+    fn swap_children(&mut self, index_a: usize, index_b: usize) -> bool {
+        match (ChildIndex::compute(self.child_count(), index_a),
+               ChildIndex::compute(self.child_count(), index_b)) {
+            (Some(new_index_a), Some(new_index_b)) => {
+                self.here_mut().children.swap(new_index_a, new_index_b);
+                return true
+            },
+            _ => return false,
+        }
+    }
+
+    fn swap_siblings(&mut self, offset_a: isize, offset_b: isize) -> bool {
+        if self.at_root() {
+            return false
+        }
+        let &(parent_ptr, here_index) = self.path.last().unwrap();
+        let parent: &mut Tree<T> = unsafe { &mut *parent_ptr };
+        match (SiblingIndex::compute(parent.children.len(), here_index, offset_a),
+               SiblingIndex::compute(parent.children.len(), here_index, offset_b)) {
+            (Some(index_a), Some(index_b)) => {
+                parent.children.swap(index_a, index_b);
+                let new_index =
+                    if here_index == index_a {
+                        index_b
+                    } else if here_index == index_b {
+                        index_a
+                    } else {
+                        here_index
+                    };
+                self.here_ptr = &mut parent.children[new_index];
+                self.path.last_mut().unwrap().1 = new_index;
+                return true
+            },
+            _ => return false,
+        }
+    }
+}
+
+/// Owned tree whose copies share their child lists until they are edited.
+///
+/// Each node keeps its children in a reference-counted `Vec`, so cloning a
+/// `CowTree` copies only the root's data and a pointer. The first edit to a
+/// child list that another copy shares clones that list (its elements being
+/// clones in turn, so only one level is copied), and editing a node below the
+/// root copies the lists on the way down to it. Reads never copy. This makes
+/// cheap logical copies of large, mostly read trees without the per-node
+/// `RefCell` of `shared::Tree`.
+///
+/// Unlike `Tree`, nodes have no `ChildId`s, since copies share nodes.
+pub struct CowTree<T> {
+    data: T, children: Rc<Vec<CowTree<T>>>,
+}
+
+impl<T: Clone> CowTree<T> {
+    pub fn new(data: T, children: Vec<CowTree<T>>) -> Self {
+        CowTree { data, children: Rc::new(children), }
+    }
+
+    pub fn leaf(data: T) -> Self {
+        CowTree::new(data, Vec::new())
+    }
+
+    /// Returns the data at this tree's root.
+    pub fn data(&self) -> &T {
+        &self.data
+    }
+
+    /// Returns the data at this tree's root, mutably. This never copies a
+    /// child list.
+    pub fn data_mut(&mut self) -> &mut T {
+        &mut self.data
+    }
+
+    /// Returns the children of this tree's root.
+    pub fn children(&self) -> &[CowTree<T>] {
+        &self.children
+    }
+
+    /// Returns the children of this tree's root for editing, first copying
+    /// the list if another tree shares it.
+    pub fn children_mut(&mut self) -> &mut Vec<CowTree<T>> {
+        Rc::make_mut(&mut self.children)
+    }
+
+    /// Returns `true` iff this tree's root has the same child list as
+    /// `other`'s, rather than an equal copy of it.
+    pub fn shares_children_with(&self, other: &CowTree<T>) -> bool {
+        Rc::ptr_eq(&self.children, &other.children)
+    }
+
+    pub fn push_child(&mut self, child: CowTree<T>) {
+        self.children_mut().push(child);
+    }
+
+    /// Inserts `child` at `index` among the children of this tree's root.
+    /// Panics if `index` is greater than the number of children.
+    pub fn insert_child(&mut self, index: usize, child: CowTree<T>) {
+        self.children_mut().insert(index, child);
+    }
+
+    /// Removes the child at `index` and returns it.
+    pub fn remove_child(&mut self, index: usize) -> Result<CowTree<T>, EditError> {
+        let index = ChildIndex::check(self.children.len(), index)?;
+        Ok(self.children_mut().remove(index))
+    }
+
+    /// Returns the subtree rooted at the node addressed by `path`, or `None` if
+    /// there is no such node.
+    pub fn subtree(&self, path: &TreePath) -> Option<&CowTree<T>> {
+        let mut here = self;
+        for &index in path.indices() {
+            here = here.children.get(index)?;
+        }
+        Some(here)
+    }
+
+    /// Returns the subtree rooted at the node addressed by `path` mutably, or
+    /// `None` if there is no such node. The child lists on the way to it that
+    /// are shared are copied, so that edits to the subtree are not seen by
+    /// other trees.
+    pub fn subtree_mut(&mut self, path: &TreePath) -> Option<&mut CowTree<T>> {
+        self.subtree(path)?;
+        let mut here = self;
+        for &index in path.indices() {
+            here = &mut here.children_mut()[index];
+        }
+        Some(here)
+    }
+
+    /// Copies this tree into a `Tree`, whose nodes get new `ChildId`s.
+    pub fn to_tree(&self) -> Tree<T> {
+        let mut stack = vec![(self, 0, Vec::with_capacity(self.children.len()))];
+        loop {
+            let next = {
+                let top = stack.last_mut().unwrap();
+                let next = top.0.children.get(top.1);
+                top.1 += 1;
+                next
+            };
+            match next {
+                Some(child) => stack.push((child, 0, Vec::with_capacity(child.children.len()))),
+                None => {
+                    let (tree, _, children) = stack.pop().unwrap();
+                    let copy = Tree::new(tree.data.clone(), children);
+                    match stack.last_mut() {
+                        Some(parent) => parent.2.push(copy),
+                        None => return copy,
+                    }
+                },
+            }
+        }
+    }
+
+    pub fn view(&self) -> CowView<'_, T> {
+        CowView { here: self, path: Vec::new(), }
+    }
+}
+
+/// Copies the root's data and shares its child list. See `CowTree`.
+impl<T: Clone> Clone for CowTree<T> {
+    fn clone(&self) -> Self {
+        CowTree { data: self.data.clone(), children: self.children.clone(), }
+    }
+}
+
+impl<T: Clone> From<Tree<T>> for CowTree<T> {
+    fn from(tree: Tree<T>) -> Self {
+        tree.fold(CowTree::new)
+    }
+}
+
+impl<T: PartialEq> PartialEq for CowTree<T> {
+    fn eq(&self, other: &CowTree<T>) -> bool {
+        let mut stack = vec![(self, other)];
+        while let Some((x, y)) = stack.pop() {
+            if x.data != y.data || x.children.len() != y.children.len() {
+                return false
+            }
+            if !Rc::ptr_eq(&x.children, &y.children) {
+                stack.extend(x.children.iter().zip(y.children.iter()));
+            }
         }
+        true
     }
+}
 
-    fn to_root(&mut self) {
-        if ! self.at_root() {
-            self.path.clear();
-            self.here_ptr = self.tree;
+impl<T: fmt::Debug> fmt::Debug for CowTree<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        enum PathElement<'a, T: 'a> {
+            Down(&'a CowTree<T>),
+            Up,
+        }
+        let mut stack = vec![PathElement::Down(self)];
+        while let Some(element) = stack.pop() {
+            match element {
+                PathElement::Down(t) => {
+                    if !ptr::eq(t, self) {
+                        f.write_str(" ")?;
+                    }
+                    f.write_str("(")?;
+                    t.data.fmt(f)?;
+                    stack.push(PathElement::Up);
+                    for child in t.children.iter().rev() {
+                        stack.push(PathElement::Down(child));
+                    }
+                },
+                PathElement::Up => f.write_str(")")?,
+            }
         }
+        Ok(())
     }
 }
 
-impl<'a, T: 'a> Editor for TreeViewMut<'a, T> {
-    type Data = T;
-    type Tree = Tree<T>;
+/// Read-only view of a `CowTree`.
+pub struct CowView<'a, T: 'a> {
+    here: &'a CowTree<T>,
+    path: Vec<(&'a CowTree<T>, usize)>,
+}
 
-    fn push_leaf(&mut self, data: T) {
-        self.push_child(Tree::leaf(data));
+impl<'a, T: 'a> CowView<'a, T> {
+    fn root(&self) -> &'a CowTree<T> {
+        self.path.first().map_or(self.here, |&(root, _)| root)
     }
+}
 
-    fn push_child(&mut self, child: Tree<T>) {
-        self.here_mut().children.push(child);
-        let new_child_index = self.here().children.len() - 1;
-        self.path.push((self.here_ptr, new_child_index));
-        self.here_ptr = &mut self.here_mut().children[new_child_index];
+impl<'a, T: 'a> Clone for CowView<'a, T> {
+    fn clone(&self) -> Self {
+        CowView { here: self.here, path: self.path.clone(), }
     }
+}
 
-    fn insert_leaf(&mut self, index: usize, data: T) -> bool {
-        self.insert_child(index, Tree::leaf(data))
+impl<'a, T: 'a> PartialEq for CowView<'a, T> {
+    fn eq(&self, other: &Self) -> bool {
+        ptr::eq(self.root(), other.root())
+            && self.path.iter().map(|&(_, i)| i).eq(other.path.iter().map(|&(_, i)| i))
     }
-    
-    fn insert_child(&mut self, index: usize, child: Tree<T>) -> bool {
-        match ChildIndex::compute(self.here().children.len(), index) {
-            Some(new_index) => {
-                self.here_mut().children.insert(new_index, child);
-                self.path.push((self.here_ptr, new_index));
-                self.here_ptr = &mut self.here_mut().children[new_index];
-                return true
-            },
-            None => return false,
+}
+
+impl<'a, T: 'a> PartialOrd for CowView<'a, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if ptr::eq(self.root(), other.root()) {
+            Some(self.path.iter().map(|&(_, i)| i).cmp(other.path.iter().map(|&(_, i)| i)))
+        } else {
+            None
         }
     }
+}
 
-    fn insert_sibling_leaf(&mut self, offset: isize, data: T) -> bool {
-        self.insert_sibling(offset, Tree::leaf(data))
+impl<'a, T: 'a> Deref for CowView<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.here.data
     }
+}
 
-    fn insert_sibling(&mut self, offset: isize, sibling: Tree<T>) -> bool {
-        if self.at_root() {
-            return false
+impl<'a, T: 'a> Nav for CowView<'a, T> {
+    fn seek_sibling(&mut self, offset: isize) -> bool {
+        if offset == 0 {
+            return true
         }
-        let (parent_ptr, here_index) = self.path[self.path.len() - 1];
-        let parent: &Tree<T> = unsafe { &*parent_ptr };
+        let (parent, here_index) = match self.path.last() {
+            None => return false,
+            Some(&(parent, here_index)) => (parent, here_index),
+        };
         match SiblingIndex::compute(parent.children.len(), here_index, offset) {
             Some(new_index) => {
-                let (parent_ptr, _) = self.path.pop().unwrap();
-                let parent: &mut Tree<T> = unsafe { &mut *parent_ptr };
-                parent.children.insert(new_index, sibling);
-                self.path.push((parent_ptr, new_index));
-                self.here_ptr = &mut parent.children[new_index];
-                return true
+                self.path.pop();
+                self.path.push((parent, new_index));
+                self.here = &parent.children[new_index];
+                true
             },
-            None => return false,
+            None => false,
         }
     }
 
-    fn remove(&mut self) -> Tree<T> {
-        let (parent_ptr, mut here_index) =
-            self.path.pop().expect("already at root");
-        let parent: &mut Tree<T> = unsafe { &mut *parent_ptr };
-        if parent.children.len() != 0 {
-            let removed = parent.children.remove(here_index);
-            // We will wind up pointing at a sibling.
-            if here_index < parent.children.len() - 1 {
-                // We can keep pointing at the same index in parent.
-                self.path.push((parent_ptr, here_index));
-                self.here_ptr = &mut parent.children[here_index];
-            } else {
-                // At rightmost child, so we bump the index one to the left.
-                here_index -= 1;
-                self.path.push((parent_ptr, here_index));
-                self.here_ptr = &mut parent.children[here_index];
-            }
-            removed
-        } else {
-            // We will wind up pointing to parent.
-            self.here_ptr = parent_ptr;
-            parent.children.remove(0)
+    fn seek_child(&mut self, index: usize) -> bool {
+        match ChildIndex::compute(self.child_count(), index) {
+            Some(new_index) => {
+                self.path.push((self.here, new_index));
+                self.here = &self.here.children[new_index];
+                true
+            },
+            None => false,
         }
     }
 
-    fn remove_child(&mut self, index: usize) -> Option<Tree<T>> {
-        ChildIndex::compute(self.child_count(), index).map(|new_index| {
-            self.here_mut().children.remove(new_index)
-        })
+    fn child_count(&self) -> usize {
+        self.here.children.len()
     }
 
-    fn remove_sibling(&mut self, offset: isize) -> Option<Tree<T>> {
-        if offset == 0 {
-            return Some(self.remove())
-        }
-        let (parent_ptr, here_index) =
-            self.path.pop().expect("already at root");
-        let parent: &mut Tree<T> = unsafe { &mut *parent_ptr };
-        SiblingIndex::compute(parent.children.len(), here_index, offset).map(|index| {
-            let removed = parent.children.remove(index);
-            let new_index =
-                if index > here_index {
-                    here_index
-                } else {
-                    here_index - 1
-                };
-            self.path.push((parent_ptr, new_index));
-            self.here_ptr = &mut parent.children[new_index];
-            removed
-        })
+    fn at_root(&self) -> bool {
+        self.path.is_empty()
     }
 
-    fn swap(&mut self, other: &mut Tree<T>) {
-        unsafe { ptr::swap(self.here_ptr, other) };
+    fn index_in_parent(&self) -> Option<usize> {
+        self.path.last().map(|&(_, index)| index)
     }
 
-    fn swap_children(&mut self, index_a: usize, index_b: usize) -> bool {
-        match (ChildIndex::compute(self.child_count(), index_a),
-               ChildIndex::compute(self.child_count(), index_b)) {
-            (Some(new_index_a), Some(new_index_b)) => {
-                self.here_mut().children.swap(new_index_a, new_index_b);
-                return true
+    fn to_parent(&mut self) -> bool {
+        match self.path.pop() {
+            Some((parent, _)) => {
+                self.here = parent;
+                true
             },
-            _ => return false,
+            None => false,
         }
     }
 
-    fn swap_siblings(&mut self, offset_a: isize, offset_b: isize) -> bool {
-        if self.at_root() {
-            return false
-        }
-        let &(parent_ptr, here_index) = self.path.last().unwrap();
-        let parent: &mut Tree<T> = unsafe { &mut *parent_ptr };
-        match (SiblingIndex::compute(parent.children.len(), here_index, offset_a),
-               SiblingIndex::compute(parent.children.len(), here_index, offset_b)) {
-            (Some(index_a), Some(index_b)) => {
-                parent.children.swap(index_a, index_b);
-                if here_index == index_a {
-                    self.here_ptr = &mut parent.children[index_a];
-                } else if here_index == index_b {
-                    self.here_ptr = &mut parent.children[index_b];
-                }
-                return true
-            },
-            _ => return false,
+    fn to_root(&mut self) {
+        if let Some(&(root, _)) = self.path.first() {
+            self.here = root;
+            self.path.clear();
         }
     }
 }
@@ -435,7 +1849,103 @@ macro_rules! owned_tree {
 
 #[cfg(test)]
 mod test {
-    use ::owned::Tree;
+    use ::{Editor, Nav};
+    use ::error::{EditError, LengthMismatch};
+    use ::owned::{CowTree, ForestView, Nested, Tree, TreeRef};
+    use ::path::TreePath;
+    use ::traversal::Order;
+
+    #[test]
+    fn iterates_in_pre_order() {
+        let mut t = owned_tree![1, [2, [3], [4]], [5, [6]]];
+        assert_eq![vec![1, 2, 3, 4, 5, 6], t.iter().cloned().collect::<Vec<_>>()];
+        for data in t.iter_mut() {
+            *data *= 10;
+        }
+        for data in &mut t {
+            *data += 1;
+        }
+        assert_eq![owned_tree![11, [21, [31], [41]], [51, [61]]], t];
+        assert_eq![Some(&61), (&t).into_iter().last()];
+    }
+
+    #[test]
+    fn nested_round_trip() {
+        let t = owned_tree!["a", ["b", ["c"]], ["d"]];
+        let nested = t.to_nested();
+        let c = Nested("c", Vec::new());
+        let d = Nested("d", Vec::new());
+        assert_eq![Nested("a", vec![Nested("b", vec![c]), d]), nested];
+        let (data, children) = nested.clone().into();
+        assert_eq!["a", data];
+        assert_eq![2, children.len()];
+        assert_eq![nested, t.clone().into_nested()];
+        assert_eq![t, Tree::from_nested(nested)];
+    }
+
+    #[test]
+    fn cow_tree_copies_child_lists_on_write() {
+        let original = CowTree::from(owned_tree!["a", ["b", ["c"]], ["d"]]);
+        let mut copy = original.clone();
+        assert![copy.shares_children_with(&original)];
+        *copy.data_mut() = "x";
+        assert![copy.shares_children_with(&original)];
+        {
+            let c = copy.subtree_mut(&TreePath::from(vec![0, 0])).unwrap();
+            *c.data_mut() = "y";
+        }
+        assert![! copy.shares_children_with(&original)];
+        // Only the lists on the way to the edited node are copied.
+        assert![copy.children()[1].shares_children_with(&original.children()[1])];
+        assert_eq![format!["{:?}", original], "(\"a\" (\"b\" (\"c\")) (\"d\"))"];
+        assert_eq![copy.to_tree(), owned_tree!["x", ["b", ["y"]], ["d"]]];
+        assert![copy.subtree_mut(&TreePath::from(vec![2])).is_none()];
+        assert_eq![copy.remove_child(1).map(|d| *d.data()), Ok("d")];
+        assert_eq![original.children().len(), 2];
+    }
+
+    #[test]
+    fn cow_tree_view_navigates() {
+        let t = CowTree::from(owned_tree!["a", ["b", ["c"]], ["d"]]);
+        let mut v = t.view();
+        assert![v.seek_child(0)];
+        assert![v.seek_child(0)];
+        assert_eq!["c", *v];
+        assert![v.to_parent()];
+        assert![v.seek_sibling(1)];
+        assert_eq!["d", *v];
+        assert_eq![Some(1), v.index_in_parent()];
+        v.to_root();
+        assert_eq!["a", *v];
+        assert_eq![t, t.clone()];
+        assert![t != CowTree::from(owned_tree!["a", ["b"], ["d"]])];
+    }
+
+    #[test]
+    fn unfold_generates_from_seed() {
+        // Each number n has children for its proper divisors greater than 1.
+        let t = Tree::unfold(12, |&n| (n, (2..n).filter(|d| n % d == 0).collect()));
+        assert_eq![owned_tree![12, [2], [3], [4, [2]], [6, [2], [3]]], t];
+    }
+
+    #[test]
+    fn unfold_bounded_limits_depth_and_size() {
+        // A complete binary tree of infinite depth.
+        let binary = |&(depth, index): &(usize, usize)|
+            (index, vec![(depth + 1, 2 * index), (depth + 1, 2 * index + 1)]);
+        assert_eq![owned_tree![1, [2, [4], [5]], [3, [6], [7]]],
+                   Tree::unfold_bounded((0, 1), 2, usize::MAX, binary)];
+        assert_eq![owned_tree![1, [2, [4], [5]], [3]],
+                   Tree::unfold_bounded((0, 1), 2, 5, binary)];
+        assert_eq![owned_tree![1], Tree::unfold_bounded((0, 1), 0, 10, binary)];
+        let mut calls = 0;
+        let path = Tree::unfold_bounded(0, 1000, usize::MAX, |&n: &usize| {
+            calls += 1;
+            (n, vec![n + 1])
+        });
+        assert_eq![1001, calls];
+        assert_eq![Some(&1000), path.subtree(&TreePath::from(vec![0; 1000])).map(Tree::data)];
+    }
 
     #[test]
     fn eq_check() {
@@ -445,6 +1955,7 @@ mod test {
                    Tree::new("a", vec![Tree::leaf("b"), Tree::leaf("c")])];
         assert![Tree::new("a", vec![Tree::leaf("c"), Tree::leaf("b")])
                 != Tree::new("a", vec![Tree::leaf("b"), Tree::leaf("c")])];
+        assert![owned_tree!["a", ["b", ["b"]]] != owned_tree!["a", ["b"], ["b"]]];
     }
 
     #[test]
@@ -487,36 +1998,37 @@ mod test {
     }
 
     #[test]
-    #[should_panic]
-    fn remove_child_panics_no_children() {
-        owned_tree!["a"].remove_child(0);
+    fn remove_child_fails_no_children() {
+        assert_eq![owned_tree!["a"].remove_child(0),
+                   Err(EditError::ChildOutOfRange { index: 0, child_count: 0, })];
     }
 
     #[test]
-    #[should_panic]
-    fn remove_child_panics_bad_index() {
-        owned_tree!["a", ["b"], ["c"]].remove_child(2);
+    fn remove_child_fails_bad_index() {
+        let mut t = owned_tree!["a", ["b"], ["c"]];
+        assert_eq![t.remove_child(2), Err(EditError::ChildOutOfRange { index: 2, child_count: 2, })];
+        assert_eq![t, owned_tree!["a", ["b"], ["c"]]];
     }
 
     #[test]
     fn remove_child() {
         {
             let mut t = owned_tree!["a", ["b"]];
-            t.remove_child(0);
+            assert_eq![t.remove_child(0), Ok(owned_tree!["b"])];
             assert_eq![t, owned_tree!["a"]];
         }
         {
             let mut t = owned_tree!["a", ["b"], ["c"]];
-            t.remove_child(0);
+            t.remove_child(0).unwrap();
             assert_eq![t, owned_tree!["a", ["c"]]];
-            t.remove_child(0);
+            t.remove_child(0).unwrap();
             assert_eq![t, owned_tree!["a"]];
         }
         {
             let mut t = owned_tree!["a", ["b"], ["c"]];
-            t.remove_child(1);
+            t.remove_child(1).unwrap();
             assert_eq![t, owned_tree!["a", ["b"]]];
-            t.remove_child(0);
+            t.remove_child(0).unwrap();
             assert_eq![t, owned_tree!["a"]];
         }
     }
@@ -579,6 +2091,376 @@ mod test {
         assert_eq![children[1], owned_tree!["c", ["d"]]];
     }
 
+    #[test]
+    fn tree_ref_mirrors_tree() {
+        let t = owned_tree!["a", ["b", ["c"]], ["d"]];
+        let r = t.tree_ref();
+        assert_eq![*r.data(), "a"];
+        assert_eq![r.id(), t.id()];
+        assert_eq![r.child_count(), 2];
+        assert_eq![r.children().map(|c| *c.data()).collect::<Vec<_>>(), vec!["b", "d"]];
+        assert_eq![r.child(0).unwrap(), owned_tree!["b", ["c"]]];
+        assert![r.child(2).is_none()];
+        assert_eq![r.subtree(&TreePath::from(vec![0, 0])).unwrap(), owned_tree!["c"]];
+        assert_eq![r.child_position(t.children()[1].id()), Some(1)];
+        assert_eq![r, t];
+        assert_eq![format!["{:?}", r], format!["{:?}", t]];
+    }
+
+    #[test]
+    fn tree_ref_accepts_trees_and_subtrees() {
+        fn leaf_data<'a>(tree: TreeRef<'a, &'static str>) -> Vec<&'static str> {
+            let mut stack = vec![tree];
+            let mut leaves = Vec::new();
+            while let Some(tree) = stack.pop() {
+                if tree.child_count() == 0 {
+                    leaves.push(*tree.data());
+                }
+                stack.extend(tree.children().rev());
+            }
+            leaves
+        }
+        let t = owned_tree!["a", ["b", ["c"], ["d"]], ["e"]];
+        assert_eq![leaf_data(TreeRef::from(&t)), vec!["c", "d", "e"]];
+        let mut v = t.view();
+        assert![v.seek_child(0)];
+        assert_eq![leaf_data(v.tree_ref()), vec!["c", "d"]];
+        let copy = v.tree_ref().to_owned();
+        assert_eq![copy, owned_tree!["b", ["c"], ["d"]]];
+        assert![copy.id() != v.id()];
+    }
+
+    #[test]
+    fn forest_view_empty() {
+        let forest: Vec<Tree<&str>> = vec![];
+        assert![ForestView::new(&forest).is_none()];
+    }
+
+    #[test]
+    fn forest_view_seeks_between_roots() {
+        let forest = vec![owned_tree!["a", ["b"]], owned_tree!["c"], owned_tree!["d"]];
+        let mut v = ForestView::new(&forest).unwrap();
+        assert_eq![*v, "a"];
+        assert![v.at_root()];
+        assert![! v.seek_sibling(-1)];
+        assert![v.seek_sibling(2)];
+        assert_eq![*v, "d"];
+        assert_eq![v.root_index(), 2];
+        assert![! v.seek_sibling(1)];
+        assert![v.seek_sibling(-1)];
+        assert_eq![*v, "c"];
+        v.seek_first_sibling();
+        assert_eq![*v, "a"];
+        v.seek_last_sibling();
+        assert_eq![*v, "d"];
+    }
+
+    #[test]
+    fn enumerate_siblings() {
+        let mut t = owned_tree!["a", ["b"], ["c", ["d"]], ["e"]];
+        {
+            let mut v = t.view();
+            assert_eq![v.enumerate_siblings().collect::<Vec<_>>(), vec![(0, &"a")]];
+            assert![v.seek_child(1)];
+            assert_eq![v.enumerate_siblings().collect::<Vec<_>>(),
+                       vec![(-1, &"b"), (0, &"c"), (1, &"e")]];
+            assert![v.seek_child(0)];
+            assert_eq![v.enumerate_siblings().len(), 1];
+        }
+        let mut v = t.view_mut();
+        assert![v.seek_child(2)];
+        assert_eq![v.enumerate_siblings().collect::<Vec<_>>(),
+                   vec![(-2, &"b"), (-1, &"c"), (0, &"e")]];
+    }
+
+    #[test]
+    fn forest_view_enumerates_roots_as_siblings() {
+        let forest = vec![owned_tree!["a", ["b"], ["c"]], owned_tree!["d"]];
+        let mut v = ForestView::new(&forest).unwrap();
+        assert![v.seek_sibling(1)];
+        assert_eq![v.enumerate_siblings().collect::<Vec<_>>(), vec![(-1, &"a"), (0, &"d")]];
+        assert![v.seek_sibling(-1)];
+        assert![v.seek_child(0)];
+        assert_eq![v.enumerate_siblings().collect::<Vec<_>>(), vec![(0, &"b"), (1, &"c")]];
+    }
+
+    #[test]
+    fn forest_view_to_parent_at_root_fails() {
+        let forest = vec![owned_tree!["a", ["b"], ["c"]], owned_tree!["d"]];
+        let mut v = ForestView::new(&forest).unwrap();
+        assert![! v.to_parent()];
+        assert![v.seek_child(1)];
+        assert_eq![*v, "c"];
+        assert![! v.seek_sibling(1)];
+        assert![v.to_parent()];
+        assert![! v.to_parent()];
+        assert_eq![*v, "a"];
+    }
+
+    #[test]
+    fn forest_view_to_root_stays_in_tree() {
+        let forest = vec![owned_tree!["a"], owned_tree!["b", ["c", ["d"]]]];
+        let mut v = ForestView::new(&forest).unwrap();
+        assert![v.seek_sibling(1)];
+        assert![v.seek_child(0)];
+        assert![v.seek_child(0)];
+        assert_eq![*v, "d"];
+        v.to_root();
+        assert_eq![*v, "b"];
+        assert_eq![v.root_index(), 1];
+        assert![v.seek_root(0)];
+        assert_eq![*v, "a"];
+        assert![! v.seek_root(2)];
+    }
+
+    #[test]
+    fn child_ids_are_unique() {
+        let t = owned_tree!["a", ["b"], ["c"]];
+        assert![t.id() != t.children[0].id()];
+        assert![t.children[0].id() != t.children[1].id()];
+    }
+
+    #[test]
+    fn child_ids_survive_swap() {
+        let mut t = owned_tree!["a", ["b"], ["c"], ["d"]];
+        let b_id = t.children[0].id();
+        let d_id = t.children[2].id();
+        {
+            let mut v = t.view_mut();
+            assert![v.swap_children(0, 2)];
+            assert![v.seek_child(0)];
+            assert_eq![v.id(), d_id];
+        }
+        assert_eq![t.child_position(b_id), Some(2)];
+        assert_eq![t.child_position(d_id), Some(0)];
+        assert_eq![t.children[2].data, "b"];
+    }
+
+    #[test]
+    fn sort_children_by_preserves_ids() {
+        let mut t = owned_tree!["a", ["d"], ["b"], ["c"]];
+        let ids: Vec<_> = t.children.iter().map(|c| (c.data, c.id())).collect();
+        t.sort_children_by(|x, y| x.cmp(y));
+        assert_eq![t, owned_tree!["a", ["b"], ["c"], ["d"]]];
+        for (data, id) in ids {
+            let index = t.child_position(id).unwrap();
+            assert_eq![t.children[index].data, data];
+        }
+        assert_eq![t.view().id(), t.id()];
+    }
+
+    #[test]
+    fn optimize_layout_preserves_tree() {
+        fn build() -> Tree<&'static str> {
+            let mut t = owned_tree!["a", ["b", ["c"], ["d"]], ["e", ["f", ["g"]]]];
+            for i in 0..10 {
+                t.children[1].push_child(Tree::leaf(if i % 2 == 0 { "h" } else { "i" }));
+            }
+            t
+        }
+
+        for &order in &[Order::DepthFirst, Order::BreadthFirst] {
+            let mut t = build();
+            let expected = build();
+            let ids: Vec<_> = t.children.iter().map(|c| c.id()).collect();
+            t.optimize_layout(order);
+            assert_eq![t, expected];
+            assert_eq![t.children.iter().map(|c| c.id()).collect::<Vec<_>>(), ids];
+        }
+    }
+
+    #[test]
+    fn overlays_data_in_order() {
+        let mut t = owned_tree![0, [0, [0], [0]], [0, [0]]];
+        assert_eq![Ok(()), t.overlay_data(Order::DepthFirst, 1..7)];
+        assert_eq![owned_tree![1, [2, [3], [4]], [5, [6]]], t];
+        assert_eq![Ok(()), t.overlay_data(Order::BreadthFirst, 1..7)];
+        assert_eq![owned_tree![1, [2, [4], [5]], [3, [6]]], t];
+        assert_eq![Err(LengthMismatch { expected: 6, actual: 7, }),
+                   t.overlay_data(Order::DepthFirst, 0..7)];
+        assert_eq![Err(LengthMismatch { expected: 6, actual: 5, }),
+                   t.overlay_data(Order::BreadthFirst, 0..5)];
+        assert_eq![owned_tree![1, [2, [4], [5]], [3, [6]]], t];
+    }
+
+    #[test]
+    fn truncate_depth_removes_deep_subtrees() {
+        let mut t = owned_tree!["r", ["a", ["b", ["c"]], ["d"]], ["e"], ["f", ["g"]]];
+        assert_eq![Vec::<Tree<&str>>::new(), t.clone().truncate_depth(3)];
+        let removed = t.truncate_depth(1);
+        assert_eq![vec![owned_tree!["b", ["c"]], owned_tree!["d"], owned_tree!["g"]], removed];
+        assert_eq![owned_tree!["r", ["a"], ["e"], ["f"]], t];
+        assert_eq![3, t.truncate_depth(0).len()];
+        assert_eq![owned_tree!["r"], t];
+    }
+
+    #[test]
+    fn reroot_reverses_path_to_new_root() {
+        let t = owned_tree!["r", ["a", ["b", ["c"]], ["d"]], ["e"]];
+        let copy = t.clone();
+        let b_id = copy.subtree(&"/0/0".parse().unwrap()).unwrap().id();
+        let rerooted = copy.reroot(&"/0/0".parse().unwrap());
+        assert_eq![owned_tree!["b", ["c"], ["a", ["d"], ["r", ["e"]]]], rerooted];
+        assert_eq![b_id, rerooted.id()];
+        assert_eq![t, t.clone().reroot(&TreePath::root())];
+        let mut leaf = t.reroot(&"/1".parse().unwrap());
+        assert_eq![owned_tree!["e", ["r", ["a", ["b", ["c"]], ["d"]]]], leaf];
+        assert_eq!["e", leaf.set_root_data("E")];
+        assert_eq!["E", *leaf.data()];
+    }
+
+    #[test]
+    fn prune_to_budget_removes_low_priority_subtrees() {
+        // Each node's data is its weight and priority.
+        let t = owned_tree![1, [5, [1], [2]], [3, [9]], [4]];
+        let prune = |budget| {
+            let mut t = t.clone();
+            let removed = t.prune_to_budget(budget, |&d| d, |&d| d as u64);
+            (t, removed)
+        };
+        assert_eq![(t.clone(), vec![]), prune(25)];
+        // The leaf weighing 1 goes first, then 2 (now the lightest remaining),
+        // then the subtree at 3, whose child is removed with it.
+        assert_eq![(owned_tree![1, [5, [2]], [3, [9]], [4]], vec![owned_tree![1]]), prune(24)];
+        assert_eq![(owned_tree![1, [5], [4]],
+                    vec![owned_tree![1], owned_tree![2], owned_tree![3, [9]]]), prune(10)];
+        let (pruned, removed) = prune(0);
+        assert_eq![owned_tree![1], pruned];
+        assert_eq![vec![owned_tree![1], owned_tree![2], owned_tree![3, [9]], owned_tree![4],
+                        owned_tree![5]], removed];
+    }
+
+    #[test]
+    fn take_children_reattaches_as_forest() {
+        let mut t = owned_tree![0, [1, [2]], [3]];
+        let forest = t.take_children();
+        assert_eq![owned_tree![0], t];
+        assert_eq![vec![1, 3], ForestView::new(&forest).unwrap().enumerate_siblings()
+                   .map(|(_, &d)| d).collect::<Vec<_>>()];
+        let mut other = owned_tree![4];
+        for child in forest {
+            other.push_child(child);
+        }
+        assert_eq![owned_tree![4, [1, [2]], [3]], other];
+    }
+
+    #[test]
+    fn children_page_slices_children() {
+        let t = owned_tree![0, [1], [2], [3], [4], [5]];
+        let data = |page: &[Tree<i32>]| page.iter().map(|c| c.data).collect::<Vec<_>>();
+        assert_eq![vec![1, 2], data(t.children_page(0, 2))];
+        assert_eq![vec![5], data(t.children_page(2, 2))];
+        assert![t.children_page(3, 2).is_empty()];
+        assert![t.children_page(usize::MAX, usize::MAX).is_empty()];
+        let mut v = t.view();
+        assert![v.seek_child(0)];
+        assert![v.children_page(0, 1).is_empty()];
+    }
+
+    #[test]
+    #[should_panic]
+    fn reroot_panics_on_missing_node() {
+        owned_tree!["r", ["a"]].reroot(&"/1".parse().unwrap());
+    }
+
+    #[test]
+    fn clamped_view_hides_deep_nodes() {
+        let t = owned_tree!["r", ["a", ["b", ["c"]]], ["d"]];
+        let mut v = t.clamped_view(2);
+        assert![v.seek_child(0)];
+        assert![v.seek_child(0)];
+        assert_eq!["b", *v];
+        assert![v.at_leaf()];
+        assert![! v.seek_child(0)];
+        assert![v.to_parent()];
+        assert![v.seek_sibling(1)];
+        assert_eq!["d", *v];
+        let mut root = t.clamped_view(0);
+        assert_eq![0, root.child_count()];
+        assert![! root.seek_child(0)];
+    }
+
+    #[test]
+    fn clamped_forest_and_cow_views_compare_positions() {
+        let t = owned_tree!["r", ["a", ["b"]], ["c"]];
+        let mut a = t.clamped_view(1);
+        let mut b = t.clamped_view(2);
+        assert![a == b];
+        assert![b.seek_child(0)];
+        assert![a < b];
+        assert![a.seek_child(1)];
+        assert![b < a];
+        assert_eq![None, a.partial_cmp(&owned_tree!["r"].clamped_view(1))];
+
+        let forest = vec![owned_tree![1, [2]], owned_tree![3]];
+        let mut a = ForestView::new(&forest).unwrap();
+        let mut b = ForestView::new(&forest).unwrap();
+        assert![a.seek_child(0)];
+        assert![b.seek_sibling(1)];
+        assert![a < b];
+        assert![a.to_parent()];
+        assert![b.seek_sibling(-1)];
+        assert![a == b];
+        let other = forest.clone();
+        assert_eq![None, a.partial_cmp(&ForestView::new(&other).unwrap())];
+
+        let t = CowTree::from(owned_tree!["a", ["b", ["c"]], ["d"]]);
+        let mut a = t.view();
+        let mut b = t.view();
+        assert![a.seek_child(0)];
+        assert![b.seek_child(1)];
+        assert![a < b];
+        assert![b.seek_sibling(-1)];
+        assert![a == b];
+        assert_eq![None, a.partial_cmp(&t.clone().view())];
+    }
+
+    #[test]
+    fn with_subtree_restores_focus() {
+        let mut t = owned_tree!["r", ["a", ["b", ["c"]], ["d"]], ["e"]];
+        {
+            let mut v = t.view_mut();
+            assert![v.seek_child(0)];
+            let found = v.with_subtree(0, |v| {
+                assert_eq!["b", **v];
+                if !v.seek_child(0) {
+                    return None
+                }
+                v.push_leaf("x");
+                Some(**v)
+            });
+            assert_eq![Some("x"), found];
+            assert_eq!["a", *v];
+            v.with_subtree(1, |v| {
+                v.to_root();
+                assert![v.seek_child(1)];
+                **v = "E";
+            });
+            assert_eq!["a", *v];
+            assert![v.to_parent()];
+            assert![v.at_root()];
+        }
+        assert_eq![owned_tree!["r", ["a", ["b", ["c", ["x"]]], ["d"]], ["E"]], t];
+    }
+
+    #[test]
+    #[should_panic]
+    fn with_subtree_needs_child() {
+        let mut t = owned_tree!["r", ["a"]];
+        t.view_mut().with_subtree(1, |_| ());
+    }
+
+    #[test]
+    fn clone_is_deep_with_new_ids() {
+        let t = owned_tree!["a", ["b", ["c"]], ["d"]];
+        let mut copy = t.clone();
+        assert_eq![copy, t];
+        assert![copy.id() != t.id()];
+        assert![copy.children[0].children[0].id() != t.children[0].children[0].id()];
+        copy.children[1].data = "e";
+        assert_eq![t.children[1].data, "d"];
+    }
+
     #[test]
     fn debug_fmt() {
         assert_eq!["(\"a\")", format!["{:?}", owned_tree!["a"]]];
@@ -0,0 +1,350 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Address of a tree node, given by the sequence of child indices that leads to
+/// it from the root.
+///
+/// Paths are ordered lexicographically, which coincides with the pre-order
+/// (document order) position of the nodes they address.
+///
+/// With the `serde` feature, a path is serialized as its list of indices.
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(transparent))]
+pub struct TreePath {
+    indices: Vec<usize>,
+}
+
+impl TreePath {
+    /// Returns the path of the tree root.
+    pub fn root() -> Self {
+        TreePath { indices: Vec::new(), }
+    }
+
+    pub fn new(indices: Vec<usize>) -> Self {
+        TreePath { indices, }
+    }
+
+    /// Returns `true` iff this is the path of the tree root.
+    pub fn is_root(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// Returns the number of edges between the root and the addressed node.
+    pub fn depth(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// Returns the child indices that make up this path.
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+
+    /// Extends this path to address the child at `index` of the node it
+    /// currently addresses.
+    pub fn push(&mut self, index: usize) {
+        self.indices.push(index);
+    }
+
+    /// Shortens this path to address the parent of the node it currently
+    /// addresses, returning the removed child index. Returns `None` if this is
+    /// the path of the root.
+    pub fn pop(&mut self) -> Option<usize> {
+        self.indices.pop()
+    }
+
+    /// Returns the path of the child at `index` of the addressed node.
+    pub fn child(&self, index: usize) -> TreePath {
+        let mut path = self.clone();
+        path.push(index);
+        path
+    }
+
+    /// Returns the path of the parent of the addressed node, or `None` if this
+    /// is the path of the root.
+    pub fn parent(&self) -> Option<TreePath> {
+        let mut path = self.clone();
+        path.pop().map(|_| path)
+    }
+
+    /// Returns `true` iff the node addressed by this path is `other` or one of
+    /// its ancestors.
+    pub fn contains(&self, other: &TreePath) -> bool {
+        other.indices.starts_with(&self.indices)
+    }
+
+    /// Returns the steps that lead from the node addressed by `from` to the
+    /// one addressed by this path: up to their deepest common ancestor, then
+    /// down to this node.
+    pub fn relative_to(&self, from: &TreePath) -> RelativePath {
+        let common = self.indices.iter().zip(&from.indices).take_while(|&(a, b)| a == b).count();
+        let ups = (common..from.indices.len()).map(|_| Step::Up);
+        let children = self.indices[common..].iter().map(|&i| Step::Child(i));
+        RelativePath { steps: ups.chain(children).collect(), }
+    }
+}
+
+impl From<Vec<usize>> for TreePath {
+    fn from(indices: Vec<usize>) -> Self {
+        TreePath::new(indices)
+    }
+}
+
+impl<'a> From<&'a [usize]> for TreePath {
+    fn from(indices: &'a [usize]) -> Self {
+        TreePath::new(indices.to_vec())
+    }
+}
+
+/// Formats a path as its indices separated by slashes (e.g., `/0/2`), with the
+/// root path written as `/`.
+impl fmt::Display for TreePath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.indices.is_empty() {
+            return f.write_str("/")
+        }
+        for index in &self.indices {
+            write!(f, "/{}", index)?;
+        }
+        Ok(())
+    }
+}
+
+/// Single move of a focus, as a step of a `RelativePath`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Step {
+    /// Moves to the parent.
+    Up,
+    /// Moves to the child at the given index.
+    Child(usize),
+    /// Moves to the sibling at the given offset.
+    Sibling(isize),
+}
+
+/// Address of a node relative to a focus, given by the sequence of steps that
+/// leads to it, such as "the second child of the parent's next sibling".
+///
+/// A relative path is followed with `Nav::seek_relative`. Paths are built step
+/// by step with `up`, `child`, and `sibling`, and joined with `then`.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct RelativePath {
+    steps: Vec<Step>,
+}
+
+impl RelativePath {
+    /// Returns the path with no steps, which leads back to the focus.
+    pub fn new() -> Self {
+        RelativePath { steps: Vec::new(), }
+    }
+
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    /// Returns `true` iff this path has no steps.
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Extends this path with a move to the parent.
+    pub fn up(mut self) -> Self {
+        self.steps.push(Step::Up);
+        self
+    }
+
+    /// Extends this path with a move to the child at `index`.
+    pub fn child(mut self, index: usize) -> Self {
+        self.steps.push(Step::Child(index));
+        self
+    }
+
+    /// Extends this path with a move to the sibling at `offset`.
+    pub fn sibling(mut self, offset: isize) -> Self {
+        self.steps.push(Step::Sibling(offset));
+        self
+    }
+
+    /// Returns the path that follows this one and then `next`.
+    pub fn then(&self, next: &RelativePath) -> RelativePath {
+        RelativePath { steps: self.steps.iter().chain(&next.steps).cloned().collect(), }
+    }
+
+    /// Returns the shortest equivalent of this path, which consists of moves
+    /// up, then at most one move to a sibling, then moves to children.
+    ///
+    /// Moves that undo one another are cancelled and runs of moves among
+    /// siblings are merged. Wherever this path leads, its normal form leads to
+    /// the same node, but the normal form may also succeed where this path
+    /// fails, since it skips the nodes that the cancelled moves passed
+    /// through.
+    pub fn normalize(&self) -> RelativePath {
+        let mut steps = Vec::with_capacity(self.steps.len());
+        for &step in &self.steps {
+            push_normalized(&mut steps, step);
+        }
+        RelativePath { steps, }
+    }
+}
+
+// Appends `step` to the normal form `steps`, keeping it normal.
+fn push_normalized(steps: &mut Vec<Step>, step: Step) {
+    match (steps.last().cloned(), step) {
+        (_, Step::Sibling(0)) => (),
+        (Some(Step::Child(_)), Step::Up) => { steps.pop(); },
+        // Moving among siblings does not change the parent.
+        (Some(Step::Sibling(_)), Step::Up) => {
+            steps.pop();
+            push_normalized(steps, Step::Up);
+        },
+        (Some(Step::Sibling(a)), Step::Sibling(b)) => {
+            steps.pop();
+            push_normalized(steps, Step::Sibling(a + b));
+        },
+        (Some(Step::Child(index)), Step::Sibling(offset))
+            if index as isize + offset >= 0 => {
+                steps.pop();
+                steps.push(Step::Child((index as isize + offset) as usize));
+            },
+        (_, step) => steps.push(step),
+    }
+}
+
+/// Error returned when parsing a malformed `TreePath`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParsePathError {
+    text: String,
+}
+
+impl fmt::Display for ParsePathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid tree path {:?}", self.text)
+    }
+}
+
+impl Error for ParsePathError {}
+
+/// Parses a path in the format written by `Display`.
+impl FromStr for TreePath {
+    type Err = ParsePathError;
+
+    fn from_str(s: &str) -> Result<Self, ParsePathError> {
+        let error = || ParsePathError { text: s.to_string(), };
+        if s == "/" {
+            return Ok(TreePath::root())
+        }
+        if !s.starts_with('/') {
+            return Err(error())
+        }
+        s[1..].split('/')
+            .map(|index| index.parse().map_err(|_| error()))
+            .collect::<Result<Vec<usize>, _>>()
+            .map(TreePath::new)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::Nav;
+    use ::owned_tree;
+    use ::path::{RelativePath, Step, TreePath};
+
+    #[test]
+    fn root_path() {
+        let root = TreePath::root();
+        assert![root.is_root()];
+        assert_eq![root.depth(), 0];
+        assert_eq![root.parent(), None];
+        assert_eq![format!["{}", root], "/"];
+    }
+
+    #[test]
+    fn child_and_parent() {
+        let path = TreePath::root().child(0).child(2);
+        assert_eq![path.indices(), &[0, 2]];
+        assert_eq![format!["{}", path], "/0/2"];
+        assert_eq![path.parent(), Some(TreePath::new(vec![0]))];
+    }
+
+    #[test]
+    fn contains() {
+        let a = TreePath::from(vec![1]);
+        assert![a.contains(&a)];
+        assert![a.contains(&TreePath::from(vec![1, 0]))];
+        assert![! a.contains(&TreePath::from(vec![0, 1]))];
+        assert![TreePath::root().contains(&a)];
+    }
+
+    #[test]
+    fn ordering_is_document_order() {
+        let mut paths = vec![TreePath::from(vec![1]),
+                             TreePath::from(vec![0, 1]),
+                             TreePath::root(),
+                             TreePath::from(vec![0])];
+        paths.sort();
+        assert_eq![paths, vec![TreePath::root(),
+                               TreePath::from(vec![0]),
+                               TreePath::from(vec![0, 1]),
+                               TreePath::from(vec![1])]];
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!["/".parse(), Ok(TreePath::root())];
+        assert_eq!["/0/12".parse(), Ok(TreePath::from(vec![0, 12]))];
+        let path = TreePath::from(vec![3, 1, 4]);
+        assert_eq![path.to_string().parse(), Ok(path)];
+        assert![" /0".parse::<TreePath>().is_err()];
+        assert!["0/1".parse::<TreePath>().is_err()];
+        assert!["/0/".parse::<TreePath>().is_err()];
+        assert!["//".parse::<TreePath>().is_err()];
+    }
+
+    #[test]
+    fn seek_relative() {
+        let t = owned_tree!["r", ["a", ["b"]], ["c", ["d"], ["e"], ["f"]]];
+        let mut v = t.view();
+        assert![v.seek_child(0)];
+        assert![v.seek_child(0)];
+        // The second child of the parent's next sibling.
+        let path = RelativePath::new().up().sibling(1).child(1);
+        assert![v.seek_relative(&path)];
+        assert_eq!["e", *v];
+        assert![! v.seek_relative(&RelativePath::new().child(0))];
+        assert_eq!["e", *v];
+        assert![v.seek_relative(&RelativePath::new())];
+        assert_eq!["e", *v];
+    }
+
+    #[test]
+    fn then_composes() {
+        let a = RelativePath::new().up().child(2);
+        let b = RelativePath::new().sibling(-1);
+        assert_eq![a.then(&b).steps(), &[Step::Up, Step::Child(2), Step::Sibling(-1)]];
+        assert![RelativePath::new().then(&RelativePath::new()).is_empty()];
+    }
+
+    #[test]
+    fn normalize() {
+        let path = RelativePath::new().child(1).up().up().sibling(2).sibling(-1).child(0)
+            .child(3).sibling(-2).sibling(0);
+        assert_eq![path.normalize().steps(),
+                   &[Step::Up, Step::Sibling(1), Step::Child(0), Step::Child(1)]];
+        let path = RelativePath::new().sibling(1).child(0).up().up().child(2).sibling(-5);
+        assert_eq![path.normalize().steps(), &[Step::Up, Step::Child(2), Step::Sibling(-5)]];
+        let path = RelativePath::new().sibling(1).sibling(-1).child(0).up();
+        assert![path.normalize().is_empty()];
+    }
+
+    #[test]
+    fn relative_to() {
+        let from = TreePath::from(vec![0, 1, 2]);
+        let to = TreePath::from(vec![0, 3]);
+        assert_eq![to.relative_to(&from).steps(), &[Step::Up, Step::Up, Step::Child(3)]];
+        assert![to.relative_to(&to).is_empty()];
+        assert_eq![from.relative_to(&TreePath::root()).steps(),
+                   &[Step::Child(0), Step::Child(1), Step::Child(2)]];
+    }
+}
@@ -0,0 +1,278 @@
+use ::{Editor, Nav};
+use ::error::EditError;
+
+use std::ops::{Deref, DerefMut};
+
+/// Node data with a priority that orders the node among its siblings.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Prioritized<K, T> {
+    /// The node's priority. Siblings with lower priorities come first.
+    pub priority: K,
+    /// The rest of the node's data.
+    pub data: T,
+}
+
+impl<K, T> Prioritized<K, T> {
+    /// Returns data with the given priority.
+    pub fn new(priority: K, data: T) -> Self {
+        Prioritized { priority, data, }
+    }
+}
+
+/// Wrapper around an `Editor` of `Prioritized` data that keeps the children of
+/// each node ordered by priority, with siblings of equal priority in the order
+/// they were added.
+///
+/// The wrapper edits only through methods that maintain the order: children
+/// are added at the position their priority calls for, and priorities change
+/// only through `set_priority`. Each of these finds the new position by binary
+/// search, so it seeks O(log n) children of a node with n children, plus the
+/// cost of the wrapped editor's own insertion and removal.
+pub struct PriorityChildren<E> {
+    editor: E,
+}
+
+impl<K, T, E> PriorityChildren<E>
+    where K: Ord + Clone,
+          E: Editor<Data=Prioritized<K, T>> + DerefMut<Target=Prioritized<K, T>> {
+        /// Wraps `editor`, first sorting the children of every node in the
+        /// subtree at its focus by priority. Siblings of equal priority keep
+        /// their order. Focus does not move.
+        pub fn new(mut editor: E) -> Self {
+            // Depth below the starting focus, so the walk stops on returning
+            // to it.
+            let mut depth = 0;
+            'walk: loop {
+                sort_children(&mut editor);
+                if editor.seek_child(0) {
+                    depth += 1;
+                    continue
+                }
+                loop {
+                    if depth == 0 {
+                        break 'walk
+                    }
+                    if editor.seek_sibling(1) {
+                        break
+                    }
+                    editor.to_parent();
+                    depth -= 1;
+                }
+            }
+            PriorityChildren { editor, }
+        }
+
+        /// Returns a mutable reference to the focus's data other than its
+        /// priority.
+        pub fn data_mut<'s>(&'s mut self) -> &'s mut T where K: 's, T: 's {
+            &mut self.editor.data
+        }
+
+        /// Adds a leaf with the given data among the focus's children, after
+        /// any with the same priority, and focuses on it. Returns its index.
+        pub fn insert_leaf(&mut self, data: Prioritized<K, T>) -> usize {
+            let index = self.position_for(&data.priority, None);
+            if index == self.editor.child_count() {
+                self.editor.push_leaf(data);
+            } else {
+                self.editor.insert_leaf(index, data);
+            }
+            index
+        }
+
+        /// Adds `child` among the focus's children, after any with the same
+        /// priority, and focuses on it. Returns its index.
+        pub fn insert_child(&mut self, child: E::Tree) -> usize {
+            self.editor.push_child(child);
+            self.editor.to_parent();
+            let index = self.reposition(self.editor.child_count() - 1);
+            self.editor.seek_child(index);
+            index
+        }
+
+        /// Sets the priority of the focus's child at `index` to `priority` and
+        /// moves the child to keep the children ordered, after any others with
+        /// the same priority. Returns the child's new index, or `None`, leaving
+        /// the tree unchanged, if there is no child at `index`. Focus does not
+        /// move.
+        pub fn set_priority(&mut self, index: usize, priority: K) -> Option<usize> {
+            if !self.editor.seek_child(index) {
+                return None
+            }
+            self.editor.priority = priority;
+            self.editor.to_parent();
+            Some(self.reposition(index))
+        }
+
+        /// Removes the focus's child at `index` and returns the subtree rooted
+        /// at it, as `Editor::remove_child` does.
+        pub fn remove_child(&mut self, index: usize) -> Result<E::Tree, EditError> {
+            self.editor.remove_child(index)
+        }
+
+        /// Removes the focus and returns the subtree rooted at it, as
+        /// `Editor::remove` does. Panics if the focus is the root.
+        pub fn remove(&mut self) -> E::Tree {
+            self.editor.remove()
+        }
+
+        /// Returns the wrapped editor.
+        pub fn into_inner(self) -> E {
+            self.editor
+        }
+
+        // Returns the priority of the focus's child at `index`.
+        fn child_priority(&mut self, index: usize) -> K {
+            self.editor.seek_child(index);
+            let priority = self.editor.priority.clone();
+            self.editor.to_parent();
+            priority
+        }
+
+        // Returns the index at which a child with priority `key` belongs among
+        // the focus's children other than the one at `skip`, after any with
+        // the same priority.
+        fn position_for(&mut self, key: &K, skip: Option<usize>) -> usize {
+            let mut low = 0;
+            let mut high = self.editor.child_count() - skip.map_or(0, |_| 1);
+            while low < high {
+                let mid = low + (high - low) / 2;
+                let index = match skip {
+                    Some(skip) if mid >= skip => mid + 1,
+                    _ => mid,
+                };
+                if self.child_priority(index) <= *key {
+                    low = mid + 1;
+                } else {
+                    high = mid;
+                }
+            }
+            low
+        }
+
+        // Moves the focus's child at `index`, whose priority may be out of
+        // order, to where it belongs among the others, and returns its new
+        // index.
+        fn reposition(&mut self, index: usize) -> usize {
+            let key = self.child_priority(index);
+            let new_index = self.position_for(&key, Some(index));
+            if new_index != index {
+                let child = self.editor.remove_child(index).unwrap();
+                if new_index == self.editor.child_count() {
+                    self.editor.push_child(child);
+                } else {
+                    self.editor.insert_child(new_index, child);
+                }
+                self.editor.to_parent();
+            }
+            new_index
+        }
+    }
+
+// Stably sorts the focus's children by priority.
+fn sort_children<K, T, E>(editor: &mut E)
+    where K: Ord + Clone,
+          E: Editor<Data=Prioritized<K, T>> + Deref<Target=Prioritized<K, T>> {
+        let mut priorities = Vec::with_capacity(editor.child_count());
+        for index in 0..editor.child_count() {
+            editor.seek_child(index);
+            priorities.push(editor.priority.clone());
+            editor.to_parent();
+        }
+        let mut perm: Vec<usize> = (0..priorities.len()).collect();
+        perm.sort_by(|&a, &b| priorities[a].cmp(&priorities[b]));
+        editor.permute_children(&perm).unwrap();
+    }
+
+impl<E: Deref> Deref for PriorityChildren<E> {
+    type Target = E::Target;
+
+    fn deref(&self) -> &E::Target {
+        &self.editor
+    }
+}
+
+impl<E: Nav> Nav for PriorityChildren<E> {
+    fn child_count(&self) -> usize {
+        self.editor.child_count()
+    }
+
+    fn at_leaf(&self) -> bool {
+        self.editor.at_leaf()
+    }
+
+    fn at_root(&self) -> bool {
+        self.editor.at_root()
+    }
+
+    fn index_in_parent(&self) -> Option<usize> {
+        self.editor.index_in_parent()
+    }
+
+    fn seek_sibling(&mut self, offset: isize) -> bool {
+        self.editor.seek_sibling(offset)
+    }
+
+    fn seek_child(&mut self, index: usize) -> bool {
+        self.editor.seek_child(index)
+    }
+
+    fn to_parent(&mut self) -> bool {
+        self.editor.to_parent()
+    }
+
+    fn to_root(&mut self) {
+        self.editor.to_root()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::Nav;
+    use ::owned::Tree;
+    use ::priority::{PriorityChildren, Prioritized};
+    use ::owned_tree;
+
+    fn p(priority: u32, data: &'static str) -> Prioritized<u32, &'static str> {
+        Prioritized::new(priority, data)
+    }
+
+    fn child_data(tree: &Tree<Prioritized<u32, &'static str>>) -> Vec<&'static str> {
+        tree.children().iter().map(|child| child.data().data).collect()
+    }
+
+    #[test]
+    fn keeps_children_ordered() {
+        let mut t = owned_tree![p(0, "r")];
+        {
+            let mut e = PriorityChildren::new(t.view_mut());
+            assert_eq![0, e.insert_leaf(p(5, "a"))];
+            e.to_parent();
+            assert_eq![0, e.insert_leaf(p(2, "b"))];
+            e.to_parent();
+            assert_eq![2, e.insert_leaf(p(5, "c"))];
+            *e.data_mut() = "c2";
+            e.to_parent();
+            assert_eq![1, e.insert_child(owned_tree![p(3, "d")])];
+            assert_eq!["d", e.data];
+        }
+        assert_eq![vec!["b", "d", "a", "c2"], child_data(&t)];
+        {
+            let mut e = PriorityChildren::new(t.view_mut());
+            assert_eq![Some(3), e.set_priority(0, 9)];
+            assert_eq![Some(0), e.set_priority(2, 0)];
+            assert_eq![Some(2), e.set_priority(2, 5)];
+            assert_eq![None, e.set_priority(4, 1)];
+            assert![e.at_root()];
+        }
+        assert_eq![vec!["c2", "d", "a", "b"], child_data(&t)];
+    }
+
+    #[test]
+    fn new_sorts_subtree() {
+        let mut t = owned_tree![p(0, "r"), [p(2, "a"), [p(7, "x")], [p(6, "y")]], [p(1, "b")], [p(2, "c")]];
+        PriorityChildren::new(t.view_mut());
+        assert_eq![vec!["b", "a", "c"], child_data(&t)];
+        assert_eq![vec!["y", "x"], child_data(&t.children()[1])];
+    }
+}
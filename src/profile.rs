@@ -0,0 +1,240 @@
+use ::Nav;
+
+use std::fmt;
+use std::ops::Deref;
+
+/// Counts of the navigation operations performed through a `ProfiledNav`.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Profile {
+    /// Calls to `seek_child`.
+    pub child_seeks: usize,
+    /// Calls to `seek_sibling`, `seek_first_sibling`, and `seek_last_sibling`.
+    pub sibling_seeks: usize,
+    /// Calls to `to_parent`.
+    pub parent_seeks: usize,
+    /// Calls to `to_root`.
+    pub root_seeks: usize,
+    /// Seeks of any kind that did not move the focus because their target did
+    /// not exist.
+    pub failed_seeks: usize,
+    /// Greatest depth the focus reached, counted from where profiling began.
+    pub max_depth: usize,
+    /// Moves to a parent that were followed by a descent into a different child
+    /// than the one just left. This pattern jumps between distant parts of a
+    /// depth-first layout, so it tends to be unfriendly to the cache.
+    pub backtracks: usize,
+}
+
+impl Profile {
+    /// Returns the total number of seeks of all kinds.
+    pub fn total_seeks(&self) -> usize {
+        self.child_seeks + self.sibling_seeks + self.parent_seeks + self.root_seeks
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "seeks: {} ({} failed)", self.total_seeks(), self.failed_seeks)?;
+        writeln!(f, "  child:   {}", self.child_seeks)?;
+        writeln!(f, "  sibling: {}", self.sibling_seeks)?;
+        writeln!(f, "  parent:  {} ({} backtracks)", self.parent_seeks, self.backtracks)?;
+        writeln!(f, "  root:    {}", self.root_seeks)?;
+        write!(f, "max depth: {}", self.max_depth)
+    }
+}
+
+/// Wrapper around a `Nav` that records how it is navigated.
+///
+/// This is meant for measuring the access pattern of an algorithm, for example
+/// to decide which backend suits it. Every navigation method is forwarded to
+/// the wrapped view and tallied in a `Profile`.
+#[derive(Clone)]
+pub struct ProfiledNav<N> {
+    nav: N,
+    profile: Profile,
+    depth: usize,
+    // Index of the child that the focus most recently left with `to_parent`,
+    // if no other navigation has happened since.
+    left_child: Option<usize>,
+    // Index of the focus among its siblings, if known.
+    here_index: Option<usize>,
+}
+
+impl<N: Nav> ProfiledNav<N> {
+    pub fn new(nav: N) -> Self {
+        ProfiledNav { nav, profile: Profile::default(), depth: 0,
+                      left_child: None, here_index: None, }
+    }
+
+    /// Returns the counts recorded so far.
+    pub fn report(&self) -> Profile {
+        self.profile
+    }
+
+    /// Clears the counts recorded so far, keeping the current focus as the
+    /// origin for depth measurements.
+    pub fn reset(&mut self) {
+        self.profile = Profile::default();
+        self.depth = 0;
+        self.left_child = None;
+    }
+
+    /// Returns the wrapped view.
+    pub fn into_inner(self) -> N {
+        self.nav
+    }
+
+    fn record(&mut self, moved: bool) -> bool {
+        if !moved {
+            self.profile.failed_seeks += 1;
+        }
+        self.left_child = None;
+        moved
+    }
+}
+
+impl<N: Nav + Deref> Deref for ProfiledNav<N> {
+    type Target = N::Target;
+
+    fn deref(&self) -> &N::Target {
+        &self.nav
+    }
+}
+
+impl<N: Nav> Nav for ProfiledNav<N> {
+    fn child_count(&self) -> usize {
+        self.nav.child_count()
+    }
+
+    fn at_leaf(&self) -> bool {
+        self.nav.at_leaf()
+    }
+
+    fn at_root(&self) -> bool {
+        self.nav.at_root()
+    }
+
+    fn index_in_parent(&self) -> Option<usize> {
+        self.nav.index_in_parent()
+    }
+
+    fn seek_sibling(&mut self, offset: isize) -> bool {
+        self.profile.sibling_seeks += 1;
+        let moved = self.nav.seek_sibling(offset);
+        if moved {
+            self.here_index = self.here_index.map(|i| (i as isize + offset) as usize);
+        }
+        self.record(moved)
+    }
+
+    fn seek_first_sibling(&mut self) {
+        self.profile.sibling_seeks += 1;
+        self.nav.seek_first_sibling();
+        if !self.nav.at_root() {
+            self.here_index = Some(0);
+        }
+        self.record(true);
+    }
+
+    fn seek_last_sibling(&mut self) {
+        self.profile.sibling_seeks += 1;
+        self.nav.seek_last_sibling();
+        self.here_index = None;
+        self.record(true);
+    }
+
+    fn seek_child(&mut self, index: usize) -> bool {
+        self.profile.child_seeks += 1;
+        if let Some(left) = self.left_child {
+            if left != index {
+                self.profile.backtracks += 1;
+            }
+        }
+        let moved = self.nav.seek_child(index);
+        if moved {
+            self.depth += 1;
+            self.profile.max_depth = self.profile.max_depth.max(self.depth);
+            self.here_index = Some(index);
+        }
+        self.record(moved)
+    }
+
+    fn to_parent(&mut self) -> bool {
+        self.profile.parent_seeks += 1;
+        let left = self.here_index;
+        let moved = self.nav.to_parent();
+        self.record(moved);
+        if moved {
+            self.depth = self.depth.saturating_sub(1);
+            self.here_index = None;
+            self.left_child = left;
+        }
+        moved
+    }
+
+    fn to_root(&mut self) {
+        self.profile.root_seeks += 1;
+        self.nav.to_root();
+        self.depth = 0;
+        self.here_index = None;
+        self.record(true);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::Nav;
+    use ::owned_tree;
+    use ::profile::{Profile, ProfiledNav};
+    use ::traversal;
+
+    #[test]
+    fn counts_seeks() {
+        let t = owned_tree!["a", ["b", ["c"]], ["d"]];
+        let mut v = ProfiledNav::new(t.view());
+        assert![v.seek_child(0)];
+        assert![v.seek_child(0)];
+        assert_eq![*v, "c"];
+        assert![! v.seek_child(0)];
+        assert![v.to_parent()];
+        assert![v.seek_sibling(1)];
+        assert![! v.seek_sibling(1)];
+        v.to_root();
+        assert_eq![v.report(), Profile { child_seeks: 3, sibling_seeks: 2, parent_seeks: 1,
+                                         root_seeks: 1, failed_seeks: 2, max_depth: 2,
+                                         backtracks: 0, }];
+        assert_eq![v.report().total_seeks(), 7];
+        v.reset();
+        assert_eq![v.report(), Profile::default()];
+    }
+
+    #[test]
+    fn counts_backtracks() {
+        let t = owned_tree!["a", ["b", ["c"], ["d"]], ["e"]];
+        let mut v = ProfiledNav::new(t.view());
+        assert![v.seek_child(0)];
+        assert![v.seek_child(1)];
+        assert![v.to_parent()];
+        assert![v.seek_child(0)];
+        assert![v.to_parent()];
+        assert![v.seek_child(0)];
+        assert_eq![v.report().backtracks, 1];
+        assert![v.to_parent()];
+        assert![v.seek_sibling(1)];
+        assert![v.to_parent()];
+        assert![v.seek_child(1)];
+        assert_eq![v.report().backtracks, 1];
+    }
+
+    #[test]
+    fn profiles_search() {
+        let t = owned_tree![1, [2, [3], [4]], [5, [6]]];
+        let v = ProfiledNav::new(t.view());
+        let found = traversal::dfs(v, |n| **n == 6).unwrap();
+        let report = found.report();
+        assert_eq![*found, 6];
+        assert_eq![report.max_depth, 2];
+        assert![report.child_seeks > 0];
+        assert![format!["{}", report].starts_with("seeks: ")];
+    }
+}
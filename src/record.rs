@@ -0,0 +1,434 @@
+use ::{Editor, Nav, RemoveFocusPolicy};
+use ::command::{self, ApplyError, ApplyErrorKind, EditCommand};
+use ::error::{EditError, NavError};
+use ::path::{RelativePath, TreePath};
+use ::util::{self, FocusPath};
+
+use std::ops::{Deref, DerefMut};
+
+/// Sequence of edits recorded by `Editor::record`, to be replayed on other
+/// trees.
+///
+/// Commands address nodes by their paths from the script's anchor, which is
+/// the highest node that recording reached, found from the focus at which
+/// recording started by following `anchor`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct EditScript<T> {
+    anchor: RelativePath,
+    commands: Vec<EditCommand<T>>,
+}
+
+impl<T> EditScript<T> {
+    /// Returns the path from the focus at which recording started to the node
+    /// that command paths start from. It consists only of moves up.
+    pub fn anchor(&self) -> &RelativePath {
+        &self.anchor
+    }
+
+    /// Returns the recorded edits, in the order they were made.
+    pub fn commands(&self) -> &[EditCommand<T>] {
+        &self.commands
+    }
+
+    pub fn into_commands(self) -> Vec<EditCommand<T>> {
+        self.commands
+    }
+
+    /// Returns `true` iff no edits were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Applies each recorded edit in turn, with `command::apply`, to the tree
+    /// that `editor` edits. Command paths are resolved from the anchor found
+    /// from the focus of `editor`, so the script edits the same place relative
+    /// to that focus as it did where it was recorded. Stops at the first edit
+    /// that fails, leaving the edits before it applied.
+    pub fn replay<E>(&self, editor: &mut E) -> Result<(), ApplyError>
+        where E: Editor<Data=T> + DerefMut<Target=T>, T: Clone {
+            let mut base = focus_path(editor);
+            for _ in self.anchor.steps() {
+                if base.pop().is_none() {
+                    return Err(ApplyError { path: TreePath::root(),
+                                            kind: ApplyErrorKind::NoSuchAnchor, })
+                }
+            }
+            self.commands.iter().try_for_each(|command| {
+                let mut command = command.clone();
+                *command.path_mut() = util::prefixed(base.indices(), command.path());
+                command::apply(editor, &command)
+            })
+        }
+}
+
+// Returns the path of the focus of `nav` from the root.
+fn focus_path<N: Nav>(nav: &mut N) -> TreePath {
+    let mut indices = Vec::new();
+    while let Some(index) = nav.index_in_parent() {
+        indices.push(index);
+        nav.to_parent();
+    }
+    indices.reverse();
+    for &index in &indices {
+        nav.seek_child(index);
+    }
+    TreePath::new(indices)
+}
+
+/// Wrapper around an `Editor` that records the edits made through it as
+/// `EditCommand`s. This is what `Editor::record` passes to its closure.
+///
+/// Commands address nodes by their paths from an anchor, which starts at the
+/// focus and moves up as the focus leaves it, so a script replays the same way
+/// from any focus that has the nodes it edits around it. Changes to data
+/// through `DerefMut` are recorded as `SetData` commands holding the data as it
+/// is when focus next moves or the next edit is made.
+///
+/// Commands carry data rather than subtrees, so edits that add a subtree
+/// (`push_child`, `insert_child`, `insert_sibling`, `replace_child`, and
+/// `swap`) cannot be recorded, and panic without changing the tree.
+pub struct Recorder<'a, E: 'a + Editor> {
+    editor: &'a mut E,
+    // The number of levels that the anchor is above the starting focus.
+    anchor_height: usize,
+    // The path of the focus from the anchor.
+    focus: FocusPath,
+    commands: Vec<EditCommand<E::Data>>,
+    // Whether the focus's data has been borrowed mutably since it was last
+    // recorded.
+    data_changed: bool,
+}
+
+impl<'a, E> Recorder<'a, E>
+    where E: Editor + DerefMut<Target=<E as Editor>::Data>, E::Data: Clone {
+        /// Starts recording edits made through `editor`, anchored at its focus.
+        pub fn new(editor: &'a mut E) -> Self {
+            Recorder { editor, anchor_height: 0, focus: FocusPath::new(), commands: Vec::new(),
+                       data_changed: false, }
+        }
+
+        /// Returns the path of the focus from the focus at which recording
+        /// started.
+        pub fn path(&self) -> RelativePath {
+            self.focus.path().indices().iter().fold(self.anchor(), |path, &index| path.child(index))
+        }
+
+        /// Stops recording and returns the edits made.
+        pub fn finish(mut self) -> EditScript<E::Data> {
+            self.record_data();
+            EditScript { anchor: self.anchor(), commands: self.commands, }
+        }
+
+        // Returns the path from the focus at which recording started to the
+        // anchor.
+        fn anchor(&self) -> RelativePath {
+            (0..self.anchor_height).fold(RelativePath::new(), |path, _| path.up())
+        }
+
+        // Records the focus's data if it may have changed.
+        fn record_data(&mut self) {
+            if self.data_changed {
+                self.data_changed = false;
+                let data = (**self.editor).clone();
+                self.commands.push(EditCommand::SetData { path: self.focus.path().clone(), data, });
+            }
+        }
+
+        // Makes sure that the focus's parent has a path from the anchor, by
+        // moving the anchor up to its parent if the focus is at the anchor.
+        // Returns false at the tree root.
+        fn reach_parent(&mut self) -> bool {
+            if self.focus.is_start() {
+                let index = match self.editor.index_in_parent() {
+                    Some(index) => index,
+                    None => return false,
+                };
+                self.anchor_height += 1;
+                for command in &mut self.commands {
+                    *command.path_mut() = util::prefixed(&[index], command.path());
+                }
+                self.focus.start_at_parent(index);
+            }
+            true
+        }
+    }
+
+fn unrecordable(method: &str) -> ! {
+    panic!["cannot record {}: edit scripts do not hold subtrees", method]
+}
+
+impl<'a, E: Editor + Deref> Deref for Recorder<'a, E> {
+    type Target = E::Target;
+
+    fn deref(&self) -> &E::Target {
+        self.editor
+    }
+}
+
+impl<'a, E: Editor + DerefMut> DerefMut for Recorder<'a, E> {
+    fn deref_mut(&mut self) -> &mut E::Target {
+        self.data_changed = true;
+        self.editor
+    }
+}
+
+impl<'a, E> Nav for Recorder<'a, E>
+    where E: Editor + DerefMut<Target=<E as Editor>::Data>, E::Data: Clone {
+        fn child_count(&self) -> usize {
+            self.editor.child_count()
+        }
+
+        fn at_root(&self) -> bool {
+            self.editor.at_root()
+        }
+
+        fn index_in_parent(&self) -> Option<usize> {
+            self.editor.index_in_parent()
+        }
+
+        fn seek_sibling(&mut self, offset: isize) -> bool {
+            self.record_data();
+            if offset == 0 {
+                return self.editor.seek_sibling(0)
+            }
+            if !self.reach_parent() {
+                return false
+            }
+            let moved = self.editor.seek_sibling(offset);
+            if moved {
+                self.focus.seek_sibling(offset);
+            }
+            moved
+        }
+
+        fn seek_child(&mut self, index: usize) -> bool {
+            self.record_data();
+            let moved = self.editor.seek_child(index);
+            if moved {
+                self.focus.seek_child(index);
+            }
+            moved
+        }
+
+        fn to_parent(&mut self) -> bool {
+            self.record_data();
+            if !self.reach_parent() {
+                return false
+            }
+            self.focus.pop();
+            self.editor.to_parent()
+        }
+
+        fn to_root(&mut self) {
+            while self.to_parent() {}
+        }
+    }
+
+impl<'a, E> Editor for Recorder<'a, E>
+    where E: Editor + DerefMut<Target=<E as Editor>::Data>, E::Data: Clone {
+        type Data = E::Data;
+        type Tree = E::Tree;
+
+        fn push_leaf(&mut self, data: E::Data) {
+            self.record_data();
+            let index = self.editor.child_count();
+            self.commands.push(EditCommand::PushChild { path: self.focus.path().clone(),
+                                                        data: data.clone(), });
+            self.editor.push_leaf(data);
+            self.focus.seek_child(index);
+        }
+
+        fn push_child(&mut self, _: E::Tree) {
+            unrecordable("push_child")
+        }
+
+        fn insert_leaf(&mut self, index: usize, data: E::Data) -> bool {
+            self.record_data();
+            let command = EditCommand::InsertChild { path: self.focus.path().clone(), index,
+                                                     data: data.clone(), };
+            let inserted = self.editor.insert_leaf(index, data);
+            if inserted {
+                self.commands.push(command);
+                self.focus.seek_child(index);
+            }
+            inserted
+        }
+
+        fn insert_child(&mut self, _: usize, _: E::Tree) -> bool {
+            unrecordable("insert_child")
+        }
+
+        fn insert_sibling_leaf(&mut self, offset: isize, data: E::Data) -> bool {
+            self.record_data();
+            if !self.reach_parent() {
+                return self.editor.insert_sibling_leaf(offset, data)
+            }
+            let inserted = self.editor.insert_sibling_leaf(offset, data.clone());
+            if inserted {
+                let index = self.focus.sibling_index(offset);
+                self.commands.push(EditCommand::InsertChild { path: self.focus.parent().unwrap(),
+                                                              index, data, });
+                self.focus.set_here_index(index);
+            }
+            inserted
+        }
+
+        fn insert_sibling(&mut self, _: isize, _: E::Tree) -> bool {
+            unrecordable("insert_sibling")
+        }
+
+        fn remove_focus_policy(&self) -> RemoveFocusPolicy {
+            self.editor.remove_focus_policy()
+        }
+
+        fn set_remove_focus_policy(&mut self, policy: RemoveFocusPolicy) -> bool {
+            self.editor.set_remove_focus_policy(policy)
+        }
+
+        fn remove(&mut self) -> E::Tree {
+            if self.editor.at_root() {
+                panic!["{}", NavError::AtRoot]
+            }
+            self.data_changed = false;
+            self.reach_parent();
+            self.commands.push(EditCommand::Remove { path: self.focus.path().clone(), });
+            util::remove_focus(self.editor, &mut self.focus).0
+        }
+
+        fn remove_child(&mut self, index: usize) -> Result<E::Tree, EditError> {
+            self.record_data();
+            let removed = self.editor.remove_child(index)?;
+            self.commands.push(EditCommand::Remove { path: self.focus.path().child(index), });
+            Ok(removed)
+        }
+
+        fn remove_sibling(&mut self, offset: isize) -> Option<E::Tree> {
+            if offset == 0 {
+                return if self.editor.at_root() { None } else { Some(self.remove()) }
+            }
+            self.record_data();
+            if !self.reach_parent() {
+                return None
+            }
+            let removed = self.editor.remove_sibling(offset);
+            if removed.is_some() {
+                let index = self.focus.sibling_index(offset);
+                self.commands.push(EditCommand::Remove { path: self.focus.parent().unwrap()
+                                                                   .child(index), });
+                self.focus.sibling_removed(offset);
+            }
+            removed
+        }
+
+        fn replace_child(&mut self, _: usize, _: E::Tree) -> E::Tree {
+            unrecordable("replace_child")
+        }
+
+        fn swap(&mut self, _: &mut E::Tree) {
+            unrecordable("swap")
+        }
+
+        fn swap_children(&mut self, index_a: usize, index_b: usize) -> bool {
+            self.record_data();
+            let swapped = self.editor.swap_children(index_a, index_b);
+            if swapped {
+                self.commands.push(EditCommand::SwapChildren { path: self.focus.path().clone(),
+                                                               a: index_a, b: index_b, });
+            }
+            swapped
+        }
+
+        fn swap_siblings(&mut self, offset_a: isize, offset_b: isize) -> bool {
+            self.record_data();
+            if !self.reach_parent() {
+                return self.editor.swap_siblings(offset_a, offset_b)
+            }
+            let swapped = self.editor.swap_siblings(offset_a, offset_b);
+            if swapped {
+                let (a, b) = (self.focus.sibling_index(offset_a), self.focus.sibling_index(offset_b));
+                self.commands.push(EditCommand::SwapChildren { path: self.focus.parent().unwrap(),
+                                                               a, b, });
+                self.focus.siblings_swapped(offset_a, offset_b);
+            }
+            swapped
+        }
+    }
+
+#[cfg(test)]
+mod test {
+    use ::{Editor, Nav};
+    use ::command::{ApplyError, ApplyErrorKind, EditCommand};
+    use ::owned_tree;
+    use ::path::{RelativePath, TreePath};
+
+    #[test]
+    fn records_and_replays_edits() {
+        let mut t = owned_tree!["r", ["a", ["b"]], ["c"]];
+        let script = {
+            let mut e = t.view_mut();
+            assert![e.seek_child(0)];
+            e.record(|r| {
+                r.push_leaf("d");
+                **r = "D";
+                assert![r.to_parent()];
+                assert_eq![RelativePath::new(), r.path()];
+                assert![r.insert_sibling_leaf(1, "e")];
+                assert![r.swap_siblings(0, -1)];
+                assert_eq![RelativePath::new().up().child(0), r.path()];
+                assert![r.seek_sibling(1)];
+                assert![r.remove_child(0).is_ok()];
+                assert![r.remove_sibling(1).is_some()];
+            })
+        };
+        assert_eq![owned_tree!["r", ["e"], ["a", ["D"]]], t];
+        let path = |s: &str| s.parse::<TreePath>().unwrap();
+        assert_eq![&RelativePath::new().up(), script.anchor()];
+        assert_eq![&[EditCommand::PushChild { path: path("/0"), data: "d", },
+                     EditCommand::SetData { path: path("/0/1"), data: "D", },
+                     EditCommand::InsertChild { path: path("/"), index: 1, data: "e", },
+                     EditCommand::SwapChildren { path: path("/"), a: 1, b: 0, },
+                     EditCommand::Remove { path: path("/1/0"), },
+                     EditCommand::Remove { path: path("/2"), }][..],
+                   script.commands()];
+
+        // Replaying from a deeper focus edits around that focus.
+        let mut u = owned_tree!["s", ["p", ["x", ["y"]], ["w"]]];
+        {
+            let mut e = u.view_mut();
+            assert![e.seek_child(0)];
+            assert![e.seek_child(0)];
+            assert_eq![Ok(()), script.replay(&mut e)];
+        }
+        assert_eq![owned_tree!["s", ["p", ["e"], ["x", ["D"]]]], u];
+
+        let mut v = owned_tree!["s", ["x", ["y"]]];
+        {
+            let mut e = v.view_mut();
+            assert![e.seek_child(0)];
+            assert_eq![Err(ApplyError { path: path("/"), kind: ApplyErrorKind::NoSuchChild(1), }),
+                       script.replay(&mut e)];
+        }
+        let mut w = owned_tree!["s"];
+        assert_eq![Err(ApplyError { path: path("/"), kind: ApplyErrorKind::NoSuchAnchor, }),
+                   script.replay(&mut w.view_mut())];
+    }
+
+    #[test]
+    fn sibling_edits_at_the_root_keep_the_anchor() {
+        let mut t = owned_tree!["r", ["a"]];
+        let script = t.view_mut().record(|r| {
+            assert![!r.seek_sibling(1)];
+            assert![!r.to_parent()];
+            r.push_leaf("b");
+        });
+        assert![script.anchor().is_empty()];
+        assert_eq![owned_tree!["r", ["a"], ["b"]], t];
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot record push_child")]
+    fn subtrees_cannot_be_recorded() {
+        let mut t = owned_tree!["r"];
+        t.view_mut().record(|r| r.push_child(owned_tree!["a"]));
+    }
+}
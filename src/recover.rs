@@ -0,0 +1,254 @@
+use ::Nav;
+use ::owned::Tree;
+use ::path::TreePath;
+use ::traversal::{preorder, VisitIterator};
+
+use std::ops::Deref;
+
+/// Node data of a tree built with error recovery, such as by a parser that
+/// keeps going past syntax errors: either data, or a placeholder standing in
+/// for input that could not be made sense of.
+///
+/// Error nodes may have children, for example the parts of a malformed
+/// construct that were recognized.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Recovered<T> {
+    Ok(T),
+    Error(String),
+}
+
+impl<T> Recovered<T> {
+    pub fn error<S: Into<String>>(message: S) -> Self {
+        Recovered::Error(message.into())
+    }
+
+    pub fn is_error(&self) -> bool {
+        matches![*self, Recovered::Error(_)]
+    }
+
+    /// Returns the data of a node that is not an error.
+    pub fn ok(&self) -> Option<&T> {
+        match *self {
+            Recovered::Ok(ref data) => Some(data),
+            Recovered::Error(_) => None,
+        }
+    }
+
+    /// Returns the message of an error node.
+    pub fn message(&self) -> Option<&str> {
+        match *self {
+            Recovered::Ok(_) => None,
+            Recovered::Error(ref message) => Some(message),
+        }
+    }
+}
+
+/// Returns the paths of the error nodes in the subtree under `nav`, relative to
+/// its focus, in pre-order.
+pub fn error_paths<N, T>(nav: N) -> Vec<TreePath>
+    where N: Nav + Clone + Deref<Target=Recovered<T>> {
+        preorder(nav).filter_nodes(|node| node.is_error()).map(|visit| visit.path).collect()
+    }
+
+/// Converts a tree with no error nodes into a tree of its data. Fails with the
+/// paths of the error nodes, in pre-order, if there are any.
+pub fn complete<T>(tree: Tree<Recovered<T>>) -> Result<Tree<T>, Vec<TreePath>> {
+    let errors = error_paths(tree.view());
+    if !errors.is_empty() {
+        return Err(errors)
+    }
+    let unwrap = |data| match data {
+        Recovered::Ok(data) => data,
+        Recovered::Error(_) => unreachable!(),
+    };
+    // Each stack entry is a node's data, its remaining children, and its
+    // converted children so far.
+    let (data, children) = tree.into_parts();
+    let mut stack = vec![(unwrap(data), children.into_iter(), Vec::new())];
+    loop {
+        let next = stack.last_mut().unwrap().1.next();
+        match next {
+            Some(child) => {
+                let (data, children) = child.into_parts();
+                stack.push((unwrap(data), children.into_iter(), Vec::new()));
+            },
+            None => {
+                let (data, _, children) = stack.pop().unwrap();
+                let tree = Tree::new(data, children);
+                match stack.last_mut() {
+                    Some(parent) => parent.2.push(tree),
+                    None => return Ok(tree),
+                }
+            },
+        }
+    }
+}
+
+/// Wrapper around a `Nav` over `Recovered` data that hides error nodes and
+/// their subtrees.
+///
+/// The remaining nodes are indexed among their siblings as though the hidden
+/// ones were not there, so traversals and searches over the wrapper see only
+/// the parts of the tree that were recovered, and dereference to their data.
+/// Counting or seeking children scans past the hidden ones, so these take time
+/// linear in the number of children.
+#[derive(Clone)]
+pub struct SkipErrors<N> {
+    nav: N,
+}
+
+impl<N, T> SkipErrors<N> where N: Nav + Clone + Deref<Target=Recovered<T>> {
+    /// Wraps `nav`, moving its focus to the root. Returns `None` if the root
+    /// is an error node, since then no node is visible.
+    pub fn new(mut nav: N) -> Option<Self> {
+        nav.to_root();
+        if nav.is_error() { None } else { Some(SkipErrors { nav, }) }
+    }
+
+    /// Returns the number of children the focus has in the underlying tree,
+    /// including error nodes.
+    pub fn hidden_child_count(&self) -> usize {
+        self.nav.child_count()
+    }
+
+    /// Returns the wrapped view.
+    pub fn into_inner(self) -> N {
+        self.nav
+    }
+
+    // Returns the underlying indices of the children of the focus that are not
+    // errors.
+    fn visible_children(&self) -> impl Iterator<Item=usize> + '_ {
+        (0..self.nav.child_count()).filter(move |&i| {
+            let mut child = self.nav.clone();
+            child.seek_child(i);
+            !child.is_error()
+        })
+    }
+}
+
+impl<N, T> Deref for SkipErrors<N> where N: Deref<Target=Recovered<T>> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.nav.ok().expect("focus of SkipErrors is never an error node")
+    }
+}
+
+impl<N, T> Nav for SkipErrors<N> where N: Nav + Clone + Deref<Target=Recovered<T>> {
+    fn child_count(&self) -> usize {
+        self.visible_children().count()
+    }
+
+    fn at_root(&self) -> bool {
+        self.nav.at_root()
+    }
+
+    /// Returns the index of the focus among its siblings that are not errors.
+    fn index_in_parent(&self) -> Option<usize> {
+        let index = self.nav.index_in_parent()?;
+        let mut parent = SkipErrors { nav: self.nav.clone(), };
+        parent.nav.to_parent();
+        Some(parent.visible_children().take_while(|&i| i < index).count())
+    }
+
+    fn seek_sibling(&mut self, offset: isize) -> bool {
+        let step = offset.signum();
+        let mut remaining = offset.unsigned_abs();
+        let mut probe = self.nav.clone();
+        while remaining > 0 {
+            if !probe.seek_sibling(step) {
+                return false
+            }
+            if !probe.is_error() {
+                remaining -= 1;
+            }
+        }
+        self.nav = probe;
+        true
+    }
+
+    fn seek_child(&mut self, index: usize) -> bool {
+        let child = self.visible_children().nth(index);
+        match child {
+            Some(i) => self.nav.seek_child(i),
+            None => false,
+        }
+    }
+
+    fn to_parent(&mut self) -> bool {
+        self.nav.to_parent()
+    }
+
+    fn to_root(&mut self) {
+        self.nav.to_root();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::Nav;
+    use ::owned::Tree;
+    use ::owned_tree;
+    use ::path::TreePath;
+    use ::recover::{complete, error_paths, Recovered, SkipErrors};
+    use ::traversal::preorder;
+
+    fn ok(data: &'static str) -> Recovered<&'static str> {
+        Recovered::Ok(data)
+    }
+
+    fn err(message: &str) -> Recovered<&'static str> {
+        Recovered::error(message)
+    }
+
+    // f(a, <error: b>, g(<error>, c))
+    fn partial() -> Tree<Recovered<&'static str>> {
+        owned_tree![ok("f"), [ok("a")], [err("unexpected ')'"), [ok("b")]],
+                    [ok("g"), [err("missing argument")], [ok("c")]]]
+    }
+
+    #[test]
+    fn collects_errors() {
+        let t = partial();
+        let paths: Vec<TreePath> = vec!["/1".parse().unwrap(), "/2/0".parse().unwrap()];
+        assert_eq![paths, error_paths(t.view())];
+        assert_eq![Some("unexpected ')'"), t.subtree(&paths[0]).unwrap().data().message()];
+        assert_eq![Err(paths), complete(t)];
+        assert_eq![Ok(owned_tree!["f", ["a"], ["g", ["c"]]]),
+                   complete(owned_tree![ok("f"), [ok("a")], [ok("g"), [ok("c")]]])];
+    }
+
+    #[test]
+    fn skips_error_subtrees() {
+        let t = partial();
+        let v = SkipErrors::new(t.view()).unwrap();
+        let labels: Vec<&str> = preorder(v.clone()).map(|visit| *visit.node).collect();
+        assert_eq![vec!["f", "a", "g", "c"], labels];
+
+        let mut v = v;
+        assert_eq![2, v.child_count()];
+        assert_eq![3, v.hidden_child_count()];
+        assert_eq![None, v.index_in_parent()];
+        assert![v.seek_child(1)];
+        assert_eq!["g", *v];
+        assert_eq![Some(1), v.index_in_parent()];
+        assert![v.seek_sibling(-1)];
+        assert_eq!["a", *v];
+        assert![! v.seek_sibling(2)];
+        assert_eq!["a", *v];
+        assert![v.seek_sibling(1)];
+        assert![v.seek_child(0)];
+        assert_eq!["c", *v];
+        assert_eq![Some(0), v.index_in_parent()];
+        assert![v.at_leaf()];
+        assert![! v.seek_sibling(-1)];
+        assert_eq!["c", *v];
+    }
+
+    #[test]
+    fn error_root_hides_everything() {
+        let t = owned_tree![err("nothing parsed"), [ok("a")]];
+        assert![SkipErrors::new(t.view()).is_none()];
+    }
+}
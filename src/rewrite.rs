@@ -0,0 +1,234 @@
+use ::owned::Tree;
+use ::path::TreePath;
+use ::template::{Template, TemplateNode};
+
+use std::collections::HashMap;
+
+/// Rule that replaces each subtree matching `pattern` with `replacement`.
+///
+/// A subtree matches the pattern if it has the same shape and data, except
+/// that each hole in the pattern matches any subtree. Holes that share a name
+/// must match equal subtrees. The replacement is instantiated with each hole
+/// bound to the subtree that it matched.
+pub struct Rule<T> {
+    name: String,
+    pattern: Template<T>,
+    replacement: Template<T>,
+}
+
+impl<T: Clone + PartialEq> Rule<T> {
+    /// Creates a rule. Panics if `replacement` has a hole that `pattern` does
+    /// not.
+    pub fn new<S: Into<String>>(name: S, pattern: Template<T>, replacement: Template<T>) -> Self {
+        let name = name.into();
+        for hole in replacement.holes() {
+            assert![pattern.holes().contains(&hole),
+                    "rule {:?} fills hole {:?}, which its pattern does not bind", name, hole];
+        }
+        Rule { name, pattern, replacement, }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the replacement for `tree`, or `None` if it does not match this
+    /// rule's pattern.
+    pub fn apply(&self, tree: &Tree<T>) -> Option<Tree<T>> {
+        let mut bindings = HashMap::new();
+        let mut stack = vec![(self.pattern.tree(), tree)];
+        while let Some((pattern, tree)) = stack.pop() {
+            match *pattern.data() {
+                TemplateNode::Hole(ref name) => match bindings.get(name) {
+                    Some(bound) if bound != tree => return None,
+                    Some(_) => (),
+                    None => { bindings.insert(name.clone(), tree.clone()); },
+                },
+                TemplateNode::Data(ref data) => {
+                    if data != tree.data() || pattern.children().len() != tree.children().len() {
+                        return None
+                    }
+                    stack.extend(pattern.children().iter().zip(tree.children()));
+                },
+            }
+        }
+        Some(self.replacement.instantiate(&bindings).expect("pattern binds every hole"))
+    }
+}
+
+/// Order in which a `Rewriter` looks for a subtree to rewrite.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Strategy {
+    /// Parents are tried before their children (pre-order).
+    TopDown,
+    /// Children are tried before their parents (post-order).
+    BottomUp,
+}
+
+/// Record of a rule rewriting the subtree at `path`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Firing {
+    pub rule: String,
+    pub path: TreePath,
+}
+
+/// Result of `Rewriter::rewrite`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Report {
+    /// Each rewrite, in the order it was made.
+    pub firings: Vec<Firing>,
+    /// Whether rewriting stopped because no rule applied anywhere, as opposed
+    /// to because the step budget ran out.
+    pub fixpoint: bool,
+}
+
+/// Ordered set of rules applied to a tree until none applies.
+///
+/// Each step rewrites one subtree: the first found in the order given by the
+/// strategy to which any rule applies, using the first rule that does. The
+/// search starts over from the root after every step.
+pub struct Rewriter<T> {
+    rules: Vec<Rule<T>>,
+    strategy: Strategy,
+    budget: usize,
+}
+
+impl<T: Clone + PartialEq> Rewriter<T> {
+    /// Creates a rewriter with no rules that searches in `strategy` order and
+    /// makes at most `budget` rewrites per call to `rewrite`.
+    pub fn new(strategy: Strategy, budget: usize) -> Self {
+        Rewriter { rules: Vec::new(), strategy, budget, }
+    }
+
+    /// Adds a rule, to be tried after those already added.
+    pub fn rule(mut self, rule: Rule<T>) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Rewrites `tree` until no rule applies or the step budget runs out.
+    pub fn rewrite(&self, tree: &mut Tree<T>) -> Report {
+        let mut firings = Vec::new();
+        loop {
+            let (path, rule, replacement) = match self.find(tree) {
+                None => return Report { firings, fixpoint: true, },
+                Some(_) if firings.len() == self.budget =>
+                    return Report { firings, fixpoint: false, },
+                Some(found) => found,
+            };
+            *tree.subtree_mut(&path).unwrap() = replacement;
+            firings.push(Firing { rule: self.rules[rule].name.clone(), path, });
+        }
+    }
+
+    // Finds the first subtree to rewrite, returning its path, the index of the
+    // rule that applies, and its replacement.
+    fn find(&self, tree: &Tree<T>) -> Option<(TreePath, usize, Tree<T>)> {
+        let try_rules = |subtree: &Tree<T>| self.rules.iter().enumerate()
+            .filter_map(|(i, rule)| rule.apply(subtree).map(|replacement| (i, replacement)))
+            .next();
+        // Each stack entry is a subtree, its path, and whether its children
+        // have already been pushed.
+        let mut stack = vec![(tree, TreePath::root(), false)];
+        while let Some((subtree, path, expanded)) = stack.pop() {
+            let try_here = match self.strategy {
+                Strategy::TopDown => true,
+                Strategy::BottomUp => expanded || subtree.children().is_empty(),
+            };
+            if try_here {
+                if let Some((rule, replacement)) = try_rules(subtree) {
+                    return Some((path, rule, replacement))
+                }
+                if self.strategy == Strategy::BottomUp {
+                    continue
+                }
+            } else {
+                stack.push((subtree, path.clone(), true));
+            }
+            for (i, child) in subtree.children().iter().enumerate().rev() {
+                stack.push((child, path.child(i), false));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::owned::Tree;
+    use ::owned_tree;
+    use ::path::TreePath;
+    use ::rewrite::{Firing, Rewriter, Rule, Strategy};
+    use ::template::{Template, TemplateNode};
+
+    fn node(d: &'static str, children: Vec<Tree<TemplateNode<&'static str>>>)
+            -> Tree<TemplateNode<&'static str>> {
+        Tree::new(TemplateNode::Data(d), children)
+    }
+
+    fn hole(name: &str) -> Tree<TemplateNode<&'static str>> {
+        Tree::leaf(TemplateNode::hole(name))
+    }
+
+    // x + 0 => x, and x * 1 => x.
+    fn identities() -> Vec<Rule<&'static str>> {
+        vec![Rule::new("add-zero", Template::new(node("+", vec![hole("x"), node("0", vec![])])),
+                       Template::new(hole("x"))),
+             Rule::new("mul-one", Template::new(node("*", vec![hole("x"), node("1", vec![])])),
+                       Template::new(hole("x")))]
+    }
+
+    fn firing(rule: &str, path: &str) -> Firing {
+        Firing { rule: rule.to_string(), path: path.parse::<TreePath>().unwrap(), }
+    }
+
+    #[test]
+    fn rewrites_to_fixpoint() {
+        let mut t = owned_tree!["f", ["+", ["*", ["a"], ["1"]], ["0"]], ["*", ["b"], ["1"]]];
+        let rewriter = identities().into_iter()
+            .fold(Rewriter::new(Strategy::TopDown, 10), Rewriter::rule);
+        let report = rewriter.rewrite(&mut t);
+        assert_eq![owned_tree!["f", ["a"], ["b"]], t];
+        assert![report.fixpoint];
+        assert_eq![vec![firing("add-zero", "/0"), firing("mul-one", "/0"), firing("mul-one", "/1")],
+                   report.firings];
+    }
+
+    #[test]
+    fn bottom_up_rewrites_children_first() {
+        let mut t = owned_tree!["f", ["+", ["*", ["a"], ["1"]], ["0"]]];
+        let rewriter = identities().into_iter()
+            .fold(Rewriter::new(Strategy::BottomUp, 10), Rewriter::rule);
+        let report = rewriter.rewrite(&mut t);
+        assert_eq![owned_tree!["f", ["a"]], t];
+        assert_eq![vec![firing("mul-one", "/0/0"), firing("add-zero", "/0")], report.firings];
+    }
+
+    #[test]
+    fn budget_limits_steps() {
+        // x => s(x) never reaches a fixpoint.
+        let grow = Rule::new("grow", Template::new(node("z", vec![])),
+                             Template::new(node("s", vec![node("z", vec![])])));
+        let mut t = owned_tree!["z"];
+        let report = Rewriter::new(Strategy::BottomUp, 3).rule(grow).rewrite(&mut t);
+        assert![! report.fixpoint];
+        assert_eq![3, report.firings.len()];
+        assert_eq![owned_tree!["s", ["s", ["s", ["z"]]]], t];
+    }
+
+    #[test]
+    fn repeated_holes_match_equal_subtrees() {
+        // x - x => 0
+        let rule = Rule::new("sub-self", Template::new(node("-", vec![hole("x"), hole("x")])),
+                             Template::new(node("0", vec![])));
+        assert_eq![None, rule.apply(&owned_tree!["-", ["a"], ["b"]])];
+        assert_eq![Some(owned_tree!["0"]),
+                   rule.apply(&owned_tree!["-", ["a", ["c"]], ["a", ["c"]]])];
+    }
+
+    #[test]
+    #[should_panic(expected = "rule \"bad\" fills hole \"y\"")]
+    fn replacement_holes_must_be_bound() {
+        Rule::new("bad", Template::new(hole("x")), Template::new(hole("y")));
+    }
+}
@@ -0,0 +1,355 @@
+use ::Nav;
+use ::error::Violation;
+use ::guard::{EditOp, Guarded, NewNode};
+use ::owned::{TreeRef, TreeViewMut};
+use ::traversal::preorder;
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::ops::Deref;
+use std::rc::Rc;
+
+/// Pattern that the kinds of a node's children, in order, must match.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Pattern<K> {
+    /// A single child of the given kind.
+    Kind(K),
+    /// A single child of any kind.
+    Any,
+    /// Each pattern in turn. An empty sequence matches no children.
+    Sequence(Vec<Pattern<K>>),
+    /// Any one of the patterns.
+    Choice(Vec<Pattern<K>>),
+    /// `pattern` repeated at least `min` times, and at most `max` times if
+    /// `max` is given.
+    Repeat { pattern: Box<Pattern<K>>, min: usize, max: Option<usize> },
+}
+
+impl<K> Pattern<K> {
+    /// Returns a pattern that matches only no children, as for leaves.
+    pub fn empty() -> Self {
+        Pattern::Sequence(Vec::new())
+    }
+
+    pub fn zero_or_more(pattern: Pattern<K>) -> Self {
+        Pattern::Repeat { pattern: Box::new(pattern), min: 0, max: None, }
+    }
+
+    pub fn one_or_more(pattern: Pattern<K>) -> Self {
+        Pattern::Repeat { pattern: Box::new(pattern), min: 1, max: None, }
+    }
+
+    pub fn optional(pattern: Pattern<K>) -> Self {
+        Pattern::Repeat { pattern: Box::new(pattern), min: 0, max: Some(1), }
+    }
+}
+
+impl<K: Eq> Pattern<K> {
+    /// Returns true iff `kinds` matches this pattern, or, if `partial`, iff
+    /// `kinds` is a subsequence of some sequence of kinds that does. A partial
+    /// match can be completed by inserting children.
+    fn matches(&self, kinds: &[K], partial: bool) -> bool {
+        self.ends(kinds, &Some(0).into_iter().collect(), partial).contains(&kinds.len())
+    }
+
+    // Returns the positions in `kinds` at which a match of this pattern
+    // starting at any of `starts` can end. When matching partially, each kind
+    // in the pattern may also be skipped.
+    fn ends(&self, kinds: &[K], starts: &BTreeSet<usize>, partial: bool) -> BTreeSet<usize> {
+        match *self {
+            Pattern::Kind(ref kind) => starts.iter()
+                .flat_map(|&start| {
+                    let skip = if partial { Some(start) } else { None };
+                    let step = if kinds.get(start) == Some(kind) { Some(start + 1) } else { None };
+                    skip.into_iter().chain(step)
+                })
+                .collect(),
+            Pattern::Any => starts.iter()
+                .flat_map(|&start| {
+                    let skip = if partial { Some(start) } else { None };
+                    let step = if start < kinds.len() { Some(start + 1) } else { None };
+                    skip.into_iter().chain(step)
+                })
+                .collect(),
+            Pattern::Sequence(ref patterns) =>
+                patterns.iter().fold(starts.clone(), |at, p| p.ends(kinds, &at, partial)),
+            Pattern::Choice(ref patterns) =>
+                patterns.iter().flat_map(|p| p.ends(kinds, starts, partial)).collect(),
+            Pattern::Repeat { ref pattern, min, max } => {
+                let mut ends = if min == 0 { starts.clone() } else { BTreeSet::new() };
+                let mut at = starts.clone();
+                let mut count = 0;
+                while max.is_none_or(|max| count < max) && !at.is_empty() {
+                    at = pattern.ends(kinds, &at, partial);
+                    count += 1;
+                    if count >= min {
+                        // Once past `min`, repetitions that reach no new
+                        // positions cannot lead anywhere new.
+                        if at.is_subset(&ends) {
+                            break
+                        }
+                        ends.extend(at.iter().cloned());
+                    }
+                }
+                ends
+            },
+        }
+    }
+}
+
+type KindOf<T, K> = Rc<dyn Fn(&T) -> K>;
+
+/// Declaration of the kinds of node that may appear in a tree and the children
+/// that each kind of node may have.
+///
+/// The kind of each node is computed from its data by the function given to
+/// `new`. A tree is valid if every node's kind is declared and the kinds of
+/// its children match the pattern declared for it.
+pub struct Schema<T, K> {
+    kind_of: KindOf<T, K>,
+    rules: HashMap<K, Pattern<K>>,
+}
+
+impl<T, K> Schema<T, K> where K: Clone + Debug + Eq + Hash {
+    /// Creates a schema with no kinds declared, in which `kind_of` gives the
+    /// kind of a node from its data.
+    pub fn new<F>(kind_of: F) -> Self where F: Fn(&T) -> K + 'static {
+        Schema { kind_of: Rc::new(kind_of), rules: HashMap::new(), }
+    }
+
+    /// Declares a kind of node whose children must match `children`,
+    /// replacing any earlier declaration of it.
+    pub fn declare(mut self, kind: K, children: Pattern<K>) -> Self {
+        self.rules.insert(kind, children);
+        self
+    }
+
+    /// Returns the kind of a node with the given data.
+    pub fn kind_of(&self, data: &T) -> K {
+        (self.kind_of)(data)
+    }
+
+    /// Checks the subtree under `nav`, returning a violation for each node
+    /// whose kind is not declared or whose children do not match its pattern,
+    /// in pre-order. Paths are relative to `nav`.
+    pub fn validate<N>(&self, nav: N) -> Vec<Violation>
+        where N: Nav + Clone + Deref<Target=T> {
+            self.check(nav, false)
+        }
+
+    /// Wraps `editor` so that it refuses edits after which the tree could not
+    /// be made valid by inserting more nodes. Edits that only remove nodes are
+    /// always allowed, so a tree built or edited this way may still need
+    /// nodes added before `validate` finds it valid.
+    pub fn editor<'a>(&self, editor: TreeViewMut<'a, T>) -> Guarded<TreeViewMut<'a, T>>
+        where T: 'static, K: 'static {
+            let schema = Schema { kind_of: self.kind_of.clone(), rules: self.rules.clone(), };
+            let mut guarded = Guarded::new(editor);
+            guarded.set_guard(move |op, context| schema.check_edit(op, context));
+            guarded
+        }
+
+    fn check<N>(&self, nav: N, partial: bool) -> Vec<Violation>
+        where N: Nav + Clone + Deref<Target=T> {
+            let mut violations = Vec::new();
+            for visit in preorder(nav) {
+                let kinds: Vec<K> = (0..visit.node.child_count())
+                    .map(|i| {
+                        let mut child = visit.node.clone();
+                        child.seek_child(i);
+                        self.kind_of(&child)
+                    })
+                    .collect();
+                if let Err(message) = self.check_children(&self.kind_of(&visit.node), &kinds,
+                                                          partial) {
+                    violations.push(Violation::at(visit.path, message));
+                }
+            }
+            violations
+        }
+
+    fn check_children(&self, kind: &K, kinds: &[K], partial: bool) -> Result<(), String> {
+        match self.rules.get(kind) {
+            None => Err(format!["kind {:?} is not declared", kind]),
+            Some(pattern) if !pattern.matches(kinds, partial) =>
+                Err(format!["children {:?} do not match the pattern for kind {:?}", kinds, kind]),
+            Some(_) => Ok(()),
+        }
+    }
+
+    // Checks that after `op`, the node it adds (if any) and the children of
+    // the node it changes can be completed into a valid tree.
+    fn check_edit(&self, op: &EditOp<T, ::owned::Tree<T>>, context: &TreeViewMut<T>)
+                  -> Result<(), Violation> {
+        let focus = context.tree_ref();
+        let parent = context.parent_ref();
+        // The node whose children change, and their kinds after the edit.
+        let (changed, kinds) = match *op {
+            EditOp::InsertChild { index, ref child } => {
+                let mut kinds = self.child_kinds(&focus);
+                if index > kinds.len() {
+                    return Ok(())
+                }
+                kinds.insert(index, self.check_new_node(child)?);
+                (focus, kinds)
+            },
+            EditOp::InsertSibling { offset, ref sibling } => {
+                let parent = match parent {
+                    Some(parent) => parent,
+                    None => return Ok(()),
+                };
+                let mut kinds = self.child_kinds(&parent);
+                let index = parent.child_position(focus.id()).unwrap() as isize + offset;
+                if index < 0 || index as usize > kinds.len() {
+                    return Ok(())
+                }
+                kinds.insert(index as usize, self.check_new_node(sibling)?);
+                (parent, kinds)
+            },
+            EditOp::Swap { other } => {
+                let parent = match parent {
+                    Some(parent) => parent,
+                    None => return self.check_new_node(&NewNode::Tree(other)).map(|_| ()),
+                };
+                let mut kinds = self.child_kinds(&parent);
+                let index = parent.child_position(focus.id()).unwrap();
+                kinds[index] = self.check_new_node(&NewNode::Tree(other))?;
+                (parent, kinds)
+            },
+            EditOp::ReplaceChild { index, child } => {
+                let mut kinds = self.child_kinds(&focus);
+                if index >= kinds.len() {
+                    return Ok(())
+                }
+                kinds[index] = self.check_new_node(&NewNode::Tree(child))?;
+                (focus, kinds)
+            },
+            EditOp::SwapChildren { a, b } => {
+                let mut kinds = self.child_kinds(&focus);
+                if a >= kinds.len() || b >= kinds.len() {
+                    return Ok(())
+                }
+                kinds.swap(a, b);
+                (focus, kinds)
+            },
+            EditOp::SwapSiblings { a, b } => {
+                let parent = match parent {
+                    Some(parent) => parent,
+                    None => return Ok(()),
+                };
+                let mut kinds = self.child_kinds(&parent);
+                let here = parent.child_position(focus.id()).unwrap() as isize;
+                let (a, b) = (here + a, here + b);
+                if a < 0 || b < 0 || a as usize >= kinds.len() || b as usize >= kinds.len() {
+                    return Ok(())
+                }
+                kinds.swap(a as usize, b as usize);
+                (parent, kinds)
+            },
+            EditOp::Remove | EditOp::RemoveChild { .. } | EditOp::RemoveSibling { .. } |
+            EditOp::TakeChildren => return Ok(()),
+        };
+        self.check_children(&self.kind_of(changed.data()), &kinds, true).map_err(Violation::new)
+    }
+
+    // Checks that a node to be added can be completed into a valid subtree,
+    // and returns its kind.
+    fn check_new_node(&self, node: &NewNode<T, ::owned::Tree<T>>) -> Result<K, Violation> {
+        match *node {
+            NewNode::Leaf(data) => {
+                let kind = self.kind_of(data);
+                self.check_children(&kind, &[], true).map_err(Violation::new)?;
+                Ok(kind)
+            },
+            NewNode::Tree(tree) => match self.check(tree.view(), true).into_iter().next() {
+                Some(violation) => Err(violation),
+                None => Ok(self.kind_of(tree.data())),
+            },
+        }
+    }
+
+    fn child_kinds(&self, tree: &TreeRef<T>) -> Vec<K> {
+        tree.children().map(|child| self.kind_of(child.data())).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ::{Editor, Nav};
+    use ::error::Violation;
+    use ::owned_tree;
+    use ::path::TreePath;
+    use ::schema::{Pattern, Schema};
+
+    // A document is a title followed by any number of sections, each of which
+    // holds paragraphs and, optionally, a figure.
+    fn schema() -> Schema<&'static str, &'static str> {
+        Schema::new(|data: &&'static str| *data)
+            .declare("doc", Pattern::Sequence(vec![
+                Pattern::Kind("title"), Pattern::zero_or_more(Pattern::Kind("section"))]))
+            .declare("section", Pattern::Sequence(vec![
+                Pattern::one_or_more(Pattern::Kind("para")),
+                Pattern::optional(Pattern::Kind("figure"))]))
+            .declare("title", Pattern::empty())
+            .declare("para", Pattern::empty())
+            .declare("figure", Pattern::empty())
+    }
+
+    #[test]
+    fn pattern_matches_kinds() {
+        let p = Pattern::Sequence(vec![
+            Pattern::Kind('a'),
+            Pattern::Choice(vec![Pattern::Kind('b'), Pattern::Any]),
+            Pattern::Repeat { pattern: Box::new(Pattern::Kind('c')), min: 1, max: Some(2), }]);
+        assert![p.matches(&['a', 'b', 'c'], false)];
+        assert![p.matches(&['a', 'x', 'c', 'c'], false)];
+        assert![! p.matches(&['a', 'b'], false)];
+        assert![! p.matches(&['a', 'b', 'c', 'c', 'c'], false)];
+        assert![p.matches(&['a', 'c'], true)];
+        assert![p.matches(&[], true)];
+        assert![! p.matches(&['c', 'a'], true)];
+        assert![Pattern::zero_or_more(Pattern::optional(Pattern::Kind('a')))
+                .matches(&['a', 'a'], false)];
+    }
+
+    #[test]
+    fn validate_reports_each_bad_node() {
+        let s = schema();
+        assert![s.validate(owned_tree!["doc", ["title"], ["section", ["para"], ["figure"]]].view())
+                .is_empty()];
+        let t = owned_tree!["doc", ["section", ["figure"]], ["title", ["para"]], ["quote"]];
+        let paths: Vec<String> = s.validate(t.view()).iter()
+            .map(|v| v.path().unwrap().to_string())
+            .collect();
+        assert_eq![vec!["/", "/0", "/1", "/2"], paths];
+        assert_eq![Violation::at(TreePath::root().child(2), "kind \"quote\" is not declared"),
+                   s.validate(t.view()).pop().unwrap()];
+    }
+
+    #[test]
+    fn editor_refuses_invalid_inserts() {
+        let s = schema();
+        let mut t = owned_tree!["doc"];
+        {
+            let mut e = s.editor(t.view_mut());
+            // A section may come before its title is added, but not vice versa.
+            e.push_leaf("section");
+            assert![! e.insert_sibling_leaf(1, "title")];
+            assert![e.insert_sibling_leaf(0, "title")];
+            assert![e.seek_sibling(1)];
+            assert![! e.insert_child(0, owned_tree!["para", ["para"]])];
+            assert![e.take_violation().unwrap().path().is_some()];
+            e.push_leaf("figure");
+            assert![! e.insert_sibling_leaf(1, "para")];
+            assert![e.insert_sibling_leaf(0, "para")];
+            assert![! e.swap_siblings(0, 1)];
+            assert![e.to_parent()];
+            assert![e.to_parent()];
+            assert![! e.insert_leaf(0, "quote")];
+            assert_eq!["kind \"quote\" is not declared", e.take_violation().unwrap().message()];
+        }
+        assert_eq![owned_tree!["doc", ["title"], ["section", ["para"], ["figure"]]], t];
+        assert![s.validate(t.view()).is_empty()];
+    }
+}
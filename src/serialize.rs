@@ -0,0 +1,184 @@
+use ::Nav;
+
+use std::fmt;
+use std::io::{self, Write};
+use std::ops::Deref;
+
+/// Output format for tree serialization.
+///
+/// A codec receives a stream of events describing a depth-first traversal of a
+/// tree: `enter` is called when a node is first visited, and `leave` is called
+/// after all of the node's descendants have been visited.
+pub trait Codec<T> {
+    /// Prepares to write a new tree, forgetting anything about trees written
+    /// before. This is called before the first `enter` of each
+    /// serialization. The default implementation does nothing.
+    fn start(&mut self) {}
+
+    /// Writes the start of the node with the given data. `sibling_index` is the
+    /// position of the node among its siblings (and is 0 for the node at which
+    /// serialization started).
+    fn enter<W: Write>(&mut self, writer: &mut W, data: &T, sibling_index: usize)
+                       -> io::Result<()>;
+
+    /// Writes the end of the most recently entered node that has not yet been
+    /// left.
+    fn leave<W: Write>(&mut self, writer: &mut W) -> io::Result<()>;
+}
+
+/// Codec that writes s-expressions in the same format as the `Debug`
+/// implementations of tree types, e.g., `("a" ("b") ("c"))`.
+pub struct SExprCodec {
+    started: bool,
+}
+
+impl SExprCodec {
+    pub fn new() -> Self {
+        SExprCodec { started: false, }
+    }
+}
+
+impl Default for SExprCodec {
+    fn default() -> Self {
+        SExprCodec::new()
+    }
+}
+
+impl<T: fmt::Debug> Codec<T> for SExprCodec {
+    fn start(&mut self) {
+        self.started = false;
+    }
+
+    fn enter<W: Write>(&mut self, writer: &mut W, data: &T, _: usize) -> io::Result<()> {
+        if self.started {
+            writer.write_all(b" ")?;
+        }
+        self.started = true;
+        write!(writer, "({:?}", data)
+    }
+
+    fn leave<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(b")")
+    }
+}
+
+/// Codec that writes JSON objects of the form `{"data":...,"children":[...]}`,
+/// in which node data is written as a JSON string containing its `Display`
+/// representation.
+pub struct JsonCodec;
+
+impl<T: fmt::Display> Codec<T> for JsonCodec {
+    fn enter<W: Write>(&mut self, writer: &mut W, data: &T, sibling_index: usize)
+                       -> io::Result<()> {
+        if sibling_index > 0 {
+            writer.write_all(b",")?;
+        }
+        writer.write_all(b"{\"data\":\"")?;
+        for c in data.to_string().chars() {
+            match c {
+                '"' => writer.write_all(b"\\\"")?,
+                '\\' => writer.write_all(b"\\\\")?,
+                '\n' => writer.write_all(b"\\n")?,
+                '\r' => writer.write_all(b"\\r")?,
+                '\t' => writer.write_all(b"\\t")?,
+                c if (c as u32) < 0x20 => write!(writer, "\\u{:04x}", c as u32)?,
+                c => write!(writer, "{}", c)?,
+            }
+        }
+        writer.write_all(b"\",\"children\":[")
+    }
+
+    fn leave<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(b"]}")
+    }
+}
+
+/// Serializes the subtree rooted at the focus of `nav` to `writer`.
+///
+/// Output is written incrementally as `nav` is moved through the subtree, so
+/// no intermediate buffers are materialized. Aside from whatever `nav` itself
+/// requires, memory use is proportional to the depth of the subtree.
+pub fn serialize_events<N, T, W, C>(mut nav: N, writer: &mut W, codec: &mut C)
+                                    -> io::Result<()>
+    where N: Nav + Deref<Target=T>, W: Write, C: Codec<T> {
+        // Sibling indices of the nodes on the path from the starting node to
+        // the focus.
+        let mut indices = vec![0usize];
+        codec.start();
+        codec.enter(writer, &*nav, 0)?;
+        loop {
+            if nav.seek_child(0) {
+                indices.push(0);
+                codec.enter(writer, &*nav, 0)?;
+                continue
+            }
+            loop {
+                codec.leave(writer)?;
+                if indices.len() == 1 {
+                    return Ok(())
+                }
+                if nav.seek_sibling(1) {
+                    let index = indices.last_mut().unwrap();
+                    *index += 1;
+                    codec.enter(writer, &*nav, *index)?;
+                    break
+                }
+                nav.to_parent();
+                indices.pop();
+            }
+        }
+    }
+
+#[cfg(test)]
+mod test {
+    use ::Nav;
+    use ::serialize::{serialize_events, JsonCodec, SExprCodec};
+    use ::{owned_tree, shared_tree};
+
+    fn sexpr<N, T>(nav: N) -> String
+        where N: Nav + ::std::ops::Deref<Target=T>, T: ::std::fmt::Debug {
+            let mut out = Vec::new();
+            serialize_events(nav, &mut out, &mut SExprCodec::new()).unwrap();
+            String::from_utf8(out).unwrap()
+        }
+
+    #[test]
+    fn sexpr_matches_debug() {
+        for t in [owned_tree!["a"],
+                      owned_tree!["a", ["b"], ["c"]],
+                      owned_tree!["a", ["b"], ["c", ["d"], ["e"]]],
+                      owned_tree!["a", ["b", ["c", ["d"]]], ["e", ["f"]]]] {
+            assert_eq![sexpr(t.view()), format!["{:?}", t]];
+        }
+    }
+
+    #[test]
+    fn sexpr_of_subtree() {
+        let t = shared_tree!["a", ["b"], ["c", ["d"], ["e"]], ["f"]];
+        let mut v = t.view();
+        assert![v.seek_child(1)];
+        assert_eq![sexpr(v), "(\"c\" (\"d\") (\"e\"))"];
+    }
+
+    #[test]
+    fn sexpr_codec_is_reusable() {
+        let mut codec = SExprCodec::new();
+        let mut out = Vec::new();
+        serialize_events(owned_tree!["a", ["b"]].view(), &mut out, &mut codec).unwrap();
+        out.push(b'\n');
+        serialize_events(owned_tree!["c"].view(), &mut out, &mut codec).unwrap();
+        assert_eq![String::from_utf8(out).unwrap(), "(\"a\" (\"b\"))\n(\"c\")"];
+    }
+
+    #[test]
+    fn json_output() {
+        let t = owned_tree!["a", ["b\"q"], ["c", ["d"]]];
+        let mut out = Vec::new();
+        serialize_events(t.view(), &mut out, &mut JsonCodec).unwrap();
+        assert_eq![String::from_utf8(out).unwrap(),
+                   concat!["{\"data\":\"a\",\"children\":[",
+                           "{\"data\":\"b\\\"q\",\"children\":[]},",
+                           "{\"data\":\"c\",\"children\":[",
+                           "{\"data\":\"d\",\"children\":[]}]}]}"]];
+    }
+}
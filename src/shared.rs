@@ -1,14 +1,21 @@
-use ::{Editor, Nav};
-use ::util::{ChildIndex, SiblingIndex};
+use ::{Editor, Nav, RemoveFocusPolicy};
+use ::error::{EditError, NavError};
+use ::owned;
+use ::path::TreePath;
+use ::traversal::EnumerateSiblings;
+use ::index::{ChildIndex, SiblingIndex};
 
 use std::borrow::Borrow;
 use std::cell::{Ref, RefCell, RefMut};
 use std::clone::Clone;
+use std::cmp::Ordering;
+use std::error::Error;
 use std::fmt;
 use std::mem;
 use std::ops::Deref;
 use std::rc::Rc;
 use std::result::Result;
+use std::slice;
 
 struct TreeInternal<T> {
     data: T, children: RefCell<Vec<Tree<T>>>,
@@ -38,10 +45,11 @@ impl<T> Tree<T> {
         self.internal.children.borrow_mut().push(child);
     }
 
-    pub fn remove_child(&mut self, index: usize) {
-        assert![index < self.internal.children.borrow().len(),
-                "cannot remove child at index {} (only {} children)", index, self.internal.children.borrow().len()];
-        self.internal.children.borrow_mut().remove(index);
+    /// Removes the child at `index` and returns it.
+    pub fn remove_child(&mut self, index: usize) -> Result<Tree<T>, EditError> {
+        let mut children = self.internal.children.borrow_mut();
+        let index = ChildIndex::check(children.len(), index)?;
+        Ok(children.remove(index))
     }
 
     pub fn insert_child(&mut self, index: usize, child: Tree<T>) {
@@ -55,13 +63,120 @@ impl<T> Tree<T> {
         }
     }
 
+    /// Converts this into an owned tree, moving its data. Fails if any of its
+    /// nodes is referenced from anywhere other than its parent (or, for the
+    /// root, `self`), reporting the first such node in pre-order.
+    ///
+    /// On failure no node is changed, but `self` is consumed, so the tree
+    /// stays reachable only through its other references.
+    pub fn try_into_owned(self) -> Result<owned::Tree<T>, SharedError> {
+        // Each node is referenced by its parent and by its handle on the stack.
+        let mut stack = vec![(self.clone(), TreePath::root())];
+        while let Some((tree, path)) = stack.pop() {
+            if Rc::strong_count(&tree.internal) != 2 {
+                return Err(SharedError { path, })
+            }
+            for (i, child) in tree.internal.children.borrow().iter().enumerate().rev() {
+                stack.push((child.clone(), path.child(i)));
+            }
+        }
+        Ok(self.convert(Tree::into_parts))
+    }
+
+    /// Converts this into an owned tree, moving the data of nodes that are only
+    /// referenced from their parents and cloning the rest.
+    pub fn into_owned(self) -> owned::Tree<T> where T: Clone {
+        self.convert(|tree| match Rc::try_unwrap(tree.internal) {
+            Result::Ok(internal) => (internal.data, internal.children.into_inner()),
+            Result::Err(internal) => (internal.data.clone(), internal.children.borrow().clone()),
+        })
+    }
+
+    fn convert<F>(self, mut into_parts: F) -> owned::Tree<T>
+        where F: FnMut(Tree<T>) -> (T, Vec<Tree<T>>) {
+            let (data, children) = into_parts(self);
+            let mut stack = vec![(data, children.into_iter(), Vec::new())];
+            loop {
+                let next = stack.last_mut().unwrap().1.next();
+                match next {
+                    Some(child) => {
+                        let (data, children) = into_parts(child);
+                        let converted = Vec::with_capacity(children.len());
+                        stack.push((data, children.into_iter(), converted));
+                    },
+                    None => {
+                        let (data, _, children) = stack.pop().unwrap();
+                        let tree = owned::Tree::new(data, children);
+                        match stack.last_mut() {
+                            Some(parent) => parent.2.push(tree),
+                            None => return tree,
+                        }
+                    },
+                }
+            }
+        }
+
     pub fn view<'s>(&'s self) -> TreeView<'s, T> {
         TreeView::new(self)
     }
+
+    /// Returns a view of this tree that holds its own handles to the tree's
+    /// nodes rather than borrowing it.
+    pub fn owning_view(&self) -> OwningView<T> {
+        OwningView::new(self.clone())
+    }
+
+    /// Returns an editor of this tree that copies shared nodes on write.
+    ///
+    /// Before an edit changes the children of a node, each node from the root
+    /// down to that one which has handles other than its parent's (or, for the
+    /// root, `self`) is replaced with a copy of itself whose children are
+    /// handles to the original's. The edit is then seen only through `self`,
+    /// while other handles keep the tree as it was, and subtrees the edit does
+    /// not reach remain shared between the two.
+    pub fn view_mut(&mut self) -> CowEditor<'_, T> where T: Clone {
+        CowEditor::new(self)
+    }
+
+    /// Returns an editor of this tree that makes edits in place, so they are
+    /// seen through every handle to the nodes they change, including those
+    /// held as children by other trees.
+    pub fn view_mut_in_place(&mut self) -> TreeEditor<'_, T> {
+        TreeEditor { root: self, path: Vec::new(),
+                     remove_focus_policy: RemoveFocusPolicy::default(), }
+    }
+
+    // Returns a new node with a clone of this one's data and handles to its
+    // children.
+    fn shallow_copy(&self) -> Tree<T> where T: Clone {
+        Tree::new(self.internal.data.clone(), self.internal.children.borrow().clone())
+    }
+}
+
+/// Error returned when converting a tree with a node that is referenced from
+/// more than one place.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SharedError {
+    /// Path of the first shared node found.
+    pub path: TreePath,
 }
 
+impl fmt::Display for SharedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "node at {} is shared", self.path)
+    }
+}
+
+impl Error for SharedError {}
+
 /// Creates a new reference to this tree, such that modifying the reference also
 /// modifies the original tree.
+impl<T> From<owned::Tree<T>> for Tree<T> {
+    fn from(tree: owned::Tree<T>) -> Self {
+        tree.fold(Tree::new)
+    }
+}
+
 impl<T> Clone for Tree<T> {
     fn clone(&self) -> Self {
         Tree { internal: self.internal.clone(), }
@@ -76,7 +191,8 @@ impl<T: PartialEq> PartialEq<Tree<T>> for Tree<T> {
             match (x_stack.pop(), y_stack.pop()) {
                 (None, None) => return true,
                 (Some(x), Some(y)) => {
-                    if x.internal.data == y.internal.data {
+                    if x.internal.data == y.internal.data
+                        && x.internal.children.borrow().len() == y.internal.children.borrow().len() {
                         for child in x.internal.children.borrow().iter() {
                             x_stack.push(child.clone());
                         }
@@ -142,6 +258,17 @@ impl<'a, T: 'a> TreeView<'a, T> {
             Some(&(ref siblings, ref index)) => &siblings[*index],
         }
     }
+
+    /// Iterates over the data of the focus and its siblings, in order, with
+    /// each one's offset from the focus.
+    pub fn enumerate_siblings(&self)
+                              -> EnumerateSiblings<impl ExactSizeIterator<Item=&T> + '_> {
+        let (siblings, here_index) = match self.path.last() {
+            None => (slice::from_ref(self.root), 0),
+            Some(&(ref siblings, here_index)) => (&siblings[..], here_index),
+        };
+        EnumerateSiblings::new(siblings.iter().map(|s| &s.internal.data), here_index)
+    }
 }
 
 /// Due to the internal representation of the path back from the tree root, this
@@ -159,6 +286,23 @@ impl<'a, T: 'a> Clone for TreeView<'a, T> {
     }
 }
 
+impl<'a, T: 'a> PartialEq for TreeView<'a, T> {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.root.internal, &other.root.internal)
+            && self.path.iter().map(|&(_, i)| i).eq(other.path.iter().map(|&(_, i)| i))
+    }
+}
+
+impl<'a, T: 'a> PartialOrd for TreeView<'a, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if Rc::ptr_eq(&self.root.internal, &other.root.internal) {
+            Some(self.path.iter().map(|&(_, i)| i).cmp(other.path.iter().map(|&(_, i)| i)))
+        } else {
+            None
+        }
+    }
+}
+
 impl<'a, T: 'a> Deref for TreeView<'a, T> {
     type Target = T;
 
@@ -207,6 +351,10 @@ impl<'a, T: 'a> Nav for TreeView<'a, T> {
         self.path.is_empty()
     }
 
+    fn index_in_parent(&self) -> Option<usize> {
+        self.path.last().map(|&(_, index)| index)
+    }
+
     fn to_parent(&mut self) -> bool {
         match self.path.pop() {
             Some(_) => return true,
@@ -219,9 +367,142 @@ impl<'a, T: 'a> Nav for TreeView<'a, T> {
     }
 }
 
+/// Navigator that holds handles to the nodes on its path rather than borrows,
+/// so that it is not tied to the lifetime of a `Tree`.
+///
+/// An `OwningView` can be cloned cheaply, stored in structs, and moved into
+/// callbacks. Because it keeps the nodes it has visited alive, it continues to
+/// work if the tree is edited elsewhere, seeing each node's children as they
+/// are when it navigates. If its focus or an ancestor is removed from the tree,
+/// it continues to navigate the removed subtree. Like `Tree`, it cannot be
+/// sent between threads.
+pub struct OwningView<T> {
+    // Each ancestor of the focus, with the index of the next node on the path
+    // among its children.
+    path: Vec<(Tree<T>, usize)>,
+    here: Tree<T>,
+}
+
+impl<T> OwningView<T> {
+    /// Creates a view focused on the root of `tree`.
+    pub fn new(tree: Tree<T>) -> Self {
+        OwningView { path: Vec::new(), here: tree, }
+    }
+
+    /// Returns a handle to the subtree rooted at the focus.
+    pub fn subtree(&self) -> Tree<T> {
+        self.here.clone()
+    }
+
+    /// Returns the index of the focus among its siblings, or `None` at the
+    /// root.
+    pub fn here_index(&self) -> Option<usize> {
+        self.path.last().map(|&(_, index)| index)
+    }
+
+    // Returns the root of the tree that this view navigates, which is the
+    // removed subtree if an ancestor of the focus has been removed.
+    fn root(&self) -> &Tree<T> {
+        self.path.first().map_or(&self.here, |&(ref root, _)| root)
+    }
+}
+
+impl<T> Clone for OwningView<T> {
+    fn clone(&self) -> Self {
+        OwningView { path: self.path.clone(), here: self.here.clone(), }
+    }
+}
+
+impl<T> PartialEq for OwningView<T> {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.root().internal, &other.root().internal)
+            && self.path.iter().map(|&(_, i)| i).eq(other.path.iter().map(|&(_, i)| i))
+    }
+}
+
+impl<T> PartialOrd for OwningView<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if Rc::ptr_eq(&self.root().internal, &other.root().internal) {
+            Some(self.path.iter().map(|&(_, i)| i).cmp(other.path.iter().map(|&(_, i)| i)))
+        } else {
+            None
+        }
+    }
+}
+
+impl<T> Deref for OwningView<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.here.internal.data
+    }
+}
+
+impl<T> Nav for OwningView<T> {
+    fn seek_sibling(&mut self, offset: isize) -> bool {
+        let sibling = match self.path.last() {
+            None => return offset == 0,
+            Some(&(ref parent, index)) => {
+                let siblings = parent.internal.children.borrow();
+                SiblingIndex::compute(siblings.len(), index, offset)
+                    .map(|new_index| (siblings[new_index].clone(), new_index))
+            },
+        };
+        match sibling {
+            Some((sibling, new_index)) => {
+                self.path.last_mut().unwrap().1 = new_index;
+                self.here = sibling;
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn seek_child(&mut self, index: usize) -> bool {
+        let child = match self.here.internal.children.borrow().get(index) {
+            Some(child) => child.clone(),
+            None => return false,
+        };
+        let parent = mem::replace(&mut self.here, child);
+        self.path.push((parent, index));
+        true
+    }
+
+    fn child_count(&self) -> usize {
+        self.here.internal.children.borrow().len()
+    }
+
+    fn at_root(&self) -> bool {
+        self.path.is_empty()
+    }
+
+    fn index_in_parent(&self) -> Option<usize> {
+        self.here_index()
+    }
+
+    fn to_parent(&mut self) -> bool {
+        match self.path.pop() {
+            Some((parent, _)) => {
+                self.here = parent;
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn to_root(&mut self) {
+        if !self.path.is_empty() {
+            let (root, _) = self.path.swap_remove(0);
+            self.path.clear();
+            self.here = root;
+        }
+    }
+}
+
 pub struct TreeEditor<'a, T: 'a> {
     root: &'a mut Tree<T>,
     path: Vec<(RefMut<'a, Vec<Tree<T>>>, usize)>,
+    remove_focus_policy: RemoveFocusPolicy,
 }
 
 impl<'a, T: 'a> TreeEditor<'a, T> {
@@ -243,13 +524,24 @@ impl<'a, T: 'a> TreeEditor<'a, T> {
             &mut parent[index]
         }
     }
+
+    /// Iterates over the data of the focus and its siblings, in order, with
+    /// each one's offset from the focus.
+    pub fn enumerate_siblings(&self)
+                              -> EnumerateSiblings<impl ExactSizeIterator<Item=&T> + '_> {
+        let (siblings, here_index) = match self.path.last() {
+            None => (slice::from_ref(&*self.root), 0),
+            Some(&(ref siblings, here_index)) => (&siblings[..], here_index),
+        };
+        EnumerateSiblings::new(siblings.iter().map(|s| &s.internal.data), here_index)
+    }
 }
 
 impl<'a, T: 'a> Nav for TreeEditor<'a, T> {
     fn seek_sibling(&mut self, offset: isize) -> bool {
         let new_index_result =
             match self.path.last() {
-                None => return false,
+                None => return offset == 0,
                 Some(&(ref siblings, ref index)) =>
                     SiblingIndex::compute(siblings.len(), *index, offset),
             };
@@ -268,7 +560,7 @@ impl<'a, T: 'a> Nav for TreeEditor<'a, T> {
         match ChildIndex::compute(child_count, index) {
             Some(new_index) => {
                 let children = unsafe {
-                    mem::transmute(self.here().internal.children.borrow())
+                    mem::transmute(self.here().internal.children.borrow_mut())
                 };
                 self.path.push((children, new_index));
                 return true
@@ -285,6 +577,10 @@ impl<'a, T: 'a> Nav for TreeEditor<'a, T> {
         self.path.is_empty()
     }
 
+    fn index_in_parent(&self) -> Option<usize> {
+        self.path.last().map(|&(_, index)| index)
+    }
+
     fn to_parent(&mut self) -> bool {
         self.path.pop().is_some()
     }
@@ -337,33 +633,12 @@ impl<'a, T: 'a> Editor for TreeEditor<'a, T> {
     }
 
     fn insert_child(&mut self, index: usize, child: Tree<T>) -> bool {
-        match self.path.pop() {
-            None => {
-                let mut children: RefMut<'a, Vec<Tree<T>>> = unsafe {
-                    mem::transmute(self.root.internal.children.borrow_mut())
-                };
-                match ChildIndex::compute(children.len(), index) {
-                    Some(new_index) => {
-                        children.insert(new_index, child);
-                        self.path.push((children, index));
-                        return true
-                    },
-                    None => return false,
-                }
-            },
-            Some((parent_children, here_index)) => {
-                let mut children: RefMut<'a, Vec<Tree<T>>> = unsafe {
-                    mem::transmute(parent_children[here_index].internal.children.borrow_mut())
-                };
-                match ChildIndex::compute(children.len(), index) {
-                    Some(new_index) => {
-                        children.insert(new_index, child);
-                        self.path.push((children, new_index));
-                        return true
-                    },
-                    None => return false,
-                }
+        match ChildIndex::compute(self.child_count(), index) {
+            Some(new_index) => {
+                self.here().internal.children.borrow_mut().insert(new_index, child);
+                self.seek_child(new_index)
             },
+            None => false,
         }
     }
 
@@ -378,54 +653,53 @@ impl<'a, T: 'a> Editor for TreeEditor<'a, T> {
                 Some(&(ref siblings, ref index)) =>
                     SiblingIndex::compute(siblings.len(), *index, offset),
             };
-        let (mut siblings, _) = self.path.pop().unwrap();
+        let (mut siblings, here_index) = self.path.pop().unwrap();
         match new_index_result {
             Some(new_index) => {
                 siblings.insert(new_index, sibling);
                 self.path.push((siblings, new_index));
                 return true
             },
-            None => return false,
+            None => {
+                self.path.push((siblings, here_index));
+                return false
+            },
         }
     }
 
+    fn remove_focus_policy(&self) -> RemoveFocusPolicy {
+        self.remove_focus_policy
+    }
+
+    fn set_remove_focus_policy(&mut self, policy: RemoveFocusPolicy) -> bool {
+        self.remove_focus_policy = policy;
+        true
+    }
+
     fn remove(&mut self) -> Tree<T> {
-        let (mut parent_children, mut here_index) =
-            self.path.pop().expect("already at root");
-        if parent_children.len() != 0 {
-            let removed = parent_children.remove(here_index);
-            // We will wind up pointing at a sibling.
-            if here_index < parent_children.len() - 1 {
-                // We can keep pointing at the same index in parent.
-                self.path.push((parent_children, here_index));
-            } else {
-                // At rightmost child, so we bump the index one to the left.
-                here_index -= 1;
-                self.path.push((parent_children, here_index));
-            }
-            removed
-        } else {
-            // We will wind up pointing to parent.
-            parent_children.remove(0)
+        let (mut parent_children, here_index) =
+            self.path.pop().unwrap_or_else(|| panic!["{}", NavError::AtRoot]);
+        let removed = parent_children.remove(here_index);
+        let remaining = parent_children.len();
+        if let Some(new_index) = self.remove_focus_policy.focus_after_remove(here_index, remaining) {
+            self.path.push((parent_children, new_index));
         }
+        removed
     }
 
-    fn remove_child(&mut self, index: usize) -> Option<Tree<T>> {
-        // TODO return None instead of panicking
-        match self.path.pop() {
-            None => {
-                // At root.
-                Some(self.root.internal.children.borrow_mut().remove(index))
-            },
-            Some((parent_children, here_index)) => {
-                let mut children =
-                    parent_children[here_index].internal.children.borrow_mut();
-                Some(children.remove(here_index))
-            },
-        }
+    fn remove_child(&mut self, index: usize) -> Result<Tree<T>, EditError> {
+        let mut children = self.here().internal.children.borrow_mut();
+        let index = ChildIndex::check(children.len(), index)?;
+        Ok(children.remove(index))
     }
 
     fn remove_sibling(&mut self, offset: isize) -> Option<Tree<T>> {
+        if self.at_root() {
+            return None
+        }
+        if offset == 0 {
+            return Some(self.remove())
+        }
         let index_result = {
             match self.path.last() {
                 None => None,
@@ -434,17 +708,12 @@ impl<'a, T: 'a> Editor for TreeEditor<'a, T> {
                         parent_children.len(), here_index, offset),
             }
         };
-        let (mut parent_children, here_index) = self.path.pop().unwrap();
         index_result.map(|index| {
-            let removed = parent_children.remove(index);
-            let new_index =
-                if index > here_index {
-                    here_index
-                } else {
-                    here_index - 1
-                };
-            self.path.push((parent_children, new_index));
-            removed
+            let &mut (ref mut parent_children, ref mut here_index) = self.path.last_mut().unwrap();
+            if index < *here_index {
+                *here_index -= 1;
+            }
+            parent_children.remove(index)
         })
     }
 
@@ -484,8 +753,298 @@ impl<'a, T: 'a> Editor for TreeEditor<'a, T> {
                 self.path.push((parent_children, here_index));
                 return true
             },
-            _ => return false,
+            _ => {
+                self.path.push((parent_children, here_index));
+                return false
+            },
+        }
+    }
+}
+
+/// Editor that copies shared nodes on write, made by `Tree::view_mut`.
+///
+/// Like `OwningView`, the editor holds handles to the nodes on its path rather
+/// than borrows of their children, so navigating never stops other handles
+/// from reading the tree. Before an edit changes the children of a node, each
+/// node from the root down to that one which has other handles is replaced
+/// with a copy of itself whose children are handles to the original's, and
+/// only then are the children borrowed for writing.
+pub struct CowEditor<'a, T: 'a> {
+    root: &'a mut Tree<T>,
+    // Each ancestor of the focus, with the index of the next node on the path
+    // among its children.
+    path: Vec<(Tree<T>, usize)>,
+    here: Tree<T>,
+    remove_focus_policy: RemoveFocusPolicy,
+}
+
+impl<'a, T: Clone + 'a> CowEditor<'a, T> {
+    fn new(root: &'a mut Tree<T>) -> Self {
+        let here = root.clone();
+        CowEditor { root, path: Vec::new(), here, remove_focus_policy: RemoveFocusPolicy::default(), }
+    }
+
+    // Returns the node at `level` on the path from the root to the focus.
+    fn spine(&self, level: usize) -> &Tree<T> {
+        match self.path.get(level) {
+            Some((node, _)) => node,
+            None => &self.here,
+        }
+    }
+
+    // Replaces each shared node among the root and the first `depth` nodes
+    // below it on the path to the focus with a copy, so that the children of
+    // the deepest of them may be borrowed for writing. Focus does not move.
+    fn unshare(&mut self, depth: usize) {
+        for level in 0..depth + 1 {
+            // The node is held by its parent (or, for the root, `self.root`)
+            // and by this editor; any other handle means it is shared.
+            if Rc::strong_count(&self.spine(level).internal) <= 2 {
+                continue
+            }
+            let copy = self.spine(level).shallow_copy();
+            if level == 0 {
+                *self.root = copy.clone();
+            } else {
+                let (ref parent, index) = self.path[level - 1];
+                parent.internal.children.borrow_mut()[index] = copy.clone();
+            }
+            match self.path.get_mut(level) {
+                Some(&mut (ref mut node, _)) => *node = copy,
+                None => self.here = copy,
+            }
+        }
+    }
+
+    // Unshares the nodes down to the focus's parent, which must exist.
+    fn unshare_parent(&mut self) {
+        let depth = self.path.len() - 1;
+        self.unshare(depth);
+    }
+
+    // Moves the focus to the parent's child at `index`, which must exist.
+    fn refocus(&mut self, index: usize) {
+        let &mut (ref parent, ref mut here_index) = self.path.last_mut().unwrap();
+        *here_index = index;
+        self.here = parent.internal.children.borrow()[index].clone();
+    }
+}
+
+impl<'a, T: Clone + 'a> Nav for CowEditor<'a, T> {
+    fn seek_sibling(&mut self, offset: isize) -> bool {
+        let new_index = match self.path.last() {
+            None => return offset == 0,
+            Some(&(ref parent, index)) =>
+                SiblingIndex::compute(parent.internal.children.borrow().len(), index, offset),
+        };
+        match new_index {
+            Some(new_index) => {
+                self.refocus(new_index);
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn seek_child(&mut self, index: usize) -> bool {
+        let child = match self.here.internal.children.borrow().get(index) {
+            Some(child) => child.clone(),
+            None => return false,
+        };
+        let parent = mem::replace(&mut self.here, child);
+        self.path.push((parent, index));
+        true
+    }
+
+    fn child_count(&self) -> usize {
+        self.here.internal.children.borrow().len()
+    }
+
+    fn at_root(&self) -> bool {
+        self.path.is_empty()
+    }
+
+    fn index_in_parent(&self) -> Option<usize> {
+        self.path.last().map(|&(_, index)| index)
+    }
+
+    fn to_parent(&mut self) -> bool {
+        match self.path.pop() {
+            Some((parent, _)) => {
+                self.here = parent;
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn to_root(&mut self) {
+        self.path.clear();
+        self.here = self.root.clone();
+    }
+}
+
+impl<'a, T: Clone + 'a> Borrow<T> for CowEditor<'a, T> {
+    fn borrow(&self) -> &T {
+        &self.here.internal.data
+    }
+}
+
+impl<'a, T: Clone + 'a> Editor for CowEditor<'a, T> {
+    type Data = T;
+    type Tree = Tree<T>;
+
+    fn push_leaf(&mut self, data: T) {
+        self.push_child(Tree::leaf(data));
+    }
+
+    fn push_child(&mut self, child: Tree<T>) {
+        let depth = self.path.len();
+        self.unshare(depth);
+        let index = {
+            let mut children = self.here.internal.children.borrow_mut();
+            children.push(child);
+            children.len() - 1
+        };
+        self.seek_child(index);
+    }
+
+    fn insert_leaf(&mut self, index: usize, data: T) -> bool {
+        self.insert_child(index, Tree::leaf(data))
+    }
+
+    fn insert_child(&mut self, index: usize, child: Tree<T>) -> bool {
+        match ChildIndex::compute(self.child_count(), index) {
+            Some(new_index) => {
+                let depth = self.path.len();
+                self.unshare(depth);
+                self.here.internal.children.borrow_mut().insert(new_index, child);
+                self.seek_child(new_index)
+            },
+            None => false,
+        }
+    }
+
+    fn insert_sibling_leaf(&mut self, offset: isize, data: T) -> bool {
+        self.insert_sibling(offset, Tree::leaf(data))
+    }
+
+    fn insert_sibling(&mut self, offset: isize, sibling: Tree<T>) -> bool {
+        let new_index = match self.path.last() {
+            None => return false,
+            Some(&(ref parent, index)) =>
+                SiblingIndex::compute(parent.internal.children.borrow().len(), index, offset),
+        };
+        match new_index {
+            Some(new_index) => {
+                self.unshare_parent();
+                self.path.last().unwrap().0.internal.children.borrow_mut().insert(new_index, sibling);
+                self.refocus(new_index);
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn remove_focus_policy(&self) -> RemoveFocusPolicy {
+        self.remove_focus_policy
+    }
+
+    fn set_remove_focus_policy(&mut self, policy: RemoveFocusPolicy) -> bool {
+        self.remove_focus_policy = policy;
+        true
+    }
+
+    fn remove(&mut self) -> Tree<T> {
+        if self.path.is_empty() {
+            panic!["{}", NavError::AtRoot];
+        }
+        self.unshare_parent();
+        let (parent, here_index) = self.path.pop().unwrap();
+        let (removed, remaining) = {
+            let mut children = parent.internal.children.borrow_mut();
+            (children.remove(here_index), children.len())
+        };
+        // Let go of the removed node before handing it back.
+        self.here = parent;
+        if let Some(new_index) = self.remove_focus_policy.focus_after_remove(here_index, remaining) {
+            self.seek_child(new_index);
+        }
+        removed
+    }
+
+    fn remove_child(&mut self, index: usize) -> Result<Tree<T>, EditError> {
+        let index = ChildIndex::check(self.child_count(), index)?;
+        let depth = self.path.len();
+        self.unshare(depth);
+        Ok(self.here.internal.children.borrow_mut().remove(index))
+    }
+
+    fn remove_sibling(&mut self, offset: isize) -> Option<Tree<T>> {
+        if self.at_root() {
+            return None
+        }
+        if offset == 0 {
+            return Some(self.remove())
+        }
+        let index = match self.path.last() {
+            None => return None,
+            Some(&(ref parent, here_index)) =>
+                SiblingIndex::compute(parent.internal.children.borrow().len(), here_index, offset)?,
+        };
+        self.unshare_parent();
+        let &mut (ref parent, ref mut here_index) = self.path.last_mut().unwrap();
+        if index < *here_index {
+            *here_index -= 1;
+        }
+        let removed = parent.internal.children.borrow_mut().remove(index);
+        Some(removed)
+    }
+
+    fn swap(&mut self, other: &mut Tree<T>) {
+        if self.path.is_empty() {
+            mem::swap(self.root, other);
+            self.here = self.root.clone();
+            return
+        }
+        self.unshare_parent();
+        let &(ref parent, here_index) = self.path.last().unwrap();
+        mem::swap(&mut parent.internal.children.borrow_mut()[here_index], other);
+        self.here = parent.internal.children.borrow()[here_index].clone();
+    }
+
+    fn swap_children(&mut self, index_a: usize, index_b: usize) -> bool {
+        let child_count = self.child_count();
+        if index_a >= child_count || index_b >= child_count {
+            return false
+        }
+        let depth = self.path.len();
+        self.unshare(depth);
+        self.here.internal.children.borrow_mut().swap(index_a, index_b);
+        true
+    }
+
+    fn swap_siblings(&mut self, offset_a: isize, offset_b: isize) -> bool {
+        let (index_a, index_b) = match self.path.last() {
+            None => return false,
+            Some(&(ref parent, here_index)) => {
+                let sibling_count = parent.internal.children.borrow().len();
+                match (SiblingIndex::compute(sibling_count, here_index, offset_a),
+                       SiblingIndex::compute(sibling_count, here_index, offset_b)) {
+                    (Some(index_a), Some(index_b)) => (index_a, index_b),
+                    _ => return false,
+                }
+            },
+        };
+        self.unshare_parent();
+        let &mut (ref parent, ref mut here_index) = self.path.last_mut().unwrap();
+        parent.internal.children.borrow_mut().swap(index_a, index_b);
+        if *here_index == index_a {
+            *here_index = index_b;
+        } else if *here_index == index_b {
+            *here_index = index_a;
         }
+        true
     }
 }
 
@@ -499,7 +1058,166 @@ macro_rules! shared_tree {
 
 #[cfg(test)]
 mod test {
-    use ::shared::Tree;
+    use ::{Editor, Nav};
+    use ::error::EditError;
+    use ::owned_tree;
+    use ::shared::{OwningView, SharedError, Tree};
+
+    use std::rc::Rc;
+
+    #[test]
+    fn owning_view_outlives_borrows() {
+        fn make_view() -> OwningView<&'static str> {
+            let t = Tree::from(owned_tree!["a", ["b", ["c"]], ["d"]]);
+            let mut v = t.owning_view();
+            assert![v.seek_child(0)];
+            v
+        }
+
+        let mut v = make_view();
+        assert_eq!["b", *v];
+        assert_eq![Some(0), v.here_index()];
+        let saved = v.clone();
+        let mut callback: Box<dyn FnMut() -> &'static str> = Box::new(move || {
+            assert![v.seek_sibling(1)];
+            *v
+        });
+        assert_eq!["d", callback()];
+        let mut v = saved;
+        assert![! v.seek_sibling(-1)];
+        assert![v.seek_child(0)];
+        assert_eq!["c", *v];
+        assert![v.at_leaf()];
+        assert![! v.seek_child(0)];
+        v.to_root();
+        assert![v.at_root()];
+        assert_eq!["a", *v];
+        assert_eq![None, v.here_index()];
+        assert![! v.to_parent()];
+        assert![v.seek_sibling(0)];
+    }
+
+    #[test]
+    fn owning_view_sees_edits() {
+        let mut t = Tree::new("a", vec![Tree::leaf("b")]);
+        let mut v = t.owning_view();
+        assert![v.seek_child(0)];
+        t.push_child(Tree::leaf("c"));
+        assert![v.seek_sibling(1)];
+        assert_eq!["c", *v];
+        assert_eq![Tree::leaf("c"), v.subtree()];
+        assert![v.to_parent()];
+        assert_eq![2, v.child_count()];
+    }
+
+    #[test]
+    fn owning_views_compare_positions() {
+        let t = Tree::from(owned_tree!["a", ["b", ["c"]], ["d"]]);
+        let mut a = t.owning_view();
+        let mut b = t.owning_view();
+        assert![a.seek_child(0)];
+        assert![b.seek_child(1)];
+        assert![a < b];
+        assert![b.seek_sibling(-1)];
+        assert![a == b];
+        assert![a.seek_child(0)];
+        assert![b < a];
+        let other = Tree::from(owned_tree!["a", ["b", ["c"]], ["d"]]);
+        assert_eq![None, other.owning_view().partial_cmp(&t.owning_view())];
+    }
+
+    #[test]
+    fn view_mut_leaves_other_owners_unchanged() {
+        let c = Tree::new("c", vec![Tree::leaf("e")]);
+        let mut t = Tree::new("a", vec![c.clone(), Tree::new("d", vec![c.clone()])]);
+        let snapshot = t.clone();
+        let mut v = t.owning_view();
+        assert![v.seek_child(1)];
+        {
+            let mut e = t.view_mut();
+            assert![e.seek_child(1)];
+            assert![e.seek_child(0)];
+            e.push_leaf("f");
+            e.to_root();
+            assert![e.seek_child(0)];
+            assert_eq![Ok(Tree::leaf("e")), e.remove_child(0)];
+        }
+        assert_eq![shared_tree!["a", ["c"], ["d", ["c", ["e"], ["f"]]]], t];
+        assert_eq![shared_tree!["a", ["c", ["e"]], ["d", ["c", ["e"]]]], snapshot];
+        assert_eq![shared_tree!["c", ["e"]], c];
+        assert_eq![shared_tree!["d", ["c", ["e"]]], v.subtree()];
+    }
+
+    #[test]
+    fn view_mut_lets_other_owners_read() {
+        let mut t = shared_tree!["a", ["b", ["c"]]];
+        let other = t.clone();
+        let mut e = t.view_mut();
+        assert![e.seek_child(0)];
+        assert![e.seek_child(0)];
+        let mut v = other.view();
+        assert![v.seek_child(0)];
+        assert![v.seek_child(0)];
+        assert_eq!["c", *v];
+        drop(v);
+        e.push_leaf("d");
+        let mut v = other.view();
+        assert![v.seek_child(0)];
+        assert![v.seek_child(0)];
+        assert![v.at_leaf()];
+        assert_eq![shared_tree!["a", ["b", ["c"]]], other];
+    }
+
+    #[test]
+    fn view_mut_edits_unique_nodes_in_place() {
+        let mut t = shared_tree!["a", ["b"]];
+        let b = t.internal.children.borrow()[0].internal.clone();
+        {
+            let mut e = t.view_mut();
+            e.push_leaf("c");
+            assert![e.to_parent()];
+            assert![e.swap_children(0, 1)];
+        }
+        assert_eq![shared_tree!["a", ["c"], ["b"]], t];
+        assert![Rc::ptr_eq(&b, &t.internal.children.borrow()[1].internal)];
+    }
+
+    #[test]
+    fn view_mut_in_place_edits_shared_nodes() {
+        let c = Tree::leaf("c");
+        let mut t = Tree::new("a", vec![c.clone()]);
+        {
+            let mut e = t.view_mut_in_place();
+            assert![e.seek_child(0)];
+            e.push_leaf("d");
+        }
+        assert_eq![shared_tree!["c", ["d"]], c];
+    }
+
+    #[test]
+    fn try_into_owned_moves_unique_trees() {
+        let t = Tree::from(owned_tree!["a", ["b", ["c"]], ["d"]]);
+        assert_eq![owned_tree!["a", ["b", ["c"]], ["d"]], t.try_into_owned().unwrap()];
+    }
+
+    #[test]
+    fn try_into_owned_reports_first_shared_node() {
+        let c = Tree::leaf("c");
+        let b = Tree::new("b", vec![Tree::leaf("x"), c.clone()]);
+        let t = Tree::new("a", vec![b, Tree::new("d", vec![c.clone()])]);
+        let copy = t.clone();
+        assert_eq![Err(SharedError { path: "/".parse().unwrap(), }), copy.try_into_owned()];
+        drop(c);
+        assert_eq![Err(SharedError { path: "/0/1".parse().unwrap(), }), t.try_into_owned()];
+    }
+
+    #[test]
+    fn into_owned_clones_shared_nodes() {
+        let c = Tree::new("c", vec![Tree::leaf("e")]);
+        let t = Tree::new("a", vec![c.clone(), Tree::new("d", vec![c.clone()])]);
+        assert_eq![owned_tree!["a", ["c", ["e"]], ["d", ["c", ["e"]]]], t.into_owned()];
+        assert_eq![owned_tree!["c", ["e"]], c.try_into_owned().unwrap()];
+    }
 
     #[test]
     fn eq_check() {
@@ -507,6 +1225,7 @@ mod test {
         assert![Tree::leaf("a") != Tree::leaf("b")];
         assert_eq![Tree::new("a", vec![Tree::leaf("b"), Tree::leaf("c")]),
                    Tree::new("a", vec![Tree::leaf("b"), Tree::leaf("c")])];
+        assert![shared_tree!["a", ["b", ["b"]]] != shared_tree!["a", ["b"], ["b"]]];
     }
 
     #[test]
@@ -544,36 +1263,37 @@ mod test {
     }
 
     #[test]
-    #[should_panic]
-    fn remove_child_panics_no_children() {
-        shared_tree!["a"].remove_child(0);
+    fn remove_child_fails_no_children() {
+        assert_eq![shared_tree!["a"].remove_child(0),
+                   Err(EditError::ChildOutOfRange { index: 0, child_count: 0, })];
     }
 
     #[test]
-    #[should_panic]
-    fn remove_child_panics_bad_index() {
-        shared_tree!["a", ["b"], ["c"]].remove_child(2);
+    fn remove_child_fails_bad_index() {
+        let mut t = shared_tree!["a", ["b"], ["c"]];
+        assert_eq![t.remove_child(2), Err(EditError::ChildOutOfRange { index: 2, child_count: 2, })];
+        assert_eq![t, shared_tree!["a", ["b"], ["c"]]];
     }
 
     #[test]
     fn remove_child() {
         {
             let mut t = shared_tree!["a", ["b"]];
-            t.remove_child(0);
+            assert_eq![t.remove_child(0), Ok(shared_tree!["b"])];
             assert_eq![t, shared_tree!["a"]];
         }
         {
             let mut t = shared_tree!["a", ["b"], ["c"]];
-            t.remove_child(0);
+            t.remove_child(0).unwrap();
             assert_eq![t, shared_tree!["a", ["c"]]];
-            t.remove_child(0);
+            t.remove_child(0).unwrap();
             assert_eq![t, shared_tree!["a"]];
         }
         {
             let mut t = shared_tree!["a", ["b"], ["c"]];
-            t.remove_child(1);
+            t.remove_child(1).unwrap();
             assert_eq![t, shared_tree!["a", ["b"]]];
-            t.remove_child(0);
+            t.remove_child(0).unwrap();
             assert_eq![t, shared_tree!["a"]];
         }
     }
@@ -645,6 +1365,22 @@ mod test {
         let _ = t.into_parts();
     }
 
+    #[test]
+    fn enumerate_siblings() {
+        let mut t = shared_tree!["a", ["b"], ["c"], ["d"]];
+        {
+            let mut v = t.view();
+            assert_eq![v.enumerate_siblings().collect::<Vec<_>>(), vec![(0, &"a")]];
+            assert![v.seek_child(0)];
+            assert_eq![v.enumerate_siblings().collect::<Vec<_>>(),
+                       vec![(0, &"b"), (1, &"c"), (2, &"d")]];
+        }
+        let mut e = t.view_mut_in_place();
+        assert![e.seek_child(2)];
+        assert_eq![e.enumerate_siblings().map(|(offset, _)| offset).collect::<Vec<_>>(),
+                   vec![-2, -1, 0]];
+    }
+
     #[test]
     fn debug_fmt() {
         assert_eq!["(\"a\")", format!["{:?}", shared_tree!["a"]]];
@@ -0,0 +1,278 @@
+use ::owned::Tree;
+use ::path::TreePath;
+
+use std::ops::{Deref, DerefMut, Range};
+
+/// Node data together with the range of source bytes it came from.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Spanned<T> {
+    pub span: Range<usize>,
+    pub data: T,
+}
+
+impl<T> Spanned<T> {
+    pub fn new(span: Range<usize>, data: T) -> Self {
+        Spanned { span, data, }
+    }
+}
+
+impl<T> Deref for Spanned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.data
+    }
+}
+
+impl<T> DerefMut for Spanned<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.data
+    }
+}
+
+struct IndexNode {
+    span: Range<usize>,
+    // The smallest range covering the spans of this node and its descendants.
+    extent: Range<usize>,
+    children: Vec<usize>,
+    // Whether the children's extents are in order and do not overlap, so that
+    // they can be binary searched.
+    ordered: bool,
+}
+
+/// Index of the spans of a tree of `Spanned` nodes, for finding the nodes that
+/// contain a source offset.
+///
+/// Lookups descend from the root through the children whose subtrees cover the
+/// offset. Where the children of a node cover disjoint ranges in order, as a
+/// parser usually produces, the right one is found by binary search; otherwise
+/// every child that covers the offset is searched. The index describes the tree as it was when built, and
+/// must be rebuilt after the tree changes.
+pub struct SpanIndex {
+    // In pre-order, so that the root is first.
+    nodes: Vec<IndexNode>,
+}
+
+impl SpanIndex {
+    pub fn new<T>(tree: &Tree<Spanned<T>>) -> Self {
+        let mut nodes = Vec::new();
+        let mut parents: Vec<Option<usize>> = Vec::new();
+        let mut stack = vec![(tree, None)];
+        while let Some((tree, parent)) = stack.pop() {
+            let index = nodes.len();
+            let span = tree.data().span.clone();
+            nodes.push(IndexNode { extent: span.clone(), span, children: Vec::new(),
+                                   ordered: true, });
+            parents.push(parent);
+            if let Some(parent) = parent {
+                nodes[parent].children.push(index);
+            }
+            stack.extend(tree.children().iter().rev().map(|child| (child, Some(index))));
+        }
+        // Children come after their parents, so their extents are final by the
+        // time they are merged into their parents'.
+        for index in (1..nodes.len()).rev() {
+            let parent = parents[index].unwrap();
+            let extent = nodes[index].extent.clone();
+            let parent_extent = &mut nodes[parent].extent;
+            parent_extent.start = parent_extent.start.min(extent.start);
+            parent_extent.end = parent_extent.end.max(extent.end);
+        }
+        for index in 0..nodes.len() {
+            let ordered = nodes[index].children.windows(2)
+                .all(|pair| nodes[pair[0]].extent.end <= nodes[pair[1]].extent.start);
+            nodes[index].ordered = ordered;
+        }
+        SpanIndex { nodes, }
+    }
+
+    /// Returns the path of the deepest node whose span contains `offset`, or
+    /// `None` if no node's span does. Of several such nodes at the same depth,
+    /// the first in pre-order is returned.
+    pub fn node_at_offset(&self, offset: usize) -> Option<TreePath> {
+        let mut found: Option<TreePath> = None;
+        // Nodes whose extents contain `offset`, with their paths, to be
+        // visited in pre-order.
+        let mut stack = vec![(0, TreePath::root())];
+        while let Some((index, path)) = stack.pop() {
+            let node = &self.nodes[index];
+            let deeper = found.as_ref().is_none_or(|found| path.depth() > found.depth());
+            if deeper && node.span.contains(&offset) {
+                found = Some(path.clone());
+            }
+            if node.ordered {
+                let after = node.children
+                    .partition_point(|&child| self.nodes[child].extent.start <= offset);
+                if let Some(i) = after.checked_sub(1) {
+                    if self.nodes[node.children[i]].extent.contains(&offset) {
+                        stack.push((node.children[i], path.child(i)));
+                    }
+                }
+            } else {
+                // Overlapping children may each hold a node containing
+                // `offset`, so all of them are searched.
+                for (i, &child) in node.children.iter().enumerate().rev() {
+                    if self.nodes[child].extent.contains(&offset) {
+                        stack.push((child, path.child(i)));
+                    }
+                }
+            }
+        }
+        found
+    }
+
+    /// Returns the path of the deepest node whose span contains the byte at
+    /// `line` and `column` of the source indexed by `lines`, or `None` if no
+    /// node's span does or there is no such position.
+    pub fn node_at_position(&self, lines: &LineIndex, line: usize, column: usize)
+                            -> Option<TreePath> {
+        lines.offset(line, column).and_then(|offset| self.node_at_offset(offset))
+    }
+}
+
+/// Index of the lines of a source text, for converting between byte offsets
+/// and line and column positions in O(log n) time.
+///
+/// Lines and columns are counted from 0, and columns in bytes. Lines end after
+/// each `\n`, so a `\r` before it is the last byte of its line.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LineIndex {
+    // The offset of the first byte of each line.
+    line_starts: Vec<usize>,
+    len: usize,
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> Self {
+        let line_starts = Some(0).into_iter()
+            .chain(text.bytes().enumerate().filter(|&(_, b)| b == b'\n').map(|(i, _)| i + 1))
+            .collect();
+        LineIndex { line_starts, len: text.len(), }
+    }
+
+    /// Returns the number of lines. This is one more than the number of line
+    /// breaks, so text ending in a line break has an empty last line.
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Returns the line and column of the byte at `offset`. An offset at the
+    /// end of the text is placed after its last byte. Returns `None` if
+    /// `offset` is past the end.
+    pub fn position(&self, offset: usize) -> Option<(usize, usize)> {
+        if offset > self.len {
+            return None
+        }
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        Some((line, offset - self.line_starts[line]))
+    }
+
+    /// Returns the byte offset of `column` on `line`, or `None` if there is
+    /// no such line or the line is shorter than `column`. The position just
+    /// after the last byte of a line is allowed.
+    pub fn offset(&self, line: usize, column: usize) -> Option<usize> {
+        let start = *self.line_starts.get(line)?;
+        let end = self.line_starts.get(line + 1).map_or(self.len, |&next| next - 1);
+        if start + column > end {
+            return None
+        }
+        Some(start + column)
+    }
+}
+
+/// Returns the path of the deepest node of `tree` whose span contains `offset`,
+/// or `None` if no node's span does. This builds a `SpanIndex`, which should be
+/// kept instead for repeated lookups.
+pub fn node_at_offset<T>(tree: &Tree<Spanned<T>>, offset: usize) -> Option<TreePath> {
+    SpanIndex::new(tree).node_at_offset(offset)
+}
+
+#[cfg(test)]
+mod test {
+    use ::owned::Tree;
+    use ::path::TreePath;
+    use ::span::{node_at_offset, LineIndex, SpanIndex, Spanned};
+
+    fn node(start: usize, end: usize, children: Vec<Tree<Spanned<()>>>) -> Tree<Spanned<()>> {
+        Tree::new(Spanned::new(start..end, ()), children)
+    }
+
+    fn path(indices: Vec<usize>) -> Option<TreePath> {
+        Some(TreePath::from(indices))
+    }
+
+    #[test]
+    fn finds_deepest_node() {
+        // f(a, g(b)) with the call spanning 0..10.
+        let t = node(0, 10, vec![node(0, 1, vec![]),
+                                 node(2, 3, vec![]),
+                                 node(5, 9, vec![node(5, 6, vec![]), node(7, 8, vec![])])]);
+        let index = SpanIndex::new(&t);
+        assert_eq![path(vec![]), index.node_at_offset(1)];
+        assert_eq![path(vec![1]), index.node_at_offset(2)];
+        assert_eq![path(vec![2, 1]), index.node_at_offset(7)];
+        assert_eq![path(vec![2]), index.node_at_offset(8)];
+        assert_eq![path(vec![]), index.node_at_offset(9)];
+        assert_eq![None, index.node_at_offset(10)];
+        assert_eq![path(vec![2, 0]), node_at_offset(&t, 5)];
+    }
+
+    #[test]
+    fn children_may_extend_past_parent() {
+        // Children out of order, and extending past their parent's own span.
+        let t = node(4, 6, vec![node(8, 12, vec![node(10, 11, vec![])]), node(0, 2, vec![])]);
+        let index = SpanIndex::new(&t);
+        assert_eq![path(vec![0, 0]), index.node_at_offset(10)];
+        assert_eq![path(vec![1]), index.node_at_offset(1)];
+        assert_eq![None, index.node_at_offset(3)];
+        assert_eq![path(vec![]), index.node_at_offset(5)];
+    }
+
+    #[test]
+    fn searches_overlapping_children() {
+        // The first child covers the second, but only the second has a node
+        // below it at 5.
+        let t = node(0, 10, vec![node(0, 8, vec![]),
+                                 node(4, 6, vec![node(5, 6, vec![])]),
+                                 node(4, 9, vec![node(5, 7, vec![])])]);
+        let index = SpanIndex::new(&t);
+        assert_eq![path(vec![1, 0]), index.node_at_offset(5)];
+        assert_eq![path(vec![0]), index.node_at_offset(4)];
+        assert_eq![path(vec![2, 0]), index.node_at_offset(6)];
+        assert_eq![path(vec![2]), index.node_at_offset(8)];
+    }
+
+    #[test]
+    fn line_index_converts_positions() {
+        let lines = LineIndex::new("ab\n\ncd\n");
+        assert_eq![4, lines.line_count()];
+        assert_eq![Some((0, 0)), lines.position(0)];
+        assert_eq![Some((0, 2)), lines.position(2)];
+        assert_eq![Some((1, 0)), lines.position(3)];
+        assert_eq![Some((2, 1)), lines.position(5)];
+        assert_eq![Some((3, 0)), lines.position(7)];
+        assert_eq![None, lines.position(8)];
+        assert_eq![Some(5), lines.offset(2, 1)];
+        assert_eq![Some(6), lines.offset(2, 2)];
+        assert_eq![None, lines.offset(1, 1)];
+        assert_eq![Some(7), lines.offset(3, 0)];
+        assert_eq![None, lines.offset(4, 0)];
+        for offset in 0..8 {
+            let (line, column) = lines.position(offset).unwrap();
+            assert_eq![Some(offset), lines.offset(line, column)];
+        }
+    }
+
+    #[test]
+    fn finds_node_at_position() {
+        // f(\n  x)
+        let source = "f(\n  x)";
+        let t = node(0, 7, vec![node(0, 1, vec![]), node(5, 6, vec![])]);
+        let index = SpanIndex::new(&t);
+        let lines = LineIndex::new(source);
+        assert_eq![path(vec![0]), index.node_at_position(&lines, 0, 0)];
+        assert_eq![path(vec![1]), index.node_at_position(&lines, 1, 2)];
+        assert_eq![path(vec![]), index.node_at_position(&lines, 1, 3)];
+        assert_eq![None, index.node_at_position(&lines, 1, 9)];
+    }
+}
@@ -0,0 +1,846 @@
+use ::{Editor, Nav, RemoveFocusPolicy};
+use ::error::{EditError, NavError};
+use ::owned;
+use ::path::TreePath;
+use ::index::{ChildIndex, SiblingIndex};
+
+use std::cell::RefCell;
+use std::clone::Clone;
+use std::cmp::Ordering;
+use std::fmt;
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::ptr;
+use std::rc::Rc;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::sync::atomic::{self, Ordering as MemoryOrdering};
+
+struct Node<T> {
+    data: T, children: Vec<Arc<RwLock<Node<T>>>>,
+}
+
+thread_local! {
+    // Lock ranks of the trees on which this thread holds a navigator.
+    static HELD_RANKS: RefCell<Vec<usize>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Record of a navigator held by the current thread, which enforces the
+/// ordering in which navigators may be acquired.
+struct Registration {
+    rank: usize,
+}
+
+impl Registration {
+    fn acquire(rank: usize) -> Self {
+        HELD_RANKS.with(|held| {
+            let mut held = held.borrow_mut();
+            if let Some(&highest) = held.iter().max() {
+                assert![rank > highest,
+                        "sync tree navigators must be acquired in increasing order of lock rank \
+                         (acquiring {} while holding {})", rank, highest];
+            }
+            held.push(rank);
+        });
+        Registration { rank, }
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        HELD_RANKS.with(|held| {
+            let mut held = held.borrow_mut();
+            if let Some(i) = held.iter().position(|&r| r == self.rank) {
+                held.swap_remove(i);
+            }
+        });
+    }
+}
+
+fn read_node<T>(lock: &RwLock<Node<T>>) -> RwLockReadGuard<'_, Node<T>> {
+    lock.read().expect("sync tree lock poisoned")
+}
+
+fn write_node<T>(lock: &RwLock<Node<T>>) -> RwLockWriteGuard<'_, Node<T>> {
+    lock.write().expect("sync tree lock poisoned")
+}
+
+/// Thread-safe tree whose nodes are guarded by reader-writer locks.
+///
+/// This tree has the same structure as [owned::Tree](../owned/struct.Tree.html),
+/// but each node is stored in an `Arc<RwLock<_>>`, and the tree can be shared
+/// between threads by cloning it. It is navigated with either of two
+/// navigators:
+///
+/// * `read` returns a `ReadGuardNav`. Any number of threads can read the tree
+///   at once. A reader takes a read lock on the root for as long as it lives
+///   and takes read locks on the other nodes lazily, as it navigates to them.
+/// * `write` returns a `WriteNav`, which takes a write lock on the root and so
+///   excludes all other readers and writers from the whole tree. It can then
+///   edit the tree freely.
+///
+/// # Lock ordering
+///
+/// Within a tree, locks are only ever taken from the root downward, and a
+/// navigator holds locks only on the path from the root to its focus. Because
+/// every navigator holds the root lock before any other, only the root lock is
+/// ever contended, and navigators on a single tree cannot deadlock each other.
+///
+/// A thread that holds navigators on more than one tree at once must acquire
+/// them in increasing order of `lock_rank`. The same rule forbids a thread from
+/// acquiring a second navigator on a tree it is already navigating, which could
+/// otherwise wait forever on itself. Acquiring navigators out of order panics
+/// instead of risking deadlock. Cloning a `ReadGuardNav` shares its locks, so
+/// it is always allowed.
+pub struct Tree<T> {
+    root: Arc<RwLock<Node<T>>>,
+}
+
+impl<T> Tree<T> {
+    pub fn new(data: T, children: Vec<Tree<T>>) -> Self {
+        let children = children.into_iter().map(Tree::into_child).collect();
+        Tree { root: Arc::new(RwLock::new(Node { data, children, })), }
+    }
+
+    pub fn leaf(data: T) -> Self {
+        Tree::new(data, Vec::new())
+    }
+
+    /// Unwraps a tree for insertion as a child of another tree. Panics if this
+    /// tree has other handles, which would otherwise bypass the lock ordering
+    /// of its new parent.
+    fn into_child(mut self) -> Arc<RwLock<Node<T>>> {
+        assert![Arc::get_mut(&mut self.root).is_some(),
+                "cannot make a child of a sync tree that has other handles"];
+        self.root
+    }
+
+    /// Returns the rank that orders navigator acquisition across trees. This is
+    /// the same for all handles to a tree.
+    pub fn lock_rank(&self) -> usize {
+        Arc::as_ptr(&self.root) as usize
+    }
+
+    /// Acquires a read-only navigator, blocking while a writer holds the tree.
+    /// Panics if this would violate lock ordering.
+    pub fn read(&self) -> ReadGuardNav<'_, T> {
+        let registration = Registration::acquire(self.lock_rank());
+        let guard = read_node(&self.root);
+        ReadGuardNav { path: Vec::new(),
+                       root: Rc::new(RootReadGuard { guard, _registration: registration, }), }
+    }
+
+    /// Alias for `read`.
+    pub fn view(&self) -> ReadGuardNav<'_, T> {
+        self.read()
+    }
+
+    /// Acquires a navigator that can edit the tree, blocking while any other
+    /// navigator holds the tree. Panics if this would violate lock ordering.
+    pub fn write(&self) -> WriteNav<'_, T> {
+        let registration = Registration::acquire(self.lock_rank());
+        let root = write_node(&self.root);
+        WriteNav { path: Vec::new(), root, remove_focus_policy: RemoveFocusPolicy::default(),
+                   _registration: registration, }
+    }
+}
+
+impl<T> From<owned::Tree<T>> for Tree<T> {
+    fn from(tree: owned::Tree<T>) -> Self {
+        tree.fold(Tree::new)
+    }
+}
+
+/// Creates a new handle to this tree, such that modifying the tree through
+/// either handle is visible through the other.
+impl<T> Clone for Tree<T> {
+    fn clone(&self) -> Self {
+        Tree { root: self.root.clone(), }
+    }
+}
+
+impl<T: PartialEq> PartialEq for Tree<T> {
+    fn eq(&self, other: &Tree<T>) -> bool {
+        if Arc::ptr_eq(&self.root, &other.root) {
+            return true
+        }
+        let (first, second) = if self.lock_rank() < other.lock_rank() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        let mut x = first.read();
+        let mut y = second.read();
+        // Both views move in lockstep through a pre-order traversal.
+        loop {
+            if *x != *y || x.child_count() != y.child_count() {
+                return false
+            }
+            if x.seek_child(0) {
+                y.seek_child(0);
+                continue
+            }
+            loop {
+                if x.seek_sibling(1) {
+                    if !y.seek_sibling(1) {
+                        return false
+                    }
+                    break
+                }
+                if y.seek_sibling(1) {
+                    return false
+                }
+                if !x.to_parent() {
+                    return true
+                }
+                y.to_parent();
+            }
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Tree<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut v = self.read();
+        write!(f, "({:?}", *v)?;
+        loop {
+            if v.seek_child(0) {
+                write!(f, " ({:?}", *v)?;
+                continue
+            }
+            loop {
+                f.write_str(")")?;
+                if v.seek_sibling(1) {
+                    write!(f, " ({:?}", *v)?;
+                    break
+                }
+                if !v.to_parent() {
+                    return Ok(())
+                }
+            }
+        }
+    }
+}
+
+/// Node data that can be read and replaced through a shared reference without
+/// taking a lock, such as the atomic types of `std::sync::atomic`.
+///
+/// The data of a sync tree of `AtomicData` can be updated by `update_data`
+/// while other threads read the tree. Other cells, such as one holding an
+/// `Arc` that is swapped atomically, can implement this trait too.
+pub trait AtomicData: Sync {
+    /// The type of the value held.
+    type Value: Copy;
+
+    /// Returns the value held.
+    fn load(&self) -> Self::Value;
+
+    /// Replaces the value held with `new` iff it is `current`. Returns the
+    /// value held before, as `Ok` if it was replaced and as `Err` otherwise.
+    fn compare_exchange(&self, current: Self::Value, new: Self::Value)
+                        -> Result<Self::Value, Self::Value>;
+}
+
+macro_rules! atomic_data {
+    ($($atomic:ident: $value:ty),*) => ($(
+        impl AtomicData for atomic::$atomic {
+            type Value = $value;
+
+            fn load(&self) -> $value {
+                atomic::$atomic::load(self, MemoryOrdering::Acquire)
+            }
+
+            fn compare_exchange(&self, current: $value, new: $value) -> Result<$value, $value> {
+                atomic::$atomic::compare_exchange(
+                    self, current, new, MemoryOrdering::AcqRel, MemoryOrdering::Acquire)
+            }
+        }
+    )*);
+}
+
+atomic_data![AtomicBool: bool,
+             AtomicI8: i8, AtomicI16: i16, AtomicI32: i32, AtomicIsize: isize,
+             AtomicU8: u8, AtomicU16: u16, AtomicU32: u32, AtomicUsize: usize];
+
+#[cfg(target_has_atomic = "64")]
+atomic_data![AtomicI64: i64, AtomicU64: u64];
+
+impl<A: AtomicData> Tree<A> {
+    /// Returns the value held at the node addressed by `path`, or `None` if
+    /// there is no such node.
+    ///
+    /// This takes read locks on the path to the node, as `read` does.
+    pub fn load_data(&self, path: &TreePath) -> Option<A::Value> {
+        let mut r = self.read();
+        if path.indices().iter().all(|&index| r.seek_child(index)) {
+            Some(r.load())
+        } else {
+            None
+        }
+    }
+
+    /// Replaces the value held at the node addressed by `path` with the result
+    /// of calling `f` on it, and returns the new value. Returns `None`, without
+    /// calling `f`, if there is no such node.
+    ///
+    /// The node's topology is locked for reading only, so that readers and
+    /// other updates of data proceed meanwhile. If another thread replaces the
+    /// value between its load and the store of the result, `f` is called again
+    /// on the new value, so `f` may be called more than once. This blocks only
+    /// while a writer holds the tree, and it panics as `read` does if it would
+    /// violate lock ordering.
+    pub fn update_data<F>(&self, path: &TreePath, mut f: F) -> Option<A::Value>
+        where F: FnMut(A::Value) -> A::Value {
+            let mut r = self.read();
+            if !path.indices().iter().all(|&index| r.seek_child(index)) {
+                return None
+            }
+            let mut current = r.load();
+            loop {
+                let new = f(current);
+                match r.compare_exchange(current, new) {
+                    Ok(_) => return Some(new),
+                    Err(seen) => current = seen,
+                }
+            }
+        }
+}
+
+struct RootReadGuard<'a, T: 'a> {
+    guard: RwLockReadGuard<'a, Node<T>>,
+    _registration: Registration,
+}
+
+/// Read-only navigator of a sync tree.
+///
+/// Holds a read lock on the root and on each node between the root and the
+/// focus. Clones share the locks they have in common.
+pub struct ReadGuardNav<'a, T: 'a> {
+    // Guards on the nodes below the root on the path to the focus, each with
+    // the index of its node among its siblings.
+    path: Vec<(Rc<RwLockReadGuard<'a, Node<T>>>, usize)>,
+    // Declared last so that it is released last.
+    root: Rc<RootReadGuard<'a, T>>,
+}
+
+impl<'a, T: 'a> ReadGuardNav<'a, T> {
+    fn here(&self) -> &Node<T> {
+        match self.path.last() {
+            None => &self.root.guard,
+            Some((guard, _)) => guard,
+        }
+    }
+
+    fn parent(&self) -> Option<&Node<T>> {
+        match self.path.len() {
+            0 => None,
+            1 => Some(&self.root.guard),
+            n => Some(&self.path[n - 2].0),
+        }
+    }
+
+    fn lock(lock: &Arc<RwLock<Node<T>>>) -> Rc<RwLockReadGuard<'a, Node<T>>> {
+        // The node outlives this guard: it can only be removed from the tree
+        // by a writer, and no writer can run while the root guard is held.
+        let guard: RwLockReadGuard<'a, Node<T>> = unsafe { mem::transmute(read_node(lock)) };
+        Rc::new(guard)
+    }
+}
+
+impl<'a, T: 'a> Clone for ReadGuardNav<'a, T> {
+    fn clone(&self) -> Self {
+        ReadGuardNav { path: self.path.clone(), root: self.root.clone(), }
+    }
+}
+
+impl<'a, T: 'a> PartialEq for ReadGuardNav<'a, T> {
+    fn eq(&self, other: &Self) -> bool {
+        ptr::eq(&*self.root.guard, &*other.root.guard)
+            && self.path.iter().map(|&(_, i)| i).eq(other.path.iter().map(|&(_, i)| i))
+    }
+}
+
+impl<'a, T: 'a> PartialOrd for ReadGuardNav<'a, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if ptr::eq(&*self.root.guard, &*other.root.guard) {
+            Some(self.path.iter().map(|&(_, i)| i).cmp(other.path.iter().map(|&(_, i)| i)))
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, T: 'a> Deref for ReadGuardNav<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.here().data
+    }
+}
+
+impl<'a, T: 'a> Nav for ReadGuardNav<'a, T> {
+    fn seek_sibling(&mut self, offset: isize) -> bool {
+        if offset == 0 {
+            return true
+        }
+        let new_index = match (self.parent(), self.path.last()) {
+            (Some(parent), Some(&(_, here_index))) =>
+                SiblingIndex::compute(parent.children.len(), here_index, offset),
+            _ => return false,
+        };
+        match new_index {
+            Some(new_index) => {
+                self.path.pop();
+                let guard = ReadGuardNav::lock(&self.here().children[new_index]);
+                self.path.push((guard, new_index));
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn seek_child(&mut self, index: usize) -> bool {
+        match ChildIndex::compute(self.child_count(), index) {
+            Some(new_index) => {
+                let guard = ReadGuardNav::lock(&self.here().children[new_index]);
+                self.path.push((guard, new_index));
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn child_count(&self) -> usize {
+        self.here().children.len()
+    }
+
+    fn at_root(&self) -> bool {
+        self.path.is_empty()
+    }
+
+    fn index_in_parent(&self) -> Option<usize> {
+        self.path.last().map(|&(_, index)| index)
+    }
+
+    fn to_parent(&mut self) -> bool {
+        self.path.pop().is_some()
+    }
+
+    fn to_root(&mut self) {
+        self.path.clear();
+    }
+}
+
+/// Navigator that can edit a sync tree.
+///
+/// Holds a write lock on the root, which excludes every other navigator from
+/// the tree, and on each node between the root and the focus.
+pub struct WriteNav<'a, T: 'a> {
+    // Guards on the nodes below the root on the path to the focus, each with
+    // the index of its node among its siblings.
+    path: Vec<(RwLockWriteGuard<'a, Node<T>>, usize)>,
+    root: RwLockWriteGuard<'a, Node<T>>,
+    remove_focus_policy: RemoveFocusPolicy,
+    _registration: Registration,
+}
+
+impl<'a, T: 'a> WriteNav<'a, T> {
+    fn here(&self) -> &Node<T> {
+        match self.path.last() {
+            None => &self.root,
+            Some((guard, _)) => guard,
+        }
+    }
+
+    fn here_mut(&mut self) -> &mut Node<T> {
+        match self.path.last_mut() {
+            None => &mut self.root,
+            Some((guard, _)) => guard,
+        }
+    }
+
+    fn parent_mut(&mut self) -> Option<&mut Node<T>> {
+        match self.path.len() {
+            0 => None,
+            1 => Some(&mut self.root),
+            n => Some(&mut self.path[n - 2].0),
+        }
+    }
+
+    fn here_index(&self) -> Option<usize> {
+        self.path.last().map(|&(_, index)| index)
+    }
+
+    // Focuses on the child of the focus at `index`, which must exist.
+    fn descend(&mut self, index: usize) {
+        // The node outlives this guard: it can only be removed from the tree
+        // by this writer, which releases the guard first.
+        let guard: RwLockWriteGuard<'a, Node<T>> = unsafe {
+            mem::transmute(write_node(&self.here().children[index]))
+        };
+        self.path.push((guard, index));
+    }
+}
+
+impl<'a, T: 'a> Deref for WriteNav<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.here().data
+    }
+}
+
+impl<'a, T: 'a> DerefMut for WriteNav<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.here_mut().data
+    }
+}
+
+impl<'a, T: 'a> Nav for WriteNav<'a, T> {
+    fn seek_sibling(&mut self, offset: isize) -> bool {
+        if offset == 0 {
+            return true
+        }
+        let here_index = match self.here_index() {
+            Some(here_index) => here_index,
+            None => return false,
+        };
+        self.path.pop();
+        let sibling_count = self.here().children.len();
+        let index = SiblingIndex::compute(sibling_count, here_index, offset);
+        self.descend(index.unwrap_or(here_index));
+        index.is_some()
+    }
+
+    fn seek_child(&mut self, index: usize) -> bool {
+        match ChildIndex::compute(self.child_count(), index) {
+            Some(new_index) => {
+                self.descend(new_index);
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn child_count(&self) -> usize {
+        self.here().children.len()
+    }
+
+    fn at_root(&self) -> bool {
+        self.path.is_empty()
+    }
+
+    fn index_in_parent(&self) -> Option<usize> {
+        self.here_index()
+    }
+
+    fn to_parent(&mut self) -> bool {
+        self.path.pop().is_some()
+    }
+
+    fn to_root(&mut self) {
+        while self.path.pop().is_some() {}
+    }
+}
+
+impl<'a, T: 'a> Editor for WriteNav<'a, T> {
+    type Data = T;
+    type Tree = Tree<T>;
+
+    fn push_leaf(&mut self, data: T) {
+        self.push_child(Tree::leaf(data));
+    }
+
+    fn push_child(&mut self, child: Tree<T>) {
+        let index = {
+            let here = self.here_mut();
+            here.children.push(child.into_child());
+            here.children.len() - 1
+        };
+        self.descend(index);
+    }
+
+    fn insert_leaf(&mut self, index: usize, data: T) -> bool {
+        self.insert_child(index, Tree::leaf(data))
+    }
+
+    fn insert_child(&mut self, index: usize, child: Tree<T>) -> bool {
+        match ChildIndex::compute(self.child_count(), index) {
+            Some(new_index) => {
+                self.here_mut().children.insert(new_index, child.into_child());
+                self.descend(new_index);
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn insert_sibling_leaf(&mut self, offset: isize, data: T) -> bool {
+        self.insert_sibling(offset, Tree::leaf(data))
+    }
+
+    fn insert_sibling(&mut self, offset: isize, sibling: Tree<T>) -> bool {
+        let here_index = match self.here_index() {
+            Some(here_index) => here_index,
+            None => return false,
+        };
+        self.path.pop();
+        let sibling_count = self.here().children.len();
+        match SiblingIndex::compute(sibling_count, here_index, offset) {
+            Some(new_index) => {
+                self.here_mut().children.insert(new_index, sibling.into_child());
+                self.descend(new_index);
+                true
+            },
+            None => {
+                self.descend(here_index);
+                false
+            },
+        }
+    }
+
+    fn remove_focus_policy(&self) -> RemoveFocusPolicy {
+        self.remove_focus_policy
+    }
+
+    fn set_remove_focus_policy(&mut self, policy: RemoveFocusPolicy) -> bool {
+        self.remove_focus_policy = policy;
+        true
+    }
+
+    fn remove(&mut self) -> Tree<T> {
+        let here_index = self.here_index().unwrap_or_else(|| panic!["{}", NavError::AtRoot]);
+        self.path.pop();
+        let (removed, remaining) = {
+            let parent = self.here_mut();
+            let removed = parent.children.remove(here_index);
+            (removed, parent.children.len())
+        };
+        if let Some(new_index) = self.remove_focus_policy.focus_after_remove(here_index, remaining) {
+            self.descend(new_index);
+        }
+        Tree { root: removed, }
+    }
+
+    fn remove_child(&mut self, index: usize) -> Result<Tree<T>, EditError> {
+        let index = ChildIndex::check(self.child_count(), index)?;
+        Ok(Tree { root: self.here_mut().children.remove(index), })
+    }
+
+    fn remove_sibling(&mut self, offset: isize) -> Option<Tree<T>> {
+        if self.at_root() {
+            return None
+        }
+        if offset == 0 {
+            return Some(self.remove())
+        }
+        let here_index = self.here_index()?;
+        let sibling_count = self.parent_mut().unwrap().children.len();
+        SiblingIndex::compute(sibling_count, here_index, offset).map(|index| {
+            let removed = self.parent_mut().unwrap().children.remove(index);
+            if index < here_index {
+                self.path.last_mut().unwrap().1 -= 1;
+            }
+            Tree { root: removed, }
+        })
+    }
+
+    fn swap(&mut self, other: &mut Tree<T>) {
+        let other_node = Arc::get_mut(&mut other.root)
+            .expect("cannot swap with a sync tree that has other handles")
+            .get_mut()
+            .expect("sync tree lock poisoned");
+        mem::swap(self.here_mut(), other_node);
+    }
+
+    fn swap_children(&mut self, index_a: usize, index_b: usize) -> bool {
+        let child_count = self.child_count();
+        match (ChildIndex::compute(child_count, index_a),
+               ChildIndex::compute(child_count, index_b)) {
+            (Some(index_a), Some(index_b)) => {
+                self.here_mut().children.swap(index_a, index_b);
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn swap_siblings(&mut self, offset_a: isize, offset_b: isize) -> bool {
+        let here_index = match self.here_index() {
+            Some(here_index) => here_index,
+            None => return false,
+        };
+        let sibling_count = self.parent_mut().unwrap().children.len();
+        match (SiblingIndex::compute(sibling_count, here_index, offset_a),
+               SiblingIndex::compute(sibling_count, here_index, offset_b)) {
+            (Some(index_a), Some(index_b)) => {
+                self.parent_mut().unwrap().children.swap(index_a, index_b);
+                let here = &mut self.path.last_mut().unwrap().1;
+                if *here == index_a {
+                    *here = index_b;
+                } else if *here == index_b {
+                    *here = index_a;
+                }
+                true
+            },
+            _ => false,
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! sync_tree {
+    ($data:expr) => ($crate::sync::Tree::leaf($data));
+    ($data:expr, [$($first:tt)*] $(,[$($rest:tt)*])*) =>
+        ($crate::sync::Tree::new($data, vec![sync_tree![$($first)*]
+                                             $(,sync_tree![$($rest)*])*]));
+}
+
+#[cfg(test)]
+mod test {
+    use ::{Editor, Nav};
+    use ::path::TreePath;
+    use ::sync::Tree;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::mpsc;
+    use std::thread;
+
+    #[test]
+    fn eq_and_debug() {
+        let t = sync_tree!["a", ["b", ["c"]], ["d"]];
+        assert_eq![t, sync_tree!["a", ["b", ["c"]], ["d"]]];
+        assert![t != sync_tree!["a", ["b"], ["d"]]];
+        assert![t != sync_tree!["a", ["b", ["c"]], ["d"], ["e"]]];
+        assert_eq![t, t.clone()];
+        assert_eq![format!["{:?}", t], "(\"a\" (\"b\" (\"c\")) (\"d\"))"];
+    }
+
+    #[test]
+    fn readers_share_locks() {
+        let t = sync_tree![1, [2, [3]], [4]];
+        let mut r = t.read();
+        assert![r.seek_child(0)];
+        let mut s = r.clone();
+        assert![s.seek_child(0)];
+        assert_eq![*s, 3];
+        assert![r.seek_sibling(1)];
+        assert_eq![*r, 4];
+    }
+
+    #[test]
+    fn writer_edits() {
+        let t = sync_tree![1, [2], [3]];
+        let (removed_child, swapped, removed) = {
+            let mut w = t.write();
+            assert![w.seek_child(1)];
+            *w = 30;
+            w.push_leaf(31);
+            w.to_root();
+            let removed_child = w.remove_child(0).unwrap();
+            assert![w.insert_leaf(0, 20)];
+            assert![w.insert_sibling_leaf(0, 10)];
+            assert![w.swap_siblings(0, 2)];
+            assert_eq![*w, 10];
+            let mut other = sync_tree![40, [41]];
+            w.swap(&mut other);
+            let removed = w.remove();
+            assert_eq![*w, 20];
+            (removed_child, other, removed)
+        };
+        assert_eq![removed_child, sync_tree![2]];
+        assert_eq![swapped, sync_tree![10]];
+        assert_eq![removed, sync_tree![40, [41]]];
+        assert_eq![t, sync_tree![1, [30, [31]], [20]]];
+    }
+
+    #[test]
+    #[should_panic]
+    fn nested_acquisition_panics() {
+        let t = sync_tree![1];
+        let _r = t.read();
+        t.write();
+    }
+
+    #[test]
+    fn acquisition_in_rank_order() {
+        let a = sync_tree!["a"];
+        let b = sync_tree!["b"];
+        let (low, high) = if a.lock_rank() < b.lock_rank() { (&a, &b) } else { (&b, &a) };
+        let r = low.read();
+        let mut w = high.write();
+        *w = *r;
+        drop(w);
+        drop(r);
+        assert_eq![a, b];
+        // Releasing both makes either order legal again.
+        let _w = high.write();
+    }
+
+    #[test]
+    fn threads_read_while_one_writes() {
+        let t = sync_tree![0, [0], [0]];
+        let (tx, rx) = mpsc::channel();
+        let mut handles = Vec::new();
+        for _ in 0..4 {
+            let t = t.clone();
+            let tx = tx.clone();
+            handles.push(thread::spawn(move || {
+                for _ in 0..100 {
+                    // Every writer update leaves all nodes with equal values.
+                    let mut r = t.read();
+                    let root = *r;
+                    assert![r.seek_child(1)];
+                    assert_eq![*r, root];
+                    assert![r.seek_sibling(-1)];
+                    assert_eq![*r, root];
+                }
+                tx.send(()).unwrap();
+            }));
+        }
+        for i in 1..100 {
+            let mut w = t.write();
+            *w = i;
+            for c in 0..2 {
+                assert![w.seek_child(c)];
+                *w = i;
+                w.to_parent();
+            }
+        }
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq![rx.iter().take(4).count(), 4];
+        let t2: Tree<i32> = sync_tree![99, [99], [99]];
+        assert_eq![t, t2];
+    }
+
+    #[test]
+    fn updates_atomic_data_while_reading() {
+        let t = sync_tree![AtomicUsize::new(0), [AtomicUsize::new(0)], [AtomicUsize::new(0)]];
+        let leaf = TreePath::from(vec![1]);
+        let mut handles = Vec::new();
+        for _ in 0..4 {
+            let t = t.clone();
+            let leaf = leaf.clone();
+            handles.push(thread::spawn(move || {
+                for _ in 0..250 {
+                    assert![t.update_data(&leaf, |n| n + 1).is_some()];
+                }
+            }));
+        }
+        {
+            // Updates go on while this thread holds a reader.
+            let mut r = t.read();
+            assert![r.seek_child(1)];
+            while r.load(Ordering::Acquire) < 1000 {
+                thread::yield_now();
+            }
+        }
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq![Some(1000), t.load_data(&leaf)];
+        assert_eq![Some(0), t.load_data(&TreePath::root())];
+        assert_eq![None, t.update_data(&TreePath::from(vec![2]), |_| panic!["no such node"])];
+    }
+}
@@ -0,0 +1,165 @@
+use ::owned::Tree;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+/// Node of a template: either data to copy, or a named hole to fill.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum TemplateNode<T> {
+    Data(T),
+    Hole(String),
+}
+
+impl<T> TemplateNode<T> {
+    pub fn hole<S: Into<String>>(name: S) -> Self {
+        TemplateNode::Hole(name.into())
+    }
+}
+
+/// Tree with named holes, from which trees are made by substituting a subtree
+/// for each hole.
+///
+/// Holes are leaves, and any number of them may share a name, in which case
+/// each is filled with its own copy of the same subtree.
+#[derive(Debug)]
+pub struct Template<T> {
+    tree: Tree<TemplateNode<T>>,
+}
+
+impl<T> Template<T> {
+    /// Creates a template from `tree`. Panics if any hole has children.
+    pub fn new(tree: Tree<TemplateNode<T>>) -> Self {
+        let mut stack = vec![&tree];
+        while let Some(node) = stack.pop() {
+            if let TemplateNode::Hole(ref name) = *node.data() {
+                assert![node.children().is_empty(), "template hole {:?} has children", name];
+            }
+            stack.extend(node.children());
+        }
+        Template { tree, }
+    }
+
+    /// Returns the tree underlying this template.
+    pub fn tree(&self) -> &Tree<TemplateNode<T>> {
+        &self.tree
+    }
+
+    /// Returns the names of the holes in this template, in pre-order. A name
+    /// appears once for each hole that has it.
+    pub fn holes(&self) -> Vec<&str> {
+        let mut holes = Vec::new();
+        let mut stack = vec![&self.tree];
+        while let Some(node) = stack.pop() {
+            if let TemplateNode::Hole(ref name) = *node.data() {
+                holes.push(name.as_str());
+            }
+            stack.extend(node.children().iter().rev());
+        }
+        holes
+    }
+
+    /// Makes a tree from this template by copying its data and replacing each
+    /// hole with a copy of the subtree bound to its name. Fails with the first
+    /// hole, in pre-order, that has no binding. Bindings that no hole uses are
+    /// ignored.
+    pub fn instantiate(&self, bindings: &HashMap<String, Tree<T>>)
+                       -> Result<Tree<T>, MissingHole> where T: Clone {
+        let bound = |name: &String| bindings.get(name).cloned()
+            .ok_or_else(|| MissingHole { name: name.clone(), });
+        let root = match *self.tree.data() {
+            TemplateNode::Hole(ref name) => return bound(name),
+            TemplateNode::Data(ref data) => data.clone(),
+        };
+        // Each stack entry is a node's data, its remaining template children,
+        // and its instantiated children so far.
+        let mut stack = vec![(root, self.tree.children().iter(), Vec::new())];
+        loop {
+            let next = stack.last_mut().unwrap().1.next();
+            match next {
+                Some(child) => match *child.data() {
+                    TemplateNode::Hole(ref name) => {
+                        let tree = bound(name)?;
+                        stack.last_mut().unwrap().2.push(tree);
+                    },
+                    TemplateNode::Data(ref data) =>
+                        stack.push((data.clone(), child.children().iter(), Vec::new())),
+                },
+                None => {
+                    let (data, _, children) = stack.pop().unwrap();
+                    let tree = Tree::new(data, children);
+                    match stack.last_mut() {
+                        Some(parent) => parent.2.push(tree),
+                        None => return Ok(tree),
+                    }
+                },
+            }
+        }
+    }
+}
+
+/// Error returned when instantiating a template with no binding for one of
+/// its holes.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct MissingHole {
+    pub name: String,
+}
+
+impl fmt::Display for MissingHole {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "no binding for template hole {:?}", self.name)
+    }
+}
+
+impl Error for MissingHole {}
+
+#[cfg(test)]
+mod test {
+    use ::owned::Tree;
+    use ::owned_tree;
+    use ::template::{MissingHole, Template, TemplateNode};
+
+    use std::collections::HashMap;
+
+    fn data(d: &'static str) -> TemplateNode<&'static str> {
+        TemplateNode::Data(d)
+    }
+
+    fn hole(name: &str) -> Tree<TemplateNode<&'static str>> {
+        Tree::leaf(TemplateNode::hole(name))
+    }
+
+    #[test]
+    fn instantiate_fills_holes() {
+        // if $cond { $body } else { $body }
+        let template = Template::new(Tree::new(data("if"), vec![
+            hole("cond"),
+            Tree::new(data("then"), vec![hole("body")]),
+            Tree::new(data("else"), vec![hole("body")])]));
+        assert_eq![vec!["cond", "body", "body"], template.holes()];
+
+        let mut bindings = HashMap::new();
+        bindings.insert("cond".to_string(), owned_tree!["x"]);
+        assert_eq![Err(MissingHole { name: "body".to_string(), }),
+                   template.instantiate(&bindings)];
+        bindings.insert("body".to_string(), owned_tree!["call", ["f"]]);
+        bindings.insert("unused".to_string(), owned_tree!["y"]);
+        assert_eq![Ok(owned_tree!["if", ["x"], ["then", ["call", ["f"]]],
+                                  ["else", ["call", ["f"]]]]),
+                   template.instantiate(&bindings)];
+    }
+
+    #[test]
+    fn root_hole_is_replaced() {
+        let template = Template::new(hole("all"));
+        let mut bindings = HashMap::new();
+        bindings.insert("all".to_string(), owned_tree!["a", ["b"]]);
+        assert_eq![Ok(owned_tree!["a", ["b"]]), template.instantiate(&bindings)];
+    }
+
+    #[test]
+    #[should_panic(expected = "template hole \"h\" has children")]
+    fn holes_must_be_leaves() {
+        Template::new(Tree::new(TemplateNode::hole("h"), vec![Tree::leaf(data("a"))]));
+    }
+}
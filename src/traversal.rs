@@ -1,5 +1,7 @@
 use ::Nav;
-use std::collections::VecDeque;
+use ::path::TreePath;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, VecDeque};
 use std::marker::PhantomData;
 
 /// Persistent queue that imposes an ordering on data.
@@ -57,6 +59,10 @@ impl<T> DepthQueue<T> {
     pub fn new() -> Self { DepthQueue { v: Vec::new(), } }
 }
 
+impl<T> Default for DepthQueue<T> {
+    fn default() -> Self { DepthQueue::new() }
+}
+
 impl<T> Queue<T> for DepthQueue<T> {
     fn len(&self) -> usize { self.v.len() }
     fn first(&self) -> Option<&T> { self.v.first() }
@@ -66,6 +72,9 @@ impl<T> Queue<T> for DepthQueue<T> {
 
 /// `std::collections::VecDeque`-backed queue with first in, first out
 /// ordering. Used for breadth-first search.
+///
+/// Passed to `fixed::Tree::from_traversal`, this lays a tree out in level
+/// order, so that each level's nodes are contiguous.
 pub struct BreadthQueue<T> {
     v: VecDeque<T>,
 }
@@ -74,6 +83,10 @@ impl<T> BreadthQueue<T> {
     pub fn new() -> Self { BreadthQueue { v: VecDeque::new(), } }
 }
 
+impl<T> Default for BreadthQueue<T> {
+    fn default() -> Self { BreadthQueue::new() }
+}
+
 impl<T> Queue<T> for BreadthQueue<T> {
     fn len(&self) -> usize { self.v.len() }
     fn first(&self) -> Option<&T> { self.v.front() }
@@ -81,6 +94,74 @@ impl<T> Queue<T> for BreadthQueue<T> {
     fn shift(&mut self) -> Option<T> { self.v.pop_front() }
 }
 
+/// `std::collections::BinaryHeap`-backed queue that returns the item with the
+/// least key first. Used for best-first search.
+///
+/// Keys are computed once, when an item is added. Items with equal keys are
+/// returned in the order in which they were added.
+pub struct PriorityQueue<T, K, F> {
+    heap: BinaryHeap<Prioritized<T, K>>,
+    key: F,
+    next_sequence: u64,
+}
+
+impl<T, K, F> PriorityQueue<T, K, F> where K: Ord, F: Fn(&T) -> K {
+    pub fn new(key: F) -> Self {
+        PriorityQueue { heap: BinaryHeap::new(), key, next_sequence: 0, }
+    }
+}
+
+impl<T, K, F> Queue<T> for PriorityQueue<T, K, F> where K: Ord, F: Fn(&T) -> K {
+    fn len(&self) -> usize { self.heap.len() }
+    fn first(&self) -> Option<&T> { self.heap.peek().map(|p| &p.item) }
+
+    fn unshift(&mut self, item: T) {
+        let key = (self.key)(&item);
+        self.heap.push(Prioritized { key, sequence: self.next_sequence, item, });
+        self.next_sequence += 1;
+    }
+
+    fn shift(&mut self) -> Option<T> { self.heap.pop().map(|p| p.item) }
+}
+
+// Heap entry of a `PriorityQueue`, ordered so that the max-heap yields the
+// least key, and then the earliest sequence number, first.
+struct Prioritized<T, K> {
+    key: K,
+    sequence: u64,
+    item: T,
+}
+
+impl<T, K: Ord> Ord for Prioritized<T, K> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.key.cmp(&self.key).then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+impl<T, K: Ord> PartialOrd for Prioritized<T, K> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T, K: Ord> PartialEq for Prioritized<T, K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T, K: Ord> Eq for Prioritized<T, K> {}
+
+/// Order in which the nodes of a tree are visited.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Order {
+    /// Each node is visited before its children, and each child's subtree is
+    /// visited before its next sibling (pre-order).
+    DepthFirst,
+    /// Nodes are visited level by level, from left to right.
+    BreadthFirst,
+}
+
 /// Traverses a tree with a visitor function that is called at each node.
 ///
 /// The traversal starts at the tree location `v` and proceeds through it and
@@ -250,6 +331,384 @@ pub fn find_all<N, Q, F>(n: N, mut queue: Q, predicate: F) -> FindIter<N, Q, F>
         FindIter { phantom: PhantomData, predicate: predicate, queue: queue, }
     }
 
+/// Iterator over a focus node and its siblings, in order, pairing each item of
+/// `I` with its offset from the focus (so the focus itself has offset 0).
+///
+/// The backends' `enumerate_siblings` methods return this over references to
+/// the sibling data.
+pub struct EnumerateSiblings<I> {
+    siblings: I,
+    offset: isize,
+}
+
+impl<I> EnumerateSiblings<I> {
+    /// Creates an iterator over `siblings`, the focus being at `here_index`.
+    pub fn new(siblings: I, here_index: usize) -> Self {
+        EnumerateSiblings { siblings: siblings, offset: -(here_index as isize), }
+    }
+}
+
+impl<I: Iterator> Iterator for EnumerateSiblings<I> {
+    type Item = (isize, I::Item);
+
+    fn next(&mut self) -> Option<(isize, I::Item)> {
+        self.siblings.next().map(|item| {
+            let offset = self.offset;
+            self.offset += 1;
+            (offset, item)
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.siblings.size_hint()
+    }
+}
+
+impl<I: ExactSizeIterator> ExactSizeIterator for EnumerateSiblings<I> {}
+
+/// A node reached by a traversal, with its depth and path relative to the node
+/// at which the traversal started.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Visit<N> {
+    pub node: N,
+    pub depth: usize,
+    pub path: TreePath,
+}
+
+/// Iterator over a subtree in pre-order, yielding each node as a `Visit`.
+///
+/// Unlike searches driven by a `DepthQueue`, this visits each node's children
+/// in order.
+pub struct Preorder<N> {
+    stack: Vec<Visit<N>>,
+}
+
+/// Traverses the subtree at `n` in pre-order.
+pub fn preorder<N: Nav + Clone>(n: N) -> Preorder<N> {
+    Preorder { stack: vec![Visit { node: n, depth: 0, path: TreePath::root(), }], }
+}
+
+impl<N: Nav + Clone> Iterator for Preorder<N> {
+    type Item = Visit<N>;
+
+    fn next(&mut self) -> Option<Visit<N>> {
+        let visit = self.stack.pop()?;
+        for i in (0..visit.node.child_count()).rev() {
+            let mut child = visit.node.clone();
+            child.seek_child(i);
+            self.stack.push(Visit { node: child,
+                                    depth: visit.depth + 1,
+                                    path: visit.path.child(i), });
+        }
+        Some(visit)
+    }
+}
+
+/// Adapters for iterators of `Visit`s that keep each visit's depth and path.
+///
+/// Filtering the nodes yielded by a traversal with `Iterator::filter` after
+/// mapping them to their data loses their positions in the tree. These
+/// adapters act on the node of each visit and carry its position along.
+pub trait VisitIterator<N>: Iterator<Item=Visit<N>> + Sized {
+    /// Yields only the visits whose nodes satisfy `predicate`. The descendants
+    /// of a rejected node are still visited.
+    fn filter_nodes<F>(self, predicate: F) -> FilterNodes<Self, F> where F: FnMut(&N) -> bool {
+        FilterNodes { visits: self, predicate, }
+    }
+
+    /// Replaces the node of each visit with the result of calling `f` on it.
+    fn map_nodes<U, F>(self, f: F) -> MapNodes<Self, F> where F: FnMut(N) -> U {
+        MapNodes { visits: self, f, }
+    }
+
+    /// Yields only the visits at most `max_depth` below the start of the
+    /// traversal.
+    fn take_depth(self, max_depth: usize) -> TakeDepth<Self> {
+        TakeDepth { visits: self, max_depth, }
+    }
+}
+
+impl<N, I> VisitIterator<N> for I where I: Iterator<Item=Visit<N>> {}
+
+/// Iterator returned by `VisitIterator::filter_nodes`.
+pub struct FilterNodes<I, F> {
+    visits: I,
+    predicate: F,
+}
+
+impl<N, I, F> Iterator for FilterNodes<I, F>
+    where I: Iterator<Item=Visit<N>>, F: FnMut(&N) -> bool {
+        type Item = Visit<N>;
+
+        fn next(&mut self) -> Option<Visit<N>> {
+            let predicate = &mut self.predicate;
+            self.visits.find(|visit| predicate(&visit.node))
+        }
+    }
+
+/// Iterator returned by `VisitIterator::map_nodes`.
+pub struct MapNodes<I, F> {
+    visits: I,
+    f: F,
+}
+
+impl<N, U, I, F> Iterator for MapNodes<I, F>
+    where I: Iterator<Item=Visit<N>>, F: FnMut(N) -> U {
+        type Item = Visit<U>;
+
+        fn next(&mut self) -> Option<Visit<U>> {
+            self.visits.next().map(|visit| Visit { node: (self.f)(visit.node),
+                                                   depth: visit.depth,
+                                                   path: visit.path, })
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            self.visits.size_hint()
+        }
+    }
+
+/// Iterator returned by `VisitIterator::take_depth`.
+pub struct TakeDepth<I> {
+    visits: I,
+    max_depth: usize,
+}
+
+impl<N, I> Iterator for TakeDepth<I> where I: Iterator<Item=Visit<N>> {
+    type Item = Visit<N>;
+
+    fn next(&mut self) -> Option<Visit<N>> {
+        let max_depth = self.max_depth;
+        self.visits.find(|visit| visit.depth <= max_depth)
+    }
+}
+
+/// Node being walked by a `TreeWalker`, with the state kept for it while its
+/// children are walked.
+pub struct Frame<N, S> {
+    pub node: N,
+    pub state: S,
+    depth: usize,
+    // Children still to be walked, in order.
+    children: VecDeque<(N, S)>,
+}
+
+impl<N, S> Frame<N, S> {
+    /// Returns how far below the start of the walk this node is.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Schedules `node` to be walked, starting with `state`, after the
+    /// children already scheduled.
+    pub fn push_child(&mut self, node: N, state: S) {
+        self.children.push_back((node, state));
+    }
+
+    /// Schedules each child of this node to be walked, in order, starting with
+    /// the state `state` gives for it.
+    pub fn push_children<F>(&mut self, mut state: F) where N: Nav + Clone, F: FnMut(&N) -> S {
+        for i in 0..self.node.child_count() {
+            let mut child = self.node.clone();
+            child.seek_child(i);
+            let child_state = state(&child);
+            self.children.push_back((child, child_state));
+        }
+    }
+}
+
+/// Step of a walk, returned by `TreeWalker::step`.
+pub enum WalkStep<'a, N: 'a, S: 'a> {
+    /// A node was reached. The children scheduled on its frame are walked
+    /// next.
+    Enter(&'a mut Frame<N, S>),
+    /// A node's children have all been walked, and its frame removed. Its
+    /// parent's frame, if it has one, is now `TreeWalker::top_mut`.
+    Exit(N, S),
+}
+
+/// Depth-first walk with an explicit stack of frames, for algorithms that
+/// would otherwise recurse over a tree and so overflow the call stack on deep
+/// ones.
+///
+/// Each node is entered when it is reached, which is when its children are
+/// scheduled, and exited once they have all been walked, which is when what
+/// was worked out for it can be passed to its parent's state. Nodes need not be
+/// `Nav`s: references to subtrees or indices into another structure do as
+/// well, as long as each node's children are scheduled with
+/// `Frame::push_child`.
+///
+/// A walk is driven either by calling `step` in a loop, or by `walk` with a
+/// hook for each kind of step.
+pub struct TreeWalker<N, S> {
+    stack: Vec<Frame<N, S>>,
+    // The first node, until it is entered.
+    start: Option<(N, S)>,
+}
+
+impl<N, S> TreeWalker<N, S> {
+    /// Starts a walk at `node`, with `state` as its state.
+    pub fn new(node: N, state: S) -> Self {
+        TreeWalker { stack: Vec::new(), start: Some((node, state)), }
+    }
+
+    /// Returns the number of frames on the stack: the nodes entered but not
+    /// yet exited.
+    pub fn frame_count(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Returns the frame of the node whose children are being walked, or
+    /// `None` once the walk is over.
+    pub fn top_mut(&mut self) -> Option<&mut Frame<N, S>> {
+        self.stack.last_mut()
+    }
+
+    /// Enters the next scheduled child of the top frame, or exits the top
+    /// frame if it has none left. Returns `None` once the start node has been
+    /// exited.
+    pub fn step(&mut self) -> Option<WalkStep<'_, N, S>> {
+        let next = match self.start.take() {
+            Some((node, state)) => Some((node, state, 0)),
+            None => {
+                let top = self.stack.last_mut()?;
+                let depth = top.depth + 1;
+                top.children.pop_front().map(|(node, state)| (node, state, depth))
+            },
+        };
+        match next {
+            Some((node, state, depth)) => {
+                self.stack.push(Frame { node, state, depth, children: VecDeque::new(), });
+                Some(WalkStep::Enter(self.stack.last_mut().unwrap()))
+            },
+            None => {
+                let frame = self.stack.pop().unwrap();
+                Some(WalkStep::Exit(frame.node, frame.state))
+            },
+        }
+    }
+
+    /// Walks to the end, calling `on_enter` for each node entered and
+    /// `on_exit` for each node exited, with the state of its parent unless it
+    /// is the start node.
+    pub fn walk<E, X>(mut self, mut on_enter: E, mut on_exit: X)
+        where E: FnMut(&mut Frame<N, S>), X: FnMut(N, S, Option<&mut S>) {
+            while let Some(step) = self.step() {
+                match step {
+                    WalkStep::Enter(frame) => on_enter(frame),
+                    WalkStep::Exit(node, state) =>
+                        on_exit(node, state, self.top_mut().map(|parent| &mut parent.state)),
+                }
+            }
+        }
+}
+
+#[cfg(test)]
+mod test {
+    use ::owned::TreeView;
+    use ::owned_tree;
+    use ::traversal::{find_all, preorder, PriorityQueue, Queue, TreeWalker, VisitIterator,
+                      WalkStep};
+
+    #[test]
+    fn priority_queue_shifts_least_key_first() {
+        let mut q = PriorityQueue::new(|&(k, _): &(i32, char)| k);
+        for &item in &[(3, 'a'), (1, 'b'), (2, 'c'), (1, 'd')] {
+            q.unshift(item);
+        }
+        assert_eq![4, q.len()];
+        assert_eq![Some(&(1, 'b')), q.first()];
+        let order: Vec<char> = (0..4).map(|_| q.shift().unwrap().1).collect();
+        assert_eq![vec!['b', 'd', 'c', 'a'], order];
+        assert![q.is_empty()];
+    }
+
+    #[test]
+    fn priority_queue_orders_searches_best_first() {
+        let t = owned_tree![5, [7, [1]], [3, [6], [2]]];
+        let queue = PriorityQueue::new(|v: &TreeView<i32>| **v);
+        let found: Vec<i32> = find_all(t.view(), queue, |_| true).map(|v| *v).collect();
+        assert_eq![vec![5, 3, 2, 6, 7, 1], found];
+    }
+
+    #[test]
+    fn preorder_visits_children_in_order() {
+        let t = owned_tree![1, [2, [3], [4]], [5, [6]]];
+        let visits: Vec<(i32, usize, String)> = preorder(t.view())
+            .map(|v| (*v.node, v.depth, v.path.to_string()))
+            .collect();
+        assert_eq![vec![(1, 0, "/".to_string()), (2, 1, "/0".to_string()),
+                        (3, 2, "/0/0".to_string()), (4, 2, "/0/1".to_string()),
+                        (5, 1, "/1".to_string()), (6, 2, "/1/0".to_string())],
+                   visits];
+    }
+
+    #[test]
+    fn adapters_keep_positions() {
+        let t = owned_tree![1, [2, [3], [4]], [5, [6, [8]]]];
+        let visits: Vec<(i32, usize, String)> = preorder(t.view())
+            .map_nodes(|v| *v)
+            .filter_nodes(|&data| data % 2 == 0)
+            .take_depth(2)
+            .map(|v| (v.node, v.depth, v.path.to_string()))
+            .collect();
+        assert_eq![vec![(2, 1, "/0".to_string()), (4, 2, "/0/1".to_string()),
+                        (6, 2, "/1/0".to_string())],
+                   visits];
+    }
+
+    #[test]
+    fn walker_evaluates_bottom_up() {
+        let t = owned_tree!["+", ["1"], ["*", ["2"], ["3"], ["4"]], ["5"]];
+        // Each frame's state is the values of its children walked so far.
+        let mut result = None;
+        let mut depths = Vec::new();
+        TreeWalker::new(t.view(), Vec::new()).walk(
+            |frame| {
+                depths.push(frame.depth());
+                frame.push_children(|_| Vec::new());
+            },
+            |node, operands: Vec<i32>, parent| {
+                let value = match *node {
+                    "+" => operands.iter().sum(),
+                    "*" => operands.iter().product(),
+                    leaf => leaf.parse().unwrap(),
+                };
+                match parent {
+                    Some(parent) => parent.push(value),
+                    None => result = Some(value),
+                }
+            });
+        assert_eq![Some(30), result];
+        assert_eq![vec![0, 1, 1, 2, 2, 2, 1], depths];
+    }
+
+    #[test]
+    fn walker_steps_over_deep_trees() {
+        // A path 100000 nodes long, given by each node's successor.
+        let n = 100000;
+        let next: Vec<Option<usize>> = (0..n).map(|i| if i + 1 < n { Some(i + 1) } else { None })
+            .collect();
+        let mut walker = TreeWalker::new(0, 0);
+        let mut max_frames = 0;
+        let mut height = None;
+        while let Some(step) = walker.step() {
+            match step {
+                WalkStep::Enter(frame) => if let Some(child) = next[frame.node] {
+                    frame.push_child(child, 0);
+                },
+                WalkStep::Exit(_, h) => match walker.top_mut() {
+                    Some(parent) => parent.state = h + 1,
+                    None => height = Some(h),
+                },
+            }
+            max_frames = max_frames.max(walker.frame_count());
+        }
+        assert_eq![Some(n - 1), height];
+        assert_eq![n, max_frames];
+        assert![walker.step().is_none()];
+    }
+}
+
 // #[cfg(test)]
 // mod test {
 //     fn iter_eq<T, I, J>(i: I, j: J) -> bool
@@ -1,104 +1,177 @@
-use std::convert::Into;
-
-/// The result of computing the index of a nonroot tree node's sibling.
-pub enum SiblingIndex {
-    /// Numerical underflow in computing the index.
-    Underflow,
-    /// Numerical overflow in computing the index.
-    Overflow,
-    /// The computed index is out of range, with the second value giving the
-    /// number of siblings.
-    OutOfRange(usize, usize),
-    /// A successfully computed index value.
-    Valid(usize),
+use ::Editor;
+use ::error::EditError;
+use ::path::TreePath;
+
+/// Validates that `perm` is a permutation of the indices of `child_count`
+/// children, i.e., that it holds each of `0..child_count` exactly once.
+pub fn check_permutation(perm: &[usize], child_count: usize) -> Result<(), EditError> {
+    let mut seen = vec![false; child_count];
+    if perm.len() != child_count {
+        return Err(EditError::NotPermutation { child_count, })
+    }
+    for &index in perm {
+        if index >= child_count || seen[index] {
+            return Err(EditError::NotPermutation { child_count, })
+        }
+        seen[index] = true;
+    }
+    Ok(())
+}
+
+/// Path of a wrapped editor's focus, which wrappers that track where edits
+/// happen keep in step with the focus. It is usually the path from the root,
+/// but may start at any node above the focus.
+#[derive(Clone, Debug, Default)]
+pub struct FocusPath {
+    path: TreePath,
 }
 
-impl SiblingIndex {
-    pub fn of(sibling_count: usize,
-              here_index: usize,
-              offset: isize) -> Self {
-        let offset_abs = offset.abs();
-        if offset_abs < 0 {
-            // offset is Int::min_value().
-            let mut new_index = match here_index.checked_sub(1) {
-                Some(x) => x,
-                None => return SiblingIndex::Underflow,
-            };
-            new_index = match new_index.checked_sub((offset_abs + 1isize).abs() as usize) {
-                Some(x) => x,
-                None => return SiblingIndex::Underflow,
-            };
-            SiblingIndex::Valid(new_index)
-        } else if offset_abs == 0 {
-            SiblingIndex::Valid(here_index)
-        } else {
-            let new_index = match here_index.checked_add(offset_abs as usize) {
-                Some(x) => x,
-                None => return SiblingIndex::Overflow,
-            };
-            if new_index >= sibling_count {
-                return SiblingIndex::OutOfRange(new_index, sibling_count);
-            }
-            SiblingIndex::Valid(new_index)
+impl FocusPath {
+    /// Returns the path of a focus at the node the path starts from.
+    pub fn new() -> Self {
+        FocusPath { path: TreePath::root(), }
+    }
+
+    pub fn path(&self) -> &TreePath {
+        &self.path
+    }
+
+    /// Returns `true` iff the focus is at the node the path starts from.
+    pub fn is_start(&self) -> bool {
+        self.path.is_root()
+    }
+
+    /// Returns the path of the focus's parent, or `None` at the start.
+    pub fn parent(&self) -> Option<TreePath> {
+        self.path.parent()
+    }
+
+    /// Returns the index of the focus among its siblings. Panics at the start.
+    pub fn here_index(&self) -> usize {
+        *self.path.indices().last().unwrap()
+    }
+
+    /// Returns the index of the sibling at `offset` from the focus, which must
+    /// exist.
+    pub fn sibling_index(&self, offset: isize) -> usize {
+        (self.here_index() as isize + offset) as usize
+    }
+
+    pub fn set_here_index(&mut self, index: usize) {
+        self.path.pop();
+        self.path.push(index);
+    }
+
+    /// Follows a successful `Nav::seek_sibling`.
+    pub fn seek_sibling(&mut self, offset: isize) {
+        if offset != 0 {
+            let index = self.sibling_index(offset);
+            self.set_here_index(index);
         }
     }
 
-    /// Safely computes the index of a tree node's sibling.
-    ///
-    /// For `sibling_count` siblings and the current node at `here_index`, the
-    /// index of the node that is the given offset from `here_index` is computed
-    /// using checked arithmetic.
-    pub fn compute(sibling_count: usize,
-                   here_index: usize,
-                   offset: isize) -> Option<usize> {
-        SiblingIndex::of(sibling_count, here_index, offset).into()
+    /// Follows a successful `Nav::seek_child`.
+    pub fn seek_child(&mut self, index: usize) {
+        self.path.push(index);
+    }
+
+    /// Follows a successful `Nav::to_parent`.
+    pub fn pop(&mut self) {
+        self.path.pop();
+    }
+
+    /// Follows `Nav::to_root`, for a path that starts from the root.
+    pub fn reset(&mut self) {
+        self.path = TreePath::root();
+    }
+
+    /// Starts the path from the parent of the node it started from, which is
+    /// that parent's child at `index`.
+    pub fn start_at_parent(&mut self, index: usize) {
+        self.path = prefixed(&[index], &self.path);
+    }
+
+    /// Follows a successful `Editor::remove_sibling` with a nonzero offset.
+    pub fn sibling_removed(&mut self, offset: isize) {
+        if offset < 0 {
+            let index = self.here_index() - 1;
+            self.set_here_index(index);
+        }
     }
-}
 
-impl Into<Option<usize>> for SiblingIndex {
-    /// Unwraps the index to get its value, or panics with an error message if
-    /// `self` is not `SiblingIndex::Valid`.
-    fn into(self) -> Option<usize> {
-        match self {
-            SiblingIndex::Underflow => panic!["numerical underflow computing sibling offset"],
-            SiblingIndex::Overflow => panic!["numerical overflow computing sibling offset"],
-            SiblingIndex::OutOfRange(_, _) => None,
-            SiblingIndex::Valid(new_index) => Some(new_index),
+    /// Follows a successful `Editor::swap_siblings`.
+    pub fn siblings_swapped(&mut self, offset_a: isize, offset_b: isize) {
+        if offset_a == 0 {
+            self.seek_sibling(offset_b);
+        } else if offset_b == 0 {
+            self.seek_sibling(offset_a);
         }
     }
 }
 
-/// The result of computing the index of a child.
-pub enum ChildIndex {
-    /// The computed index is out of range, with the second value giving the
-    /// actual number of children.
-    OutOfRange(usize, usize),
-    /// A successfully computed index value.
-    Valid(usize),
+/// Removes the focus of `editor`, which must not be the root, and moves
+/// `focus` to wherever the editor's remove focus policy leaves it. Returns the
+/// removed subtree and the index it had among its siblings.
+pub fn remove_focus<E: Editor>(editor: &mut E, focus: &mut FocusPath) -> (E::Tree, usize) {
+    let index = focus.here_index();
+    editor.to_parent();
+    let remaining = editor.child_count() - 1;
+    editor.seek_child(index);
+    let removed = editor.remove();
+    focus.pop();
+    if let Some(index) = editor.remove_focus_policy().focus_after_remove(index, remaining) {
+        focus.seek_child(index);
+    }
+    (removed, index)
+}
+
+/// Returns `path` with `prefix` before its indices.
+pub fn prefixed(prefix: &[usize], path: &TreePath) -> TreePath {
+    prefix.iter().chain(path.indices()).cloned().collect::<Vec<_>>().into()
 }
 
-impl ChildIndex {
-    /// Validates that a tree node has a child at the given index.
-    pub fn of(child_count: usize, index: usize) -> Self {
-        if index >= child_count {
-            ChildIndex::OutOfRange(index, child_count)
-        } else {
-            ChildIndex::Valid(index)
+/// Maps the index that `path` has among the children of `parent` with `f`, if
+/// `path` addresses a node below `parent`. Returns false, leaving `path`
+/// unchanged, if `f` returns `None` because that child is gone.
+pub fn reindex_below<F>(path: &mut TreePath, parent: &TreePath, f: F) -> bool
+    where F: Fn(usize) -> Option<usize> {
+        let depth = parent.depth();
+        if path.depth() <= depth || !parent.contains(path) {
+            return true
+        }
+        let mut indices = path.indices().to_vec();
+        match f(indices[depth]) {
+            Some(index) => {
+                indices[depth] = index;
+                *path = TreePath::new(indices);
+                true
+            },
+            None => false,
         }
     }
 
-    pub fn compute(child_count: usize, index: usize) -> Option<usize> {
-        ChildIndex::of(child_count, index).into()
+/// Maps a child's index to the one it has after a child is inserted at
+/// `index`.
+pub fn after_insert(index: usize) -> impl Fn(usize) -> Option<usize> {
+    move |i| Some(if i >= index { i + 1 } else { i })
+}
+
+/// Maps a child's index to the one it has after the child at `index` is
+/// removed, or to `None` for the removed child.
+pub fn after_remove(index: usize) -> impl Fn(usize) -> Option<usize> {
+    move |i| match i {
+        _ if i < index => Some(i),
+        _ if i == index => None,
+        _ => Some(i - 1),
     }
 }
 
-impl Into<Option<usize>> for ChildIndex {
-    /// Unwraps the index to get its value, or panics with an error message if
-    /// `self` is not `ChildIndex::Valid`.    
-    fn into(self) -> Option<usize> {
-        match self {
-            ChildIndex::OutOfRange(_, _) => None,
-            ChildIndex::Valid(new_index) => Some(new_index),
-        }
-     }
+/// Maps a child's index to the one it has after the children at `index_a` and
+/// `index_b` are swapped.
+pub fn after_swap(index_a: usize, index_b: usize) -> impl Fn(usize) -> Option<usize> {
+    move |i| Some(match i {
+        _ if i == index_a => index_b,
+        _ if i == index_b => index_a,
+        _ => i,
+    })
 }
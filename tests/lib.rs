@@ -1,14 +1,73 @@
-#[macro_use(owned_tree, shared_tree)]
+#[macro_use(editor_tests, fixed_tree, linked_tree, owned_tree, shared_tree, sync_tree)]
 extern crate entmut;
 
 /// Defines macros for generalized tests of Nav impls.
 #[macro_use]
 mod view_tests;
 
+mod fixed {
+    view_tests!(fixed_tree);
+    view_tests_across_trees!(fixed_tree);
+}
+
+mod linked {
+    view_tests!(linked_tree);
+    view_tests_across_trees!(linked_tree);
+
+    mod editor {
+        editor_tests!(linked_tree, view_mut, |e| **e);
+    }
+}
+
 mod owned {
     view_tests!(owned_tree);
+    view_tests_across_trees!(owned_tree);
+
+    mod editor {
+        editor_tests!(owned_tree, view_mut, |e| **e);
+    }
 }
 
 mod shared {
     view_tests!(shared_tree);
+    view_tests_across_trees!(shared_tree);
+
+    mod editor {
+        use std::borrow::Borrow;
+
+        editor_tests!(shared_tree, view_mut, |e| *Borrow::<&str>::borrow(e));
+    }
+
+    mod in_place_editor {
+        use std::borrow::Borrow;
+
+        editor_tests!(shared_tree, view_mut_in_place, |e| *Borrow::<&str>::borrow(e));
+    }
+}
+
+mod sync {
+    view_tests!(sync_tree);
+
+    #[test]
+    fn views_of_different_trees_are_unordered() {
+        let t = sync_tree!["a", ["b", ["c"]], ["d"]];
+        let other = sync_tree!["a", ["b", ["c"]], ["d"]];
+        // Views on two trees must be acquired in order of lock rank.
+        let (low, high) = if t.lock_rank() < other.lock_rank() {
+            (&t, &other)
+        } else {
+            (&other, &t)
+        };
+        let low_root = low.view();
+        let high_root = high.view();
+        assert![low_root != high_root];
+        assert_eq![None, high_root.partial_cmp(&low_root)];
+    }
+
+    mod editor {
+        // Sync trees are written through shared references.
+        #![allow(unused_mut)]
+
+        editor_tests!(sync_tree, write, |e| **e);
+    }
 }
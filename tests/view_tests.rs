@@ -45,6 +45,132 @@ macro_rules! view_tests {
             assert![! v.seek_sibling(1)];
         }
 
+        #[test]
+        fn view_seek_sibling_negative_offset() {
+            let t = $tree_macro!["a", ["b"], ["c"], ["d"]];
+            let mut v = t.view();
+            assert![v.seek_child(2)];
+            assert![v.seek_sibling(-2)];
+            assert_eq!["b", *v];
+            assert![! v.seek_sibling(-1)];
+            assert_eq!["b", *v];
+            assert![v.seek_sibling(1)];
+            assert_eq!["c", *v];
+        }
+
+        #[test]
+        fn view_compares_positions() {
+            use std::cmp::Ordering;
+
+            let t = $tree_macro!["a", ["b", ["c"]], ["d"]];
+            let root = t.view();
+            let mut b = root.clone();
+            assert![b.seek_child(0)];
+            let mut c = b.clone();
+            assert![c.seek_child(0)];
+            let mut d = root.clone();
+            assert![d.seek_child(1)];
+            assert![root < b && b < c && c < d];
+            assert_eq![Some(Ordering::Greater), d.partial_cmp(&root)];
+            assert![d.seek_sibling(-1)];
+            assert![d == b];
+            assert![d != c];
+        }
+
+        #[test]
+        fn view_index_in_parent_tracks_focus() {
+            let t = $tree_macro!["a", ["b"], ["c", ["d"], ["e"]], ["f"]];
+            let mut v = t.view();
+            assert_eq![None, v.index_in_parent()];
+            assert![v.seek_child(1)];
+            assert_eq![Some(1), v.index_in_parent()];
+            assert![v.seek_child(1)];
+            assert_eq![Some(1), v.index_in_parent()];
+            assert![v.seek_sibling(-1)];
+            assert_eq![Some(0), v.index_in_parent()];
+            assert![v.to_parent()];
+            assert![v.seek_sibling(1)];
+            assert_eq![Some(2), v.index_in_parent()];
+            v.seek_first_sibling();
+            assert_eq![Some(0), v.index_in_parent()];
+            v.to_root();
+            assert_eq![None, v.index_in_parent()];
+        }
+
+        #[test]
+        fn view_seeks_outermost_leaves() {
+            let t = $tree_macro!["a", ["b", ["c"], ["d"]], ["e"], ["f", ["g", ["h"]], ["i", ["j"], ["k"]]]];
+            let mut v = t.view();
+            v.to_leftmost_leaf();
+            assert_eq!["c", *v];
+            v.to_leftmost_leaf();
+            assert_eq!["c", *v];
+            v.to_root();
+            v.to_rightmost_leaf();
+            assert_eq!["k", *v];
+            assert![v.to_parent()];
+            assert![v.seek_sibling(-1)];
+            v.to_rightmost_leaf();
+            assert_eq!["h", *v];
+            assert![v.to_parent()];
+            assert![v.to_parent()];
+            assert![v.seek_sibling(-1)];
+            v.to_rightmost_leaf();
+            assert_eq!["e", *v];
+        }
+
+        #[test]
+        fn view_steps_through_document_order() {
+            let t = $tree_macro!["a", ["b", ["c"], ["d"]], ["e"], ["f", ["g", ["h"]], ["i"]]];
+            let mut v = t.view();
+            let mut forward = vec![*v];
+            while v.to_next_in_document_order() {
+                forward.push(*v);
+            }
+            assert_eq![vec!["a", "b", "c", "d", "e", "f", "g", "h", "i"], forward];
+            assert_eq!["i", *v];
+            let mut backward = vec![*v];
+            while v.to_prev_in_document_order() {
+                backward.push(*v);
+            }
+            forward.reverse();
+            assert_eq![forward, backward];
+            assert![v.at_root()];
+        }
+
+        #[test]
+        fn view_seek_sibling_clamped() {
+            let t = $tree_macro!["a", ["b"], ["c"], ["d"], ["e"]];
+            let mut v = t.view();
+            assert_eq![-3, v.seek_sibling_clamped(-3)];
+            assert![v.seek_child(1)];
+            assert_eq![0, v.seek_sibling_clamped(2)];
+            assert_eq!["e", *v];
+            assert_eq![5, v.seek_sibling_clamped(5)];
+            assert_eq!["e", *v];
+            assert_eq![-2, v.seek_sibling_clamped(-5)];
+            assert_eq!["b", *v];
+            assert_eq![0, v.seek_sibling_clamped(0)];
+        }
+
+        #[test]
+        fn view_seeks_child_pages() {
+            let t = $tree_macro!["a", ["b"], ["c"], ["d"], ["e"], ["f"]];
+            let mut v = t.view();
+            assert_eq![3, v.child_page_count(2)];
+            assert_eq![1, v.child_page_count(5)];
+            assert![v.seek_child_page(1, 2, 1)];
+            assert_eq!["e", *v];
+            assert_eq![0, v.child_page_count(2)];
+            assert![v.to_parent()];
+            assert![! v.seek_child_page(0, 2, 2)];
+            assert![! v.seek_child_page(2, 2, 1)];
+            assert![! v.seek_child_page(usize::MAX, 2, 0)];
+            assert_eq!["a", *v];
+            assert![v.seek_child_page(2, 2, 0)];
+            assert_eq!["f", *v];
+        }
+
         #[test]
         fn view_counts_children_correctly() {
             let t = $tree_macro!["a", ["b", ["e"], ["f"]], ["c"], ["d"]];
@@ -132,7 +258,7 @@ macro_rules! view_tests {
 
         impl<N, T> NavIter<N, T>
             where T: Copy + Eq + PartialEq + Hash, N: Nav + Deref<Target=T> {
-                fn new(nav: N) -> Self { NavIter { nav: nav, state: HashMap::new(), } }
+                fn new(nav: N) -> Self { NavIter { nav, state: HashMap::new(), } }
             }
 
         impl<N, T> Iterator for NavIter<N, T>
@@ -217,3 +343,20 @@ macro_rules! view_tests {
         // navigation operations.
         );
 }
+
+/// Tests of views over two trees at once, for backends that allow a thread to
+/// acquire views in any order.
+#[macro_export]
+macro_rules! view_tests_across_trees {
+    ($tree_macro:ident) => (
+        #[test]
+        fn views_of_different_trees_are_unordered() {
+            let t = $tree_macro!["a", ["b", ["c"]], ["d"]];
+            let other = $tree_macro!["a", ["b", ["c"]], ["d"]];
+            let root = t.view();
+            let other_root = other.view();
+            assert![other_root != root];
+            assert_eq![None, other_root.partial_cmp(&root)];
+        }
+        );
+}